@@ -1,9 +1,15 @@
 use clap::{
-    Args, Parser, Subcommand,
+    Args, Parser, Subcommand, ValueEnum,
     builder::styling::{self, AnsiColor},
 };
 
-use crate::providers::provider::ProviderKind;
+use std::path::PathBuf;
+
+use crate::{
+    changelog::ChangelogFormat, git::StagingStrategy,
+    providers::provider::ProviderKind, release::Bump,
+    schema::review::Severity,
+};
 
 pub const STYLING: styling::Styles = clap::builder::Styles::styled()
     .header(
@@ -62,6 +68,29 @@ pub struct GlobalArgs {
     /// Print with compact outputs (no pretty trees)
     #[arg(long)]
     pub compact: bool,
+
+    /// Output format, json is non-interactive and meant
+    /// for scripting and editor integrations
+    #[arg(long, value_enum, default_value_t = OutputFormat::Pretty)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// trees, menus and spinners
+    #[default]
+    Pretty,
+
+    /// structured json on stdout, errors
+    /// are json objects with a stable code
+    Json,
+}
+
+impl GlobalArgs {
+    /// helper so each cmd doesn't have to match
+    pub fn is_json(&self) -> bool {
+        matches!(self.output, OutputFormat::Json)
+    }
 }
 
 #[derive(Debug, Subcommand)]
@@ -78,14 +107,59 @@ pub enum Commands {
     /// Generate commits from the diffs in the working tree
     Commit(CommitArgs),
 
+    /// Apply a commit plan written by `gai commit --plan-out`
+    Apply(ApplyArgs),
+
+    /// Review the staged changes or a range of commits
+    Review(ReviewArgs),
+
     /// Query a specific commit from git logs
     Find(FindArgs),
 
+    /// Build or update the local commit index used by `gai find`
+    Index(IndexArgs),
+
+    /// Explain how and why a file or a range of lines changed
+    Blame(BlameArgs),
+
+    /// Summarize what a commit or a range of commits did
+    Explain(ExplainArgs),
+
+    /// Generate release notes between two refs
+    Changelog(ChangelogArgs),
+
+    /// Write a pull request title and description for the current branch
+    Pr(PrArgs),
+
+    /// Name and create a branch from the uncommitted changes or a description
+    Branch(BranchArgs),
+
+    /// Tag the next semver release from the commits since the last one
+    Release(ReleaseArgs),
+
+    /// Fetch and rebase the current branch onto its upstream
+    Sync(SyncArgs),
+
+    /// Suggest merges for conflicted files and stage the results
+    Resolve(ResolveArgs),
+
     /// Generate a rebase plan for a branch
     Rebase(RebaseArgs),
 
+    /// Split an existing commit into several smaller ones
+    Split(SplitArgs),
+
+    /// Turn uncommitted changes into fixups of the commits they belong to
+    Absorb(AbsorbArgs),
+
     /// Reword or amend a commit message
     Reword(RewordArgs),
+
+    /// Plan a git-rebase-todo, git runs this for GIT_SEQUENCE_EDITOR=gai
+    Todo(TodoArgs),
+
+    /// List, restore or prune the branch tips saved before rewrites
+    Backups(BackupsArgs),
 }
 
 #[derive(Debug, Subcommand)]
@@ -111,13 +185,51 @@ pub struct AuthArgs {
 // Each command has its own args struct
 #[derive(Debug, Args)]
 pub struct CommitArgs {
-    /// Skips the confirmation prompt
+    /// Skips the confirmation prompt.
+    /// With --output json, this applies the generated commits
     #[arg(short = 'y', long)]
     pub skip_confirmation: bool,
 
     /// Only generate for currently staged files/hunks
     #[arg(short = 's', long)]
     pub staged: bool,
+
+    /// Write the generated commits to a plan file instead of
+    /// applying them, apply it later with `gai apply`
+    #[arg(long, value_name = "FILE")]
+    pub plan_out: Option<PathBuf>,
+
+    /// Review the diffs first and stop on findings at or
+    /// above settings.review.block_on
+    #[arg(long)]
+    pub review: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ReviewArgs {
+    /// Commits to review instead of the staged changes,
+    /// ex. main..HEAD, or HEAD~3 for HEAD~3..HEAD
+    #[arg(value_name = "RANGE")]
+    pub range: Option<String>,
+
+    /// Include unstaged and untracked changes
+    #[arg(short = 'a', long, conflicts_with = "range")]
+    pub all: bool,
+
+    /// Also write the findings as SARIF, - for stdout
+    #[arg(long, value_name = "PATH")]
+    pub sarif: Option<PathBuf>,
+
+    /// Exit with an error on findings at or above this severity
+    #[arg(long, value_enum, value_name = "SEVERITY")]
+    pub fail_on: Option<Severity>,
+}
+
+#[derive(Debug, Args)]
+pub struct ApplyArgs {
+    /// Plan file written by `gai commit --plan-out`
+    #[arg(value_name = "PLAN")]
+    pub plan: PathBuf,
 }
 
 #[derive(Debug, Args)]
@@ -140,10 +252,19 @@ pub struct LogArgs {
 
 #[derive(Debug, Args)]
 pub struct FindArgs {
-    /// Max number of commits to query from
+    /// What to search for, prompts for it if empty
+    #[arg(value_name = "QUERY")]
+    pub query: Option<String>,
+
+    /// Max number of commits to query from, 0 for the full history.
+    /// With a commit index the whole history is pre-filtered instead
     #[arg(short = 'n', long, default_value_t = 50)]
     pub number: usize,
 
+    /// Number of ranked matches to show
+    #[arg(short = 't', long, default_value_t = 3)]
+    pub top: usize,
+
     /// Reverse the order of commits
     #[arg(long)]
     pub reverse: bool,
@@ -178,18 +299,260 @@ pub struct FindArgs {
     /// Only search commits from this ago. ex. "1year", "1month", etc
     #[arg(long, value_parser = humantime::parse_duration)]
     pub since: Option<std::time::Duration>,
+
+    /// Only search commits by this author, matches the name or email
+    #[arg(long)]
+    pub author: Option<String>,
+
+    /// Only search commits touching this file or directory
+    #[arg(long)]
+    pub path: Option<String>,
+
+    /// Token budget per request, larger histories are searched in
+    /// batches then ranked again. Use with -n 0 to search everything
+    #[arg(long, default_value_t = 24_000)]
+    pub batch_tokens: u32,
+
+    /// Search the local commit index only, without the LLM
+    #[arg(long, conflicts_with = "no_index")]
+    pub offline: bool,
+
+    /// Ignore the local commit index, even if one exists
+    #[arg(long)]
+    pub no_index: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct BlameArgs {
+    /// File to explain, with an optional line range as of HEAD.
+    /// ex. src/main.rs:10-20 or src/main.rs:12
+    #[arg(value_name = "PATH[:START-END]")]
+    pub target: String,
+
+    /// Max number of commits to walk back through
+    #[arg(short = 'd', long, default_value_t = 10)]
+    pub depth: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct ChangelogArgs {
+    /// Start of the range (exclusive), defaults to the previous tag
+    #[arg(long, value_name = "REF")]
+    pub from: Option<String>,
+
+    /// End of the range (inclusive)
+    #[arg(long, value_name = "REF", default_value = "HEAD")]
+    pub to: String,
+
+    /// Release title, defaults to the tag on --to or Unreleased
+    #[arg(long)]
+    pub title: Option<String>,
+
+    #[arg(long, value_enum, default_value_t = ChangelogFormat::Markdown)]
+    pub format: ChangelogFormat,
+
+    /// Ask the LLM for a short summary of each section
+    #[arg(short = 's', long)]
+    pub summarize: bool,
+
+    /// Prepend to a changelog file instead of printing,
+    /// rerunning replaces the same release
+    #[arg(
+        short = 'w',
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        default_missing_value = "CHANGELOG.md"
+    )]
+    pub write: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct ExplainArgs {
+    /// Commit or range to explain, ex. HEAD, v1.0..v1.1 or HEAD..@{u}
+    #[arg(value_name = "REV")]
+    pub rev: String,
+
+    /// Token budget per request, larger ranges are explained
+    /// in chunks then combined
+    #[arg(long, default_value_t = 24_000)]
+    pub max_tokens: u32,
+}
+
+#[derive(Debug, Args)]
+pub struct BranchArgs {
+    /// What the branch is for, ex. "ABC-12 add oauth login"
+    #[arg(value_name = "DESCRIPTION")]
+    pub description: Vec<String>,
+
+    /// Ticket id to put in the name, overrides one from the description
+    #[arg(short = 't', long)]
+    pub ticket: Option<String>,
+
+    /// Override settings.branch.pattern, ex. "{type}/{slug}"
+    #[arg(long)]
+    pub pattern: Option<String>,
+
+    /// Number of names to suggest
+    #[arg(short = 'n', long, default_value_t = 3)]
+    pub count: usize,
+
+    /// Don't send the uncommitted diff, only the description
+    #[arg(long)]
+    pub no_diff: bool,
+
+    /// Create the branch without switching to it
+    #[arg(long)]
+    pub no_checkout: bool,
+
+    /// Use the first suggestion without asking
+    #[arg(short = 'y', long)]
+    pub skip_confirmation: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct PrArgs {
+    /// Branch the pull request goes into, defaults to origin/HEAD, main or master
+    #[arg(short = 'b', long)]
+    pub base: Option<String>,
+
+    /// Write to a file instead of stdout
+    #[arg(short = 'o', long, value_name = "PATH")]
+    pub out: Option<PathBuf>,
+
+    /// Template to fill in, defaults to the repo's pull_request_template.md
+    #[arg(long, value_name = "PATH", conflicts_with = "no_template")]
+    pub template: Option<PathBuf>,
+
+    /// Ignore the repo's pull request template
+    #[arg(long)]
+    pub no_template: bool,
+
+    /// Token budget for the combined diff, files past it are only counted
+    #[arg(long, default_value_t = 24_000)]
+    pub max_diff_tokens: u32,
+}
+
+#[derive(Debug, Args)]
+pub struct ReleaseArgs {
+    /// Force a bump instead of working it out from the commits
+    #[arg(long, value_enum, conflicts_with = "exact")]
+    pub bump: Option<Bump>,
+
+    /// Release exactly this version, ex. 2.0.0-rc.1
+    #[arg(long, value_name = "VERSION")]
+    pub exact: Option<String>,
+
+    /// Bump Cargo.toml, Cargo.lock and package.json in a release commit
+    #[arg(long)]
+    pub commit: bool,
+
+    /// Prepend the notes to a changelog file in the release commit
+    #[arg(
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        default_missing_value = "CHANGELOG.md"
+    )]
+    pub changelog: Option<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = ChangelogFormat::Markdown)]
+    pub format: ChangelogFormat,
+
+    /// Ask the LLM for a short summary of each section
+    #[arg(short = 's', long)]
+    pub summarize: bool,
+
+    /// Treat non-conventional commits as patches, without the LLM
+    #[arg(long)]
+    pub no_llm: bool,
+
+    /// Print the next version and notes without tagging
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Skip the confirmation before tagging
+    #[arg(short = 'y', long)]
+    pub skip_confirmation: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SyncArgs {
+    /// Continue a sync that stopped on conflicts
+    #[arg(long = "continue", conflicts_with = "abort")]
+    pub resume: bool,
+
+    /// Abort a sync that stopped on conflicts
+    #[arg(long)]
+    pub abort: bool,
+
+    /// Use the upstream as is, without fetching first
+    #[arg(long)]
+    pub no_fetch: bool,
+
+    /// Skip the confirmation before syncing
+    #[arg(short = 'y', long)]
+    pub skip_confirmation: bool,
+
+    /// Stop on conflicts without suggesting resolutions
+    #[arg(long)]
+    pub no_resolve: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ResolveArgs {
+    /// Only resolve these files or directories
+    #[arg(value_name = "PATH")]
+    pub paths: Vec<String>,
+
+    /// Apply every suggestion without reviewing them
+    #[arg(short = 'y', long)]
+    pub accept_all: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct IndexArgs {
+    /// Throw away the existing index and start over
+    #[arg(long)]
+    pub rebuild: bool,
+
+    /// Skip embeddings even if an endpoint is configured
+    #[arg(long)]
+    pub no_embed: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct RebaseArgs {
     #[command(subcommand)]
-    pub scope: RebaseScope,
+    pub scope: Option<RebaseScope>,
+
+    /// Go on with a stopped rebase, after amending an edit
+    /// or staging a resolved conflict
+    #[arg(long = "continue", conflicts_with_all = ["abort", "skip"])]
+    pub resume: bool,
+
+    /// Stop the rebase and put the branch back where it was
+    #[arg(long, conflicts_with = "skip")]
+    pub abort: bool,
+
+    /// Drop the commit that conflicted and go on with the rest
+    #[arg(long)]
+    pub skip: bool,
 
     #[arg(
         long,
         help = "Generate a Rebase plan using RebaseOperations, in place of entire commits.\nThis is synonymous with `git rebase --edit-todo` during an interactive rebase."
     )]
     pub plan: bool,
+
+    /// Write the plan as a git-rebase-todo instead of applying it,
+    /// use it with `GIT_SEQUENCE_EDITOR="cp FILE" git rebase -i`
+    #[arg(long, value_name = "FILE", requires = "plan")]
+    pub todo_out: Option<PathBuf>,
+
+    /// Apply a hand-edited git-rebase-todo instead of generating a plan
+    #[arg(long, value_name = "FILE", conflicts_with = "plan")]
+    pub todo_in: Option<PathBuf>,
 }
 
 #[derive(Debug, Subcommand)]
@@ -218,6 +581,82 @@ pub enum RebaseScope {
     },
 }
 
+#[derive(Debug, Args)]
+pub struct TodoArgs {
+    /// The todo file git rebase -i wrote
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct BackupsArgs {
+    /// Defaults to list
+    #[command(subcommand)]
+    pub action: Option<BackupsAction>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BackupsAction {
+    /// List the backups, oldest first
+    List,
+
+    /// Reset the current branch hard to a backup,
+    /// the tip it replaces is backed up first
+    Restore {
+        /// Backup name as listed, ex. 20260118-142301
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+
+    /// Delete all but the newest backups
+    Prune {
+        /// Number of backups to keep
+        #[arg(long, default_value_t = 5)]
+        keep: usize,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct SplitArgs {
+    /// Commit to split, the commits after it are replayed on top
+    #[arg(value_name = "COMMIT")]
+    pub commit: String,
+
+    /// Override settings.staging_type for this split
+    #[arg(short = 's', long, value_enum)]
+    pub staging: Option<StagingStrategy>,
+
+    /// Skips the confirmation prompt.
+    /// With --output json, this applies the split
+    #[arg(short = 'y', long)]
+    pub skip_confirmation: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct AbsorbArgs {
+    /// Only absorb into commits after this one,
+    /// defaults to the merge base with the upstream
+    #[arg(long, value_name = "REV")]
+    pub base: Option<String>,
+
+    /// How many recent commits to consider
+    #[arg(short = 'n', long, default_value_t = 10)]
+    pub number: usize,
+
+    /// Squash the fixups into their targets right away
+    #[arg(long)]
+    pub squash: bool,
+
+    /// Only use blame, hunks it cant settle stay in the workdir
+    #[arg(long)]
+    pub no_llm: bool,
+
+    /// Skips the confirmation prompt.
+    /// With --output json, this creates the fixups
+    #[arg(short = 'y', long)]
+    pub skip_confirmation: bool,
+}
+
 #[derive(Debug, Args)]
 pub struct RewordArgs {
     #[command(subcommand)]
@@ -0,0 +1,519 @@
+// release notes from conventional commits,
+// grouped by type then scope. rendering and
+// prepending to CHANGELOG.md live here too
+// so gai release can reuse them
+
+use std::collections::BTreeMap;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::git::log::GitLog;
+
+const KEEP_A_CHANGELOG_HEADER: &str = "# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/),
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+";
+
+const MARKDOWN_HEADER: &str = "# Changelog
+";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ChangelogFormat {
+    /// sections per commit type, ex. Features, Bug Fixes
+    #[default]
+    Markdown,
+
+    /// Added, Changed, Fixed... see keepachangelog.com
+    KeepAChangelog,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Changelog {
+    /// ex. v1.3.0 or Unreleased
+    pub title: String,
+
+    /// YYYY-MM-DD
+    pub date: String,
+
+    /// breaking changes from every section
+    pub breaking: Vec<ChangelogEntry>,
+
+    /// in display order, empty ones left out
+    pub sections: Vec<ChangelogSection>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ChangelogSection {
+    pub title: String,
+
+    /// filled by the llm with --summarize
+    pub summary: Option<String>,
+
+    /// sorted by scope, unscoped first
+    pub entries: Vec<ChangelogEntry>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ChangelogEntry {
+    pub scope: Option<String>,
+    pub header: String,
+    pub commit_hash: String,
+    pub breaking: bool,
+}
+
+impl ChangelogFormat {
+    /// section title for a commit prefix
+    fn section(
+        &self,
+        prefix: Option<&str>,
+    ) -> &'static str {
+        let prefix = prefix
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match self {
+            Self::Markdown => match prefix.as_str() {
+                "feat" => "Features",
+                "fix" => "Bug Fixes",
+                "perf" => "Performance",
+                "refactor" => "Refactoring",
+                "revert" => "Reverts",
+                "docs" => "Documentation",
+                "test" => "Tests",
+                "build" | "ci" => "Build System",
+                "style" | "chore" => "Chores",
+                _ => "Other",
+            },
+            Self::KeepAChangelog => match prefix.as_str() {
+                "feat" => "Added",
+                "fix" => "Fixed",
+                "revert" => "Removed",
+                "security" => "Security",
+                "deprecate" | "deprecated" => "Deprecated",
+                _ => "Changed",
+            },
+        }
+    }
+
+    /// every section title in display order
+    fn order(&self) -> &'static [&'static str] {
+        match self {
+            Self::Markdown => &[
+                "Features",
+                "Bug Fixes",
+                "Performance",
+                "Refactoring",
+                "Reverts",
+                "Documentation",
+                "Tests",
+                "Build System",
+                "Chores",
+                "Other",
+            ],
+            Self::KeepAChangelog => &[
+                "Added",
+                "Changed",
+                "Deprecated",
+                "Removed",
+                "Fixed",
+                "Security",
+            ],
+        }
+    }
+
+    fn file_header(&self) -> &'static str {
+        match self {
+            Self::Markdown => MARKDOWN_HEADER,
+            Self::KeepAChangelog => KEEP_A_CHANGELOG_HEADER,
+        }
+    }
+
+    fn heading(
+        &self,
+        title: &str,
+        date: &str,
+    ) -> String {
+        match self {
+            Self::Markdown => format!("## {} ({})", title, date),
+            Self::KeepAChangelog if title == "Unreleased" => {
+                "## [Unreleased]".to_owned()
+            }
+            Self::KeepAChangelog => {
+                format!("## [{}] - {}", title, date)
+            }
+        }
+    }
+}
+
+impl Changelog {
+    /// group the logs, merge commits and
+    /// fixups are left out
+    pub fn new(
+        title: &str,
+        date: &str,
+        logs: &[GitLog],
+        format: ChangelogFormat,
+    ) -> Self {
+        let mut grouped: BTreeMap<&str, Vec<ChangelogEntry>> =
+            BTreeMap::new();
+
+        let mut breaking = Vec::new();
+
+        for log in logs {
+            let first_line = log
+                .raw
+                .lines()
+                .next()
+                .unwrap_or_default();
+
+            if log
+                .raw
+                .starts_with("Merge ")
+                || first_line.starts_with("fixup!")
+                || first_line.starts_with("squash!")
+            {
+                continue;
+            }
+
+            let header = log
+                .header
+                .to_owned()
+                .unwrap_or_else(|| first_line.to_owned());
+
+            // BREAKING CHANGE footers count too
+            let is_breaking = log.breaking
+                || log
+                    .body
+                    .as_deref()
+                    .is_some_and(|b| {
+                        b.contains("BREAKING CHANGE")
+                            || b.contains("BREAKING-CHANGE")
+                    });
+
+            let entry = ChangelogEntry {
+                scope: log.scope.to_owned(),
+                header,
+                commit_hash: log
+                    .commit_hash
+                    .to_owned(),
+                breaking: is_breaking,
+            };
+
+            if is_breaking {
+                breaking.push(entry.to_owned());
+            }
+
+            grouped
+                .entry(
+                    format.section(
+                        log.prefix
+                            .as_deref(),
+                    ),
+                )
+                .or_default()
+                .push(entry);
+        }
+
+        let sections = format
+            .order()
+            .iter()
+            .filter_map(|title| {
+                let mut entries = grouped.remove(title)?;
+
+                // stable, keeps commit order in a scope
+                entries.sort_by(|a, b| {
+                    a.scope
+                        .cmp(&b.scope)
+                });
+
+                Some(ChangelogSection {
+                    title: title.to_string(),
+                    summary: None,
+                    entries,
+                })
+            })
+            .collect();
+
+        Self {
+            title: title.to_owned(),
+            date: date.to_owned(),
+            breaking,
+            sections,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sections
+            .is_empty()
+    }
+
+    /// this release as a markdown block,
+    /// starting with its ## heading
+    pub fn render(
+        &self,
+        format: ChangelogFormat,
+    ) -> String {
+        let mut out = format.heading(&self.title, &self.date);
+        out.push('\n');
+
+        if !self
+            .breaking
+            .is_empty()
+        {
+            let title = match format {
+                ChangelogFormat::Markdown => "### ⚠ BREAKING CHANGES",
+                ChangelogFormat::KeepAChangelog => "### Breaking",
+            };
+
+            out.push_str(&format!("\n{}\n\n", title));
+
+            for entry in &self.breaking {
+                out.push_str(&entry_line(entry));
+            }
+        }
+
+        for section in &self.sections {
+            out.push_str(&format!("\n### {}\n\n", section.title));
+
+            if let Some(ref summary) = section.summary {
+                out.push_str(summary.trim());
+                out.push_str("\n\n");
+            }
+
+            for entry in &section.entries {
+                out.push_str(&entry_line(entry));
+            }
+        }
+
+        out
+    }
+}
+
+fn entry_line(entry: &ChangelogEntry) -> String {
+    let scope = entry
+        .scope
+        .as_ref()
+        .map(|s| format!("**{}:** ", s))
+        .unwrap_or_default();
+
+    format!(
+        "- {}{} ({})\n",
+        scope,
+        entry.header,
+        &entry.commit_hash[..7.min(
+            entry
+                .commit_hash
+                .len()
+        )]
+    )
+}
+
+/// put a rendered release at the top of an
+/// existing changelog. if a release with the
+/// same heading is already there its replaced,
+/// so running it twice gives the same file
+pub fn prepend(
+    existing: &str,
+    release: &str,
+    format: ChangelogFormat,
+) -> String {
+    if existing
+        .trim()
+        .is_empty()
+    {
+        return format!("{}\n{}", format.file_header(), release);
+    }
+
+    let heading = release
+        .lines()
+        .next()
+        .unwrap_or_default();
+
+    // only the version part, the date
+    // might differ between runs
+    let key = heading_key(heading);
+
+    let lines: Vec<&str> = existing
+        .lines()
+        .collect();
+
+    let is_release = |l: &&str| l.starts_with("## ");
+
+    let first = lines
+        .iter()
+        .position(is_release)
+        .unwrap_or(lines.len());
+
+    let existing_release = lines
+        .iter()
+        .position(|l| is_release(l) && heading_key(l) == key);
+
+    let (start, end) = match existing_release {
+        Some(start) => {
+            let end = lines[start + 1..]
+                .iter()
+                .position(is_release)
+                .map(|p| start + 1 + p)
+                .unwrap_or(lines.len());
+
+            (start, end)
+        }
+        // new versions go under [Unreleased]
+        None if key != "Unreleased"
+            && lines
+                .get(first)
+                .is_some_and(|l| heading_key(l) == "Unreleased") =>
+        {
+            let after = lines[first + 1..]
+                .iter()
+                .position(is_release)
+                .map(|p| first + 1 + p)
+                .unwrap_or(lines.len());
+
+            (after, after)
+        }
+        None => (first, first),
+    };
+
+    let mut out = lines[..start].join("\n");
+
+    if !out.is_empty() {
+        out.push('\n');
+        if !out.ends_with("\n\n") {
+            out.push('\n');
+        }
+    }
+
+    out.push_str(release.trim_end());
+    out.push('\n');
+
+    if end < lines.len() {
+        out.push('\n');
+        out.push_str(&lines[end..].join("\n"));
+        out.push('\n');
+    }
+
+    out
+}
+
+/// "## [v1.2.0] - 2024-01-01" and
+/// "## v1.2.0 (2024-01-01)" -> "v1.2.0"
+fn heading_key(heading: &str) -> String {
+    heading
+        .trim_start_matches('#')
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .trim_matches(|c| c == '[' || c == ']')
+        .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(
+        raw: &str,
+        hash: &str,
+    ) -> GitLog {
+        let mut log: GitLog = raw
+            .as_bytes()
+            .into();
+        log.commit_hash = hash.to_owned();
+        log
+    }
+
+    #[test]
+    fn test_changelog_groups() {
+        let logs = vec![
+            log("fix(git): handle root commits", "aaaaaaa1"),
+            log("feat(find)!: rank by relevance", "bbbbbbb2"),
+            log("feat: add changelog", "ccccccc3"),
+            log("Merge branch 'main'", "ddddddd4"),
+            log("update readme", "eeeeeee5"),
+        ];
+
+        let md = Changelog::new(
+            "v1.0.0",
+            "2024-01-01",
+            &logs,
+            ChangelogFormat::Markdown,
+        );
+
+        let titles: Vec<&str> = md
+            .sections
+            .iter()
+            .map(|s| s.title.as_str())
+            .collect();
+
+        assert_eq!(titles, vec!["Features", "Bug Fixes", "Other"]);
+        assert_eq!(md.breaking.len(), 1);
+
+        // unscoped first
+        assert_eq!(md.sections[0].entries[0].header, "add changelog");
+
+        let rendered = md.render(ChangelogFormat::Markdown);
+
+        assert!(rendered.starts_with("## v1.0.0 (2024-01-01)\n"));
+        assert!(
+            rendered
+                .contains("- **find:** rank by relevance (bbbbbbb)")
+        );
+
+        let kac = Changelog::new(
+            "v1.0.0",
+            "2024-01-01",
+            &logs,
+            ChangelogFormat::KeepAChangelog,
+        );
+
+        let titles: Vec<&str> = kac
+            .sections
+            .iter()
+            .map(|s| s.title.as_str())
+            .collect();
+
+        assert_eq!(titles, vec!["Added", "Changed", "Fixed"]);
+    }
+
+    #[test]
+    fn test_prepend_idempotent() {
+        let format = ChangelogFormat::KeepAChangelog;
+
+        let old = "## [v0.9.0] - 2023-12-01\n\n### Fixed\n\n- old (1234567)\n";
+        let release = "## [v1.0.0] - 2024-01-01\n\n### Added\n\n- new (7654321)\n";
+
+        let once = prepend(old, release, format);
+        let twice = prepend(&once, release, format);
+
+        assert_eq!(once, twice);
+        assert!(once.starts_with("## [v1.0.0]"));
+        assert!(once.contains("## [v0.9.0]"));
+
+        // same version, new date, replaced not duplicated
+        let redated = release.replace("2024-01-01", "2024-01-02");
+        let replaced = prepend(&twice, &redated, format);
+
+        assert_eq!(
+            replaced
+                .matches("## [v1.0.0]")
+                .count(),
+            1
+        );
+        assert!(replaced.contains("2024-01-02"));
+
+        let unreleased = prepend(&twice, "## [Unreleased]\n", format);
+
+        assert!(unreleased.starts_with("## [Unreleased]\n"));
+        assert_eq!(prepend(&unreleased, release, format), unreleased);
+
+        let fresh = prepend("", release, format);
+
+        assert!(fresh.starts_with("# Changelog"));
+        assert_eq!(prepend(&fresh, release, format), fresh);
+    }
+}
@@ -0,0 +1,442 @@
+use git2::Repository;
+use owo_colors::OwoColorize;
+use serde_json::Value;
+
+use crate::{
+    args::{AbsorbArgs, GlobalArgs},
+    cmd::commit::diff_strategy,
+    git::{
+        Diffs, StagingStrategy, StatusStrategy,
+        absorb::{
+            AbsorbCandidate, HunkOwners, candidate_commits,
+            default_base, hunk_owners,
+        },
+        commit::{GitCommit, apply_commits, find_parent_commit},
+        diffs::{FileDiff, get_diffs_from_statuses},
+        errors::GitError,
+        rebase::autosquash,
+        reset::reset_repo_mixed,
+        rewrite::{RewriteState, clear_rewrite, ensure_no_rewrite},
+        status::{get_commit_stats, get_status},
+        tag::resolve_rev,
+        utils::get_head_repo,
+    },
+    print::{
+        self,
+        json::{AbsorbOutput, AppliedCommit},
+        menu::Menu,
+        spinner::SpinnerBuilder,
+    },
+    providers::{extract_from_provider, provider::ProviderKind},
+    requests::absorb::create_absorb_request,
+    responses::absorb::{AbsorbSource, Absorbed, parse_to_absorbed},
+    schema::{SchemaSettings, absorb::create_absorb_schema},
+    settings::Settings,
+    state::State,
+};
+
+#[derive(Debug, Clone)]
+enum AbsorbActions {
+    Apply,
+    Quit,
+}
+
+pub fn run(
+    args: &AbsorbArgs,
+    global: &GlobalArgs,
+) -> anyhow::Result<()> {
+    let state = State::new(
+        global
+            .config
+            .as_deref(),
+        global,
+    )?;
+
+    let json = global.is_json();
+    let repo = &state.git.repo;
+
+    ensure_no_rewrite(repo)?;
+
+    // hunks are staged against the index, anything
+    // already staged would end up in the first fixup
+    if !get_status(repo, &StatusStrategy::Stage)?
+        .statuses
+        .is_empty()
+    {
+        return Err(GitError::Generic(
+            "absorb only works on unstaged changes, unstage them first"
+                .to_owned(),
+        )
+        .into());
+    }
+
+    let diffs = get_diffs_from_statuses(
+        repo,
+        &state.git.workdir,
+        &diff_strategy(&state.settings, StatusStrategy::WorkingDir),
+    )?;
+
+    if diffs
+        .files
+        .is_empty()
+    {
+        if json {
+            return print::json::emit(&AbsorbOutput {
+                absorbed: Vec::new(),
+                unabsorbed: Vec::new(),
+                applied: None,
+                squashed: None,
+            });
+        }
+
+        println!(
+            "{}",
+            "Nothing to absorb."
+                .yellow()
+                .bold()
+        );
+        return Ok(());
+    }
+
+    let base = match args.base {
+        Some(ref rev) => Some(resolve_rev(repo, rev)?),
+        None => default_base(repo),
+    };
+
+    let candidates = candidate_commits(repo, base, args.number)?;
+
+    if candidates.is_empty() {
+        return Err(GitError::Generic(
+            "no commits to absorb into, try a different --base"
+                .to_owned(),
+        )
+        .into());
+    }
+
+    let mut owners = Vec::new();
+
+    for file in &diffs.files {
+        owners.extend(hunk_owners(repo, file, &candidates)?);
+    }
+
+    let (settled, ambiguous): (Vec<HunkOwners>, Vec<HunkOwners>) =
+        owners
+            .iter()
+            .cloned()
+            .partition(|h| h.owners.len() == 1);
+
+    let mut absorbed: Vec<Absorbed> = settled
+        .into_iter()
+        .map(|h| Absorbed {
+            hunk_id: h.hunk_id,
+            commit: h.owners[0].to_owned(),
+            source: AbsorbSource::Blame,
+        })
+        .collect();
+
+    if !args.no_llm && !ambiguous.is_empty() {
+        if !json {
+            print::status::provider_info(
+                &state
+                    .settings
+                    .provider,
+                &state
+                    .settings
+                    .providers,
+            )?;
+        }
+
+        absorbed.extend(match_ambiguous(
+            &state.settings,
+            &candidates,
+            &diffs,
+            &ambiguous,
+            json,
+        )?);
+    }
+
+    let unabsorbed: Vec<String> = owners
+        .into_iter()
+        .map(|h| h.hunk_id)
+        .filter(|id| {
+            !absorbed
+                .iter()
+                .any(|a| &a.hunk_id == id)
+        })
+        .collect();
+
+    if json {
+        let (applied, squashed) =
+            if args.skip_confirmation && !absorbed.is_empty() {
+                let (applied, squashed) = apply_absorbed(
+                    repo,
+                    &diffs,
+                    &candidates,
+                    &absorbed,
+                    args.squash,
+                )?;
+
+                (Some(applied), squashed)
+            } else {
+                (None, None)
+            };
+
+        return print::json::emit(&AbsorbOutput {
+            absorbed,
+            unabsorbed,
+            applied,
+            squashed,
+        });
+    }
+
+    print::absorb::absorb(&candidates, &absorbed, &unabsorbed)?;
+
+    if absorbed.is_empty() {
+        println!(
+            "{}",
+            "No hunk could be matched to a commit."
+                .yellow()
+                .bold()
+        );
+        return Ok(());
+    }
+
+    if !args.skip_confirmation {
+        let opts = [
+            (AbsorbActions::Apply, 'y', "create fixups"),
+            (AbsorbActions::Quit, 'q', "quit"),
+        ];
+
+        if let AbsorbActions::Quit =
+            Menu::new("What do you want to do?", &opts).render()?
+        {
+            return Ok(());
+        }
+    }
+
+    let (applied, squashed) = apply_absorbed(
+        repo,
+        &diffs,
+        &candidates,
+        &absorbed,
+        args.squash,
+    )?;
+
+    if squashed.is_some() {
+        println!(
+            "{}",
+            format!(
+                "Squashed {} fixup/s into their commits",
+                applied.len()
+            )
+            .dimmed()
+        );
+        return Ok(());
+    }
+
+    for commit in &applied {
+        let (branch_name, files_changed, insertions, deletions) =
+            get_commit_stats(repo, &commit.oid)?;
+
+        print::commits::completed_commit(
+            &branch_name,
+            &commit.oid,
+            &commit.message,
+            files_changed,
+            insertions,
+            deletions,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// ask the provider about the hunks
+/// blame couldnt settle
+fn match_ambiguous(
+    settings: &Settings,
+    candidates: &[AbsorbCandidate],
+    diffs: &Diffs,
+    ambiguous: &[HunkOwners],
+    hidden: bool,
+) -> anyhow::Result<Vec<Absorbed>> {
+    // openai seems like the only one that needs this
+    let schema_settings =
+        if matches!(settings.provider, ProviderKind::OpenAI) {
+            SchemaSettings::default().additional_properties(false)
+        } else {
+            SchemaSettings::default()
+        };
+
+    let hunk_ids: Vec<String> = ambiguous
+        .iter()
+        .map(|h| h.hunk_id.to_owned())
+        .collect();
+
+    let commits: Vec<String> = candidates
+        .iter()
+        .map(|c| c.hash[..7].to_owned())
+        .collect();
+
+    let schema =
+        create_absorb_schema(schema_settings, &hunk_ids, &commits)?;
+
+    let req = create_absorb_request(
+        settings,
+        candidates,
+        &diffs.files,
+        ambiguous,
+    );
+
+    let handle = SpinnerBuilder::new()
+        .text("Matching hunks to commits")
+        .hidden(hidden)
+        .start();
+
+    let result: Value = match extract_from_provider(
+        &settings.provider,
+        req,
+        schema,
+    ) {
+        Ok(r) => r,
+        Err(e) => {
+            handle.error();
+            return Err(e.into());
+        }
+    };
+
+    let absorbed = parse_to_absorbed(result, &hunk_ids, candidates)?;
+
+    handle.done();
+
+    Ok(absorbed)
+}
+
+/// one fixup per target, oldest target first, then
+/// optionally squashed in. the index is reset if
+/// creating the fixups fails partway
+fn apply_absorbed(
+    repo: &Repository,
+    diffs: &Diffs,
+    candidates: &[AbsorbCandidate],
+    absorbed: &[Absorbed],
+    squash: bool,
+) -> anyhow::Result<(Vec<AppliedCommit>, Option<String>)> {
+    let mut git_commits = Vec::new();
+    let mut targets = Vec::new();
+
+    for candidate in candidates
+        .iter()
+        .rev()
+    {
+        let hunk_ids: Vec<String> = absorbed
+            .iter()
+            .filter(|a| a.commit == candidate.hash)
+            .map(|a| a.hunk_id.to_owned())
+            .collect();
+
+        if hunk_ids.is_empty() {
+            continue;
+        }
+
+        let mut files: Vec<String> = hunk_ids
+            .iter()
+            .filter_map(|h| {
+                h.rsplit_once(':')
+                    .map(|(p, _)| p.to_owned())
+            })
+            .collect();
+
+        files.sort();
+        files.dedup();
+
+        git_commits.push(GitCommit {
+            files,
+            hunk_ids,
+            line_ids: Vec::new(),
+            message: format!("fixup! {}", candidate.summary),
+        });
+
+        targets.push(
+            candidate
+                .hash
+                .to_owned(),
+        );
+    }
+
+    // only the absorbed hunks, the
+    // rest stays in the workdir
+    let mut files: Vec<FileDiff> = diffs
+        .files
+        .iter()
+        .cloned()
+        .map(|mut f| {
+            let path = f.path.to_owned();
+
+            f.hunks.retain(|h| {
+                let id = format!("{}:{}", path, h.id);
+                absorbed
+                    .iter()
+                    .any(|a| a.hunk_id == id)
+            });
+            f
+        })
+        .filter(|f| !f.hunks.is_empty())
+        .collect();
+
+    let original = get_head_repo(repo)?.to_string();
+
+    let oids = match apply_commits(
+        repo,
+        &git_commits,
+        &mut files,
+        &StagingStrategy::Hunks,
+    ) {
+        Ok(oids) => oids,
+        Err(e) => {
+            // nothing was staged before, so this
+            // puts every change back in the workdir
+            reset_repo_mixed(
+                repo,
+                &original,
+                &format!(
+                    "gai absorb (abort): back to {}",
+                    &original[..7]
+                ),
+            )?;
+            return Err(e);
+        }
+    };
+
+    let squashed = if squash {
+        let base = find_parent_commit(repo, &targets[0])?;
+
+        let pairs: Vec<(String, String)> = oids
+            .iter()
+            .cloned()
+            .zip(targets)
+            .collect();
+
+        // autosquash puts HEAD back itself on errors,
+        // the state only has to cover a crash
+        RewriteState::begin(repo, "absorb", Vec::new())?;
+
+        let res = autosquash(repo, base, &pairs);
+        clear_rewrite(repo)?;
+
+        Some(res?)
+    } else {
+        None
+    };
+
+    let applied = oids
+        .into_iter()
+        .zip(git_commits)
+        .map(|(oid, c)| AppliedCommit {
+            oid,
+            message: c.message,
+        })
+        .collect();
+
+    Ok((applied, squashed))
+}
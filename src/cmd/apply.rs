@@ -0,0 +1,105 @@
+use std::fs;
+
+use crate::{
+    args::{ApplyArgs, GlobalArgs},
+    cmd::commit::diff_strategy,
+    git::{
+        commit::apply_commits, diffs::get_diffs_from_statuses,
+        errors::GitError, plan::CommitPlan, status::get_commit_stats,
+    },
+    print::{
+        self,
+        json::{AppliedCommit, ApplyOutput},
+    },
+    state::State,
+};
+
+pub fn run(
+    args: &ApplyArgs,
+    global: &GlobalArgs,
+) -> anyhow::Result<()> {
+    let mut state = State::new(
+        global
+            .config
+            .as_deref(),
+        global,
+    )?;
+
+    let plan = CommitPlan::read(&args.plan)?;
+
+    // regenerate with the same strategy the plan
+    // was created with so the fingerprints line up
+    let diff_strategy = diff_strategy(
+        &state.settings,
+        plan.status_strategy
+            .clone(),
+    );
+
+    state.diffs = get_diffs_from_statuses(
+        &state.git.repo,
+        &state.git.workdir,
+        &diff_strategy,
+    )?;
+
+    // a plan written into the workdir is an untracked
+    // file itself, it was never part of the plan
+    let plan_path = fs::canonicalize(&args.plan)?;
+    let ignore = plan_path
+        .strip_prefix(fs::canonicalize(&state.git.workdir)?)
+        .ok()
+        .map(|p| {
+            p.to_string_lossy()
+                .replace('\\', "/")
+        });
+
+    let stale = plan.stale_files(&state.diffs, ignore.as_deref())?;
+
+    if !stale.is_empty() {
+        return Err(GitError::Generic(format!(
+            "working tree changed since the plan was created, regenerate it:\n{}",
+            stale.join("\n")
+        ))
+        .into());
+    }
+
+    plan.validate(&state.diffs)?;
+
+    let oids = apply_commits(
+        &state.git.repo,
+        &plan.commits,
+        &mut state.diffs.files,
+        &plan.staging_type,
+    )?;
+
+    if global.is_json() {
+        let applied = oids
+            .into_iter()
+            .zip(&plan.commits)
+            .map(|(oid, c)| AppliedCommit {
+                oid,
+                message: c.message.to_owned(),
+            })
+            .collect();
+
+        return print::json::emit(&ApplyOutput { applied });
+    }
+
+    for (oid, commit) in oids
+        .iter()
+        .zip(&plan.commits)
+    {
+        let (branch_name, files_changed, insertions, deletions) =
+            get_commit_stats(&state.git.repo, oid)?;
+
+        print::commits::completed_commit(
+            &branch_name,
+            oid,
+            &commit.message,
+            files_changed,
+            insertions,
+            deletions,
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use owo_colors::OwoColorize;
+
+use crate::{
+    args::{BackupsAction, BackupsArgs, GlobalArgs},
+    git::{
+        backup::{
+            create_backup, find_backup, list_backups, prune_backups,
+        },
+        errors::GitError,
+        reset::reset_repo_hard,
+        rewrite::ensure_no_rewrite,
+        status::is_workdir_clean,
+        utils::get_head_repo,
+    },
+    print::{
+        self,
+        json::{BackupOutput, BackupsOutput},
+    },
+    state::State,
+};
+
+pub fn run(
+    args: &BackupsArgs,
+    global: &GlobalArgs,
+) -> anyhow::Result<()> {
+    let state = State::new(
+        global
+            .config
+            .as_deref(),
+        global,
+    )?;
+
+    let repo = &state.git.repo;
+
+    match args
+        .action
+        .as_ref()
+        .unwrap_or(&BackupsAction::List)
+    {
+        BackupsAction::List => {
+            let backups = list_backups(repo)?;
+
+            if global.is_json() {
+                return print::json::emit(&BackupsOutput {
+                    backups: backups
+                        .into_iter()
+                        .map(|b| BackupOutput {
+                            name: b.name,
+                            commit: b.commit.to_string(),
+                            summary: b.summary,
+                            branch: b.branch,
+                            reason: b.reason,
+                        })
+                        .collect(),
+                    ..Default::default()
+                });
+            }
+
+            if backups.is_empty() {
+                println!("No backups yet");
+                return Ok(());
+            }
+
+            for backup in backups {
+                println!(
+                    "{} {} {} {}",
+                    backup.name,
+                    backup
+                        .branch
+                        .as_deref()
+                        .unwrap_or("(detached)")
+                        .cyan(),
+                    backup
+                        .commit
+                        .to_string()[..7]
+                        .to_owned()
+                        .yellow(),
+                    backup.summary
+                );
+            }
+        }
+        BackupsAction::Restore { name } => {
+            let backup = find_backup(repo, name)?;
+
+            ensure_no_rewrite(repo)?;
+
+            if !is_workdir_clean(repo)? {
+                return Err(anyhow::anyhow!(
+                    "Workdir is NOT clean, please save your changes"
+                ));
+            }
+
+            // a hard reset moves whatever is checked out,
+            // so only restore onto the branch it came from
+            let head = repo.head()?;
+            let current = head
+                .is_branch()
+                .then(|| head.shorthand())
+                .flatten()
+                .map(|b| b.to_owned());
+
+            if current != backup.branch {
+                return Err(GitError::Generic(format!(
+                    "{} is a backup of {}, switch to it first",
+                    backup.name,
+                    backup
+                        .branch
+                        .as_deref()
+                        .unwrap_or("a detached HEAD")
+                ))
+                .into());
+            }
+
+            // restoring is a rewrite too, so the
+            // tip it replaces gets its own backup
+            let saved = create_backup(
+                repo,
+                get_head_repo(repo)?,
+                current.as_deref(),
+                &format!(
+                    "gai backups: backup before restoring {name}"
+                ),
+            )?;
+
+            reset_repo_hard(
+                repo,
+                &backup
+                    .commit
+                    .to_string(),
+                &format!("gai backups (restore): {}", backup.name),
+            )?;
+
+            if global.is_json() {
+                return print::json::emit(&BackupsOutput {
+                    restored: Some(backup.name),
+                    saved: Some(saved),
+                    ..Default::default()
+                });
+            }
+
+            println!(
+                "Restored {} to {}",
+                backup.name,
+                backup
+                    .commit
+                    .to_string()[..7]
+                    .to_owned()
+                    .yellow()
+            );
+            println!(
+                "{}",
+                format!("The previous tip is backed up as {saved}")
+                    .dimmed()
+            );
+        }
+        BackupsAction::Prune { keep } => {
+            let pruned = prune_backups(repo, *keep)?;
+
+            if global.is_json() {
+                return print::json::emit(&BackupsOutput {
+                    pruned,
+                    ..Default::default()
+                });
+            }
+
+            println!("Pruned {} backup/s", pruned.len());
+        }
+    }
+
+    Ok(())
+}
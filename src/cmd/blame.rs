@@ -0,0 +1,145 @@
+use crate::{
+    args::{BlameArgs, GlobalArgs},
+    git::blame::{BlameTarget, blame_region, get_region_text},
+    print::{
+        json::{BlameOutput, BlameStepOutput},
+        spinner::SpinnerBuilder,
+    },
+    providers::{extract_from_provider, provider::ProviderKind},
+    requests::blame::create_blame_request,
+    responses::blame::parse_to_blame_schema,
+    schema::{SchemaSettings, blame::create_blame_schema},
+    state::State,
+};
+
+pub fn run(
+    args: &BlameArgs,
+    global: &GlobalArgs,
+) -> anyhow::Result<()> {
+    let state = State::new(
+        global
+            .config
+            .as_deref(),
+        global,
+    )?;
+
+    let mut target = BlameTarget::try_from(args.target.as_str())?;
+
+    let (region, lines) = get_region_text(&state.git.repo, &target)?;
+
+    // clamp to the actual file
+    target.lines = Some(lines);
+
+    let commits = blame_region(
+        &state.git.repo,
+        &target.path,
+        lines,
+        args.depth,
+    )?;
+
+    if commits.is_empty() {
+        if global.is_json() {
+            return crate::print::json::emit(&BlameOutput {
+                path: target
+                    .path
+                    .to_owned(),
+                start: lines.0,
+                end: lines.1,
+                summary: String::new(),
+                steps: Vec::new(),
+            });
+        }
+
+        println!("No commits found for {}", target);
+        return Ok(());
+    }
+
+    let schema_settings = if matches!(
+        state
+            .settings
+            .provider,
+        ProviderKind::OpenAI
+    ) {
+        SchemaSettings::default().additional_properties(false)
+    } else {
+        SchemaSettings::default()
+    };
+
+    let schema = create_blame_schema(schema_settings)?;
+
+    let req = create_blame_request(
+        &state.settings,
+        &target,
+        &region,
+        &commits,
+    );
+
+    let handle = SpinnerBuilder::new()
+        .text(format!(
+            "Explaining {} through {} commits",
+            target,
+            commits.len()
+        ))
+        .hidden(global.is_json())
+        .start();
+
+    let response = match extract_from_provider(
+        &state
+            .settings
+            .provider,
+        req,
+        schema,
+    ) {
+        Ok(r) => r,
+        Err(e) => {
+            handle.error();
+            return Err(e.into());
+        }
+    };
+
+    let explanation = parse_to_blame_schema(response, &commits)?;
+
+    handle.done();
+
+    if global.is_json() {
+        let steps = explanation
+            .steps
+            .iter()
+            .filter_map(|step| {
+                let commit = commits
+                    .iter()
+                    .find(|c| c.commit_hash == step.commit)?;
+
+                Some(BlameStepOutput {
+                    commit_hash: commit
+                        .commit_hash
+                        .to_owned(),
+                    message: commit
+                        .message
+                        .to_owned(),
+                    author: commit
+                        .author
+                        .to_owned(),
+                    date: commit
+                        .date
+                        .to_owned(),
+                    explanation: step
+                        .explanation
+                        .to_owned(),
+                })
+            })
+            .collect();
+
+        return crate::print::json::emit(&BlameOutput {
+            path: target
+                .path
+                .to_owned(),
+            start: lines.0,
+            end: lines.1,
+            summary: explanation.summary,
+            steps,
+        });
+    }
+
+    crate::print::blame::blame(&target, &explanation, &commits)
+}
@@ -0,0 +1,265 @@
+use crate::{
+    args::{BranchArgs, GlobalArgs},
+    git::{
+        DiffStrategy, StagingStrategy, branch::create_branch,
+        checkout::switch_to_new_branch,
+        diffs::get_diffs_from_statuses, errors::GitError,
+    },
+    print::{
+        self, json::BranchOutput, menu::Menu, spinner::SpinnerBuilder,
+    },
+    providers::{extract_from_provider, provider::ProviderKind},
+    requests::{
+        branch::create_branch_request, tokens::truncate_to_tokens,
+    },
+    responses::branch::parse_to_branch_names,
+    schema::{SchemaSettings, branch::create_branch_schema},
+    state::State,
+};
+
+/// more than this and the diff is only
+/// noise for picking a name
+const MAX_DIFF_TOKENS: u32 = 8_000;
+
+const PICK_KEYS: [char; 9] =
+    ['1', '2', '3', '4', '5', '6', '7', '8', '9'];
+
+#[derive(Debug, Clone)]
+enum BranchActions {
+    Pick(usize),
+    Edit,
+    Regen,
+    Quit,
+}
+
+pub fn run(
+    args: &BranchArgs,
+    global: &GlobalArgs,
+) -> anyhow::Result<()> {
+    let state = State::new(
+        global
+            .config
+            .as_deref(),
+        global,
+    )?;
+
+    let description = (!args
+        .description
+        .is_empty())
+    .then(|| {
+        args.description
+            .join(" ")
+    });
+
+    let diffs = if args.no_diff {
+        None
+    } else {
+        let diffs = get_diffs_from_statuses(
+            &state.git.repo,
+            &state.git.workdir,
+            &DiffStrategy::default(),
+        )?;
+
+        (!diffs
+            .files
+            .is_empty())
+        .then(|| {
+            let mut text = diffs
+                .to_request_string(&StagingStrategy::AtomicCommits);
+            truncate_to_tokens(&mut text, MAX_DIFF_TOKENS);
+            text
+        })
+    };
+
+    if description.is_none() && diffs.is_none() {
+        return Err(GitError::Generic(
+            "nothing to name, pass a description or make some changes"
+                .to_owned(),
+        )
+        .into());
+    }
+
+    let mut branch_settings = state
+        .settings
+        .branch
+        .to_owned();
+
+    if let Some(ref pattern) = args.pattern {
+        branch_settings.pattern = pattern.to_owned();
+    }
+
+    let schema_settings = if matches!(
+        state
+            .settings
+            .provider,
+        ProviderKind::OpenAI
+    ) {
+        SchemaSettings::default().additional_properties(false)
+    } else {
+        SchemaSettings::default()
+    };
+
+    loop {
+        let schema =
+            create_branch_schema(schema_settings.to_owned())?;
+
+        let req = create_branch_request(
+            &state.settings,
+            description.as_deref(),
+            diffs.as_deref(),
+            args.count,
+        );
+
+        let handle = SpinnerBuilder::new()
+            .text("Naming branch")
+            .hidden(global.is_json())
+            .start();
+
+        let response = match extract_from_provider(
+            &state
+                .settings
+                .provider,
+            req,
+            schema,
+        ) {
+            Ok(r) => r,
+            Err(e) => {
+                handle.error();
+                return Err(e.into());
+            }
+        };
+
+        let mut names = parse_to_branch_names(
+            response,
+            &branch_settings,
+            args.ticket
+                .as_deref(),
+        )?;
+
+        names.truncate(
+            args.count
+                .clamp(1, PICK_KEYS.len()),
+        );
+
+        if names.is_empty() {
+            handle.error();
+            return Err(GitError::Generic(
+                "no usable branch names were suggested".to_owned(),
+            )
+            .into());
+        }
+
+        handle.done();
+
+        if global.is_json() {
+            let created = if args.skip_confirmation {
+                Some(create(&state, &names[0], args.no_checkout)?)
+            } else {
+                None
+            };
+
+            return print::json::emit(&BranchOutput {
+                checked_out: created.is_some() && !args.no_checkout,
+                created,
+                suggestions: names,
+            });
+        }
+
+        if args.skip_confirmation {
+            return finish(&state, &names[0], args.no_checkout);
+        }
+
+        let mut opts: Vec<(BranchActions, char, &str)> = names
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| {
+                (
+                    BranchActions::Pick(idx),
+                    PICK_KEYS[idx],
+                    name.as_str(),
+                )
+            })
+            .collect();
+
+        opts.extend([
+            (BranchActions::Edit, 'e', "type a name"),
+            (BranchActions::Regen, 'r', "regenerate names"),
+            (BranchActions::Quit, 'q', "quit"),
+        ]);
+
+        match Menu::new("Which branch do you want?", &opts)
+            .render()?
+        {
+            BranchActions::Pick(idx) => {
+                return finish(&state, &names[idx], args.no_checkout);
+            }
+            BranchActions::Edit => {
+                let name = print::input::prompt(&format!(
+                    "branch name [{}]: ",
+                    names[0]
+                ))?;
+
+                let name = if name
+                    .trim()
+                    .is_empty()
+                {
+                    names[0].to_owned()
+                } else {
+                    name.trim()
+                        .to_owned()
+                };
+
+                return finish(&state, &name, args.no_checkout);
+            }
+            BranchActions::Regen => continue,
+            BranchActions::Quit => return Ok(()),
+        }
+    }
+}
+
+fn finish(
+    state: &State,
+    name: &str,
+    no_checkout: bool,
+) -> anyhow::Result<()> {
+    create(state, name, no_checkout)?;
+
+    if no_checkout {
+        println!("Created branch {}", name);
+    } else {
+        println!(
+            "Switched to a new branch {}, uncommitted changes came along",
+            name
+        );
+    }
+
+    Ok(())
+}
+
+/// returns the name back for json
+fn create(
+    state: &State,
+    name: &str,
+    no_checkout: bool,
+) -> anyhow::Result<String> {
+    if !git2::Reference::is_valid_name(&format!(
+        "refs/heads/{}",
+        name
+    )) {
+        return Err(GitError::Generic(format!(
+            "{} is not a valid branch name",
+            name
+        ))
+        .into());
+    }
+
+    let created = create_branch(&state.git.repo, name)?;
+
+    // nothing committed yet, moving HEAD is
+    // all a new branch is
+    if created.is_none() || !no_checkout {
+        switch_to_new_branch(&state.git.repo, name)?;
+    }
+
+    Ok(name.to_owned())
+}
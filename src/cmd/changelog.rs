@@ -0,0 +1,206 @@
+use chrono::DateTime;
+
+use crate::{
+    args::{ChangelogArgs, GlobalArgs},
+    changelog::{Changelog, prepend},
+    git::{
+        log::{LogFilters, get_logs},
+        tag::{previous_tag, resolve_rev, tag_at},
+    },
+    print::{json::ChangelogOutput, spinner::SpinnerBuilder},
+    providers::{extract_from_provider, provider::ProviderKind},
+    requests::changelog::create_changelog_request,
+    responses::changelog::apply_changelog_summaries,
+    schema::{SchemaSettings, changelog::create_changelog_schema},
+    state::State,
+};
+
+pub fn run(
+    args: &ChangelogArgs,
+    global: &GlobalArgs,
+) -> anyhow::Result<()> {
+    let state = State::new(
+        global
+            .config
+            .as_deref(),
+        global,
+    )?;
+
+    let repo = &state.git.repo;
+
+    let to = resolve_rev(repo, &args.to)?;
+
+    // default to the last release
+    let from = match args.from {
+        Some(ref from) => Some(from.to_owned()),
+        None => previous_tag(repo, to)?,
+    };
+
+    let from_oid = from
+        .as_deref()
+        .map(|f| resolve_rev(repo, f))
+        .transpose()?;
+
+    let logs = get_logs(
+        &state.git,
+        false,
+        false,
+        0,
+        false,
+        from_oid
+            .map(|o| o.to_string())
+            .as_deref(),
+        Some(&to.to_string()),
+        &LogFilters::default(),
+    )?;
+
+    let title = match args.title {
+        Some(ref title) => title.to_owned(),
+        None => tag_at(repo, to)?
+            .unwrap_or_else(|| "Unreleased".to_owned()),
+    };
+
+    let date = DateTime::from_timestamp(
+        repo.find_commit(to)?
+            .time()
+            .seconds(),
+        0,
+    )
+    .map(|dt| {
+        dt.format("%Y-%m-%d")
+            .to_string()
+    })
+    .unwrap_or_default();
+
+    let mut changelog =
+        Changelog::new(&title, &date, &logs.git_logs, args.format);
+
+    let range = format!(
+        "{}..{}",
+        from.as_deref()
+            .unwrap_or("root"),
+        args.to
+    );
+
+    if changelog.is_empty() {
+        if global.is_json() {
+            return crate::print::json::emit(&ChangelogOutput {
+                from,
+                to: args.to.to_owned(),
+                changelog,
+                markdown: String::new(),
+                written: None,
+            });
+        }
+
+        println!("No commits in {}", range);
+        return Ok(());
+    }
+
+    if args.summarize {
+        summarize(&state, &mut changelog, &logs.git_logs, global)?;
+    }
+
+    let markdown = changelog.render(args.format);
+
+    let written = match args.write {
+        Some(ref path) => {
+            let path = state
+                .git
+                .workdir
+                .join(path);
+
+            let existing =
+                std::fs::read_to_string(&path).unwrap_or_default();
+
+            let updated = prepend(&existing, &markdown, args.format);
+
+            if updated != existing {
+                std::fs::write(&path, updated)?;
+            }
+
+            Some(path)
+        }
+        None => None,
+    };
+
+    if global.is_json() {
+        return crate::print::json::emit(&ChangelogOutput {
+            from,
+            to: args.to.to_owned(),
+            changelog,
+            markdown,
+            written: written.map(|p| {
+                p.display()
+                    .to_string()
+            }),
+        });
+    }
+
+    match written {
+        Some(path) => println!(
+            "Wrote {} ({} commits) to {}",
+            title,
+            logs.git_logs.len(),
+            path.display()
+        ),
+        None => print!("{}", markdown),
+    }
+
+    Ok(())
+}
+
+/// a paragraph per section, gai release uses this too
+pub fn summarize(
+    state: &State,
+    changelog: &mut Changelog,
+    logs: &[crate::git::log::GitLog],
+    global: &GlobalArgs,
+) -> anyhow::Result<()> {
+    let schema_settings = if matches!(
+        state
+            .settings
+            .provider,
+        ProviderKind::OpenAI
+    ) {
+        SchemaSettings::default().additional_properties(false)
+    } else {
+        SchemaSettings::default()
+    };
+
+    let titles: Vec<String> = changelog
+        .sections
+        .iter()
+        .map(|s| s.title.to_owned())
+        .collect();
+
+    let schema = create_changelog_schema(schema_settings, &titles)?;
+
+    let req =
+        create_changelog_request(&state.settings, changelog, logs);
+
+    let handle = SpinnerBuilder::new()
+        .text("Summarizing changes")
+        .hidden(global.is_json())
+        .start();
+
+    let response = match extract_from_provider(
+        &state
+            .settings
+            .provider,
+        req,
+        schema,
+    ) {
+        Ok(r) => r,
+        Err(e) => {
+            handle.error();
+            return Err(e.into());
+        }
+    };
+
+    apply_changelog_summaries(response, changelog)?;
+
+    handle.stop_and_clear();
+
+    Ok(())
+}
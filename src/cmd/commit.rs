@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use owo_colors::OwoColorize;
 use serde_json::Value;
 use strum::{IntoEnumIterator, VariantNames};
@@ -9,12 +11,21 @@ use crate::{
         StatusStrategy,
         commit::{GitCommit, apply_commits},
         diffs::get_diffs_from_statuses,
+        plan::{CommitPlan, validate_commits},
         status::get_commit_stats,
     },
-    print::{self, menu::Menu, spinner::SpinnerBuilder},
+    print::{
+        self,
+        json::{AppliedCommit, CommitOutput},
+        menu::Menu,
+        spinner::SpinnerBuilder,
+    },
     providers::{extract_from_provider, provider::ProviderKind},
     requests::{Request, commit::create_commit_request},
-    responses::commit::{parse_to_commit_schema, process_commit},
+    responses::commit::{
+        insert_empty_commit, merge_with_next, move_items,
+        parse_to_commit_schema, process_commit,
+    },
     schema::{
         SchemaSettings,
         commit::{
@@ -42,6 +53,10 @@ pub enum EditActions {
     Scope,
     Header,
     Body,
+    Move,
+    Split,
+    Merge,
+    Create,
     Quit,
 }
 
@@ -63,6 +78,44 @@ pub const EDIT_OPTS: [(EditActions, char, &str); 8] = [
     (EditActions::Quit, 'q', "quit"),
 ];
 
+/// only shown when the commits were generated
+/// from diffs, with Hunks, Lines or AtomicCommits
+pub const PLAN_EDIT_OPTS: [(EditActions, char, &str); 4] = [
+    (
+        EditActions::Move,
+        'm',
+        "move a file or hunk to another commit",
+    ),
+    (EditActions::Split, 'x', "split commit in two"),
+    (EditActions::Merge, 'j', "join with the next commit"),
+    (
+        EditActions::Create,
+        'c',
+        "create an empty commit after this one",
+    ),
+];
+
+/// what edit_commits needs to move
+/// files and hunks between commits
+pub struct PlanContext<'a> {
+    pub staging_type: &'a StagingStrategy,
+    pub diffs: &'a Diffs,
+}
+
+impl PlanContext<'_> {
+    /// only these can be regrouped, OneFilePerCommit
+    /// is one file by definition and AllFilesOneCommit
+    /// has nothing to move
+    fn can_regroup(&self) -> bool {
+        matches!(
+            self.staging_type,
+            StagingStrategy::Hunks
+                | StagingStrategy::Lines
+                | StagingStrategy::AtomicCommits
+        )
+    }
+}
+
 pub fn run(
     args: &CommitArgs,
     global: &GlobalArgs,
@@ -88,48 +141,27 @@ pub fn run(
             .only_staged = true;
     }
 
-    let status_strategy = if state
-        .settings
-        .commit
-        .only_staged
-    {
-        StatusStrategy::Stage
-    } else {
-        StatusStrategy::default()
-    };
-
-    let mut diff_strategy = DiffStrategy {
-        status_strategy,
-        ..Default::default()
-    };
+    let diff_strategy = diff_strategy(
+        &state.settings,
+        status_strategy(&state.settings),
+    );
 
-    if let Some(ref files_to_truncate) = state
-        .settings
-        .context
-        .truncate_files
-    {
-        diff_strategy.truncated_files = files_to_truncate.to_owned();
-    }
+    let json = global.is_json();
 
-    if let Some(ref files_to_ignore) = state
-        .settings
-        .context
-        .ignore_files
-    {
-        diff_strategy.ignored_files = files_to_ignore.to_owned();
+    if !json {
+        print::status::provider_info(
+            &state
+                .settings
+                .provider,
+            &state
+                .settings
+                .providers,
+        )?;
     }
 
-    print::status::provider_info(
-        &state
-            .settings
-            .provider,
-        &state
-            .settings
-            .providers,
-    )?;
-
     let handle = SpinnerBuilder::new()
         .text("Generating request")
+        .hidden(json)
         .start();
 
     state.diffs = get_diffs_from_statuses(
@@ -143,6 +175,16 @@ pub fn run(
         .files
         .is_empty()
     {
+        handle.stop_and_clear();
+
+        if json {
+            return print::json::emit(&CommitOutput {
+                commits: Vec::new(),
+                applied: None,
+                plan: None,
+            });
+        }
+
         println!(
             "{}",
             "Repository does not have any known changes."
@@ -173,6 +215,9 @@ pub fn run(
         &state
             .diffs
             .as_hunks(),
+        &state
+            .diffs
+            .as_line_ids(),
     )?;
 
     let req = create_commit_request(
@@ -180,7 +225,11 @@ pub fn run(
         &state.git,
         &state
             .diffs
-            .to_string(),
+            .to_request_string(
+                &state
+                    .settings
+                    .staging_type,
+            ),
     );
 
     /* println!("{}", serde_json::to_string_pretty(&schema)?);
@@ -188,17 +237,193 @@ pub fn run(
 
     handle.done();
 
-    run_commit(req, schema, state.settings, state.git, state.diffs)?;
+    if (args.review
+        || state
+            .settings
+            .review
+            .before_commit)
+        && !crate::cmd::review::gate(
+            &state.settings,
+            &state.diffs,
+            json,
+        )?
+    {
+        return Ok(());
+    }
+
+    let plan_out = args
+        .plan_out
+        .as_deref();
+
+    if json {
+        return run_commit_json(
+            req,
+            schema,
+            state.settings,
+            state.git,
+            state.diffs,
+            args.skip_confirmation,
+            plan_out,
+        );
+    }
+
+    run_commit(
+        req,
+        schema,
+        state.settings,
+        state.git,
+        state.diffs,
+        plan_out,
+    )?;
 
     Ok(())
 }
 
+/// only_staged decides where the diffs come from
+pub fn status_strategy(cfg: &Settings) -> StatusStrategy {
+    if cfg
+        .commit
+        .only_staged
+    {
+        StatusStrategy::Stage
+    } else {
+        StatusStrategy::default()
+    }
+}
+
+pub fn diff_strategy(
+    cfg: &Settings,
+    status_strategy: StatusStrategy,
+) -> DiffStrategy {
+    let mut diff_strategy = DiffStrategy {
+        status_strategy,
+        ..Default::default()
+    };
+
+    if let Some(ref files_to_truncate) = cfg
+        .context
+        .truncate_files
+    {
+        diff_strategy.truncated_files = files_to_truncate.to_owned();
+    }
+
+    if let Some(ref files_to_ignore) = cfg
+        .context
+        .ignore_files
+    {
+        diff_strategy.ignored_files = files_to_ignore.to_owned();
+    }
+
+    diff_strategy
+}
+
+/// validate and write the commits to a plan
+/// file instead of applying them
+fn write_plan(
+    path: &Path,
+    cfg: &Settings,
+    git: &GitRepo,
+    diffs: &Diffs,
+    git_commits: Vec<GitCommit>,
+) -> anyhow::Result<()> {
+    validate_commits(&git_commits, diffs, &cfg.staging_type)?;
+
+    let plan = CommitPlan::new(
+        &git.repo,
+        diffs,
+        git_commits,
+        status_strategy(cfg),
+        cfg.staging_type
+            .to_owned(),
+        cfg.provider
+            .to_string(),
+        cfg.providers
+            .get_model(&cfg.provider)
+            .to_owned(),
+    )?;
+
+    plan.write(path)
+}
+
+/// non-interactive version of run_commit
+/// prints the proposed commits, and only
+/// applies them with --skip-confirmation
+fn run_commit_json(
+    req: Request,
+    schema: Value,
+    cfg: Settings,
+    git: GitRepo,
+    mut diffs: Diffs,
+    apply: bool,
+    plan_out: Option<&Path>,
+) -> anyhow::Result<()> {
+    let result: Value =
+        extract_from_provider(&cfg.provider, req, schema)?;
+
+    let raw_commits =
+        parse_to_commit_schema(result, &cfg.staging_type)?;
+
+    if let Some(path) = plan_out {
+        let git_commits: Vec<GitCommit> = raw_commits
+            .iter()
+            .cloned()
+            .map(|c| process_commit(c, &cfg))
+            .collect();
+
+        write_plan(path, &cfg, &git, &diffs, git_commits)?;
+
+        return print::json::emit(&CommitOutput {
+            commits: raw_commits,
+            applied: None,
+            plan: Some(
+                path.display()
+                    .to_string(),
+            ),
+        });
+    }
+
+    let applied = if apply {
+        let git_commits: Vec<GitCommit> = raw_commits
+            .iter()
+            .cloned()
+            .map(|c| process_commit(c, &cfg))
+            .collect();
+
+        let oids = apply_commits(
+            &git.repo,
+            &git_commits,
+            &mut diffs.files,
+            &cfg.staging_type,
+        )?;
+
+        let applied = oids
+            .into_iter()
+            .zip(git_commits)
+            .map(|(oid, c)| AppliedCommit {
+                oid,
+                message: c.message,
+            })
+            .collect();
+
+        Some(applied)
+    } else {
+        None
+    };
+
+    print::json::emit(&CommitOutput {
+        commits: raw_commits,
+        applied,
+        plan: None,
+    })
+}
+
 fn run_commit(
     req: Request,
     schema: Value,
     cfg: Settings,
     git: GitRepo,
     mut diffs: Diffs,
+    plan_out: Option<&Path>,
 ) -> anyhow::Result<()> {
     loop {
         let handle = SpinnerBuilder::new()
@@ -245,6 +470,26 @@ fn run_commit(
                         .map(|c| process_commit(c, &cfg))
                         .collect();
 
+                    if let Some(path) = plan_out {
+                        if let Err(e) = write_plan(
+                            path,
+                            &cfg,
+                            &git,
+                            &diffs,
+                            git_commits,
+                        ) {
+                            eprintln!("failed to write plan:\n{e}");
+                            break;
+                        }
+
+                        println!(
+                            "Plan written to {}, apply it with `gai apply`",
+                            path.display()
+                        );
+
+                        break;
+                    }
+
                     let oids = match apply_commits(
                         &git.repo,
                         &git_commits,
@@ -293,7 +538,13 @@ fn run_commit(
                     break;
                 }
                 ResponseActions::Edit => {
-                    raw_commits = edit_commits(&raw_commits)?;
+                    raw_commits = edit_commits(
+                        &raw_commits,
+                        Some(&PlanContext {
+                            staging_type: &cfg.staging_type,
+                            diffs: &diffs,
+                        }),
+                    )?;
 
                     if raw_commits.is_empty() {
                         break;
@@ -326,10 +577,23 @@ fn run_commit(
 }
 
 pub fn edit_commits(
-    commits: &[CommitSchema]
+    commits: &[CommitSchema],
+    plan: Option<&PlanContext>,
 ) -> anyhow::Result<Vec<CommitSchema>> {
     let mut res = commits.to_vec();
 
+    let plan = plan.filter(|p| p.can_regroup());
+
+    // plan actions go before quit
+    let mut opts = EDIT_OPTS.to_vec();
+    if plan.is_some() {
+        let quit = opts
+            .pop()
+            .expect("edit opts has quit");
+        opts.extend(PLAN_EDIT_OPTS);
+        opts.push(quit);
+    }
+
     // for previous to work properly
     let mut i = 0;
     while i < res.len() {
@@ -348,7 +612,7 @@ pub fn edit_commits(
                 res.len(),
             );
 
-            let action = Menu::new(&msg, &EDIT_OPTS).render()?;
+            let action = Menu::new(&msg, &opts).render()?;
 
             match action {
                 EditActions::Next => {
@@ -448,13 +712,174 @@ pub fn edit_commits(
                     continue;
                 }
 
+                EditActions::Move
+                | EditActions::Split
+                | EditActions::Merge
+                | EditActions::Create => {
+                    let Some(plan) = plan else {
+                        continue;
+                    };
+
+                    res[i] = edited.to_owned();
+
+                    regroup(&mut res, i, &action, plan)?;
+
+                    edited = res[i].to_owned();
+
+                    continue;
+                }
+
                 EditActions::Quit => {
                     res[i] = edited.to_owned();
-                    return Ok(res);
+                    return Ok(drop_empty(res, plan));
                 }
             }
         }
     }
 
-    Ok(res)
+    Ok(drop_empty(res, plan))
+}
+
+/// the move/split/merge/create actions
+/// previews the commits that were touched
+fn regroup(
+    res: &mut Vec<CommitSchema>,
+    i: usize,
+    action: &EditActions,
+    plan: &PlanContext,
+) -> anyhow::Result<()> {
+    let strategy = plan.staging_type;
+
+    let affected = match action {
+        EditActions::Move => {
+            print::commits::preview_hunks(
+                &[(i, &res[i])],
+                strategy,
+                plan.diffs,
+            )?;
+
+            let raw = print::input::prompt(
+                "files or hunk ids to move (space separated): ",
+            )?;
+
+            let selectors: Vec<&str> = raw
+                .split_whitespace()
+                .collect();
+
+            if selectors.is_empty() {
+                return Ok(());
+            }
+
+            let raw = print::input::prompt(&format!(
+                "move to commit [1-{}]: ",
+                res.len()
+            ))?;
+
+            let to = match raw
+                .trim()
+                .parse::<usize>()
+            {
+                Ok(n) if n >= 1 && n <= res.len() && n != i + 1 => {
+                    n - 1
+                }
+                _ => {
+                    eprintln!("not a valid commit");
+                    return Ok(());
+                }
+            };
+
+            let moved = move_items(res, i, to, &selectors, strategy);
+
+            if moved.is_empty() {
+                eprintln!("nothing matched, nothing moved");
+                return Ok(());
+            }
+
+            vec![i, to]
+        }
+        EditActions::Split => {
+            print::commits::preview_hunks(
+                &[(i, &res[i])],
+                strategy,
+                plan.diffs,
+            )?;
+
+            let raw = print::input::prompt(
+                "files or hunk ids for the new commit (space separated): ",
+            )?;
+
+            let selectors: Vec<&str> = raw
+                .split_whitespace()
+                .collect();
+
+            if selectors.is_empty() {
+                return Ok(());
+            }
+
+            insert_empty_commit(res, i, strategy);
+
+            if move_items(res, i, i + 1, &selectors, strategy)
+                .is_empty()
+            {
+                res.remove(i + 1);
+                eprintln!("nothing matched, nothing split");
+                return Ok(());
+            }
+
+            vec![i, i + 1]
+        }
+        EditActions::Merge => {
+            if !merge_with_next(res, i, strategy) {
+                eprintln!("no next commit to join with");
+                return Ok(());
+            }
+
+            vec![i]
+        }
+        EditActions::Create => {
+            insert_empty_commit(res, i, strategy);
+
+            vec![i, i + 1]
+        }
+        _ => return Ok(()),
+    };
+
+    let affected: Vec<(usize, &CommitSchema)> = affected
+        .into_iter()
+        .map(|idx| (idx, &res[idx]))
+        .collect();
+
+    print::commits::preview_hunks(&affected, strategy, plan.diffs)
+}
+
+/// commits that ended up with nothing
+/// to stage after regrouping would fail
+/// to apply, so leave them out
+fn drop_empty(
+    res: Vec<CommitSchema>,
+    plan: Option<&PlanContext>,
+) -> Vec<CommitSchema> {
+    let Some(plan) = plan else {
+        return res;
+    };
+
+    let before = res.len();
+
+    let res: Vec<CommitSchema> = res
+        .into_iter()
+        .filter(|c| {
+            !c.items(plan.staging_type)
+                .is_empty()
+        })
+        .collect();
+
+    if res.len() < before {
+        println!(
+            "{}",
+            format!("dropped {} empty commit/s", before - res.len())
+                .yellow()
+        );
+    }
+
+    res
 }
@@ -0,0 +1,236 @@
+use git2::Oid;
+
+use crate::{
+    args::{ExplainArgs, GlobalArgs},
+    git::{
+        commit::get_commit_patches,
+        errors::GitError,
+        log::{GitLog, LogFilters, get_log, get_logs},
+    },
+    print::{json::ExplainOutput, spinner::SpinnerBuilder},
+    providers::{extract_from_provider, provider::ProviderKind},
+    requests::{
+        explain::{
+            commit_item, create_explain_merge_request,
+            create_explain_request,
+        },
+        tokens::{batch_by_tokens, truncate_to_tokens},
+    },
+    responses::explain::parse_to_explain_schema,
+    schema::{
+        SchemaSettings,
+        explain::{ExplainSchema, create_explain_schema},
+    },
+    state::State,
+};
+
+/// share of the budget a single file patch can
+/// take, so one generated file cant hide the rest
+const FILE_SHARE: u32 = 4;
+
+pub fn run(
+    args: &ExplainArgs,
+    global: &GlobalArgs,
+) -> anyhow::Result<()> {
+    let state = State::new(
+        global
+            .config
+            .as_deref(),
+        global,
+    )?;
+
+    let logs = target_logs(&state, &args.rev)?;
+
+    if logs.is_empty() {
+        return Err(GitError::Generic(format!(
+            "no commits in {}",
+            args.rev
+        ))
+        .into());
+    }
+
+    let per_file = (args.max_tokens / FILE_SHARE).max(1);
+
+    let mut items = Vec::new();
+
+    for log in &logs {
+        let patches: Vec<String> = get_commit_patches(
+            &state.git.repo,
+            Oid::from_str(&log.commit_hash)?,
+        )?
+        .into_iter()
+        .map(|(path, patch)| {
+            let mut patch = format!("File:{}\n{}", path, patch);
+            truncate_to_tokens(&mut patch, per_file);
+            patch
+        })
+        .collect();
+
+        let mut item = commit_item(log, &patches);
+        truncate_to_tokens(&mut item, args.max_tokens);
+
+        items.push(item);
+    }
+
+    let batches = batch_by_tokens(&items, args.max_tokens);
+
+    let schema_settings = if matches!(
+        state
+            .settings
+            .provider,
+        ProviderKind::OpenAI
+    ) {
+        SchemaSettings::default().additional_properties(false)
+    } else {
+        SchemaSettings::default()
+    };
+
+    let schema = create_explain_schema(schema_settings)?;
+
+    let handle = SpinnerBuilder::new()
+        .text(format!(
+            "Explaining {} commit/s in {} chunk/s",
+            logs.len(),
+            batches.len()
+        ))
+        .hidden(global.is_json())
+        .start();
+
+    let explanation =
+        match explain_batches(&state, &args.rev, &batches, &schema) {
+            Ok(e) => e,
+            Err(e) => {
+                handle.error();
+                return Err(e);
+            }
+        };
+
+    handle.done();
+
+    if global.is_json() {
+        return crate::print::json::emit(&ExplainOutput {
+            target: args.rev.to_owned(),
+            commits: logs
+                .into_iter()
+                .map(|l| l.commit_hash)
+                .collect(),
+            chunks: batches.len(),
+            intent: explanation.intent,
+            components: explanation.components,
+            behavior_changes: explanation.behavior_changes,
+            risky_areas: explanation.risky_areas,
+        });
+    }
+
+    crate::print::explain::explain(
+        &args.rev,
+        logs.len(),
+        &explanation,
+    )
+}
+
+/// a range is every commit in it oldest first,
+/// anything else is the single commit
+fn target_logs(
+    state: &State,
+    rev: &str,
+) -> anyhow::Result<Vec<GitLog>> {
+    let repo = &state.git.repo;
+
+    let spec = repo
+        .revparse(rev)
+        .map_err(|_| {
+            GitError::Generic(format!("unknown revision {}", rev))
+        })?;
+
+    let from = spec
+        .from()
+        .ok_or_else(|| {
+            GitError::Generic(format!("unknown revision {}", rev))
+        })?
+        .peel_to_commit()?
+        .id();
+
+    let Some(to) = spec.to() else {
+        return Ok(vec![get_log(&state.git, &from.to_string())?]);
+    };
+
+    let to = to
+        .peel_to_commit()?
+        .id();
+
+    let logs = get_logs(
+        &state.git,
+        false,
+        false,
+        0,
+        true,
+        Some(&from.to_string()),
+        Some(&to.to_string()),
+        &LogFilters::default(),
+    )?;
+
+    Ok(logs.git_logs)
+}
+
+/// one request if it fits, otherwise each chunk
+/// is explained on its own and then combined
+fn explain_batches(
+    state: &State,
+    target: &str,
+    batches: &[&[String]],
+    schema: &serde_json::Value,
+) -> anyhow::Result<ExplainSchema> {
+    let provider = &state
+        .settings
+        .provider;
+
+    if batches.len() <= 1 {
+        let items = batches
+            .first()
+            .copied()
+            .unwrap_or_default();
+
+        let req = create_explain_request(
+            &state.settings,
+            target,
+            items,
+            None,
+        );
+
+        let response =
+            extract_from_provider(provider, req, schema.to_owned())?;
+
+        return parse_to_explain_schema(response);
+    }
+
+    let mut partials = Vec::new();
+
+    for (idx, batch) in batches
+        .iter()
+        .enumerate()
+    {
+        let req = create_explain_request(
+            &state.settings,
+            target,
+            batch,
+            Some((idx, batches.len())),
+        );
+
+        let response =
+            extract_from_provider(provider, req, schema.to_owned())?;
+
+        partials.push(parse_to_explain_schema(response)?);
+    }
+
+    let req = create_explain_merge_request(
+        &state.settings,
+        target,
+        &partials,
+    );
+
+    let response =
+        extract_from_provider(provider, req, schema.to_owned())?;
+
+    parse_to_explain_schema(response)
+}
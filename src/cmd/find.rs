@@ -1,28 +1,69 @@
+use std::borrow::Cow;
+
 use serde_json::Value;
 
 use crate::{
-    args::{FindArgs, GlobalArgs},
-    git::{checkout::checkout_commit, log::get_logs},
-    print::{menu::Menu, spinner::SpinnerBuilder},
+    args::{FindArgs, GlobalArgs, RewordArgs, RewordScope},
+    git::{
+        checkout::checkout_commit,
+        commit::get_commit_patch,
+        log::{
+            GitLog, LogFilters, Logs, get_log, get_log_hashes,
+            get_logs, path_matches,
+        },
+    },
+    index::CommitIndex,
+    print::{
+        json::{FindMatch, FindOutput},
+        menu::Menu,
+        spinner::SpinnerBuilder,
+    },
     providers::{extract_from_provider, provider::ProviderKind},
-    requests::find::create_find_request,
-    responses::find::parse_to_find_schema,
-    schema::{SchemaSettings, find::create_find_schema},
+    requests::{
+        find::{create_find_candidates_request, create_find_request},
+        tokens::{batch_by_tokens, truncate_to_tokens},
+    },
+    responses::find::parse_to_find_response,
+    schema::{
+        SchemaSettings,
+        find::{Confidence, FindCommitSchema, create_find_schema},
+    },
     state::State,
+    utils::clipboard,
 };
 
+/// commits shown around a match
+/// with "show in log"
+const LOG_CONTEXT: usize = 3;
+
+/// candidates asked for per batch
+/// when map reducing the history
+const CANDIDATES_PER_BATCH: usize = 5;
+
+/// max candidates kept for the final
+/// ranking pass, these get their diffs
+const MAX_SHORTLIST: usize = 15;
+
 #[derive(Debug, Clone)]
 enum ResponseActions {
     Checkout,
-    ReQuery,
     Full,
+    CopyHash,
+    Reword,
+    Log,
+    Select,
+    ReQuery,
     Retry,
     Quit,
 }
 
-const RESPONSE_OPTS: [(ResponseActions, char, &str); 5] = [
+const RESPONSE_OPTS: [(ResponseActions, char, &str); 9] = [
     (ResponseActions::Checkout, 'c', "checkout the commit"),
     (ResponseActions::Full, 'f', "see full commit information"),
+    (ResponseActions::CopyHash, 'y', "copy the commit hash"),
+    (ResponseActions::Reword, 'w', "reword the commit"),
+    (ResponseActions::Log, 'l', "show the commit in the log"),
+    (ResponseActions::Select, 's', "select another match"),
     (ResponseActions::ReQuery, 'a', "retry with another query"),
     (ResponseActions::Retry, 'r', "retry with the same query"),
     (ResponseActions::Quit, 'q', "quit"),
@@ -34,18 +75,24 @@ pub fn run(
 ) -> anyhow::Result<()> {
     let state = State::new(None, global)?;
 
-    let count = args.number;
+    let index = open_index(&state, args, global)?;
 
-    let logs = get_logs(
-        &state.git,
-        args.files,
-        args.diffs,
-        count,
-        args.reverse,
-        args.from.as_deref(),
-        args.to.as_deref(),
-        args.since,
-    )?;
+    // with an index the candidates come from the whole
+    // history per query, only those logs get loaded
+    let logs = if index.is_some() {
+        Logs::default()
+    } else {
+        fetch_logs(&state, args)?
+    };
+
+    if index.is_none()
+        && logs
+            .git_logs
+            .is_empty()
+    {
+        println!("No commits to search through.");
+        return Ok(());
+    }
 
     let schema_settings = if matches!(
         state
@@ -60,126 +107,655 @@ pub fn run(
         SchemaSettings::default().allow_min_max_ints(true)
     };
 
-    let mut log_strs = Vec::new();
-
-    for (idx, log) in logs
+    let log_strs: Vec<String> = logs
         .git_logs
         .iter()
         .enumerate()
-    {
-        if args.files {
-            let files: String = log.files.join(",");
-
-            let item = if args.diffs {
-                let diffs = log
-                    .diffs
-                    .to_string();
-
-                format!(
-                    "CommitID:[{}]\nCommitMessage:{}\nFiles:{}\nDiffs:{}",
-                    idx, log.raw, files, diffs,
-                )
-            } else {
-                format!(
-                    "CommitID:[{}]\nCommitMessage:{}\nFiles:{}",
-                    idx, log.raw, files
-                )
-            };
-
-            log_strs.push(item);
-        } else {
-            let item = format!(
-                "CommitID:[{}]\nCommitMessage:{}",
-                idx, log.raw
-            );
+        .map(|(idx, log)| log_item(idx, log, args.files, args.diffs))
+        .collect();
 
-            log_strs.push(item);
-        }
+    if global.is_json() {
+        return run_json(
+            args,
+            &state,
+            &logs,
+            &log_strs,
+            index.as_ref(),
+            schema_settings,
+        );
     }
 
-    let count = if logs.git_logs.len() > count {
-        logs.git_logs.len() as u32
-    } else {
-        count as u32
-    };
-
-    let schema = create_find_schema(schema_settings, count)?;
+    let mut query = args
+        .query
+        .to_owned()
+        .unwrap_or_default();
 
-    let query = String::new();
-    let mut should_retry = false;
+    let mut should_retry = !query.is_empty();
 
     loop {
-        let q = if should_retry {
-            query.to_owned()
-        } else {
-            crate::print::input::prompt(
+        if !should_retry {
+            query = crate::print::input::prompt(
                 "What do you want to search for? ",
-            )?
+            )?;
+        }
+
+        if query
+            .trim()
+            .is_empty()
+        {
+            should_retry = false;
+            continue;
+        }
+
+        let selection = select_logs(
+            &state,
+            args,
+            &logs,
+            &log_strs,
+            index.as_ref(),
+            &query,
+        )?;
+
+        if selection
+            .logs
+            .is_empty()
+        {
+            println!("No matching commits found.");
+            should_retry = false;
+            continue;
+        }
+
+        let schema = create_find_schema(
+            schema_settings.to_owned(),
+            selection.logs.len() as u32,
+        )?;
+
+        let batches =
+            batch_by_tokens(&selection.strs, args.batch_tokens).len();
+
+        let text = if batches > 1 {
+            format!(
+                "Searching through {} commits in {} batches",
+                selection.logs.len(),
+                batches
+            )
+        } else {
+            "Searching through commits".to_owned()
         };
 
         let handle = SpinnerBuilder::new()
-            .text("Searching through commits")
+            .text(text)
             .start();
 
-        let req = create_find_request(&state.settings, &log_strs, &q);
-
-        /* if args.since.is_some() {
-            println!("{}", req);
-            break;
-        } */
-
-        let response: Value = match extract_from_provider(
-            &state
-                .settings
-                .provider,
-            req.to_owned(),
-            schema.to_owned(),
+        let results = match find_matches(
+            &state, args, &selection, &query, &schema,
         ) {
             Ok(r) => r,
             Err(e) => {
                 handle.error();
 
-                eprintln!("error from the provider:\n{:#}", e);
+                eprintln!("error while searching:\n{:#}", e);
 
                 break;
             }
         };
 
-        let result = parse_to_find_schema(response)?;
-
         handle.done();
 
-        let log = logs.git_logs[result.commit_id as usize].to_owned();
+        if results.is_empty() {
+            println!("No matching commits found.");
+            break;
+        }
 
-        let reasoning = result
-            .reasoning
-            .as_str();
+        let matches: Vec<(&GitLog, &FindCommitSchema)> = results
+            .iter()
+            .map(|r| (&selection.logs[r.commit_id as usize], r))
+            .collect();
 
-        crate::print::find::found_commit(
-            &log,
-            reasoning,
-            result.confidence,
-        )?;
+        crate::print::find::found_commits(&matches, args.reasoning)?;
 
-        match Menu::new("What do you want to do? ", &RESPONSE_OPTS)
-            .render()?
-        {
-            ResponseActions::Checkout => {
-                checkout_commit(&state.git.repo, &log.commit_hash)?;
-                break;
-            }
-            ResponseActions::ReQuery => {
-                should_retry = false;
-                continue;
-            }
-            ResponseActions::Full => todo!(),
-            ResponseActions::Retry => {
-                should_retry = true;
-                continue;
+        // select only makes sense with more than one match
+        let opts: Vec<_> = RESPONSE_OPTS
+            .iter()
+            .filter(|(a, ..)| {
+                matches.len() > 1
+                    || !matches!(a, ResponseActions::Select)
+            })
+            .cloned()
+            .collect();
+
+        let mut selected = 0;
+
+        loop {
+            let (log, _) = matches[selected];
+
+            let prompt = format!(
+                "[{}] What do you want to do? ",
+                &log.commit_hash[..7]
+            );
+
+            match Menu::new(&prompt, &opts).render()? {
+                ResponseActions::Checkout => {
+                    checkout_commit(
+                        &state.git.repo,
+                        &log.commit_hash,
+                    )?;
+                    return Ok(());
+                }
+                ResponseActions::Full => {
+                    let patch = get_commit_patch(
+                        &state.git.repo,
+                        &log.commit_hash,
+                    )?;
+
+                    crate::print::find::full_commit(log, &patch)?;
+                }
+                ResponseActions::CopyHash => {
+                    match clipboard::copy(&log.commit_hash) {
+                        Ok(_) => {
+                            println!("Copied {}", log.commit_hash)
+                        }
+                        Err(_) => println!(
+                            "No clipboard tool found, hash: {}",
+                            log.commit_hash
+                        ),
+                    }
+                }
+                ResponseActions::Reword => {
+                    let reword = RewordArgs {
+                        scope: RewordScope::Commit {
+                            hash: log
+                                .commit_hash
+                                .to_owned(),
+                        },
+                    };
+
+                    return crate::cmd::reword::run(&reword, global);
+                }
+                ResponseActions::Log => {
+                    // the selection may only be a subset
+                    match logs
+                        .git_logs
+                        .iter()
+                        .position(|l| {
+                            l.commit_hash == log.commit_hash
+                        }) {
+                        Some(idx) => crate::print::find::log_context(
+                            &logs.git_logs,
+                            idx,
+                            LOG_CONTEXT,
+                        )?,
+                        None => {
+                            // found through the index, only the
+                            // commits before it are cheap to get
+                            let older = get_logs(
+                                &state.git,
+                                false,
+                                false,
+                                LOG_CONTEXT + 1,
+                                false,
+                                None,
+                                Some(&log.commit_hash),
+                                &LogFilters::default(),
+                            )?;
+
+                            crate::print::find::log_context(
+                                &older.git_logs,
+                                0,
+                                LOG_CONTEXT,
+                            )?;
+                        }
+                    }
+                }
+                ResponseActions::Select => {
+                    let raw = crate::print::input::prompt(&format!(
+                        "select a match [1-{}]: ",
+                        matches.len()
+                    ))?;
+
+                    match raw
+                        .trim()
+                        .parse::<usize>()
+                    {
+                        Ok(n) if n >= 1 && n <= matches.len() => {
+                            selected = n - 1;
+                        }
+                        _ => eprintln!("not a valid match"),
+                    }
+                }
+                ResponseActions::ReQuery => {
+                    should_retry = false;
+                    break;
+                }
+                ResponseActions::Retry => {
+                    should_retry = true;
+                    break;
+                }
+                ResponseActions::Quit => return Ok(()),
             }
-            ResponseActions::Quit => break,
         }
     }
 
     Ok(())
 }
+
+/// single shot, no menu. the query is the
+/// positional arg, or read from stdin so
+/// editors can pipe it in
+fn run_json(
+    args: &FindArgs,
+    state: &State,
+    logs: &Logs,
+    log_strs: &[String],
+    index: Option<&CommitIndex>,
+    schema_settings: SchemaSettings,
+) -> anyhow::Result<()> {
+    // no prompt text, stdout should only
+    // ever contain the json output
+    let query = match args.query {
+        Some(ref q) => q.to_owned(),
+        None => crate::print::input::prompt("")?,
+    };
+
+    let selection =
+        select_logs(state, args, logs, log_strs, index, &query)?;
+
+    let results = if selection
+        .logs
+        .is_empty()
+    {
+        Vec::new()
+    } else {
+        let schema = create_find_schema(
+            schema_settings,
+            selection.logs.len() as u32,
+        )?;
+
+        find_matches(state, args, &selection, &query, &schema)?
+    };
+
+    let matches = results
+        .into_iter()
+        .map(|result| {
+            let log = &selection.logs[result.commit_id as usize];
+
+            FindMatch {
+                commit_hash: log
+                    .commit_hash
+                    .to_owned(),
+                message: log.raw.to_owned(),
+                author: log
+                    .author
+                    .to_owned(),
+                date: log.date.to_owned(),
+                files: log.files.to_owned(),
+                confidence: result.confidence,
+                reasoning: result.reasoning,
+            }
+        })
+        .collect();
+
+    crate::print::json::emit(&FindOutput { query, matches })
+}
+
+/// one commit as sent to the llm, the id
+/// is the index into the fetched logs
+fn log_item(
+    idx: usize,
+    log: &GitLog,
+    files: bool,
+    diffs: bool,
+) -> String {
+    if !files {
+        return format!(
+            "CommitID:[{}]\nCommitMessage:{}",
+            idx, log.raw
+        );
+    }
+
+    let file_list = log.files.join(",");
+
+    if diffs {
+        format!(
+            "CommitID:[{}]\nCommitMessage:{}\nFiles:{}\nDiffs:{}",
+            idx, log.raw, file_list, log.diffs,
+        )
+    } else {
+        format!(
+            "CommitID:[{}]\nCommitMessage:{}\nFiles:{}",
+            idx, log.raw, file_list
+        )
+    }
+}
+
+/// small histories go out in a single request
+/// anything over the budget is map reduced,
+/// each batch gives a few candidates, then
+/// the shortlist is ranked again with diffs
+fn search(
+    state: &State,
+    args: &FindArgs,
+    logs: &[GitLog],
+    log_strs: &[String],
+    query: &str,
+    schema: &Value,
+) -> anyhow::Result<Vec<FindCommitSchema>> {
+    let provider = &state
+        .settings
+        .provider;
+
+    let batches = batch_by_tokens(log_strs, args.batch_tokens);
+
+    if batches.len() <= 1 {
+        let req = create_find_request(
+            &state.settings,
+            log_strs,
+            query,
+            args.top,
+        );
+
+        let response =
+            extract_from_provider(provider, req, schema.to_owned())?;
+
+        return parse_to_find_response(
+            response,
+            logs.len(),
+            args.top,
+        );
+    }
+
+    let limit = CANDIDATES_PER_BATCH.max(args.top);
+
+    let mut shortlist: Vec<FindCommitSchema> = Vec::new();
+    let mut offset = 0;
+
+    for batch in batches {
+        let req = create_find_candidates_request(
+            &state.settings,
+            batch,
+            query,
+            limit,
+        );
+
+        let response =
+            extract_from_provider(provider, req, schema.to_owned())?;
+
+        let range = offset..offset + batch.len();
+
+        // ids from outside this batch are made up
+        shortlist.extend(
+            parse_to_find_response(response, logs.len(), limit)?
+                .into_iter()
+                .filter(|c| range.contains(&(c.commit_id as usize))),
+        );
+
+        offset += batch.len();
+    }
+
+    if shortlist.is_empty() {
+        return Ok(shortlist);
+    }
+
+    // stable, so earlier batches win ties
+    shortlist.sort_by_key(|c| c.confidence);
+    shortlist.truncate(MAX_SHORTLIST.max(args.top));
+
+    // split the budget between the candidates
+    // so one huge diff cant crowd out the rest
+    let per_commit = args.batch_tokens / shortlist.len() as u32;
+
+    let mut items = Vec::new();
+
+    for candidate in &shortlist {
+        let idx = candidate.commit_id as usize;
+
+        let full = get_log(&state.git, &logs[idx].commit_hash)?;
+
+        let mut item = log_item(idx, &full, true, true);
+        truncate_to_tokens(&mut item, per_commit);
+
+        items.push(item);
+    }
+
+    let req =
+        create_find_request(&state.settings, &items, query, args.top);
+
+    let response =
+        extract_from_provider(provider, req, schema.to_owned())?;
+
+    let ranked: Vec<FindCommitSchema> =
+        parse_to_find_response(response, logs.len(), args.top)?
+            .into_iter()
+            .filter(|r| {
+                shortlist
+                    .iter()
+                    .any(|c| c.commit_id == r.commit_id)
+            })
+            .collect();
+
+    // the final pass came back empty, the
+    // shortlist is still better than nothing
+    if ranked.is_empty() {
+        shortlist.truncate(args.top.max(1));
+        return Ok(shortlist);
+    }
+
+    Ok(ranked)
+}
+
+/// the commits actually sent for a query, either
+/// everything that was fetched or the best
+/// matches from the local index
+struct Selection<'a> {
+    logs: Cow<'a, [GitLog]>,
+    strs: Cow<'a, [String]>,
+
+    /// index scores, same order as logs
+    scores: Vec<f32>,
+}
+
+/// load and catch up the local index, if there
+/// is one. --offline builds it if its missing
+fn open_index(
+    state: &State,
+    args: &FindArgs,
+    global: &GlobalArgs,
+) -> anyhow::Result<Option<CommitIndex>> {
+    if args.no_index
+        || (!args.offline && !CommitIndex::exists(&state.git))
+    {
+        return Ok(None);
+    }
+
+    let handle = SpinnerBuilder::new()
+        .text("Updating the commit index")
+        .hidden(global.is_json())
+        .start();
+
+    let mut index = CommitIndex::load(&state.git)?;
+
+    // new commits wont have embeddings
+    // until the next `gai index`
+    let stats = index.update(&state.git)?;
+
+    if stats.added > 0 || stats.removed > 0 {
+        index.save(&state.git)?;
+    }
+
+    handle.stop_and_clear();
+
+    Ok(Some(index))
+}
+
+/// the commits -n and the filters pick,
+/// what gets searched without an index
+fn fetch_logs(
+    state: &State,
+    args: &FindArgs,
+) -> anyhow::Result<Logs> {
+    get_logs(
+        &state.git,
+        args.files,
+        args.diffs,
+        args.number,
+        args.reverse,
+        args.from.as_deref(),
+        args.to.as_deref(),
+        &LogFilters {
+            since: args.since,
+            author: args
+                .author
+                .as_deref(),
+            path: args.path.as_deref(),
+        },
+    )
+}
+
+/// without an index this is everything fetched. with
+/// one, every commit in range is ranked from the index
+/// and only the best ones are loaded and sent, -n
+/// doesnt apply since nothing is fetched up front
+fn select_logs<'a>(
+    state: &State,
+    args: &FindArgs,
+    logs: &'a Logs,
+    log_strs: &'a [String],
+    index: Option<&CommitIndex>,
+    query: &str,
+) -> anyhow::Result<Selection<'a>> {
+    let Some(index) = index else {
+        return Ok(Selection {
+            logs: Cow::Borrowed(&logs.git_logs),
+            strs: Cow::Borrowed(log_strs),
+            scores: Vec::new(),
+        });
+    };
+
+    let settings = &state.settings.index;
+
+    // embeddings are a bonus, keywords still work
+    let query_embedding = match index.embed_query(settings, query) {
+        Ok(e) => e,
+        Err(e) => {
+            eprintln!("skipping embeddings: {:#}", e);
+            None
+        }
+    };
+
+    let hashes = get_log_hashes(
+        &state.git,
+        args.reverse,
+        args.from.as_deref(),
+        args.to.as_deref(),
+        args.since,
+        args.author
+            .as_deref(),
+    )?;
+
+    // the index has the files, no need to diff
+    let hashes: Vec<&str> = hashes
+        .iter()
+        .map(|h| h.as_str())
+        .filter(|h| match args.path {
+            Some(ref path) => index
+                .entries
+                .get(*h)
+                .is_some_and(|e| {
+                    e.files
+                        .iter()
+                        .any(|f| path_matches(f, path))
+                }),
+            None => true,
+        })
+        .collect();
+
+    let ranked =
+        index.rank(&hashes, query, query_embedding.as_deref());
+
+    // nothing matched, let the llm look
+    // at the usual -n window instead
+    if ranked.is_empty() && !args.offline {
+        let logs = fetch_logs(state, args)?;
+        let strs = logs
+            .git_logs
+            .iter()
+            .enumerate()
+            .map(|(idx, log)| {
+                log_item(idx, log, args.files, args.diffs)
+            })
+            .collect();
+
+        return Ok(Selection {
+            logs: Cow::Owned(logs.git_logs),
+            strs: Cow::Owned(strs),
+            scores: Vec::new(),
+        });
+    }
+
+    let mut selected = Vec::new();
+    let mut scores = Vec::new();
+
+    for (pos, score) in ranked
+        .into_iter()
+        .take(
+            settings
+                .candidates
+                .max(args.top),
+        )
+    {
+        let mut log = get_log(&state.git, hashes[pos])?;
+
+        if !args.files {
+            log.files.clear();
+        }
+
+        selected.push(log);
+        scores.push(score);
+    }
+
+    let strs = selected
+        .iter()
+        .enumerate()
+        .map(|(idx, log)| log_item(idx, log, args.files, args.diffs))
+        .collect();
+
+    Ok(Selection {
+        logs: Cow::Owned(selected),
+        strs: Cow::Owned(strs),
+        scores,
+    })
+}
+
+/// offline just takes the best index scores
+/// there is no llm to judge them, so theyre
+/// never more than ambiguous
+fn find_matches(
+    state: &State,
+    args: &FindArgs,
+    selection: &Selection,
+    query: &str,
+    schema: &Value,
+) -> anyhow::Result<Vec<FindCommitSchema>> {
+    if !args.offline {
+        return search(
+            state,
+            args,
+            &selection.logs,
+            &selection.strs,
+            query,
+            schema,
+        );
+    }
+
+    Ok(selection
+        .scores
+        .iter()
+        .take(args.top.max(1))
+        .enumerate()
+        .map(|(idx, score)| FindCommitSchema {
+            reasoning: format!("index match, score {:.2}", score),
+            commit_id: idx as u32,
+            confidence: Confidence::Ambiguous,
+        })
+        .collect())
+}
@@ -0,0 +1,88 @@
+use crate::{
+    args::{GlobalArgs, IndexArgs},
+    index::CommitIndex,
+    print::{json::IndexOutput, spinner::SpinnerBuilder},
+    state::State,
+};
+
+pub fn run(
+    args: &IndexArgs,
+    global: &GlobalArgs,
+) -> anyhow::Result<()> {
+    let state = State::new(
+        global
+            .config
+            .as_deref(),
+        global,
+    )?;
+
+    let mut index = if args.rebuild {
+        CommitIndex::default()
+    } else {
+        CommitIndex::load(&state.git)?
+    };
+
+    let handle = SpinnerBuilder::new()
+        .text("Indexing commits")
+        .hidden(global.is_json())
+        .start();
+
+    let stats = match index.update(&state.git) {
+        Ok(s) => s,
+        Err(e) => {
+            handle.error();
+            return Err(e);
+        }
+    };
+
+    // save before embedding, so a flaky
+    // endpoint doesnt lose the new entries
+    index.save(&state.git)?;
+
+    let embedded = if args.no_embed {
+        0
+    } else {
+        match index.embed_missing(&state.settings.index) {
+            Ok(n) => n,
+            Err(e) => {
+                handle.error();
+                return Err(e.context("failed to embed commits"));
+            }
+        }
+    };
+
+    if embedded > 0 {
+        index.save(&state.git)?;
+    }
+
+    handle.done();
+
+    let path = CommitIndex::path(&state.git);
+
+    if global.is_json() {
+        return crate::print::json::emit(&IndexOutput {
+            path: path
+                .display()
+                .to_string(),
+            stats,
+            embedded,
+        });
+    }
+
+    println!(
+        "Indexed {} new commits, {} total",
+        stats.added, stats.total
+    );
+
+    if stats.removed > 0 {
+        println!("Dropped {} unreachable commits", stats.removed);
+    }
+
+    if embedded > 0 {
+        println!("Embedded {} commits", embedded);
+    }
+
+    println!("Index written to {}", path.display());
+
+    Ok(())
+}
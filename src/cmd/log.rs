@@ -1,6 +1,6 @@
 use crate::{
     args::{GlobalArgs, LogArgs},
-    git::log::get_logs,
+    git::log::{LogFilters, get_logs},
     state::State,
 };
 
@@ -22,7 +22,7 @@ pub fn run(
         args.reverse,
         None,
         None,
-        None,
+        &LogFilters::default(),
     )?;
 
     Ok(())
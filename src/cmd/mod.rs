@@ -1,7 +1,22 @@
+pub mod absorb;
+pub mod apply;
 pub mod auth;
+pub mod backups;
+pub mod blame;
+pub mod branch;
+pub mod changelog;
 pub mod commit;
+pub mod explain;
 pub mod find;
+pub mod index;
 pub mod log;
+pub mod pr;
 pub mod rebase;
+pub mod release;
+pub mod resolve;
+pub mod review;
 pub mod reword;
+pub mod split;
 pub mod status;
+pub mod sync;
+pub mod todo;
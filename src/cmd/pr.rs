@@ -0,0 +1,308 @@
+use std::path::{Path, PathBuf};
+
+use git2::Repository;
+
+use crate::{
+    args::{GlobalArgs, PrArgs},
+    git::{
+        branch::find_divergence_branch,
+        commit::get_range_patches,
+        errors::GitError,
+        log::{LogFilters, get_logs},
+        utils::get_head_repo,
+    },
+    print::{json::PrOutput, spinner::SpinnerBuilder},
+    providers::{extract_from_provider, provider::ProviderKind},
+    requests::{
+        pr::create_pr_request,
+        tokens::{estimate_token_count, truncate_to_tokens},
+    },
+    responses::pr::{
+        parse_to_pr_schema, render_pr_body, template_headings,
+    },
+    schema::{SchemaSettings, pr::create_pr_schema},
+    state::State,
+};
+
+/// where github looks for a single template
+const TEMPLATE_PATHS: [&str; 6] = [
+    ".github/pull_request_template.md",
+    ".github/PULL_REQUEST_TEMPLATE.md",
+    "pull_request_template.md",
+    "PULL_REQUEST_TEMPLATE.md",
+    "docs/pull_request_template.md",
+    "docs/PULL_REQUEST_TEMPLATE.md",
+];
+
+/// smallest slice of the budget a file gets
+const MIN_FILE_TOKENS: u32 = 200;
+
+/// the marker truncate_to_tokens adds past the cut
+const TRUNCATED_TOKENS: u32 = 5;
+
+pub fn run(
+    args: &PrArgs,
+    global: &GlobalArgs,
+) -> anyhow::Result<()> {
+    let state = State::new(
+        global
+            .config
+            .as_deref(),
+        global,
+    )?;
+
+    let repo = &state.git.repo;
+
+    let base = match args.base {
+        Some(ref base) => base.to_owned(),
+        None => default_base(repo)?,
+    };
+
+    let head = get_head_repo(repo)?;
+    let merge_base = find_divergence_branch(repo, &base)?;
+
+    if merge_base == head {
+        return Err(GitError::Generic(format!(
+            "no commits ahead of {}",
+            base
+        ))
+        .into());
+    }
+
+    // oldest first, reads like the branch was built
+    let logs = get_logs(
+        &state.git,
+        false,
+        false,
+        0,
+        true,
+        Some(&merge_base.to_string()),
+        Some(&head.to_string()),
+        &LogFilters::default(),
+    )?;
+
+    let patches = get_range_patches(repo, merge_base, head)?;
+
+    let patches = budget_patches(patches, args.max_diff_tokens);
+
+    let template_path = if args.no_template {
+        None
+    } else {
+        match args.template {
+            Some(ref path) => Some(path.to_owned()),
+            None => find_template(&state.git.workdir),
+        }
+    };
+
+    let template = template_path
+        .as_ref()
+        .map(|p| {
+            std::fs::read_to_string(
+                state
+                    .git
+                    .workdir
+                    .join(p),
+            )
+        })
+        .transpose()?;
+
+    let headings = template
+        .as_deref()
+        .map(template_headings)
+        .unwrap_or_default();
+
+    let schema_settings = if matches!(
+        state
+            .settings
+            .provider,
+        ProviderKind::OpenAI
+    ) {
+        SchemaSettings::default().additional_properties(false)
+    } else {
+        SchemaSettings::default()
+    };
+
+    let schema = create_pr_schema(schema_settings, &headings)?;
+
+    let req = create_pr_request(
+        &state.settings,
+        &base,
+        &logs.git_logs,
+        &patches,
+        template.as_deref(),
+    );
+
+    let handle = SpinnerBuilder::new()
+        .text(format!(
+            "Describing {} commits against {}",
+            logs.git_logs.len(),
+            base
+        ))
+        .hidden(global.is_json())
+        .start();
+
+    let response = match extract_from_provider(
+        &state
+            .settings
+            .provider,
+        req,
+        schema,
+    ) {
+        Ok(r) => r,
+        Err(e) => {
+            handle.error();
+            return Err(e.into());
+        }
+    };
+
+    let pr = parse_to_pr_schema(response, &headings)?;
+
+    handle.done();
+
+    let body = render_pr_body(&pr, template.as_deref());
+
+    if let Some(ref out) = args.out {
+        std::fs::write(
+            out,
+            format!("{}\n\n{}", pr.title.trim(), body),
+        )?;
+    }
+
+    if global.is_json() {
+        return crate::print::json::emit(&PrOutput {
+            base,
+            title: pr
+                .title
+                .trim()
+                .to_owned(),
+            body,
+            template: template_path.map(|p| {
+                p.display()
+                    .to_string()
+            }),
+            commits: logs
+                .git_logs
+                .into_iter()
+                .map(|l| l.commit_hash)
+                .collect(),
+        });
+    }
+
+    match args.out {
+        Some(ref out) => {
+            println!("Wrote pull request to {}", out.display())
+        }
+        None => println!("{}\n\n{}", pr.title.trim(), body),
+    }
+
+    Ok(())
+}
+
+/// origin/HEAD if its set, otherwise
+/// the usual default branch names
+fn default_base(repo: &Repository) -> anyhow::Result<String> {
+    if let Ok(reference) =
+        repo.find_reference("refs/remotes/origin/HEAD")
+        && let Ok(resolved) = reference.resolve()
+        && let Some(name) = resolved.shorthand()
+    {
+        return Ok(name.to_owned());
+    }
+
+    for name in ["main", "master", "origin/main", "origin/master"] {
+        if repo
+            .revparse_single(name)
+            .is_ok()
+        {
+            return Ok(name.to_owned());
+        }
+    }
+
+    Err(GitError::Generic(
+        "could not find a base branch, pass one with --base"
+            .to_owned(),
+    )
+    .into())
+}
+
+fn find_template(workdir: &Path) -> Option<PathBuf> {
+    TEMPLATE_PATHS
+        .iter()
+        .map(PathBuf::from)
+        .find(|p| {
+            workdir
+                .join(p)
+                .is_file()
+        })
+}
+
+/// splits max_tokens over the patches, each file
+/// gets at least MIN_FILE_TOKENS so past that many
+/// files the rest are only counted
+fn budget_patches(
+    patches: Vec<(String, String)>,
+    max_tokens: u32,
+) -> Vec<String> {
+    let max_files = (max_tokens / MIN_FILE_TOKENS).max(1) as usize;
+    let kept = patches
+        .len()
+        .min(max_files);
+
+    let omitted = patches.len() - kept;
+    let note = (omitted > 0)
+        .then(|| format!("{} more files omitted", omitted));
+
+    let budget = max_tokens.saturating_sub(
+        note.as_deref()
+            .map(estimate_token_count)
+            .unwrap_or_default(),
+    );
+    let per_file = (budget / kept.max(1) as u32)
+        .saturating_sub(TRUNCATED_TOKENS);
+
+    let mut res: Vec<String> = patches
+        .into_iter()
+        .take(kept)
+        .map(|(path, patch)| {
+            let mut patch = format!("File:{}\n{}", path, patch);
+            truncate_to_tokens(&mut patch, per_file);
+            patch
+        })
+        .collect();
+
+    res.extend(note);
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_patches() {
+        let patches: Vec<(String, String)> = (0..30)
+            .map(|i| (format!("f{i}.rs"), "+line\n".repeat(500)))
+            .collect();
+
+        let res = budget_patches(patches, 1000);
+
+        // 1000 / 200, so 5 files and the count
+        assert_eq!(res.len(), 6);
+        assert_eq!(res[5], "25 more files omitted");
+
+        let total: u32 = res
+            .iter()
+            .map(|p| estimate_token_count(p))
+            .sum();
+
+        assert!(total <= 1000);
+
+        let few = budget_patches(
+            vec![("a.rs".to_owned(), "+a\n".to_owned())],
+            1000,
+        );
+
+        assert_eq!(few, vec!["File:a.rs\n+a\n"]);
+    }
+}
@@ -1,4 +1,6 @@
-use git2::Oid;
+use std::fs;
+
+use git2::{Oid, Repository};
 use owo_colors::{OwoColorize, Style};
 use serde_json::Value;
 
@@ -7,16 +9,25 @@ use crate::{
     cmd::commit::{RESPONSE_OPTS, ResponseActions},
     git::{
         Diffs, GitRepo, StagingStrategy,
-        checkout::force_checkout_head,
         commit::{GitCommit, apply_commits},
         diffs::{FileDiff, get_diffs_from_commits},
-        log::{Logs, get_logs},
+        errors::GitError,
+        log::{LogFilters, Logs, get_logs},
         rebase::{
-            cherry_pick_commits, cherry_pick_reword,
-            cherry_pick_single, squash_to_head,
+            find_replay_conflict, replay_onto_head,
+            unflattenable_merges,
+        },
+        reset::{
+            reset_repo_hard, reset_to_parent_hard,
+            reset_to_parent_mixed,
+        },
+        rewrite::{
+            RewriteState, Stop, abort_rewrite, clear_rewrite,
+            continue_rewrite, ensure_no_rewrite, load_rewrite,
+            run_steps, skip_step,
         },
-        reset::{reset_repo_hard, reset_repo_mixed},
         status::{get_commit_stats, is_workdir_clean},
+        todo::{TodoCommand, TodoStep, format_todo, parse_todo},
         utils::get_head_repo,
     },
     print::{
@@ -29,7 +40,9 @@ use crate::{
     providers::{extract_from_provider, provider::ProviderKind},
     requests::rebase::create_rebase_request,
     responses::{
-        commit::process_commit, rebase::parse_from_rebase_schema,
+        commit::process_commit,
+        rebase::parse_from_rebase_schema,
+        rebase_plan::{plan_from_todo, plan_to_todo},
     },
     schema::{
         SchemaSettings,
@@ -84,6 +97,18 @@ pub fn run(
     // to rebase on top as commits
     // or merge commits?
 
+    if args.resume || args.abort || args.skip {
+        return resume(args, global);
+    }
+
+    let Some(ref scope) = args.scope else {
+        return Err(GitError::Generic(
+            "nothing to rebase, pass a scope, ex. `gai rebase last 3`"
+                .to_owned(),
+        )
+        .into());
+    };
+
     let mut state = State::new(
         global
             .config
@@ -91,6 +116,8 @@ pub fn run(
         global,
     )?;
 
+    ensure_no_rewrite(&state.git.repo)?;
+
     if !is_workdir_clean(&state.git.repo)? {
         return Err(anyhow::anyhow!(
             "Workdir is NOT clean, please save your changes"
@@ -99,14 +126,20 @@ pub fn run(
 
     //println!("{:#?}", state.settings);
 
-    print::status::provider_info(
-        &state
-            .settings
-            .provider,
-        &state
-            .settings
-            .providers,
-    )?;
+    // a todo from disk needs no provider
+    if args
+        .todo_in
+        .is_none()
+    {
+        print::status::provider_info(
+            &state
+                .settings
+                .provider,
+            &state
+                .settings
+                .providers,
+        )?;
+    }
 
     // save the original point, in case
     // we need to revert back hard
@@ -120,17 +153,23 @@ pub fn run(
         .text("Gathering logs")
         .start();
 
-    let diverge_from = match &args.scope {
+    let diverge_from = match scope {
         RebaseScope::Branch { name } => {
-            crate::git::branch::find_divergence_branch(
+            Some(crate::git::branch::find_divergence_branch(
                 &state.git.repo,
                 name,
-            )?
+            )?)
         }
         RebaseScope::Last { count } => {
             let logs = crate::git::log::get_logs(
-                &state.git, false, false, *count, false, None, None,
+                &state.git,
+                false,
+                false,
+                *count,
+                false,
                 None,
+                None,
+                &LogFilters::default(),
             )?;
 
             if *count > logs.git_logs.len() {
@@ -195,30 +234,64 @@ pub fn run(
         0,
         // should be oldest first
         true,
-        Some(&diverge_from.to_string()),
+        diverge_from
+            .map(|oid| oid.to_string())
+            .as_deref(),
         to_oid.as_deref(),
-        None,
+        &LogFilters::default(),
     )?;
 
     //println!("{:#?}", logs);
 
-    let mut log_strs = Vec::new();
+    check_merges(
+        &state.git.repo,
+        &logs,
+        diverge_from,
+        trailing_commits.as_deref(),
+        args.plan
+            || args
+                .todo_in
+                .is_some(),
+    )?;
 
-    for (idx, log) in logs
-        .git_logs
-        .iter()
-        .enumerate()
-    {
-        let files: String = log.files.join(",");
+    if let Some(ref path) = args.todo_in {
+        handle.done();
 
-        let item = format!(
-            "CommitID:[{}]\nCommitMessage:{}\nFiles:{}",
-            idx, log.raw, files
-        );
+        let steps = parse_todo(&fs::read_to_string(path)?)?;
+
+        let ops = plan_from_todo(
+            &steps,
+            &logs.git_logs,
+            state
+                .settings
+                .rebase
+                .allow_drop,
+        )?;
 
-        log_strs.push(item);
+        print_rebase_plan(&ops)?;
+
+        let opts = [
+            (PlanActions::Apply, 'y', "apply plan op/s"),
+            (PlanActions::Quit, 'q', "quit"),
+        ];
+
+        if let PlanActions::Apply =
+            Menu::new("What do you want to do?", &opts).render()?
+        {
+            run_plan(
+                &state.git,
+                &ops,
+                &logs,
+                diverge_from,
+                trailing_commits.as_deref(),
+            )?;
+        }
+
+        return Ok(());
     }
 
+    let log_strs = plan_log_strs(&logs);
+
     let to = to_oid
         .as_deref()
         .map(Oid::from_str)
@@ -266,37 +339,34 @@ pub fn run(
 
                     match selected {
                         PlanActions::Apply => {
-                            // reset to the from commit
-                            // since, compared to the
-                            // commit generation apply()
-                            // im not using the diffs/changes
-                            // but instead the existing commits
-                            reset_repo_hard(
-                                &state.git.repo,
-                                &diverge_from.to_string(),
-                            )?;
+                            if let Some(ref path) = args.todo_out {
+                                fs::write(
+                                    path,
+                                    format_todo(&plan_to_todo(
+                                        &ops,
+                                        &logs.git_logs,
+                                    )),
+                                )?;
+
+                                println!(
+                                    "{}",
+                                    format!(
+                                        "Wrote the plan to {}",
+                                        path.display()
+                                    )
+                                    .dimmed()
+                                );
+
+                                return Ok(());
+                            }
 
-                            match apply_plan(
+                            return run_plan(
                                 &state.git,
                                 &ops,
                                 &logs,
+                                diverge_from,
                                 trailing_commits.as_deref(),
-                            ) {
-                                Ok(_) => return Ok(()),
-                                Err(e) => {
-                                    eprintln!(
-                                        "couldnt apply plan: {}\nresetting",
-                                        e
-                                    );
-
-                                    reset_repo_hard(
-                                        &state.git.repo,
-                                        &original_head,
-                                    )?;
-
-                                    return Err(e);
-                                }
-                            }
+                            );
                         }
                         PlanActions::Regen => {
                             continue;
@@ -306,7 +376,11 @@ pub fn run(
                 }
 
                 Err(e) => {
-                    reset_repo_hard(&state.git.repo, &original_head)?;
+                    reset_repo_hard(
+                        &state.git.repo,
+                        &original_head,
+                        &abort_message(&original_head),
+                    )?;
                     eprintln!("error when gerating plan:\n{e}");
                     return Err(e);
                 }
@@ -319,7 +393,11 @@ pub fn run(
         &log_strs,
         &state
             .diffs
-            .to_string(),
+            .to_request_string(
+                &state
+                    .settings
+                    .staging_type,
+            ),
     );
 
     //println!("{request}");
@@ -333,6 +411,9 @@ pub fn run(
         &state
             .diffs
             .as_hunks(),
+        &state
+            .diffs
+            .as_line_ids(),
     )?;
 
     //println!("{:#}", schema);
@@ -399,15 +480,28 @@ pub fn run(
                         .map(|c| process_commit(c, &state.settings))
                         .collect();
 
+                    // rebuilt from diffs, this cant be continued
+                    // but --abort can restore after a crash
+                    RewriteState::begin(
+                        &state.git.repo,
+                        "rebase",
+                        Vec::new(),
+                    )?;
+
                     if let Some(ref to) = to_oid {
                         // reset hard to the TO commit
-                        reset_repo_hard(&state.git.repo, to)?;
+                        reset_repo_hard(
+                            &state.git.repo,
+                            to,
+                            "gai rebase (start): checkout range end",
+                        )?;
                     }
 
                     // do a mixed reset to the FROM commit
-                    reset_repo_mixed(
+                    reset_to_parent_mixed(
                         &state.git.repo,
-                        &diverge_from.to_string(),
+                        diverge_from,
+                        "gai rebase (start): uncommit the range",
                     )?;
 
                     let oids = match apply(
@@ -421,13 +515,18 @@ pub fn run(
                         trailing_commits.as_deref(),
                     ) {
                         // done
-                        Ok(oids) => oids,
+                        Ok(oids) => {
+                            clear_rewrite(&state.git.repo)?;
+                            oids
+                        }
                         Err(e) => {
                             // ideally restore on errors
                             reset_repo_hard(
                                 &state.git.repo,
                                 &original_head,
+                                &abort_message(&original_head),
                             )?;
+                            clear_rewrite(&state.git.repo)?;
                             return Err(e);
                         }
                     };
@@ -466,6 +565,12 @@ pub fn run(
                 ResponseActions::Edit => {
                     raw_commits = crate::cmd::commit::edit_commits(
                         &raw_commits,
+                        Some(&crate::cmd::commit::PlanContext {
+                            staging_type: &state
+                                .settings
+                                .staging_type,
+                            diffs: &state.diffs,
+                        }),
                     )?;
 
                     if raw_commits.is_empty() {
@@ -500,69 +605,186 @@ pub fn run(
     Ok(())
 }
 
-fn apply_plan(
+/// commit list sent along with a plan request,
+/// the ids are what commit_index points at
+pub fn plan_log_strs(logs: &Logs) -> Vec<String> {
+    logs.git_logs
+        .iter()
+        .enumerate()
+        .map(|(idx, log)| {
+            format!(
+                "CommitID:[{}]\nCommitMessage:{}\nFiles:{}",
+                idx,
+                log.raw,
+                log.files.join(",")
+            )
+        })
+        .collect()
+}
+
+/// dry runs the plan, then resets to the base and runs
+/// it as a rewrite that can stop at an edit or conflict
+/// and be continued. any other error restores the
+/// original head
+fn run_plan(
     git: &GitRepo,
     ops: &[PlanOperationSchema],
     logs: &Logs,
+    diverge_from: Option<Oid>,
     trailing: Option<&[String]>,
 ) -> anyhow::Result<()> {
-    // reordering commits by index
-    // in case the LLM decides to do so
-    // im keeping this off limits for now
-    // since, idk how im gonna handle the conflicts
-    // ideally, with drop as well
-    // or should it be an option along with Drop?
-    let mut ops = ops.to_vec();
-    ops.sort_by_key(|op| op.commit_index);
-
-    for op in ops {
-        let commit =
-            &logs.git_logs[op.commit_index as usize].commit_hash;
-
-        match op.operation {
-            PlanOperationKind::Pick => {
-                cherry_pick_single(&git.repo, commit)?;
-            }
-            PlanOperationKind::Squash => {
-                let message = if let Some(ref msg) = op.new_message {
-                    msg
-                } else {
-                    return Err(anyhow::anyhow!(
-                        "no message in schema for a squash, not good, bailing"
-                    ));
-                };
+    check_plan_conflicts(
+        &git.repo,
+        ops,
+        logs,
+        diverge_from,
+        trailing,
+    )?;
 
-                squash_to_head(&git.repo, commit, message)?;
-            }
-            PlanOperationKind::Reword => {
-                let message = if let Some(ref msg) = op.new_message {
-                    msg
-                } else {
-                    return Err(anyhow::anyhow!(
-                        "no message in schema for a reword, not good, bailing"
-                    ));
-                };
+    let mut steps: Vec<TodoStep> = plan_to_todo(ops, &logs.git_logs)
+        .into_iter()
+        .zip(ops)
+        .map(|(mut step, op)| {
+            step.commit = logs.git_logs[op.commit_index as usize]
+                .commit_hash
+                .to_owned();
+            step
+        })
+        .collect();
+
+    // trailing commits go back on top as they are
+    steps.extend(
+        trailing
+            .unwrap_or_default()
+            .iter()
+            .map(|hash| TodoStep {
+                command: TodoCommand::Pick,
+                commit: hash.to_owned(),
+                summary: String::new(),
+                message: None,
+            }),
+    );
 
-                cherry_pick_reword(&git.repo, commit, message)?;
-            }
-            PlanOperationKind::Drop => {
-                // do nothing
-            }
+    let mut rewrite =
+        RewriteState::begin(&git.repo, "rebase", steps)?;
+
+    // saved before the reset, so a crash right
+    // after it still continues from the base
+    rewrite.head = diverge_from.map(|oid| oid.to_string());
+    rewrite.save(&git.repo)?;
+
+    // reset to the from commit
+    // since, compared to the
+    // commit generation apply()
+    // im not using the diffs/changes
+    // but instead the existing commits
+    reset_to_parent_hard(
+        &git.repo,
+        diverge_from,
+        "gai rebase (start): checkout the base",
+    )?;
+
+    match run_steps(&git.repo, &mut rewrite) {
+        Ok(stop) => print_stop(&rewrite, stop),
+        Err(e) => {
+            eprintln!("couldnt apply plan: {}\nresetting", e);
+
+            abort_rewrite(&git.repo, &rewrite)?;
+
+            Err(e)
         }
     }
+}
+
+/// --continue, --skip and --abort, for a rewrite that
+/// stopped or got interrupted, even from another process
+fn resume(
+    args: &RebaseArgs,
+    global: &GlobalArgs,
+) -> anyhow::Result<()> {
+    if args.scope.is_some() {
+        return Err(GitError::Generic(
+            "--continue, --skip and --abort dont take a scope"
+                .to_owned(),
+        )
+        .into());
+    }
+
+    let state = State::new(
+        global
+            .config
+            .as_deref(),
+        global,
+    )?;
+
+    let repo = &state.git.repo;
+
+    let Some(mut rewrite) = load_rewrite(repo)? else {
+        return Err(GitError::Generic(
+            "no gai rewrite in progress".to_owned(),
+        )
+        .into());
+    };
+
+    if args.abort {
+        abort_rewrite(repo, &rewrite)?;
 
-    if let Some(trails) = trailing {
-        cherry_pick_commits(&git.repo, trails)?;
+        println!(
+            "{}",
+            format!("Restored {}", &rewrite.orig_head[..7]).dimmed()
+        );
+
+        return Ok(());
     }
 
-    // sync it
-    force_checkout_head(&git.repo)?;
+    let stop = if args.skip {
+        skip_step(repo, &mut rewrite)?
+    } else {
+        continue_rewrite(repo, &mut rewrite)?
+    };
+
+    print_stop(&rewrite, stop)
+}
+
+/// what a stopped rewrite is waiting for
+fn print_stop(
+    rewrite: &RewriteState,
+    stop: Option<Stop>,
+) -> anyhow::Result<()> {
+    let (Some(stop), Some(step)) = (stop, rewrite.current()) else {
+        println!("{}", "Rebase done".dimmed());
+        return Ok(());
+    };
+
+    match stop {
+        Stop::Edit => println!(
+            "{}",
+            format!(
+                "Stopped at {} for editing, amend it then run `gai rebase --continue`",
+                &step.commit[..7]
+            )
+            .yellow()
+            .bold()
+        ),
+        Stop::Conflict => println!(
+            "{}\n{}",
+            format!("Could not apply {}", &step.commit[..7])
+                .red()
+                .bold(),
+            concat!(
+                "Resolve the conflicts, stage them and run `gai rebase --continue`.\n",
+                "`gai rebase --skip` drops the commit, `gai rebase --abort` goes back"
+            )
+            .dimmed()
+        ),
+    }
 
     Ok(())
 }
 
 // TODO: this needs to BE RIPPED TO SHREDS
-fn apply(
+// also used by gai split
+pub fn apply(
     git: &GitRepo,
     git_commits: &[GitCommit],
     og_file_diffs: &mut Vec<FileDiff>,
@@ -601,9 +823,10 @@ fn apply(
                     ));
                 }
 
-                // reapply commits
-                if let Some(trails) = trailing {
-                    cherry_pick_commits(&git.repo, trails)?;
+                // reapply commits, the tree is the same
+                // so merges after the range keep their shape
+                if let Some(tip) = trailing.and_then(|t| t.last()) {
+                    replay_onto_head(&git.repo, to, tip)?;
                 }
             }
 
@@ -615,6 +838,108 @@ fn apply(
     }
 }
 
+/// gai rebase rebuilds the range from one diff, a merge
+/// in it is only fine when what it merged is already in
+/// the base, otherwise those changes end up in the new
+/// commits. a plan cherry picks every commit, including
+/// the trailing ones, so it cant take merges at all
+fn check_merges(
+    repo: &Repository,
+    logs: &Logs,
+    base: Option<Oid>,
+    trailing: Option<&[String]>,
+    plan: bool,
+) -> anyhow::Result<()> {
+    let hashes: Vec<String> = logs
+        .git_logs
+        .iter()
+        .map(|l| {
+            l.commit_hash
+                .to_owned()
+        })
+        .collect();
+
+    if !plan {
+        if let Some(merge) =
+            unflattenable_merges(repo, &hashes, base)?.first()
+        {
+            return Err(GitError::Generic(format!(
+                concat!(
+                    "{} merges commits that are not in the rebase base, ",
+                    "rebuilding the range from its diff would fold them ",
+                    "into the new commits. Rebase only the commits after ",
+                    "the merge, or from the merged branch with ",
+                    "`gai rebase branch <name>`"
+                ),
+                &merge[..7]
+            ))
+            .into());
+        }
+
+        return Ok(());
+    }
+
+    for hash in hashes
+        .iter()
+        .chain(trailing.unwrap_or_default())
+    {
+        if repo
+            .find_commit(Oid::from_str(hash)?)?
+            .parent_count()
+            > 1
+        {
+            return Err(GitError::Generic(format!(
+                concat!(
+                    "{} is a merge commit, a rebase plan can only ",
+                    "replay regular commits. Rebase only the commits ",
+                    "after the merge"
+                ),
+                &hash[..7]
+            ))
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+/// dry run of the plan before anything is reset,
+/// drops are left out and folds follow their
+/// target, then the trailing commits on top
+fn check_plan_conflicts(
+    repo: &Repository,
+    ops: &[PlanOperationSchema],
+    logs: &Logs,
+    base: Option<Oid>,
+    trailing: Option<&[String]>,
+) -> anyhow::Result<()> {
+    let commits: Vec<String> = ops
+        .iter()
+        .filter(|op| op.operation != PlanOperationKind::Drop)
+        .map(|op| {
+            logs.git_logs[op.commit_index as usize]
+                .commit_hash
+                .to_owned()
+        })
+        .chain(
+            trailing
+                .unwrap_or_default()
+                .iter()
+                .cloned(),
+        )
+        .collect();
+
+    if let Some(hash) = find_replay_conflict(repo, base, &commits)? {
+        return Err(GitError::Generic(format!(
+            "the plan conflicts when applying {}, nothing was changed. Regenerate it or keep that commit in its place",
+            &hash[..7]
+        ))
+        .into());
+    }
+
+    Ok(())
+}
+
 /// a gai rebase --plan will operate significantly
 /// different than the regular gai rebase.
 /// one: it will not generate commits, instead
@@ -623,7 +948,7 @@ fn apply(
 /// HANDLE ALOT differently, in terms of what can be rejected,
 /// as well as the flow within git itself
 /// WTF
-fn gen_plan(
+pub fn gen_plan(
     settings: &Settings,
     diffs: &Diffs,
     logs: &[String],
@@ -644,7 +969,9 @@ fn gen_plan(
         crate::schema::rebase_plan::create_rebase_plan_schema(
             schema_settings.to_owned(),
             logs.len(),
-            false,
+            settings
+                .rebase
+                .allow_drop,
         )?;
 
     let response: Value = match extract_from_provider(
@@ -661,6 +988,10 @@ fn gen_plan(
     let raw_ops =
         crate::responses::rebase_plan::parse_from_rebase_plan_schema(
             response,
+            logs.len(),
+            settings
+                .rebase
+                .allow_drop,
         )?;
     //println!("{:#?}", raw_ops);
 
@@ -676,7 +1007,7 @@ fn gen_plan(
 // in the future
 // so that the print:: module
 // stays free
-fn print_rebase_plan(
+pub fn print_rebase_plan(
     raw_ops: &[PlanOperationSchema]
 ) -> anyhow::Result<()> {
     let mut items = Vec::new();
@@ -687,13 +1018,19 @@ fn print_rebase_plan(
     {
         let mut children = Vec::new();
 
-        let reason_item = TreeItem::new_leaf(
-            format!("reason_{i}"),
-            format!("Why? {}", op.reasoning),
-        )
-        .style(Style::new().dimmed());
+        // plans from a todo have no reasoning
+        if !op
+            .reasoning
+            .is_empty()
+        {
+            let reason_item = TreeItem::new_leaf(
+                format!("reason_{i}"),
+                format!("Why? {}", op.reasoning),
+            )
+            .style(Style::new().dimmed());
 
-        children.push(reason_item);
+            children.push(reason_item);
+        }
 
         if let Some(ref msg) = op.new_message {
             let truncated = if msg.len() > 72 {
@@ -714,6 +1051,8 @@ fn print_rebase_plan(
             PlanOperationKind::Pick => Style::new().green(),
             PlanOperationKind::Reword => Style::new().yellow(),
             PlanOperationKind::Squash => Style::new().magenta(),
+            PlanOperationKind::Fixup => Style::new().magenta(),
+            PlanOperationKind::Edit => Style::new().cyan(),
             PlanOperationKind::Drop => Style::new().red(),
         };
 
@@ -731,8 +1070,18 @@ fn print_rebase_plan(
 
         let display = {
             let preview = match (&op.operation, &op.new_message) {
-                (PlanOperationKind::Squash, _) => {
-                    "squashing commit with previous".to_string()
+                (PlanOperationKind::Squash, _) => format!(
+                    "squashing commit into [{}]",
+                    op.squash_with
+                        .unwrap_or_default()
+                ),
+                (PlanOperationKind::Fixup, _) => format!(
+                    "folding commit into [{}], keeping its message",
+                    op.squash_with
+                        .unwrap_or_default()
+                ),
+                (PlanOperationKind::Edit, _) => {
+                    "stopping here to amend".to_string()
                 }
                 (_, Some(msg)) => {
                     if msg.len() > 50 {
@@ -758,3 +1107,7 @@ fn print_rebase_plan(
 
     Ok(())
 }
+
+fn abort_message(original_head: &str) -> String {
+    format!("gai rebase (abort): back to {}", &original_head[..7])
+}
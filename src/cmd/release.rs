@@ -0,0 +1,432 @@
+use crate::{
+    args::{GlobalArgs, ReleaseArgs},
+    changelog::{Changelog, prepend},
+    cmd::changelog::summarize,
+    git::{
+        commit::{commit_paths, get_commit_files},
+        errors::GitError,
+        log::{GitLog, LogFilters, get_logs},
+        sync::has_tracked_changes,
+        tag::{
+            create_tag, ensure_no_tag, reachable_tags, resolve_rev,
+        },
+        utils::get_head_repo,
+    },
+    print::{
+        json::ReleaseOutput, menu::Menu, spinner::SpinnerBuilder,
+    },
+    providers::{extract_from_provider, provider::ProviderKind},
+    release::{
+        Bump, Version, conventional_bump,
+        files::{VERSION_FILES, bump_file, cargo_package_name},
+        next_version,
+    },
+    requests::release::create_release_request,
+    responses::release::parse_to_bump,
+    schema::{SchemaSettings, release::create_release_schema},
+    state::State,
+};
+
+#[derive(Debug, Clone)]
+enum ReleaseActions {
+    Release,
+    Edit,
+    Quit,
+}
+
+const RELEASE_OPTS: [(ReleaseActions, char, &str); 3] = [
+    (ReleaseActions::Release, 'y', "create the release"),
+    (ReleaseActions::Edit, 'e', "edit the release notes"),
+    (ReleaseActions::Quit, 'q', "quit"),
+];
+
+pub fn run(
+    args: &ReleaseArgs,
+    global: &GlobalArgs,
+) -> anyhow::Result<()> {
+    let state = State::new(
+        global
+            .config
+            .as_deref(),
+        global,
+    )?;
+
+    let repo = &state.git.repo;
+    let head = get_head_repo(repo)?;
+
+    // highest semver tag, not the nearest,
+    // hotfix tags on old branches can be closer
+    let previous = reachable_tags(repo, head)?
+        .into_iter()
+        .filter_map(|tag| Version::parse(&tag).map(|v| (tag, v)))
+        .max_by(|a, b| a.1.cmp(&b.1));
+
+    if let Some((ref tag, _)) = previous
+        && resolve_rev(repo, tag)? == head
+        && args.exact.is_none()
+    {
+        return Err(GitError::Generic(format!(
+            "HEAD is already released as {}",
+            tag
+        ))
+        .into());
+    }
+
+    let from = match previous {
+        Some((ref tag, _)) => {
+            Some(resolve_rev(repo, tag)?.to_string())
+        }
+        None => None,
+    };
+
+    // no files, the bump and the notes only need
+    // the messages. pick_bump loads them for the
+    // few commits it sends to the llm
+    let logs = get_logs(
+        &state.git,
+        false,
+        false,
+        0,
+        false,
+        from.as_deref(),
+        Some(&head.to_string()),
+        &LogFilters::default(),
+    )?;
+
+    if logs
+        .git_logs
+        .is_empty()
+    {
+        if global.is_json() {
+            return crate::print::json::emit(&ReleaseOutput {
+                previous: previous.map(|(tag, _)| tag),
+                dry_run: args.dry_run,
+                ..Default::default()
+            });
+        }
+
+        println!("Nothing to release");
+        return Ok(());
+    }
+
+    let (bump, reasoning) = match (args.bump, &previous) {
+        (Some(bump), _) => (Some(bump), None),
+        // the first release is 0.1.0 whatever the bump
+        (None, None) => (None, None),
+        (None, Some(_)) => {
+            pick_bump(&state, args, &logs.git_logs, global)?
+        }
+    };
+
+    // tag prefix follows the previous tag
+    let prefix = match previous {
+        Some((ref tag, _)) if !tag.starts_with('v') => "",
+        _ => "v",
+    };
+
+    let version = match (&args.exact, &previous) {
+        (Some(exact), _) => {
+            Version::parse(exact).ok_or_else(|| {
+                GitError::Generic(format!(
+                    "{} is not a semver version",
+                    exact
+                ))
+            })?
+        }
+        (None, Some((_, current))) => {
+            next_version(current, bump.unwrap_or(Bump::Patch))
+        }
+        // first release
+        (None, None) => Version {
+            major: 0,
+            minor: 1,
+            patch: 0,
+            pre: None,
+        },
+    };
+
+    if let Some((_, ref current)) = previous
+        && version <= *current
+    {
+        return Err(GitError::Generic(format!(
+            "{} is not newer than {}",
+            version, current
+        ))
+        .into());
+    }
+
+    let tag = format!("{}{}", prefix, version);
+
+    // before any files are written, otherwise a
+    // release commit is left behind on HEAD
+    ensure_no_tag(repo, &tag)?;
+
+    let mut changelog = Changelog::new(
+        &tag,
+        &chrono::Utc::now()
+            .format("%Y-%m-%d")
+            .to_string(),
+        &logs.git_logs,
+        args.format,
+    );
+
+    if args.summarize {
+        summarize(&state, &mut changelog, &logs.git_logs, global)?;
+    }
+
+    let mut notes = changelog.render(args.format);
+
+    let previous_tag = previous.map(|(tag, _)| tag);
+
+    if !global.is_json() {
+        println!(
+            "{} -> {} ({}, {} commits)",
+            previous_tag
+                .as_deref()
+                .unwrap_or("none"),
+            tag,
+            bump.map(|b| b.to_string())
+                .unwrap_or_else(|| "first release".to_owned()),
+            logs.git_logs.len()
+        );
+
+        if let Some(ref reasoning) = reasoning {
+            println!("{}", reasoning);
+        }
+
+        println!("\n{}", notes);
+    }
+
+    if args.dry_run {
+        if global.is_json() {
+            return crate::print::json::emit(&ReleaseOutput {
+                previous: previous_tag,
+                tag,
+                version: version.to_string(),
+                bump,
+                commits: logs.git_logs.len(),
+                notes,
+                release_commit: None,
+                files: Vec::new(),
+                dry_run: true,
+            });
+        }
+
+        return Ok(());
+    }
+
+    if !args.skip_confirmation && !global.is_json() {
+        loop {
+            match Menu::new("What do you want to do?", &RELEASE_OPTS)
+                .render()?
+            {
+                ReleaseActions::Release => break,
+                ReleaseActions::Edit => {
+                    notes = crate::utils::open::edit(&notes)?;
+                }
+                ReleaseActions::Quit => return Ok(()),
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+
+    if args.commit
+        || args
+            .changelog
+            .is_some()
+    {
+        if has_tracked_changes(repo)? {
+            return Err(GitError::Generic(
+                "commit or stash your changes before releasing"
+                    .to_owned(),
+            )
+            .into());
+        }
+
+        if args.commit {
+            files.extend(bump_version_files(
+                &state,
+                &version.to_string(),
+            )?);
+        }
+
+        if let Some(ref path) = args.changelog {
+            let full = state
+                .git
+                .workdir
+                .join(path);
+
+            let existing =
+                std::fs::read_to_string(&full).unwrap_or_default();
+
+            let updated = prepend(&existing, &notes, args.format);
+
+            if updated != existing {
+                std::fs::write(&full, updated)?;
+                files.push(
+                    path.display()
+                        .to_string(),
+                );
+            }
+        }
+    }
+
+    let release_commit = if files.is_empty() {
+        None
+    } else {
+        Some(commit_paths(
+            repo,
+            &files,
+            &format!("chore(release): {}", tag),
+        )?)
+    };
+
+    let target = release_commit.unwrap_or(head);
+
+    create_tag(repo, &tag, target, &notes)?;
+
+    if global.is_json() {
+        return crate::print::json::emit(&ReleaseOutput {
+            previous: previous_tag,
+            tag,
+            version: version.to_string(),
+            bump,
+            commits: logs.git_logs.len(),
+            notes,
+            release_commit: release_commit.map(|o| o.to_string()),
+            files,
+            dry_run: false,
+        });
+    }
+
+    if let Some(oid) = release_commit {
+        println!(
+            "Committed {} in {}",
+            files.join(", "),
+            &oid.to_string()[..7]
+        );
+    }
+
+    println!(
+        "Tagged {} at {}, push it with `git push --follow-tags`",
+        tag,
+        &target.to_string()[..7]
+    );
+
+    Ok(())
+}
+
+/// conventional commits first, the llm only
+/// sees the ones without a prefix and can
+/// only raise the bump
+fn pick_bump(
+    state: &State,
+    args: &ReleaseArgs,
+    logs: &[GitLog],
+    global: &GlobalArgs,
+) -> anyhow::Result<(Option<Bump>, Option<String>)> {
+    let (bump, unknown) = conventional_bump(logs);
+
+    if unknown.is_empty() || args.no_llm {
+        return Ok((bump.or(Some(Bump::Patch)), None));
+    }
+
+    let schema_settings = if matches!(
+        state
+            .settings
+            .provider,
+        ProviderKind::OpenAI
+    ) {
+        SchemaSettings::default().additional_properties(false)
+    } else {
+        SchemaSettings::default()
+    };
+
+    let unknown = unknown
+        .into_iter()
+        .map(|log| with_files(state, log))
+        .collect::<anyhow::Result<Vec<GitLog>>>()?;
+
+    let schema = create_release_schema(schema_settings)?;
+    let req = create_release_request(
+        &state.settings,
+        &unknown
+            .iter()
+            .collect::<Vec<_>>(),
+    );
+
+    let handle = SpinnerBuilder::new()
+        .text(format!(
+            "Classifying {} non-conventional commits",
+            unknown.len()
+        ))
+        .hidden(global.is_json())
+        .start();
+
+    let response = match extract_from_provider(
+        &state
+            .settings
+            .provider,
+        req,
+        schema,
+    ) {
+        Ok(r) => r,
+        Err(e) => {
+            handle.error();
+            return Err(e.into());
+        }
+    };
+
+    let (llm_bump, reasoning) = parse_to_bump(response)?;
+
+    handle.stop_and_clear();
+
+    Ok((bump.max(Some(llm_bump)), Some(reasoning)))
+}
+
+fn with_files(
+    state: &State,
+    log: &GitLog,
+) -> anyhow::Result<GitLog> {
+    let oid = git2::Oid::from_str(&log.commit_hash)?;
+
+    let mut log = log.to_owned();
+    log.files = get_commit_files(&state.git.repo, oid, None)?
+        .iter()
+        .map(|f| f.path.to_string())
+        .collect();
+
+    Ok(log)
+}
+
+/// returns the files that changed
+fn bump_version_files(
+    state: &State,
+    version: &str,
+) -> anyhow::Result<Vec<String>> {
+    let workdir = &state.git.workdir;
+
+    let package = std::fs::read_to_string(workdir.join("Cargo.toml"))
+        .ok()
+        .and_then(|c| cargo_package_name(&c));
+
+    let mut changed = Vec::new();
+
+    for name in VERSION_FILES {
+        let path = workdir.join(name);
+
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+
+        if let Some(updated) =
+            bump_file(name, &content, package.as_deref(), version)
+        {
+            std::fs::write(&path, updated)?;
+            changed.push(name.to_owned());
+        }
+    }
+
+    Ok(changed)
+}
@@ -0,0 +1,487 @@
+use crate::{
+    args::{GlobalArgs, ResolveArgs},
+    git::{
+        conflicts::{
+            ConflictContext, ConflictFile, apply_resolutions,
+            get_conflict_context, get_conflict_file,
+            get_conflicted_paths, resolve_deleted, write_resolution,
+        },
+        log::path_matches,
+    },
+    print::{
+        self,
+        json::{
+            ResolveConflictOutput, ResolveFileOutput, ResolveOutput,
+        },
+        menu::Menu,
+        spinner::SpinnerBuilder,
+    },
+    providers::{extract_from_provider, provider::ProviderKind},
+    requests::resolve::create_resolve_request,
+    responses::resolve::parse_to_resolutions,
+    schema::{
+        SchemaSettings,
+        resolve::{HunkResolution, create_resolve_schema},
+    },
+    state::State,
+};
+
+#[derive(Debug, Clone)]
+enum ResolveActions {
+    Accept,
+    Edit,
+    Ours,
+    Theirs,
+    Skip,
+    Quit,
+}
+
+const RESOLVE_OPTS: [(ResolveActions, char, &str); 6] = [
+    (ResolveActions::Accept, 'a', "accept suggestion"),
+    (ResolveActions::Edit, 'e', "edit suggestion"),
+    (ResolveActions::Ours, 'o', "keep ours"),
+    (ResolveActions::Theirs, 't', "keep theirs"),
+    (ResolveActions::Skip, 's', "skip, resolve it yourself"),
+    (ResolveActions::Quit, 'q', "quit"),
+];
+
+#[derive(Debug, Clone)]
+enum DeletedActions {
+    Keep,
+    Delete,
+    Skip,
+    Quit,
+}
+
+const DELETED_OPTS: [(DeletedActions, char, &str); 4] = [
+    (DeletedActions::Keep, 'k', "keep the file"),
+    (DeletedActions::Delete, 'd', "delete the file"),
+    (DeletedActions::Skip, 's', "skip"),
+    (DeletedActions::Quit, 'q', "quit"),
+];
+
+pub fn run(
+    args: &ResolveArgs,
+    global: &GlobalArgs,
+) -> anyhow::Result<()> {
+    let state = State::new(
+        global
+            .config
+            .as_deref(),
+        global,
+    )?;
+
+    let context = get_conflict_context(&state.git.repo);
+    let paths = filter_paths(&state, &args.paths)?;
+
+    if global.is_json() {
+        return run_json(&state, &context, &paths, args.accept_all);
+    }
+
+    if paths.is_empty() {
+        println!("No conflicts to resolve");
+        return Ok(());
+    }
+
+    if let Some(ref message) = context
+        .theirs
+        .message
+    {
+        println!(
+            "Resolving {} conflicts with {}",
+            context.operation,
+            message
+                .lines()
+                .next()
+                .unwrap_or_default()
+        );
+    }
+
+    let resolved =
+        resolve_paths(&state, &context, &paths, args.accept_all)?;
+
+    let remaining = get_conflicted_paths(&state.git.repo)?;
+
+    if resolved && remaining.is_empty() {
+        println!(
+            "All conflicts resolved, run `{}` to finish",
+            context
+                .operation
+                .continue_hint()
+        );
+    } else {
+        println!("{} file/s still conflicted", remaining.len());
+    }
+
+    Ok(())
+}
+
+/// suggestions for every conflict, only
+/// written when accepting all of them
+fn run_json(
+    state: &State,
+    context: &ConflictContext,
+    paths: &[String],
+    accept_all: bool,
+) -> anyhow::Result<()> {
+    let mut files = Vec::new();
+
+    for path in paths {
+        let file = get_conflict_file(
+            &state.git.repo,
+            &state.git.workdir,
+            path,
+        )?;
+
+        if file
+            .hunks
+            .is_empty()
+        {
+            files.push(ResolveFileOutput {
+                path: path.to_owned(),
+                staged: false,
+                conflicts: Vec::new(),
+            });
+            continue;
+        }
+
+        let suggestions = suggest(state, context, &file, true)?;
+
+        let staged = accept_all
+            && write_suggestions(state, &file, &suggestions)?;
+
+        let conflicts = suggestions
+            .into_iter()
+            .enumerate()
+            .map(|(id, s)| ResolveConflictOutput {
+                id,
+                reasoning: s.as_ref().map(|s| {
+                    s.reasoning
+                        .to_owned()
+                }),
+                resolution: s.map(|s| s.resolution),
+            })
+            .collect();
+
+        files.push(ResolveFileOutput {
+            path: path.to_owned(),
+            staged,
+            conflicts,
+        });
+    }
+
+    print::json::emit(&ResolveOutput {
+        operation: context
+            .operation
+            .to_string(),
+        ours: context
+            .ours
+            .commit_hash
+            .to_owned(),
+        theirs: context
+            .theirs
+            .commit_hash
+            .to_owned(),
+        files,
+        remaining: get_conflicted_paths(&state.git.repo)?,
+    })
+}
+
+fn filter_paths(
+    state: &State,
+    filters: &[String],
+) -> anyhow::Result<Vec<String>> {
+    Ok(get_conflicted_paths(&state.git.repo)?
+        .into_iter()
+        .filter(|p| {
+            filters.is_empty()
+                || filters
+                    .iter()
+                    .any(|f| path_matches(p, f))
+        })
+        .collect())
+}
+
+/// go through every conflicted file, see
+/// resolve_paths
+/// returns true if nothing is left conflicted
+pub fn resolve_conflicts(state: &State) -> anyhow::Result<bool> {
+    let context = get_conflict_context(&state.git.repo);
+    let paths = get_conflicted_paths(&state.git.repo)?;
+
+    resolve_paths(state, &context, &paths, false)?;
+
+    Ok(get_conflicted_paths(&state.git.repo)?.is_empty())
+}
+
+/// each block in each file, asking the llm for
+/// a merge and letting the user pick. files
+/// with no markers left are staged
+/// returns false if the user quit early
+fn resolve_paths(
+    state: &State,
+    context: &ConflictContext,
+    paths: &[String],
+    accept_all: bool,
+) -> anyhow::Result<bool> {
+    for path in paths {
+        let file = get_conflict_file(
+            &state.git.repo,
+            &state.git.workdir,
+            path,
+        )?;
+
+        let done = if file.ours_deleted || file.theirs_deleted {
+            resolve_deleted_file(state, &file)?
+        } else if file
+            .hunks
+            .is_empty()
+        {
+            println!(
+                "{} has no conflict markers, stage it once its resolved",
+                path
+            );
+            true
+        } else {
+            resolve_file(state, context, &file, accept_all)?
+        };
+
+        if !done {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// returns false if the user quit
+fn resolve_file(
+    state: &State,
+    context: &ConflictContext,
+    file: &ConflictFile,
+    accept_all: bool,
+) -> anyhow::Result<bool> {
+    let suggestions = match suggest(state, context, file, false) {
+        Ok(s) => s,
+        Err(e) => {
+            println!(
+                "Failed to get suggestions for {}: {}",
+                file.path, e
+            );
+            vec![None; file.hunks.len()]
+        }
+    };
+
+    if accept_all {
+        if write_suggestions(state, file, &suggestions)? {
+            println!("Resolved and staged {}", file.path);
+        }
+
+        return Ok(true);
+    }
+
+    let mut picked: Vec<Option<String>> =
+        vec![None; file.hunks.len()];
+
+    let mut quit = false;
+
+    for (idx, hunk) in file
+        .hunks
+        .iter()
+        .enumerate()
+    {
+        let suggestion = suggestions[idx].as_ref();
+
+        print::resolve::conflict(
+            &file.path,
+            idx,
+            file.hunks.len(),
+            hunk,
+            suggestion,
+        )?;
+
+        let opts: Vec<(ResolveActions, char, &str)> = RESOLVE_OPTS
+            .iter()
+            .filter(|(a, _, _)| {
+                suggestion.is_some()
+                    || !matches!(a, ResolveActions::Accept)
+            })
+            .cloned()
+            .collect();
+
+        let selected =
+            Menu::new("How do you want to resolve this?", &opts)
+                .render()?;
+
+        picked[idx] = match selected {
+            ResolveActions::Accept => suggestion.map(|s| {
+                s.resolution
+                    .to_owned()
+            }),
+            ResolveActions::Edit => {
+                let text = suggestion
+                    .map(|s| {
+                        s.resolution
+                            .to_owned()
+                    })
+                    .unwrap_or_else(|| hunk.ours.join("\n"));
+
+                Some(crate::utils::open::edit(&text)?)
+            }
+            ResolveActions::Ours => Some(hunk.ours.join("\n")),
+            ResolveActions::Theirs => Some(
+                hunk.theirs
+                    .join("\n"),
+            ),
+            ResolveActions::Skip => None,
+            ResolveActions::Quit => {
+                quit = true;
+                break;
+            }
+        };
+    }
+
+    // keep whatever was picked before quitting
+    if picked
+        .iter()
+        .any(|p| p.is_some())
+    {
+        let content =
+            apply_resolutions(&file.content, &file.hunks, &picked);
+
+        if write_resolution(
+            &state.git.repo,
+            &state.git.workdir,
+            &file.path,
+            &content,
+        )? {
+            println!("Resolved and staged {}", file.path);
+        }
+    }
+
+    Ok(!quit)
+}
+
+/// deleted on one side, modified on the other
+fn resolve_deleted_file(
+    state: &State,
+    file: &ConflictFile,
+) -> anyhow::Result<bool> {
+    let side = if file.ours_deleted { "ours" } else { "theirs" };
+
+    println!(
+        "\n{} was deleted in {} and modified in the other",
+        file.path, side
+    );
+
+    let keep =
+        match Menu::new("What do you want to do?", &DELETED_OPTS)
+            .render()?
+        {
+            DeletedActions::Keep => true,
+            DeletedActions::Delete => false,
+            DeletedActions::Skip => return Ok(true),
+            DeletedActions::Quit => return Ok(false),
+        };
+
+    resolve_deleted(
+        &state.git.repo,
+        &state.git.workdir,
+        &file.path,
+        keep,
+    )?;
+
+    Ok(true)
+}
+
+/// apply every suggestion there is
+/// returns true if the file was staged
+fn write_suggestions(
+    state: &State,
+    file: &ConflictFile,
+    suggestions: &[Option<HunkResolution>],
+) -> anyhow::Result<bool> {
+    let picked: Vec<Option<String>> = suggestions
+        .iter()
+        .map(|s| {
+            s.as_ref().map(|s| {
+                s.resolution
+                    .to_owned()
+            })
+        })
+        .collect();
+
+    if picked
+        .iter()
+        .all(|p| p.is_none())
+    {
+        return Ok(false);
+    }
+
+    let content =
+        apply_resolutions(&file.content, &file.hunks, &picked);
+
+    write_resolution(
+        &state.git.repo,
+        &state.git.workdir,
+        &file.path,
+        &content,
+    )
+}
+
+fn suggest(
+    state: &State,
+    context: &ConflictContext,
+    file: &ConflictFile,
+    hidden: bool,
+) -> anyhow::Result<Vec<Option<HunkResolution>>> {
+    let schema_settings = if matches!(
+        state
+            .settings
+            .provider,
+        ProviderKind::OpenAI
+    ) {
+        SchemaSettings::default().additional_properties(false)
+    } else {
+        SchemaSettings::default()
+    };
+
+    let schema = create_resolve_schema(
+        schema_settings,
+        file.hunks
+            .len()
+            .saturating_sub(1) as u32,
+    )?;
+
+    let req = create_resolve_request(&state.settings, context, file);
+
+    let handle = SpinnerBuilder::new()
+        .text(format!(
+            "Resolving {} conflict/s in {}",
+            file.hunks.len(),
+            file.path
+        ))
+        .hidden(hidden)
+        .start();
+
+    let response = match extract_from_provider(
+        &state
+            .settings
+            .provider,
+        req,
+        schema,
+    ) {
+        Ok(r) => r,
+        Err(e) => {
+            handle.error();
+            return Err(e.into());
+        }
+    };
+
+    let resolutions =
+        parse_to_resolutions(response, file.hunks.len())?;
+
+    handle.done();
+
+    Ok(resolutions)
+}
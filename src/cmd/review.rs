@@ -0,0 +1,304 @@
+use std::path::Path;
+
+use git2::Repository;
+use owo_colors::OwoColorize;
+
+use crate::{
+    args::{GlobalArgs, ReviewArgs},
+    cmd::commit::diff_strategy,
+    git::{
+        Diffs, StatusStrategy,
+        diffs::{get_diffs_from_commits, get_diffs_from_statuses},
+        errors::GitError,
+        utils::get_head_repo,
+    },
+    print::{
+        self, json::ReviewOutput, menu::Menu, sarif::review_to_sarif,
+        spinner::SpinnerBuilder,
+    },
+    providers::{extract_from_provider, provider::ProviderKind},
+    requests::review::create_review_request,
+    responses::review::{Review, parse_to_review},
+    schema::{SchemaSettings, review::create_review_schema},
+    settings::Settings,
+    state::State,
+};
+
+#[derive(Debug, Clone)]
+enum GateActions {
+    Commit,
+    Quit,
+}
+
+pub fn run(
+    args: &ReviewArgs,
+    global: &GlobalArgs,
+) -> anyhow::Result<()> {
+    let mut state = State::new(
+        global
+            .config
+            .as_deref(),
+        global,
+    )?;
+
+    let json = global.is_json();
+
+    let source = match args.range {
+        Some(ref range) => {
+            state.diffs = range_diffs(
+                &state.git.repo,
+                &state.git.workdir,
+                range,
+            )?;
+
+            range.to_owned()
+        }
+        None => {
+            let status_strategy = if args.all {
+                StatusStrategy::Both
+            } else {
+                StatusStrategy::Stage
+            };
+
+            state.diffs = get_diffs_from_statuses(
+                &state.git.repo,
+                &state.git.workdir,
+                &diff_strategy(&state.settings, status_strategy),
+            )?;
+
+            if args.all { "all" } else { "staged" }.to_owned()
+        }
+    };
+
+    if state
+        .diffs
+        .files
+        .is_empty()
+    {
+        if json {
+            return print::json::emit(&ReviewOutput {
+                source,
+                summary: String::new(),
+                findings: Vec::new(),
+            });
+        }
+
+        println!(
+            "{}",
+            "Nothing to review."
+                .yellow()
+                .bold()
+        );
+        return Ok(());
+    }
+
+    if !json {
+        print::status::provider_info(
+            &state
+                .settings
+                .provider,
+            &state
+                .settings
+                .providers,
+        )?;
+    }
+
+    let review = review_diffs(&state.settings, &state.diffs, json)?;
+
+    if let Some(ref path) = args.sarif {
+        write_sarif(path, &review)?;
+    }
+
+    // sarif on stdout is the output
+    let sarif_stdout = args
+        .sarif
+        .as_deref()
+        .is_some_and(|p| p == Path::new("-"));
+
+    let failed = args
+        .fail_on
+        .map(|s| {
+            review
+                .at_or_above(s)
+                .len()
+        })
+        .unwrap_or(0);
+
+    if json {
+        if !sarif_stdout {
+            print::json::emit(&ReviewOutput {
+                source,
+                summary: review.summary,
+                findings: review.findings,
+            })?;
+        }
+
+        // same exit code as an error, without
+        // a second json document
+        if failed > 0 {
+            std::process::exit(1);
+        }
+
+        return Ok(());
+    }
+
+    if !sarif_stdout {
+        print::review::review(&review, &state.diffs)?;
+    }
+
+    if let Some(severity) = args.fail_on
+        && failed > 0
+    {
+        return Err(anyhow::anyhow!(
+            "{} finding/s at or above {}",
+            failed,
+            severity
+        ));
+    }
+
+    Ok(())
+}
+
+/// send the diffs for review, the spinner
+/// is hidden for json
+pub fn review_diffs(
+    settings: &Settings,
+    diffs: &Diffs,
+    hidden: bool,
+) -> anyhow::Result<Review> {
+    // openai seems like the only one that needs this
+    let schema_settings =
+        if matches!(settings.provider, ProviderKind::OpenAI) {
+            SchemaSettings::default().additional_properties(false)
+        } else {
+            SchemaSettings::default()
+        };
+
+    let schema =
+        create_review_schema(schema_settings, &diffs.as_hunks())?;
+
+    let req = create_review_request(settings, diffs);
+
+    let handle = SpinnerBuilder::new()
+        .text(format!(
+            "Reviewing {} hunk/s in {} file/s",
+            diffs
+                .as_hunks()
+                .len(),
+            diffs.files.len()
+        ))
+        .hidden(hidden)
+        .start();
+
+    let response = match extract_from_provider(
+        &settings.provider,
+        req,
+        schema,
+    ) {
+        Ok(r) => r,
+        Err(e) => {
+            handle.error();
+            return Err(e.into());
+        }
+    };
+
+    let review = parse_to_review(response, diffs)?;
+
+    handle.done();
+
+    Ok(review)
+}
+
+/// used by gai commit, true if committing
+/// should go ahead. with json theres no
+/// one to ask, so blocking findings are an error
+pub fn gate(
+    settings: &Settings,
+    diffs: &Diffs,
+    json: bool,
+) -> anyhow::Result<bool> {
+    let review = review_diffs(settings, diffs, json)?;
+
+    let block_on = settings
+        .review
+        .block_on;
+
+    let blocking = review
+        .at_or_above(block_on)
+        .len();
+
+    if blocking == 0 {
+        return Ok(true);
+    }
+
+    if json {
+        return Err(anyhow::anyhow!(
+            "review found {} finding/s at or above {}, run `gai review` for details",
+            blocking,
+            block_on
+        ));
+    }
+
+    print::review::review(&review, diffs)?;
+
+    let opts = [
+        (GateActions::Commit, 'c', "commit anyway"),
+        (GateActions::Quit, 'q', "quit"),
+    ];
+
+    let prompt = format!(
+        "Review found {} finding/s at or above {}",
+        blocking, block_on
+    );
+
+    match Menu::new(&prompt, &opts).render()? {
+        GateActions::Commit => Ok(true),
+        GateActions::Quit => Ok(false),
+    }
+}
+
+/// A..B as is, a single rev is rev..HEAD
+fn range_diffs(
+    repo: &Repository,
+    workdir: &Path,
+    range: &str,
+) -> anyhow::Result<Diffs> {
+    let spec = repo
+        .revparse(range)
+        .map_err(|_| {
+            GitError::Generic(format!("unknown revision {}", range))
+        })?;
+
+    let from = spec
+        .from()
+        .ok_or_else(|| {
+            GitError::Generic(format!("unknown revision {}", range))
+        })?
+        .peel_to_commit()?
+        .id();
+
+    let to = match spec.to() {
+        Some(to) => to
+            .peel_to_commit()?
+            .id(),
+        None => get_head_repo(repo)?,
+    };
+
+    get_diffs_from_commits(repo, workdir, Some(from), Some(to))
+}
+
+fn write_sarif(
+    path: &Path,
+    review: &Review,
+) -> anyhow::Result<()> {
+    let sarif =
+        serde_json::to_string_pretty(&review_to_sarif(review))?;
+
+    if path == Path::new("-") {
+        println!("{}", sarif);
+    } else {
+        std::fs::write(path, sarif + "\n")?;
+    }
+
+    Ok(())
+}
@@ -1,15 +1,17 @@
+use std::collections::HashMap;
+
+use git2::Oid;
+
 use crate::{
     args::{GlobalArgs, RewordArgs, RewordScope},
     cmd::commit::{RESPONSE_OPTS, ResponseActions, edit_commits},
     git::{
         GitRepo, StagingStrategy,
-        checkout::force_checkout_head,
-        commit::find_parent_commit,
-        log::{Logs, get_logs},
-        rebase::{
-            cherry_pick_commits, cherry_pick_reword, trailing_commits,
-        },
-        reset::reset_repo_hard,
+        errors::GitError,
+        log::{LogFilters, Logs, get_log, get_logs},
+        rebase::rewrite_keeping_trees,
+        reset::{reflog_message, reset_repo_hard, set_head_target},
+        rewrite::{RewriteState, clear_rewrite, ensure_no_rewrite},
         status::{get_commit_stats, is_workdir_clean},
         utils::get_head_repo,
     },
@@ -34,6 +36,8 @@ pub fn run(
         global,
     )?;
 
+    ensure_no_rewrite(&state.git.repo)?;
+
     if !is_workdir_clean(&state.git.repo)? {
         return Err(anyhow::anyhow!(
             "Workdir is NOT clean, please save your changes"
@@ -48,65 +52,40 @@ pub fn run(
 
     // mimicing the rebase flow, its pretty similar, the only major difference
     // is that we dont gen a plan, but only reword selected commits
-    let (logs, trailing_commits) = match args.scope {
-        RewordScope::Commit { ref hash } => {
-            // we need the parent commit, to get the root since
-            // from is exclusive
-            let parent = find_parent_commit(&state.git.repo, hash)?;
-
-            let logs = get_logs(
+    let logs = match args.scope {
+        // just the one commit, a parent..hash range
+        // would pull in the other side of a merge
+        RewordScope::Commit { ref hash } => Logs {
+            git_logs: vec![get_log(&state.git, hash)?],
+        },
+        RewordScope::Last { count } => {
+            let mut logs = get_logs(
                 &state.git,
                 true,
                 false,
-                0,
-                true,
-                Some(&parent.to_string()),
-                Some(hash),
+                count,
+                false,
                 None,
-            )?;
-
-            let trails = trailing_commits(&state.git.repo, hash)?;
-
-            (logs, trails)
-        }
-        RewordScope::Last { count } => {
-            let mut logs = get_logs(
-                &state.git, true, false, count, false, None, None,
                 None,
+                &LogFilters::default(),
             )?;
 
             // if logs are reversed
             logs.git_logs
                 .reverse();
 
-            // return an empty vec for now
-            // want to just leave trails empty
-            // instead of returning an
-            // option to unwrap
-            (logs, Vec::new())
-        }
-        RewordScope::Range { ref from, ref to } => {
-            let logs = get_logs(
-                &state.git,
-                true,
-                false,
-                0,
-                true,
-                Some(from),
-                to.as_deref(),
-                None,
-            )?;
-
-            let to_hash = to
-                .to_owned()
-                .unwrap_or(
-                    get_head_repo(&state.git.repo)?.to_string(),
-                );
-
-            let trails = trailing_commits(&state.git.repo, &to_hash)?;
-
-            (logs, trails)
+            logs
         }
+        RewordScope::Range { ref from, ref to } => get_logs(
+            &state.git,
+            true,
+            false,
+            0,
+            true,
+            Some(from),
+            to.as_deref(),
+            &LogFilters::default(),
+        )?,
     };
 
     let mut log_strs = Vec::new();
@@ -204,14 +183,18 @@ pub fn run(
                         &state.git,
                         &logs,
                         &commit_messages,
-                        &trailing_commits,
                     ) {
                         Ok(oids) => oids,
                         Err(e) => {
                             reset_repo_hard(
                                 &state.git.repo,
                                 &original_head,
+                                &format!(
+                                    "gai reword (abort): back to {}",
+                                    &original_head[..7]
+                                ),
                             )?;
+                            clear_rewrite(&state.git.repo)?;
 
                             return Err(e);
                         }
@@ -248,7 +231,7 @@ pub fn run(
                     break;
                 }
                 ResponseActions::Edit => {
-                    raw_commits = edit_commits(&raw_commits)?;
+                    raw_commits = edit_commits(&raw_commits, None)?;
 
                     if raw_commits.is_empty() {
                         break;
@@ -282,52 +265,88 @@ pub fn run(
     Ok(())
 }
 
+/// rewords in place, trees dont change so every commit
+/// after the reworded ones, merges included, is recreated
+/// with the same content and HEAD moves to the new tip
 fn apply(
     git: &GitRepo,
     logs: &Logs,
     new_commit_messages: &[String],
-    trailing_commits: &[String],
 ) -> anyhow::Result<Vec<String>> {
-    // for the range and everything to work
-    // when applying, gonna need to quickly
-    // mimic the reset flow from rebase
-    // reset to -> parent of from commit
-    let oldest = &logs.git_logs[0].commit_hash;
-
-    let parent = find_parent_commit(&git.repo, oldest)?;
-
-    let mut oids = Vec::new();
-
-    reset_repo_hard(&git.repo, &parent.to_string())?;
+    let mut messages = HashMap::new();
 
     for (idx, log) in logs
         .git_logs
         .iter()
         .enumerate()
     {
-        let commit = log
-            .commit_hash
-            .to_owned();
-
-        if let Some(message) = new_commit_messages.get(idx) {
-            let oid =
-                cherry_pick_reword(&git.repo, &commit, message)?;
-            oids.push(oid);
-        } else {
+        let Some(message) = new_commit_messages.get(idx) else {
             return Err(anyhow::anyhow!("bad index"));
+        };
+
+        messages.insert(
+            Oid::from_str(&log.commit_hash)?,
+            message.to_owned(),
+        );
+    }
+
+    // the walk stops right before the reworded commits
+    let mut hide = Vec::new();
+
+    for oid in messages.keys() {
+        for parent in git
+            .repo
+            .find_commit(*oid)?
+            .parent_ids()
+        {
+            if !messages.contains_key(&parent) {
+                hide.push(parent);
+            }
         }
     }
 
-    if !trailing_commits.is_empty() {
-        let cherry_picked =
-            cherry_pick_commits(&git.repo, trailing_commits)?;
+    // cant be resumed, but --abort can still
+    // restore the branch after a crash
+    RewriteState::begin(&git.repo, "reword", Vec::new())?;
 
-        oids.extend(cherry_picked);
+    let head = get_head_repo(&git.repo)?;
+
+    let (tip, rewritten) = rewrite_keeping_trees(
+        &git.repo,
+        head,
+        &hide,
+        HashMap::new(),
+        &messages,
+    )?;
+
+    let mut oids = Vec::new();
+
+    for log in &logs.git_logs {
+        let Some(oid) =
+            rewritten.get(&Oid::from_str(&log.commit_hash)?)
+        else {
+            return Err(GitError::Generic(format!(
+                "{} is not on the current branch",
+                &log.commit_hash[..7]
+            ))
+            .into());
+        };
+
+        oids.push(oid.to_string());
     }
-    // readd the trailing commits if any
 
-    // then sync it
-    force_checkout_head(&git.repo)?;
+    let message = match messages
+        .values()
+        .next()
+    {
+        Some(message) if messages.len() == 1 => {
+            reflog_message("reword", message)
+        }
+        _ => format!("gai reword: {} commits", messages.len()),
+    };
+
+    set_head_target(&git.repo, Some(tip), &message)?;
+    clear_rewrite(&git.repo)?;
 
     Ok(oids)
 }
@@ -0,0 +1,656 @@
+use git2::Repository;
+use owo_colors::OwoColorize;
+use serde_json::Value;
+
+use crate::{
+    args::{GlobalArgs, SplitArgs},
+    cmd::{
+        commit::{
+            PlanContext, RESPONSE_OPTS, ResponseActions, edit_commits,
+        },
+        rebase::apply,
+    },
+    git::{
+        GitRepo, StagingStrategy,
+        commit::{GitCommit, find_parent_commit},
+        diffs::{FileDiff, get_diffs_from_commits},
+        errors::GitError,
+        rebase::trailing_commits,
+        reset::{reset_repo_hard, reset_to_parent_mixed},
+        rewrite::{RewriteState, clear_rewrite, ensure_no_rewrite},
+        status::{get_commit_stats, is_workdir_clean},
+        tag::resolve_rev,
+        utils::get_head_repo,
+    },
+    print::{
+        self,
+        json::{AppliedCommit, SplitOutput},
+        menu::Menu,
+        spinner::SpinnerBuilder,
+    },
+    providers::{extract_from_provider, provider::ProviderKind},
+    requests::{Request, commit::create_commit_request},
+    responses::commit::{parse_to_commit_schema, process_commit},
+    schema::{
+        SchemaSettings,
+        commit::{CommitSchema, create_commit_response_schema},
+    },
+    settings::Settings,
+    state::State,
+};
+
+/// where the split happens, captured before
+/// anything is reset so we can roll back
+struct SplitTarget {
+    commit: String,
+
+    /// None when splitting the root commit
+    parent: Option<git2::Oid>,
+    original_head: String,
+
+    /// commits after the target, oldest first
+    trailing: Vec<String>,
+}
+
+pub fn run(
+    args: &SplitArgs,
+    global: &GlobalArgs,
+) -> anyhow::Result<()> {
+    let mut state = State::new(
+        global
+            .config
+            .as_deref(),
+        global,
+    )?;
+
+    if let Some(ref staging) = args.staging {
+        state
+            .settings
+            .staging_type = staging.to_owned();
+    }
+
+    if matches!(
+        state
+            .settings
+            .staging_type,
+        StagingStrategy::AllFilesOneCommit
+    ) {
+        return Err(GitError::Generic(
+            "AllFilesOneCommit would recreate the same commit, pick another staging strategy".to_owned(),
+        )
+        .into());
+    }
+
+    ensure_no_rewrite(&state.git.repo)?;
+
+    if !is_workdir_clean(&state.git.repo)? {
+        return Err(anyhow::anyhow!(
+            "Workdir is NOT clean, please save your changes"
+        ));
+    }
+
+    let target = split_target(&state.git.repo, &args.commit)?;
+
+    let json = global.is_json();
+
+    if !json {
+        print::status::provider_info(
+            &state
+                .settings
+                .provider,
+            &state
+                .settings
+                .providers,
+        )?;
+    }
+
+    state.diffs = get_diffs_from_commits(
+        &state.git.repo,
+        &state.git.workdir,
+        target.parent,
+        Some(git2::Oid::from_str(&target.commit)?),
+    )?;
+
+    if state
+        .diffs
+        .files
+        .is_empty()
+    {
+        return Err(GitError::Generic(format!(
+            "{} has no changes to split",
+            &target.commit[..7]
+        ))
+        .into());
+    }
+
+    // openai seems like the only one that needs this
+    let schema_settings = if matches!(
+        state
+            .settings
+            .provider,
+        ProviderKind::OpenAI
+    ) {
+        SchemaSettings::default().additional_properties(false)
+    } else {
+        SchemaSettings::default()
+    };
+
+    let schema = create_commit_response_schema(
+        schema_settings,
+        &state.settings,
+        &state
+            .diffs
+            .as_files(),
+        &state
+            .diffs
+            .as_hunks(),
+        &state
+            .diffs
+            .as_line_ids(),
+    )?;
+
+    let message = state
+        .git
+        .repo
+        .find_commit(git2::Oid::from_str(&target.commit)?)?
+        .message()
+        .unwrap_or_default()
+        .trim()
+        .to_owned();
+
+    let req = create_commit_request(
+        &state.settings,
+        &state.git,
+        &state
+            .diffs
+            .to_request_string(
+                &state
+                    .settings
+                    .staging_type,
+            ),
+    )
+    .insert_content(&format!(
+        concat!(
+            "These diffs are a single existing commit being split ",
+            "into several smaller ones, generate more than one commit.\n",
+            "Original message:{}"
+        ),
+        message
+    ));
+
+    if json {
+        return run_split_json(
+            req,
+            schema,
+            &state,
+            &target,
+            args.skip_confirmation,
+        );
+    }
+
+    run_split(req, schema, &mut state, &target)
+}
+
+/// the commit has to be on the current branch
+/// and cant be a merge, merges after it are
+/// replayed as they are
+fn split_target(
+    repo: &Repository,
+    commit: &str,
+) -> anyhow::Result<SplitTarget> {
+    let oid = resolve_rev(repo, commit)?;
+    let head = get_head_repo(repo)?;
+
+    if oid != head && !repo.graph_descendant_of(head, oid)? {
+        return Err(GitError::Generic(format!(
+            "{} is not on the current branch",
+            commit
+        ))
+        .into());
+    }
+
+    if repo
+        .find_commit(oid)?
+        .parent_count()
+        > 1
+    {
+        return Err(GitError::Generic(format!(
+            "{} is a merge commit, cannot split it",
+            commit
+        ))
+        .into());
+    }
+
+    let parent = find_parent_commit(repo, &oid.to_string())?;
+    let trailing = trailing_commits(repo, &oid.to_string())?;
+
+    Ok(SplitTarget {
+        commit: oid.to_string(),
+        parent,
+        original_head: head.to_string(),
+        trailing,
+    })
+}
+
+/// resets onto the parent with the commit's changes,
+/// applies the new commits and replays the rest,
+/// any failure restores the original head
+fn apply_split(
+    git: &GitRepo,
+    cfg: &Settings,
+    target: &SplitTarget,
+    git_commits: &[GitCommit],
+    og_file_diffs: &mut Vec<FileDiff>,
+) -> anyhow::Result<Vec<String>> {
+    RewriteState::begin(&git.repo, "split", Vec::new())?;
+
+    // check out the commit itself, then uncommit it
+    // so its changes are in the workdir to re-stage
+    reset_repo_hard(
+        &git.repo,
+        &target.commit,
+        "gai split (start): checkout the commit",
+    )?;
+    reset_to_parent_mixed(
+        &git.repo,
+        target.parent,
+        "gai split (start): uncommit the commit",
+    )?;
+
+    match apply(
+        git,
+        git_commits,
+        og_file_diffs,
+        &cfg.staging_type,
+        Some(&target.commit),
+        Some(&target.trailing),
+    ) {
+        Ok(oids) => {
+            clear_rewrite(&git.repo)?;
+            Ok(oids)
+        }
+        Err(e) => {
+            reset_repo_hard(
+                &git.repo,
+                &target.original_head,
+                &format!(
+                    "gai split (abort): back to {}",
+                    &target.original_head[..7]
+                ),
+            )?;
+            clear_rewrite(&git.repo)?;
+
+            Err(anyhow::anyhow!(
+                "couldnt split {}, restored {}:\n{}",
+                &target.commit[..7],
+                &target.original_head[..7],
+                e
+            ))
+        }
+    }
+}
+
+/// prints the proposed commits, only
+/// applies them with --skip-confirmation
+fn run_split_json(
+    req: Request,
+    schema: Value,
+    state: &State,
+    target: &SplitTarget,
+    apply: bool,
+) -> anyhow::Result<()> {
+    let cfg = &state.settings;
+
+    let result: Value =
+        extract_from_provider(&cfg.provider, req, schema)?;
+
+    let raw_commits =
+        parse_to_commit_schema(result, &cfg.staging_type)?;
+
+    let applied = if apply {
+        let git_commits: Vec<GitCommit> = raw_commits
+            .iter()
+            .cloned()
+            .map(|c| process_commit(c, cfg))
+            .collect();
+
+        let mut files = state
+            .diffs
+            .files
+            .to_owned();
+
+        let oids = apply_split(
+            &state.git,
+            cfg,
+            target,
+            &git_commits,
+            &mut files,
+        )?;
+
+        Some(
+            oids.into_iter()
+                .zip(git_commits)
+                .map(|(oid, c)| AppliedCommit {
+                    oid,
+                    message: c.message,
+                })
+                .collect(),
+        )
+    } else {
+        None
+    };
+
+    print::json::emit(&SplitOutput {
+        commit: target
+            .commit
+            .to_owned(),
+        commits: raw_commits,
+        applied,
+        replayed: target
+            .trailing
+            .len(),
+    })
+}
+
+fn run_split(
+    req: Request,
+    schema: Value,
+    state: &mut State,
+    target: &SplitTarget,
+) -> anyhow::Result<()> {
+    let hunks = matches!(
+        state
+            .settings
+            .staging_type,
+        StagingStrategy::Hunks
+    );
+
+    loop {
+        let handle = SpinnerBuilder::new()
+            .text(format!("Splitting {}", &target.commit[..7]))
+            .start();
+
+        let result: Value = match extract_from_provider(
+            &state
+                .settings
+                .provider,
+            req.to_owned(),
+            schema.to_owned(),
+        ) {
+            Ok(r) => r,
+            Err(e) => {
+                handle.error();
+                return Err(e.into());
+            }
+        };
+
+        let mut raw_commits: Vec<CommitSchema> =
+            parse_to_commit_schema(
+                result,
+                &state
+                    .settings
+                    .staging_type,
+            )?;
+
+        handle.done();
+
+        print::commits::response_commits(&raw_commits, hunks)?;
+
+        loop {
+            match Menu::new("What do you want to do?", &RESPONSE_OPTS)
+                .render()?
+            {
+                ResponseActions::Apply => {
+                    let git_commits: Vec<GitCommit> = raw_commits
+                        .iter()
+                        .cloned()
+                        .map(|c| process_commit(c, &state.settings))
+                        .collect();
+
+                    let oids = apply_split(
+                        &state.git,
+                        &state.settings,
+                        target,
+                        &git_commits,
+                        &mut state.diffs.files,
+                    )?;
+
+                    for (oid, git_commit) in oids
+                        .iter()
+                        .zip(&git_commits)
+                    {
+                        let (
+                            branch_name,
+                            files_changed,
+                            insertions,
+                            deletions,
+                        ) = get_commit_stats(&state.git.repo, oid)?;
+
+                        print::commits::completed_commit(
+                            &branch_name,
+                            oid,
+                            &git_commit.message,
+                            files_changed,
+                            insertions,
+                            deletions,
+                        )?;
+                    }
+
+                    if !target
+                        .trailing
+                        .is_empty()
+                    {
+                        println!(
+                            "{}",
+                            format!(
+                                "Replayed {} commit/s on top",
+                                target
+                                    .trailing
+                                    .len()
+                            )
+                            .dimmed()
+                        );
+                    }
+
+                    return Ok(());
+                }
+                ResponseActions::Regen => break,
+                ResponseActions::Edit => {
+                    raw_commits = edit_commits(
+                        &raw_commits,
+                        Some(&PlanContext {
+                            staging_type: &state
+                                .settings
+                                .staging_type,
+                            diffs: &state.diffs,
+                        }),
+                    )?;
+
+                    if raw_commits.is_empty() {
+                        return Ok(());
+                    }
+
+                    print::commits::response_commits(
+                        &raw_commits,
+                        hunks,
+                    )?;
+                }
+                ResponseActions::Quit => return Ok(()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::{
+        StagingStrategy,
+        rewrite::load_rewrite,
+        tests::{repo_init, write_commit_file},
+    };
+
+    /// base, then one commit touching a.txt and
+    /// b.txt to split, then one trailing commit
+    fn split_repo() -> (tempfile::TempDir, GitRepo, SplitTarget) {
+        let (td, repo) = repo_init();
+
+        write_commit_file(&repo, "a.txt", "a\n", "add a");
+
+        // staged first so it lands in the same commit
+        std::fs::write(
+            td.path()
+                .join("b.txt"),
+            "b\n",
+        )
+        .unwrap();
+        let mut index = repo
+            .index()
+            .unwrap();
+        index
+            .add_path(std::path::Path::new("b.txt"))
+            .unwrap();
+        index
+            .write()
+            .unwrap();
+
+        let target = write_commit_file(
+            &repo,
+            "a.txt",
+            "aa\n",
+            "change a, add b",
+        );
+        write_commit_file(&repo, "c.txt", "c\n", "add c");
+
+        let target =
+            split_target(&repo, &target.to_string()).unwrap();
+
+        let git = GitRepo {
+            workdir: td.path().to_owned(),
+            repo,
+        };
+
+        (td, git, target)
+    }
+
+    fn commit_diffs(
+        git: &GitRepo,
+        target: &SplitTarget,
+    ) -> Vec<FileDiff> {
+        get_diffs_from_commits(
+            &git.repo,
+            &git.workdir,
+            target.parent,
+            Some(git2::Oid::from_str(&target.commit).unwrap()),
+        )
+        .unwrap()
+        .files
+    }
+
+    fn commit(
+        file: &str,
+        message: &str,
+    ) -> GitCommit {
+        GitCommit {
+            files: vec![file.to_owned()],
+            hunk_ids: Vec::new(),
+            line_ids: Vec::new(),
+            message: message.to_owned(),
+        }
+    }
+
+    fn settings() -> Settings {
+        Settings {
+            staging_type: StagingStrategy::AtomicCommits,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_apply_split() {
+        let (_td, git, target) = split_repo();
+
+        assert_eq!(
+            target
+                .trailing
+                .len(),
+            1
+        );
+
+        let mut diffs = commit_diffs(&git, &target);
+
+        let oids = apply_split(
+            &git,
+            &settings(),
+            &target,
+            &[commit("a.txt", "change a"), commit("b.txt", "add b")],
+            &mut diffs,
+        )
+        .unwrap();
+
+        assert_eq!(oids.len(), 2);
+
+        // the trailing commit is replayed on top,
+        // with the same tree as before
+        let head = git
+            .repo
+            .head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+
+        assert_eq!(head.summary(), Some("add c"));
+        assert_eq!(
+            head.parent_id(0)
+                .unwrap()
+                .to_string(),
+            oids[1]
+        );
+        assert_eq!(
+            head.tree_id(),
+            git.repo
+                .find_commit(
+                    git2::Oid::from_str(&target.original_head)
+                        .unwrap()
+                )
+                .unwrap()
+                .tree_id()
+        );
+        assert!(
+            load_rewrite(&git.repo)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_apply_split_rolls_back() {
+        let (_td, git, target) = split_repo();
+
+        let mut diffs = commit_diffs(&git, &target);
+
+        // b.txt is left out, so the new tree
+        // doesnt match the original commit
+        let res = apply_split(
+            &git,
+            &settings(),
+            &target,
+            &[commit("a.txt", "change a")],
+            &mut diffs,
+        );
+
+        assert!(res.is_err());
+        assert_eq!(
+            get_head_repo(&git.repo)
+                .unwrap()
+                .to_string(),
+            target.original_head
+        );
+        assert!(is_workdir_clean(&git.repo).unwrap());
+        assert!(
+            load_rewrite(&git.repo)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
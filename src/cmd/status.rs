@@ -1,10 +1,15 @@
 use crate::{
     args::{GlobalArgs, StatusArgs},
+    cmd::commit::diff_strategy,
     git::{
-        DiffStrategy, StatusStrategy, diffs::get_diffs_from_statuses,
-        status::get_status,
+        Diffs, StatusStrategy,
+        diffs::get_diffs_from_statuses,
+        status::{StatusItemType, get_status},
+    },
+    print::{
+        json::{FileTokens, StatusOutput, TokenEstimates},
+        status,
     },
-    print::status,
     requests::tokens::estimate_token_count,
     state::State,
 };
@@ -32,6 +37,24 @@ pub fn run(
         .settings
         .provider;
 
+    if global.is_json() {
+        let diffs = get_diffs(&state)?;
+
+        let tokens = estimate_tokens(&diffs);
+
+        return crate::print::json::emit(&StatusOutput {
+            provider: provider.to_string(),
+            model: state
+                .settings
+                .providers
+                .get_model(&provider),
+            branch: &staged.branch_name,
+            staged: &staged.statuses,
+            working_dir: &working_dir.statuses,
+            tokens,
+        });
+    }
+
     status::provider_info(
         &provider,
         &state
@@ -46,59 +69,72 @@ pub fn run(
         global.compact,
     )?;
 
+    if working_dir
+        .statuses
+        .iter()
+        .chain(&staged.statuses)
+        .any(|s| matches!(s.status, StatusItemType::Conflicted))
+    {
+        println!("Conflicts found, run `gai resolve` to fix them up");
+    }
+
     if args.verbose {
-        let mut diff_strategy = DiffStrategy {
-            ..Default::default()
-        };
+        let diffs = get_diffs(&state)?;
 
-        if let Some(ref files_to_truncate) = state
-            .settings
-            .context
-            .truncate_files
-        {
-            diff_strategy.truncated_files =
-                files_to_truncate.to_owned();
+        for file in estimate_tokens(&diffs).files {
+            // temp println
+            // TODO: remove, use status::repo_status
+            println!("file:{} tokens:{}", file.path, file.tokens);
         }
+    }
 
-        if let Some(ref files_to_ignore) = state
-            .settings
-            .context
-            .ignore_files
-        {
-            diff_strategy.ignored_files = files_to_ignore.to_owned();
-        }
+    Ok(())
+}
 
-        let diffs = get_diffs_from_statuses(
-            &state.git.repo,
-            &state.git.workdir,
-            &diff_strategy,
-        )?;
+fn get_diffs(state: &State) -> anyhow::Result<Diffs> {
+    let diff_strategy =
+        diff_strategy(&state.settings, StatusStrategy::default());
+
+    get_diffs_from_statuses(
+        &state.git.repo,
+        &state.git.workdir,
+        &diff_strategy,
+    )
+}
 
-        for file in &diffs.files {
-            let mut txt = String::new();
+/// per file token estimates
+fn estimate_tokens(diffs: &Diffs) -> TokenEstimates {
+    let mut estimates = TokenEstimates::default();
 
-            // mimicing what gets sent to the prompt
-            // ideally, this is done near the request
-            for hunk in &file.hunks {
+    for file in &diffs.files {
+        let mut txt = String::new();
+
+        // mimicing what gets sent to the prompt
+        // ideally, this is done near the request
+        for hunk in &file.hunks {
+            txt.push_str(&format!(
+                "HunkId[{}:{}]\n",
+                file.path, hunk.id
+            ));
+
+            for line in &hunk.lines {
                 txt.push_str(&format!(
-                    "HunkId[{}:{}]\n",
-                    file.path, hunk.id
+                    "{}{}\n",
+                    line.line_type, line.content
                 ));
-
-                for line in &hunk.lines {
-                    txt.push_str(&format!(
-                        "{}{}\n",
-                        line.line_type, line.content
-                    ));
-                }
             }
-
-            let token_estimate = estimate_token_count(&txt);
-            // temp println
-            // TODO: remove, use status::repo_status
-            println!("file:{} tokens:{}", file.path, token_estimate);
         }
+
+        let tokens = estimate_token_count(&txt);
+
+        estimates.total += tokens;
+        estimates
+            .files
+            .push(FileTokens {
+                path: file.path.to_owned(),
+                tokens,
+            });
     }
 
-    Ok(())
+    estimates
 }
@@ -0,0 +1,190 @@
+use crate::{
+    args::{GlobalArgs, SyncArgs},
+    cmd::resolve::resolve_conflicts,
+    git::sync::{
+        SyncOutcome, abort_sync, continue_sync, fetch_upstream,
+        get_local_commits, get_sync_info, start_sync,
+    },
+    print::{
+        self, json::SyncOutput, menu::Menu, spinner::SpinnerBuilder,
+    },
+    state::State,
+};
+
+#[derive(Debug, Clone)]
+enum SyncActions {
+    Sync,
+    Quit,
+}
+
+const SYNC_OPTS: [(SyncActions, char, &str); 2] = [
+    (SyncActions::Sync, 'y', "sync with upstream"),
+    (SyncActions::Quit, 'q', "quit"),
+];
+
+#[derive(Debug, Clone)]
+enum ContinueActions {
+    Continue,
+    Stop,
+}
+
+const CONTINUE_OPTS: [(ContinueActions, char, &str); 2] = [
+    (ContinueActions::Continue, 'y', "continue syncing"),
+    (ContinueActions::Stop, 'q', "stop here, continue later"),
+];
+
+pub fn run(
+    args: &SyncArgs,
+    global: &GlobalArgs,
+) -> anyhow::Result<()> {
+    let state = State::new(
+        global
+            .config
+            .as_deref(),
+        global,
+    )?;
+
+    let repo = &state.git.repo;
+
+    if args.abort {
+        abort_sync(repo)?;
+
+        if global.is_json() {
+            return print::json::emit(&SyncOutput::status("aborted"));
+        }
+
+        println!("Sync aborted");
+        return Ok(());
+    }
+
+    let outcome = if args.resume {
+        continue_sync(repo)?
+    } else {
+        if !args.no_fetch {
+            let handle = SpinnerBuilder::new()
+                .text("Fetching upstream")
+                .hidden(global.is_json())
+                .start();
+
+            if let Err(e) = fetch_upstream(repo) {
+                handle.error();
+                return Err(e);
+            }
+
+            handle.done();
+        }
+
+        let info = get_sync_info(repo)?;
+        let local_commits = get_local_commits(repo, &info)?;
+
+        if info
+            .divergence
+            .behind
+            == 0
+        {
+            if global.is_json() {
+                return print::json::emit(&SyncOutput {
+                    branch: Some(info.branch),
+                    upstream: Some(info.upstream),
+                    ahead: info
+                        .divergence
+                        .ahead,
+                    ..SyncOutput::status("up_to_date")
+                });
+            }
+
+            println!("Already up to date with {}", info.upstream);
+            return Ok(());
+        }
+
+        if !global.is_json() {
+            print::sync::sync_status(&info, &local_commits)?;
+
+            if !args.skip_confirmation
+                && matches!(
+                    Menu::new("What do you want to do?", &SYNC_OPTS)
+                        .render()?,
+                    SyncActions::Quit
+                )
+            {
+                return Ok(());
+            }
+        }
+
+        let outcome = start_sync(repo, &info)?;
+
+        if global.is_json() {
+            return print::json::emit(&SyncOutput {
+                branch: Some(info.branch),
+                upstream: Some(info.upstream),
+                ahead: info
+                    .divergence
+                    .ahead,
+                behind: info
+                    .divergence
+                    .behind,
+                ..SyncOutput::from(&outcome)
+            });
+        }
+
+        outcome
+    };
+
+    if global.is_json() {
+        return print::json::emit(&SyncOutput::from(&outcome));
+    }
+
+    handle_outcome(&state, args, outcome)
+}
+
+fn handle_outcome(
+    state: &State,
+    args: &SyncArgs,
+    mut outcome: SyncOutcome,
+) -> anyhow::Result<()> {
+    loop {
+        match outcome {
+            SyncOutcome::UpToDate => {
+                println!("Already up to date");
+            }
+            SyncOutcome::FastForward => {
+                println!("Fast-forwarded to upstream");
+            }
+            SyncOutcome::Rebased(ref oids) => {
+                println!(
+                    "Synced, replayed {} commit/s onto upstream",
+                    oids.len()
+                );
+            }
+            SyncOutcome::Conflicts(ref paths) => {
+                println!("Stopped on conflicts in:");
+                for path in paths {
+                    println!("  {}", path);
+                }
+
+                if args.no_resolve || !resolve_conflicts(state)? {
+                    println!(
+                        "Resolve the remaining conflicts, then run `gai sync --continue`, or `gai sync --abort`"
+                    );
+                    return Ok(());
+                }
+
+                let selected = Menu::new(
+                    "All conflicts resolved, continue?",
+                    &CONTINUE_OPTS,
+                )
+                .render()?;
+
+                if matches!(selected, ContinueActions::Stop) {
+                    println!("Run `gai sync --continue` when ready");
+                    return Ok(());
+                }
+
+                outcome = continue_sync(&state.git.repo)?;
+                continue;
+            }
+        }
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,145 @@
+use std::fs;
+
+use git2::Oid;
+use owo_colors::OwoColorize;
+
+use crate::{
+    args::{GlobalArgs, TodoArgs},
+    cmd::rebase::{gen_plan, plan_log_strs},
+    git::{
+        commit::find_parent_commit,
+        diffs::get_diffs_from_commits,
+        errors::GitError,
+        log::{Logs, get_log},
+        todo::{TodoCommand, format_todo, parse_todo},
+    },
+    print::{self, menu::Menu},
+    providers::provider::ProviderKind,
+    responses::rebase_plan::plan_to_todo,
+    schema::SchemaSettings,
+    state::State,
+};
+
+#[derive(Debug, Clone)]
+enum TodoActions {
+    Write,
+    Regen,
+    Quit,
+}
+
+/// gai as GIT_SEQUENCE_EDITOR, plans the picks git wrote
+/// and writes the plan back for git to run. quitting
+/// leaves the todo as git wrote it
+pub fn run(
+    args: &TodoArgs,
+    global: &GlobalArgs,
+) -> anyhow::Result<()> {
+    let state = State::new(
+        global
+            .config
+            .as_deref(),
+        global,
+    )?;
+
+    let steps = parse_todo(&fs::read_to_string(&args.file)?)?;
+
+    // git writes a noop todo when there is nothing to do
+    if steps.is_empty() {
+        return Ok(());
+    }
+
+    if steps
+        .iter()
+        .any(|s| {
+            s.command != TodoCommand::Pick || s.message.is_some()
+        })
+    {
+        return Err(GitError::Generic(
+            "gai only plans a fresh todo, this one was already edited"
+                .to_owned(),
+        )
+        .into());
+    }
+
+    let mut logs = Logs::default();
+
+    for step in &steps {
+        let oid = state
+            .git
+            .repo
+            .revparse_single(&step.commit)?
+            .peel_to_commit()?
+            .id();
+
+        logs.git_logs
+            .push(get_log(&state.git, &oid.to_string())?);
+    }
+
+    let first = &logs.git_logs[0].commit_hash;
+    let last = &logs.git_logs[logs.git_logs.len() - 1].commit_hash;
+
+    let diffs = get_diffs_from_commits(
+        &state.git.repo,
+        &state.git.workdir,
+        find_parent_commit(&state.git.repo, first)?,
+        Some(Oid::from_str(last)?),
+    )?;
+
+    let schema_settings = if matches!(
+        state
+            .settings
+            .provider,
+        ProviderKind::OpenAI
+    ) {
+        SchemaSettings::default()
+            .additional_properties(false)
+            .allow_min_max_ints(true)
+    } else {
+        SchemaSettings::default().allow_min_max_ints(true)
+    };
+
+    print::status::provider_info(
+        &state
+            .settings
+            .provider,
+        &state
+            .settings
+            .providers,
+    )?;
+
+    let log_strs = plan_log_strs(&logs);
+
+    let opts = [
+        (TodoActions::Write, 'y', "write the plan to the todo"),
+        (TodoActions::Regen, 'r', "regenerate operations"),
+        (TodoActions::Quit, 'q', "keep the todo as is"),
+    ];
+
+    loop {
+        let ops = gen_plan(
+            &state.settings,
+            &diffs,
+            &log_strs,
+            &schema_settings,
+        )?;
+
+        match Menu::new("What do you want to do?", &opts).render()? {
+            TodoActions::Write => {
+                fs::write(
+                    &args.file,
+                    format_todo(&plan_to_todo(&ops, &logs.git_logs)),
+                )?;
+
+                println!(
+                    "{}",
+                    format!("Wrote {} step/s to the todo", ops.len())
+                        .dimmed()
+                );
+
+                return Ok(());
+            }
+            TodoActions::Regen => continue,
+            TodoActions::Quit => return Ok(()),
+        }
+    }
+}
@@ -0,0 +1,266 @@
+// gai absorb, route uncommitted hunks into the
+// recent commits they belong to as fixup! commits
+//
+// blame decides when it can, a hunk whose touched
+// lines all come from one candidate commit belongs
+// to that commit. anything else is left for the llm
+
+use std::path::Path;
+
+use git2::{BlameOptions, Oid, Repository};
+
+use super::{
+    diffs::{DiffLineType, FileDiff, Hunk},
+    utils::get_head_repo,
+};
+
+/// a commit hunks can be absorbed into
+#[derive(Debug, Clone)]
+pub struct AbsorbCandidate {
+    pub hash: String,
+    pub summary: String,
+}
+
+/// the commits blame found for one hunk,
+/// only ones that are candidates
+#[derive(Debug, Clone)]
+pub struct HunkOwners {
+    pub hunk_id: String,
+    pub owners: Vec<String>,
+}
+
+/// where absorb looks by default, the merge
+/// base with the upstream if there is one
+pub fn default_base(repo: &Repository) -> Option<Oid> {
+    let head = get_head_repo(repo).ok()?;
+    let upstream = repo
+        .revparse_single("@{upstream}")
+        .ok()?
+        .id();
+
+    repo.merge_base(head, upstream)
+        .ok()
+}
+
+/// commits from HEAD back to base, newest first.
+/// stops at merges since those cant be replayed,
+/// existing fixups are skipped since they arent
+/// real targets
+pub fn candidate_commits(
+    repo: &Repository,
+    base: Option<Oid>,
+    max: usize,
+) -> anyhow::Result<Vec<AbsorbCandidate>> {
+    let mut res = Vec::new();
+    let mut oid = get_head_repo(repo)?;
+
+    while res.len() < max && Some(oid) != base {
+        let commit = repo.find_commit(oid)?;
+
+        if commit.parent_count() > 1 {
+            break;
+        }
+
+        let summary = commit
+            .summary()
+            .unwrap_or_default()
+            .to_owned();
+
+        if !summary.starts_with("fixup! ") {
+            res.push(AbsorbCandidate {
+                hash: oid.to_string(),
+                summary,
+            });
+        }
+
+        // the root can be absorbed into too
+        if commit.parent_count() == 0 {
+            break;
+        }
+
+        oid = commit.parent_id(0)?;
+    }
+
+    Ok(res)
+}
+
+/// blame the lines each hunk touches at HEAD,
+/// deleted lines vote for the commit that last
+/// changed them, pure additions use the lines
+/// right around them
+pub fn hunk_owners(
+    repo: &Repository,
+    file_diff: &FileDiff,
+    candidates: &[AbsorbCandidate],
+) -> anyhow::Result<Vec<HunkOwners>> {
+    let hunk_id =
+        |hunk: &Hunk| format!("{}:{}", file_diff.path, hunk.id);
+
+    let mut opts = BlameOptions::new();
+    opts.newest_commit(get_head_repo(repo)?);
+
+    // new or untracked files have nothing to blame
+    let blame = if file_diff.untracked {
+        None
+    } else {
+        repo.blame_file(Path::new(&file_diff.path), Some(&mut opts))
+            .ok()
+    };
+
+    let mut res = Vec::new();
+
+    for hunk in &file_diff.hunks {
+        let mut owners: Vec<String> = Vec::new();
+
+        if let Some(ref blame) = blame {
+            for line in touched_lines(hunk) {
+                let Some(blamed) = blame.get_line(line as usize)
+                else {
+                    continue;
+                };
+
+                let oid = blamed
+                    .final_commit_id()
+                    .to_string();
+
+                if candidates
+                    .iter()
+                    .any(|c| c.hash == oid)
+                    && !owners.contains(&oid)
+                {
+                    owners.push(oid);
+                }
+            }
+        }
+
+        res.push(HunkOwners {
+            hunk_id: hunk_id(hunk),
+            owners,
+        });
+    }
+
+    Ok(res)
+}
+
+/// old line numbers a hunk touches
+fn touched_lines(hunk: &Hunk) -> Vec<u32> {
+    let deleted: Vec<u32> = hunk
+        .lines
+        .iter()
+        .filter(|l| l.line_type == DiffLineType::Delete)
+        .filter_map(|l| {
+            l.position
+                .old_lineno
+        })
+        .collect();
+
+    if !deleted.is_empty() {
+        return deleted;
+    }
+
+    let mut res = Vec::new();
+
+    for (i, line) in hunk
+        .lines
+        .iter()
+        .enumerate()
+    {
+        if line.line_type != DiffLineType::Add {
+            continue;
+        }
+
+        let neighbours = [
+            i.checked_sub(1)
+                .and_then(|i| hunk.lines.get(i)),
+            hunk.lines
+                .get(i + 1),
+        ];
+
+        for n in neighbours
+            .into_iter()
+            .flatten()
+        {
+            if n.line_type == DiffLineType::None
+                && let Some(old) = n
+                    .position
+                    .old_lineno
+                && !res.contains(&old)
+            {
+                res.push(old);
+            }
+        }
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::{
+        DiffStrategy, StatusStrategy,
+        diffs::get_diffs_from_statuses,
+        tests::{repo_init, write_commit_file},
+    };
+
+    #[test]
+    fn test_hunk_owners() {
+        let (_dir, repo) = repo_init();
+
+        let a = write_commit_file(
+            &repo,
+            "a.txt",
+            "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n",
+            "add a",
+        );
+        let b = write_commit_file(
+            &repo,
+            "a.txt",
+            "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\nb\n",
+            "change last line",
+        );
+
+        // one hunk per end of the file
+        std::fs::write(
+            repo.workdir()
+                .unwrap()
+                .join("a.txt"),
+            "one\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\nbb\n",
+        )
+        .unwrap();
+
+        let candidates = candidate_commits(&repo, None, 10).unwrap();
+
+        assert_eq!(candidates.len(), 3);
+        assert_eq!(candidates[0].hash, b.to_string());
+
+        let diffs = get_diffs_from_statuses(
+            &repo,
+            repo.workdir()
+                .unwrap(),
+            &DiffStrategy {
+                status_strategy: StatusStrategy::WorkingDir,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let owners =
+            hunk_owners(&repo, &diffs.files[0], &candidates).unwrap();
+
+        assert_eq!(owners.len(), 2);
+        assert_eq!(owners[0].owners, vec![a.to_string()]);
+        assert_eq!(owners[1].owners, vec![b.to_string()]);
+
+        // outside the candidates, nobody owns it
+        let owners =
+            hunk_owners(&repo, &diffs.files[0], &candidates[..1])
+                .unwrap();
+
+        assert!(
+            owners[0]
+                .owners
+                .is_empty()
+        );
+    }
+}
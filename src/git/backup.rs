@@ -0,0 +1,267 @@
+// backups of branch tips, saved under refs/gai/backup
+// right before a history rewrite moves anything
+//
+// libgit2 keeps no reflog for refs/gai, so what the
+// backup was taken from goes in a sidecar next to the
+// rewrite state, .git/gai/backup/<name>.json. names are
+// the utc time they were taken, which also sorts them
+// oldest first:
+//
+//   refs/gai/backup/20260118-142301
+//   refs/gai/backup/20260118-142301-2
+
+use std::{fs, path::PathBuf};
+
+use chrono::Utc;
+use git2::{Oid, Repository};
+use serde::{Deserialize, Serialize};
+
+use super::errors::GitError;
+
+const BACKUP_PREFIX: &str = "refs/gai/backup/";
+
+#[derive(Debug, Clone)]
+pub struct Backup {
+    /// the name after refs/gai/backup/
+    pub name: String,
+    pub commit: Oid,
+    pub summary: String,
+
+    /// short branch name, None when HEAD was
+    /// detached or the sidecar is missing
+    pub branch: Option<String>,
+
+    /// what the backup was taken for
+    pub reason: String,
+}
+
+/// the sidecar, everything the ref cant hold
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BackupInfo {
+    branch: Option<String>,
+    reason: String,
+}
+
+/// saves oid as a new backup of branch, a second
+/// one in the same second gets a -2, -3.. suffix
+pub fn create_backup(
+    repo: &Repository,
+    oid: Oid,
+    branch: Option<&str>,
+    reason: &str,
+) -> anyhow::Result<String> {
+    let stamp = Utc::now()
+        .format("%Y%m%d-%H%M%S")
+        .to_string();
+
+    let mut name = stamp.to_owned();
+    let mut n = 1;
+
+    while repo
+        .find_reference(&format!("{BACKUP_PREFIX}{name}"))
+        .is_ok()
+    {
+        n += 1;
+        name = format!("{stamp}-{n}");
+    }
+
+    let info = BackupInfo {
+        branch: branch.map(|b| {
+            b.trim_start_matches("refs/heads/")
+                .to_owned()
+        }),
+        reason: reason.to_owned(),
+    };
+
+    // sidecar first, a ref without one
+    // still lists, just without a branch
+    fs::create_dir_all(info_dir(repo))?;
+    fs::write(
+        info_path(repo, &name),
+        serde_json::to_string_pretty(&info)?,
+    )?;
+
+    repo.reference(
+        &format!("{BACKUP_PREFIX}{name}"),
+        oid,
+        false,
+        reason,
+    )?;
+
+    Ok(name)
+}
+
+/// every backup, oldest first
+pub fn list_backups(
+    repo: &Repository
+) -> anyhow::Result<Vec<Backup>> {
+    let mut res = Vec::new();
+
+    for reference in
+        repo.references_glob(&format!("{BACKUP_PREFIX}*"))?
+    {
+        let reference = reference?;
+
+        let (Some(name), Ok(commit)) =
+            (reference.name(), reference.peel_to_commit())
+        else {
+            continue;
+        };
+
+        res.push(backup(repo, name, &commit));
+    }
+
+    res.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(res)
+}
+
+/// a backup, by name or by its full ref
+pub fn find_backup(
+    repo: &Repository,
+    name: &str,
+) -> anyhow::Result<Backup> {
+    let name = name.trim_start_matches(BACKUP_PREFIX);
+
+    let commit = repo
+        .find_reference(&format!("{BACKUP_PREFIX}{name}"))
+        .and_then(|r| r.peel_to_commit())
+        .map_err(|_| {
+            GitError::Generic(format!("no backup named {name}"))
+        })?;
+
+    Ok(backup(repo, name, &commit))
+}
+
+/// deletes all but the newest keep backups,
+/// returns the names it deleted
+pub fn prune_backups(
+    repo: &Repository,
+    keep: usize,
+) -> anyhow::Result<Vec<String>> {
+    let backups = list_backups(repo)?;
+    let count = backups
+        .len()
+        .saturating_sub(keep);
+
+    let mut res = Vec::new();
+
+    for backup in backups
+        .into_iter()
+        .take(count)
+    {
+        repo.find_reference(&format!(
+            "{BACKUP_PREFIX}{}",
+            backup.name
+        ))?
+        .delete()?;
+
+        let info = info_path(repo, &backup.name);
+
+        if info.exists() {
+            fs::remove_file(info)?;
+        }
+
+        res.push(backup.name);
+    }
+
+    Ok(res)
+}
+
+fn backup(
+    repo: &Repository,
+    name: &str,
+    commit: &git2::Commit,
+) -> Backup {
+    let name = name.trim_start_matches(BACKUP_PREFIX);
+
+    let info: BackupInfo = fs::read_to_string(info_path(repo, name))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+
+    Backup {
+        name: name.to_owned(),
+        commit: commit.id(),
+        summary: commit
+            .summary()
+            .unwrap_or_default()
+            .to_owned(),
+        branch: info.branch,
+        reason: info.reason,
+    }
+}
+
+fn info_dir(repo: &Repository) -> PathBuf {
+    repo.path()
+        .join("gai")
+        .join("backup")
+}
+
+fn info_path(
+    repo: &Repository,
+    name: &str,
+) -> PathBuf {
+    info_dir(repo).join(format!("{name}.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::tests::{repo_init, write_commit_file};
+
+    #[test]
+    fn test_backups() {
+        let (_dir, repo) = repo_init();
+
+        let a = write_commit_file(&repo, "a.txt", "a\n", "add a");
+        let b = write_commit_file(&repo, "a.txt", "b\n", "change a");
+
+        let first = create_backup(
+            &repo,
+            a,
+            Some("refs/heads/feature"),
+            "gai rebase: backup before rewrite",
+        )
+        .unwrap();
+        let second = create_backup(&repo, b, None, "test").unwrap();
+        let third = create_backup(&repo, b, None, "test").unwrap();
+
+        assert_ne!(first, second);
+        assert_ne!(second, third);
+
+        let backups = list_backups(&repo).unwrap();
+
+        assert_eq!(backups.len(), 3);
+        assert_eq!(backups[0].commit, a);
+        assert_eq!(backups[0].summary, "add a");
+        assert_eq!(
+            backups[0]
+                .branch
+                .as_deref(),
+            Some("feature")
+        );
+        assert_eq!(
+            backups[0].reason,
+            "gai rebase: backup before rewrite"
+        );
+
+        let found = find_backup(&repo, &second).unwrap();
+
+        assert_eq!(found.commit, b);
+        assert_eq!(found.branch, None);
+        assert!(find_backup(&repo, "nope").is_err());
+
+        assert_eq!(
+            prune_backups(&repo, 1).unwrap(),
+            vec![first.to_owned(), second]
+        );
+        assert_eq!(
+            list_backups(&repo)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(!info_path(&repo, &first).exists());
+    }
+}
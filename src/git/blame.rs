@@ -0,0 +1,487 @@
+// blame for a region of a file, then keep
+// walking back through the hunks that touched it
+// so we get more than just the last change
+
+use std::{
+    collections::{HashSet, VecDeque},
+    path::Path,
+};
+
+use chrono::DateTime;
+use git2::{BlameOptions, Oid, Repository};
+
+use super::{commit::get_commit_diff, errors::GitError};
+
+/// lines kept per hunk, anything past
+/// this isnt worth the tokens
+const MAX_HUNK_LINES: usize = 60;
+
+/// path with an optional line range
+/// ex. src/main.rs:10-20 or src/main.rs:12
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlameTarget {
+    pub path: String,
+
+    /// 1 based and inclusive, none
+    /// is the whole file
+    pub lines: Option<(usize, usize)>,
+}
+
+/// a commit that touched the region
+#[derive(Clone, Debug)]
+pub struct RegionCommit {
+    pub commit_hash: String,
+    pub message: String,
+    pub author: String,
+    pub date: String,
+    pub timestamp: i64,
+
+    /// path in that commit, can differ
+    /// from the target after renames
+    pub path: String,
+
+    /// hunks of this commit that
+    /// overlap the region
+    pub hunks: Vec<String>,
+}
+
+/// the region at a given commit,
+/// what we run blame on
+struct Region {
+    commit: Oid,
+    path: String,
+    start: usize,
+    end: usize,
+}
+
+/// a hunk that overlaps the region
+/// plus where it came from in the parent
+struct RegionHunk {
+    text: String,
+    old_path: String,
+    old_start: usize,
+    old_lines: usize,
+}
+
+impl TryFrom<&str> for BlameTarget {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        // only treat the suffix as a range if
+        // it parses, paths can have colons too
+        if let Some((path, range)) = value.rsplit_once(':')
+            && let Some(lines) = parse_range(range)
+        {
+            let (start, end) = lines?;
+
+            return Ok(Self {
+                path: path.to_owned(),
+                lines: Some((start, end)),
+            });
+        }
+
+        Ok(Self {
+            path: value.to_owned(),
+            lines: None,
+        })
+    }
+}
+
+impl std::fmt::Display for BlameTarget {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self.lines {
+            Some((start, end)) if start == end => {
+                write!(f, "{}:{}", self.path, start)
+            }
+            Some((start, end)) => {
+                write!(f, "{}:{}-{}", self.path, start, end)
+            }
+            None => write!(f, "{}", self.path),
+        }
+    }
+}
+
+/// none if this doesnt look like a range
+/// at all, an error if it does but is invalid
+fn parse_range(
+    range: &str
+) -> Option<anyhow::Result<(usize, usize)>> {
+    let (start, end) = match range.split_once('-') {
+        Some((start, end)) => (start, end),
+        None => (range, range),
+    };
+
+    let start = start
+        .trim()
+        .parse::<usize>()
+        .ok()?;
+    let end = end
+        .trim()
+        .parse::<usize>()
+        .ok()?;
+
+    if start == 0 || end < start {
+        return Some(Err(GitError::Generic(format!(
+            "invalid line range {}, lines start at 1",
+            range
+        ))
+        .into()));
+    }
+
+    Some(Ok((start, end)))
+}
+
+/// the region as it is at HEAD, numbered
+/// returns the text and the line range
+/// actually used (whole file if none)
+pub fn get_region_text(
+    repo: &Repository,
+    target: &BlameTarget,
+) -> anyhow::Result<(String, (usize, usize))> {
+    let tree = repo
+        .head()?
+        .peel_to_tree()?;
+
+    let blob = tree
+        .get_path(Path::new(&target.path))?
+        .to_object(repo)?
+        .peel_to_blob()?;
+
+    let content = String::from_utf8_lossy(blob.content());
+    let line_count = content
+        .lines()
+        .count();
+
+    if line_count == 0 {
+        return Err(GitError::Generic(format!(
+            "{} is empty",
+            target.path
+        ))
+        .into());
+    }
+
+    let (start, end) = target
+        .lines
+        .unwrap_or((1, line_count));
+
+    if start > line_count {
+        return Err(GitError::Generic(format!(
+            "{} only has {} lines",
+            target.path, line_count
+        ))
+        .into());
+    }
+
+    let end = end.min(line_count);
+
+    let text = content
+        .lines()
+        .enumerate()
+        .skip(start - 1)
+        .take(end - start + 1)
+        .map(|(idx, line)| format!("{:>5} {}", idx + 1, line))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok((text, (start, end)))
+}
+
+/// blame the region at HEAD, then follow each
+/// blamed hunk back into its parent and blame
+/// that region again, breadth first so the
+/// most recent changes come first. stops after
+/// max_commits, newest first
+pub fn blame_region(
+    repo: &Repository,
+    path: &str,
+    lines: (usize, usize),
+    max_commits: usize,
+) -> anyhow::Result<Vec<RegionCommit>> {
+    let head = super::utils::get_head_repo(repo)?;
+
+    let mut queue = VecDeque::from([Region {
+        commit: head,
+        path: path.to_owned(),
+        start: lines.0,
+        end: lines.1,
+    }]);
+
+    let mut visited = HashSet::new();
+    let mut res = Vec::new();
+
+    while let Some(region) = queue.pop_front() {
+        if res.len() >= max_commits {
+            break;
+        }
+
+        let mut opts = BlameOptions::new();
+
+        opts.newest_commit(region.commit)
+            .min_line(region.start)
+            .max_line(region.end);
+
+        // the file might not exist that far
+        // back under this name, thats fine
+        let Ok(blame) =
+            repo.blame_file(Path::new(&region.path), Some(&mut opts))
+        else {
+            continue;
+        };
+
+        for hunk in blame.iter() {
+            let oid = hunk.final_commit_id();
+
+            if !visited.insert(oid) {
+                continue;
+            }
+
+            let path = hunk
+                .path()
+                .and_then(|p| p.to_str())
+                .unwrap_or(&region.path)
+                .to_owned();
+
+            let start = hunk.orig_start_line();
+            let end = start
+                + hunk
+                    .lines_in_hunk()
+                    .max(1)
+                - 1;
+
+            let region_hunks =
+                get_region_hunks(repo, oid, &path, start, end)?;
+
+            let commit = repo.find_commit(oid)?;
+
+            // keep going through the parent, with
+            // the old side of every hunk we found
+            if !hunk.is_boundary() && commit.parent_count() > 0 {
+                let parent = commit.parent_id(0)?;
+
+                for h in &region_hunks {
+                    if h.old_lines == 0 {
+                        continue;
+                    }
+
+                    queue.push_back(Region {
+                        commit: parent,
+                        path: h
+                            .old_path
+                            .to_owned(),
+                        start: h.old_start,
+                        end: h.old_start + h.old_lines - 1,
+                    });
+                }
+            }
+
+            res.push(region_commit(
+                &commit,
+                path,
+                region_hunks
+                    .into_iter()
+                    .map(|h| h.text)
+                    .collect(),
+            ));
+
+            if res.len() >= max_commits {
+                break;
+            }
+        }
+    }
+
+    res.sort_by(|a, b| {
+        b.timestamp
+            .cmp(&a.timestamp)
+    });
+
+    Ok(res)
+}
+
+fn region_commit(
+    commit: &git2::Commit,
+    path: String,
+    hunks: Vec<String>,
+) -> RegionCommit {
+    let author = commit.author();
+    let timestamp = author
+        .when()
+        .seconds();
+
+    RegionCommit {
+        commit_hash: commit
+            .id()
+            .to_string(),
+        message: String::from_utf8_lossy(commit.message_bytes())
+            .trim()
+            .to_owned(),
+        author: author
+            .name()
+            .unwrap_or("unknown author")
+            .to_owned(),
+        date: DateTime::from_timestamp(timestamp, 0)
+            .map(|dt| {
+                dt.format("%m/%d/%Y %H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_default(),
+        timestamp,
+        path,
+        hunks,
+    }
+}
+
+/// hunks of a commit for a file that overlap
+/// start..=end on the new side, if none do
+/// (blame can be off with moved code) all of
+/// the files hunks are used instead
+fn get_region_hunks(
+    repo: &Repository,
+    oid: Oid,
+    path: &str,
+    start: usize,
+    end: usize,
+) -> anyhow::Result<Vec<RegionHunk>> {
+    let diff = get_commit_diff(repo, oid)?;
+
+    let mut overlapping = Vec::new();
+    let mut all = Vec::new();
+
+    for idx in 0..diff.deltas().len() {
+        let Some(patch) = git2::Patch::from_diff(&diff, idx)? else {
+            continue;
+        };
+
+        let delta = patch.delta();
+
+        if delta
+            .new_file()
+            .path()
+            .and_then(|p| p.to_str())
+            != Some(path)
+        {
+            continue;
+        }
+
+        let old_path = delta
+            .old_file()
+            .path()
+            .and_then(|p| p.to_str())
+            .unwrap_or(path)
+            .to_owned();
+
+        for h in 0..patch.num_hunks() {
+            let (hunk, line_count) = patch.hunk(h)?;
+
+            let new_start = hunk.new_start() as usize;
+            let new_end =
+                new_start + (hunk.new_lines() as usize).max(1) - 1;
+
+            let mut text = String::from_utf8_lossy(hunk.header())
+                .trim_end()
+                .to_owned();
+
+            for l in 0..line_count.min(MAX_HUNK_LINES) {
+                let line = patch.line_in_hunk(h, l)?;
+
+                text.push('\n');
+                text.push(line.origin());
+                text.push_str(
+                    String::from_utf8_lossy(line.content())
+                        .trim_end_matches(super::utils::is_newline),
+                );
+            }
+
+            if line_count > MAX_HUNK_LINES {
+                text.push_str("\n...(truncated)");
+            }
+
+            let region_hunk = RegionHunk {
+                text,
+                old_path: old_path.to_owned(),
+                old_start: hunk.old_start() as usize,
+                old_lines: hunk.old_lines() as usize,
+            };
+
+            if new_start <= end && new_end >= start {
+                overlapping.push(region_hunk);
+            } else {
+                all.push(region_hunk);
+            }
+        }
+    }
+
+    if overlapping.is_empty() {
+        return Ok(all);
+    }
+
+    Ok(overlapping)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::tests::{repo_init, write_commit_file};
+
+    #[test]
+    fn test_blame_target() {
+        let t = BlameTarget::try_from("src/main.rs:10-20").unwrap();
+        assert_eq!(t.path, "src/main.rs");
+        assert_eq!(t.lines, Some((10, 20)));
+
+        let t = BlameTarget::try_from("src/main.rs:7").unwrap();
+        assert_eq!(t.lines, Some((7, 7)));
+        assert_eq!(t.to_string(), "src/main.rs:7");
+
+        let t = BlameTarget::try_from("src/main.rs").unwrap();
+        assert_eq!(t.lines, None);
+
+        assert!(BlameTarget::try_from("src/main.rs:20-10").is_err());
+        assert!(BlameTarget::try_from("src/main.rs:0").is_err());
+    }
+
+    #[test]
+    fn test_blame_region_walks_back() {
+        let (_td, repo) = repo_init();
+
+        let first =
+            write_commit_file(&repo, "a.txt", "a\nb\nc\n", "add a");
+        let second = write_commit_file(
+            &repo,
+            "a.txt",
+            "a\nB\nc\n",
+            "capitalize b",
+        );
+
+        // shifts the line, doesnt touch it
+        write_commit_file(
+            &repo,
+            "a.txt",
+            "x\ny\na\nB\nc\n",
+            "prepend lines",
+        );
+
+        let commits =
+            blame_region(&repo, "a.txt", (4, 4), 10).unwrap();
+
+        let hashes: Vec<String> = commits
+            .iter()
+            .map(|c| {
+                c.commit_hash
+                    .to_owned()
+            })
+            .collect();
+
+        assert_eq!(
+            hashes,
+            vec![second.to_string(), first.to_string()]
+        );
+        assert!(commits[0].hunks[0].contains("+B"));
+
+        let limited =
+            blame_region(&repo, "a.txt", (4, 4), 1).unwrap();
+        assert_eq!(limited.len(), 1);
+    }
+}
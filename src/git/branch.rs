@@ -69,6 +69,39 @@ impl BranchInfo {
     }
 }
 
+/// create a local branch at HEAD, on an
+/// unborn HEAD theres nothing to point at
+/// yet so none is returned and only HEAD
+/// should be moved
+pub fn create_branch(
+    repo: &Repository,
+    name: &str,
+) -> anyhow::Result<Option<Oid>> {
+    if repo
+        .find_branch(name, BranchType::Local)
+        .is_ok()
+    {
+        return Err(GitError::Generic(format!(
+            "branch {} already exists",
+            name
+        ))
+        .into());
+    }
+
+    let Ok(head) = repo
+        .head()
+        .and_then(|h| h.peel_to_commit())
+    else {
+        return Ok(None);
+    };
+
+    let branch = repo.branch(name, &head, false)?;
+
+    Ok(branch
+        .get()
+        .target())
+}
+
 /// finds the divergence
 /// commit from a specified
 /// spec str
@@ -52,3 +52,81 @@ pub fn force_checkout_head(repo: &Repository) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// point HEAD at a local branch without
+/// touching the working tree or index, for
+/// a branch just made from HEAD so the
+/// uncommitted changes come along
+pub fn switch_to_new_branch(
+    repo: &Repository,
+    name: &str,
+) -> anyhow::Result<()> {
+    repo.set_head(&format!("refs/heads/{}", name))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::{
+        branch::create_branch,
+        tests::{repo_init, write_commit_file},
+    };
+
+    #[test]
+    fn test_new_branch_keeps_changes() {
+        let (td, repo) = repo_init();
+
+        write_commit_file(&repo, "a.txt", "a", "add a");
+
+        // uncommitted, staged and not
+        std::fs::write(
+            td.path()
+                .join("a.txt"),
+            "changed",
+        )
+        .unwrap();
+        std::fs::write(
+            td.path()
+                .join("b.txt"),
+            "new",
+        )
+        .unwrap();
+
+        let mut index = repo
+            .index()
+            .unwrap();
+        index
+            .add_path(std::path::Path::new("b.txt"))
+            .unwrap();
+        index
+            .write()
+            .unwrap();
+
+        create_branch(&repo, "feat/x").unwrap();
+        switch_to_new_branch(&repo, "feat/x").unwrap();
+
+        assert_eq!(
+            repo.head()
+                .unwrap()
+                .shorthand(),
+            Some("feat/x")
+        );
+        assert_eq!(
+            std::fs::read_to_string(
+                td.path()
+                    .join("a.txt")
+            )
+            .unwrap(),
+            "changed"
+        );
+        assert!(
+            repo.status_file(std::path::Path::new("b.txt"))
+                .unwrap()
+                .is_index_new()
+        );
+
+        assert!(create_branch(&repo, "feat/x").is_err());
+    }
+}
@@ -1,19 +1,31 @@
-use std::{collections::HashMap, fmt};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
 
 use git2::{Diff, Oid, Repository};
 
 use super::{
-    diffs::{FileDiff, HunkId},
+    diffs::{FileDiff, HunkId, LineId},
     errors::GitError,
-    staging::{StagingStrategy, stage_all, stage_file, stage_hunks},
+    reset::{reflog_message, set_head_target},
+    staging::{
+        StagingStrategy, stage_all, stage_file, stage_hunks,
+        stage_line_blocks,
+    },
     status::{FileStatus, StatusItemType},
     utils::get_head_repo,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct GitCommit {
     pub files: Vec<String>,
     pub hunk_ids: Vec<String>,
+
+    /// only for Lines staging
+    #[serde(default)]
+    pub line_ids: Vec<String>,
+
     pub message: String,
 }
 
@@ -44,11 +56,13 @@ pub(super) struct OldNew<T> {
 
 /// returns the parent commit
 /// used specifically when specifying a N
-/// commit range, where we want to reset TO
+/// commit range, where we want to reset TO.
+/// None means this is the root commit, see
+/// reset::reset_to_parent_hard for rewriting it
 pub fn find_parent_commit(
     repo: &Repository,
     commit_hash: &str,
-) -> anyhow::Result<Oid> {
+) -> anyhow::Result<Option<Oid>> {
     let c = match repo.find_commit(Oid::from_str(commit_hash)?) {
         Ok(c) => c,
         Err(e) => {
@@ -56,19 +70,11 @@ pub fn find_parent_commit(
         }
     };
 
-    match c.parent_id(0) {
-        Ok(p) => Ok(p),
-        Err(_) => {
-            // no parent means this is the initial commit
-            // user is trying to rewrite entire history
-            // FIXME: implement initial commit rebase
-            // what do here
-
-            Err(GitError::Generic(
-                "this is an initial commit, no parent, should support this".to_string())
-                .into())
-        }
+    if c.parent_count() == 0 {
+        return Ok(None);
     }
+
+    Ok(Some(c.parent_id(0)?))
 }
 
 /// apply commits and return commit hashes
@@ -84,6 +90,11 @@ pub fn apply_commits(
     // before we apply commits
     let mut res = Vec::new();
 
+    // for Lines, a hunk is only done once
+    // all of its blocks have been staged
+    let mut staged_blocks: HashMap<(String, usize), HashSet<usize>> =
+        HashMap::new();
+
     for git_commit in git_commits {
         match staging_stragey {
             StagingStrategy::AllFilesOneCommit => {
@@ -164,6 +175,66 @@ pub fn apply_commits(
                     );
                 }
             }
+            StagingStrategy::Lines => {
+                // same as hunks, group per file
+                let mut files: HashMap<String, Vec<LineId>> =
+                    HashMap::new();
+
+                for line in &git_commit.line_ids {
+                    let line_id = LineId::try_from(line.as_str())?;
+                    files
+                        .entry(line_id.path.clone())
+                        .or_default()
+                        .push(line_id);
+                }
+
+                for (file_path, line_ids) in files {
+                    let og_file_diff = super::diffs::find_file_diff(
+                        og_file_diffs,
+                        &file_path,
+                    )?
+                    .to_owned();
+
+                    // no index entry to stage lines against
+                    if og_file_diff.untracked {
+                        stage_file(repo, &file_path)?;
+                        og_file_diffs.retain(|f| f.path != file_path);
+                        continue;
+                    }
+
+                    stage_line_blocks(
+                        repo,
+                        &og_file_diff,
+                        &line_ids,
+                    )?;
+
+                    for id in &line_ids {
+                        staged_blocks
+                            .entry((file_path.clone(), id.hunk))
+                            .or_default()
+                            .insert(id.block);
+                    }
+
+                    let done: Vec<usize> = og_file_diff
+                        .hunks
+                        .iter()
+                        .filter(|h| {
+                            staged_blocks
+                                .get(&(file_path.clone(), h.id))
+                                .is_some_and(|s| {
+                                    s.len() >= h.blocks().len()
+                                })
+                        })
+                        .map(|h| h.id)
+                        .collect();
+
+                    super::diffs::remove_hunks(
+                        og_file_diffs,
+                        &file_path,
+                        &done,
+                    );
+                }
+            }
         }
 
         let oid = commit(repo, git_commit)?;
@@ -202,7 +273,7 @@ fn commit(
         .collect::<Vec<_>>();
 
     let oid = repo.commit(
-        Some("HEAD"),
+        None,
         &signature,
         &signature,
         &commit.message,
@@ -210,9 +281,37 @@ fn commit(
         parents.as_slice(),
     )?;
 
+    set_head_target(
+        repo,
+        Some(oid),
+        &reflog_message("commit", &commit.message),
+    )?;
+
     Ok(oid)
 }
 
+/// stage the paths and commit them on HEAD
+/// with the rest of the index as is
+pub fn commit_paths(
+    repo: &Repository,
+    paths: &[String],
+    message: &str,
+) -> anyhow::Result<Oid> {
+    for path in paths {
+        stage_file(repo, path)?;
+    }
+
+    commit(
+        repo,
+        &GitCommit {
+            files: paths.to_owned(),
+            hunk_ids: Vec::new(),
+            line_ids: Vec::new(),
+            message: message.to_owned(),
+        },
+    )
+}
+
 pub fn get_commit_files(
     repo: &Repository,
     id: Oid,
@@ -279,6 +378,48 @@ pub fn get_commit_diff<'a>(
     Ok(diff)
 }
 
+/// stats and patch of a single commit
+/// used for the full commit view
+pub struct CommitPatch {
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+
+    /// (origin, content) for every line
+    /// of the patch as git prints it
+    pub lines: Vec<(char, String)>,
+}
+
+/// get_commit_diff as a printable patch
+/// works on root commits as well
+pub fn get_commit_patch(
+    repo: &Repository,
+    hash: &str,
+) -> anyhow::Result<CommitPatch> {
+    let diff = get_commit_diff(repo, Oid::from_str(hash)?)?;
+
+    let stats = diff.stats()?;
+
+    let mut lines = Vec::new();
+
+    diff.print(git2::DiffFormat::Patch, |_, _, line| {
+        let content = String::from_utf8_lossy(line.content())
+            .trim_end_matches(super::utils::is_newline)
+            .to_string();
+
+        lines.push((line.origin(), content));
+
+        true
+    })?;
+
+    Ok(CommitPatch {
+        files_changed: stats.files_changed(),
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+        lines,
+    })
+}
+
 /// get diff of two arbitrary commits
 pub(super) fn get_compare_commits_diff(
     repo: &Repository,
@@ -321,3 +462,78 @@ fn sort_commits(
         })
     }
 }
+
+/// per file patches between two commits,
+/// (path, patch) in diff order, used to
+/// send a whole branch at once
+pub fn get_range_patches(
+    repo: &Repository,
+    from: Oid,
+    to: Oid,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let from_tree = repo
+        .find_commit(from)?
+        .tree()?;
+    let to_tree = repo
+        .find_commit(to)?
+        .tree()?;
+
+    let diff = repo.diff_tree_to_tree(
+        Some(&from_tree),
+        Some(&to_tree),
+        None,
+    )?;
+
+    diff_patches(&diff)
+}
+
+/// same as get_range_patches but for a single
+/// commit against its first parent, works on
+/// root commits as well
+pub fn get_commit_patches(
+    repo: &Repository,
+    id: Oid,
+) -> anyhow::Result<Vec<(String, String)>> {
+    diff_patches(&get_commit_diff(repo, id)?)
+}
+
+fn diff_patches(
+    diff: &Diff
+) -> anyhow::Result<Vec<(String, String)>> {
+    let mut res = Vec::new();
+
+    for idx in 0..diff.deltas().len() {
+        let Some(mut patch) = git2::Patch::from_diff(diff, idx)?
+        else {
+            continue;
+        };
+
+        let delta = patch.delta();
+
+        let path = delta
+            .new_file()
+            .path()
+            .or(delta
+                .old_file()
+                .path())
+            .and_then(|p| p.to_str())
+            .unwrap_or_default()
+            .to_owned();
+
+        // get_commit_diff shows binary, thats
+        // just noise for the llm
+        if delta
+            .flags()
+            .is_binary()
+        {
+            res.push((path, "Binary file changed".to_owned()));
+            continue;
+        }
+
+        let text = patch.to_buf()?;
+
+        res.push((path, String::from_utf8_lossy(&text).into_owned()));
+    }
+
+    Ok(res)
+}
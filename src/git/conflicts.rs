@@ -0,0 +1,688 @@
+// conflicted files in the working tree, parsed
+// from the conflict markers git/libgit2 writes
+// so each block can be resolved on its own
+// the index entries (base/ours/theirs) fill in
+// what the markers dont have
+
+use std::path::Path;
+
+use git2::{
+    IndexEntry, MergeFileOptions, Repository, RepositoryState,
+};
+
+const OURS_MARKER: &str = "<<<<<<<";
+const BASE_MARKER: &str = "|||||||";
+const SPLIT_MARKER: &str = "=======";
+const THEIRS_MARKER: &str = ">>>>>>>";
+
+/// a single <<<<<<< ... >>>>>>> block
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConflictHunk {
+    /// 0 based line of the <<<<<<< marker
+    pub start: usize,
+
+    /// 0 based line of the >>>>>>> marker
+    pub end: usize,
+
+    pub ours_label: String,
+    pub theirs_label: String,
+
+    pub ours: Vec<String>,
+
+    /// only with diff3 style markers
+    pub base: Option<Vec<String>>,
+
+    pub theirs: Vec<String>,
+}
+
+/// a conflicted file and its blocks, hunks
+/// is empty for binary files or conflicts
+/// without markers (deleted on one side)
+#[derive(Clone, Debug, Default)]
+pub struct ConflictFile {
+    pub path: String,
+    pub content: String,
+    pub hunks: Vec<ConflictHunk>,
+
+    /// no index entry for that side
+    pub ours_deleted: bool,
+    pub theirs_deleted: bool,
+}
+
+/// what left the repo conflicted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictOperation {
+    Merge,
+    Rebase,
+    CherryPick,
+    Revert,
+
+    /// conflicts in the index with
+    /// nothing in progress, ex. stash pop
+    Unknown,
+}
+
+/// one side of the conflict
+#[derive(Clone, Debug, Default)]
+pub struct ConflictSide {
+    pub commit_hash: Option<String>,
+    pub message: Option<String>,
+}
+
+/// the operation and the commits on
+/// either side of it
+#[derive(Clone, Debug)]
+pub struct ConflictContext {
+    pub operation: ConflictOperation,
+    pub ours: ConflictSide,
+    pub theirs: ConflictSide,
+}
+
+impl std::fmt::Display for ConflictOperation {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        let s = match self {
+            Self::Merge => "merge",
+            Self::Rebase => "rebase",
+            Self::CherryPick => "cherry-pick",
+            Self::Revert => "revert",
+            Self::Unknown => "unknown",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+impl ConflictOperation {
+    /// what to run once everything is staged
+    pub fn continue_hint(&self) -> &'static str {
+        match self {
+            Self::Merge => "git commit",
+            Self::Rebase => {
+                "gai sync --continue (or git rebase --continue)"
+            }
+            Self::CherryPick => "git cherry-pick --continue",
+            Self::Revert => "git revert --continue",
+            Self::Unknown => "git commit",
+        }
+    }
+}
+
+enum Section {
+    Outside,
+    Ours,
+    Base,
+    Theirs,
+}
+
+fn is_marker(
+    line: &str,
+    marker: &str,
+) -> bool {
+    line.strip_prefix(marker)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+}
+
+fn marker_label(
+    line: &str,
+    marker: &str,
+) -> String {
+    line[marker.len()..]
+        .trim()
+        .to_owned()
+}
+
+/// any conflict markers left
+pub fn has_markers(content: &str) -> bool {
+    !parse_conflicts(content).is_empty()
+}
+
+/// every complete conflict block, a block
+/// missing its closing marker is ignored
+pub fn parse_conflicts(content: &str) -> Vec<ConflictHunk> {
+    let mut hunks = Vec::new();
+
+    let mut section = Section::Outside;
+    let mut current = ConflictHunk::default();
+
+    for (idx, line) in content
+        .lines()
+        .enumerate()
+    {
+        match section {
+            Section::Outside => {
+                if is_marker(line, OURS_MARKER) {
+                    current = ConflictHunk {
+                        start: idx,
+                        ours_label: marker_label(line, OURS_MARKER),
+                        ..Default::default()
+                    };
+                    section = Section::Ours;
+                }
+            }
+            Section::Ours | Section::Base
+                if line.trim_end() == SPLIT_MARKER =>
+            {
+                section = Section::Theirs;
+            }
+            Section::Ours if is_marker(line, BASE_MARKER) => {
+                current.base = Some(Vec::new());
+                section = Section::Base;
+            }
+            Section::Ours => current
+                .ours
+                .push(line.to_owned()),
+            Section::Base => {
+                if let Some(ref mut base) = current.base {
+                    base.push(line.to_owned());
+                }
+            }
+            Section::Theirs if is_marker(line, THEIRS_MARKER) => {
+                current.end = idx;
+                current.theirs_label =
+                    marker_label(line, THEIRS_MARKER);
+
+                hunks.push(std::mem::take(&mut current));
+                section = Section::Outside;
+            }
+            Section::Theirs => current
+                .theirs
+                .push(line.to_owned()),
+        }
+    }
+
+    hunks
+}
+
+/// replace each block with its resolution
+/// none leaves that block untouched
+pub fn apply_resolutions(
+    content: &str,
+    hunks: &[ConflictHunk],
+    resolutions: &[Option<String>],
+) -> String {
+    let newline = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+
+    let lines: Vec<&str> = content
+        .lines()
+        .collect();
+
+    let mut res: Vec<String> = Vec::new();
+    let mut idx = 0;
+
+    for (hunk, resolution) in hunks
+        .iter()
+        .zip(resolutions)
+    {
+        let Some(resolution) = resolution else {
+            continue;
+        };
+
+        res.extend(
+            lines[idx..hunk.start]
+                .iter()
+                .map(|l| l.to_string()),
+        );
+
+        res.extend(
+            resolution
+                .lines()
+                .map(|l| l.to_owned()),
+        );
+
+        idx = hunk.end + 1;
+    }
+
+    res.extend(
+        lines[idx..]
+            .iter()
+            .map(|l| l.to_string()),
+    );
+
+    let mut out = res.join(newline);
+
+    if content.ends_with('\n') && !out.is_empty() {
+        out.push_str(newline);
+    }
+
+    out
+}
+
+/// paths with conflict entries in the index
+pub fn get_conflicted_paths(
+    repo: &Repository
+) -> anyhow::Result<Vec<String>> {
+    let index = repo.index()?;
+
+    let mut paths = Vec::new();
+
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+
+        let entry = conflict
+            .our
+            .or(conflict.their)
+            .or(conflict.ancestor);
+
+        if let Some(entry) = entry {
+            let path =
+                String::from_utf8_lossy(&entry.path).into_owned();
+
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+
+    Ok(paths)
+}
+
+/// the operation in progress and the
+/// commits on each side, in a rebase ours is
+/// the upstream being rebased onto and theirs
+/// is the commit being replayed
+pub fn get_conflict_context(repo: &Repository) -> ConflictContext {
+    let operation = match repo.state() {
+        RepositoryState::Merge => ConflictOperation::Merge,
+        RepositoryState::Rebase
+        | RepositoryState::RebaseMerge
+        | RepositoryState::RebaseInteractive => {
+            ConflictOperation::Rebase
+        }
+        RepositoryState::CherryPick
+        | RepositoryState::CherryPickSequence => {
+            ConflictOperation::CherryPick
+        }
+        RepositoryState::Revert | RepositoryState::RevertSequence => {
+            ConflictOperation::Revert
+        }
+        _ => ConflictOperation::Unknown,
+    };
+
+    let theirs = match operation {
+        ConflictOperation::Merge => side_from_rev(repo, "MERGE_HEAD"),
+        ConflictOperation::CherryPick => {
+            side_from_rev(repo, "CHERRY_PICK_HEAD")
+        }
+        ConflictOperation::Revert => {
+            side_from_rev(repo, "REVERT_HEAD")
+        }
+        ConflictOperation::Rebase => rebase_side(repo)
+            .unwrap_or_else(|| side_from_rev(repo, "REBASE_HEAD")),
+        ConflictOperation::Unknown => ConflictSide::default(),
+    };
+
+    ConflictContext {
+        operation,
+        ours: side_from_rev(repo, "HEAD"),
+        theirs,
+    }
+}
+
+fn side_from_rev(
+    repo: &Repository,
+    rev: &str,
+) -> ConflictSide {
+    let Ok(commit) = repo
+        .revparse_single(rev)
+        .and_then(|o| o.peel_to_commit())
+    else {
+        return ConflictSide::default();
+    };
+
+    ConflictSide {
+        commit_hash: Some(
+            commit
+                .id()
+                .to_string(),
+        ),
+        message: Some(
+            String::from_utf8_lossy(commit.message_bytes())
+                .trim()
+                .to_owned(),
+        ),
+    }
+}
+
+/// the commit being replayed, works for
+/// libgit2 and `git rebase -m` state
+fn rebase_side(repo: &Repository) -> Option<ConflictSide> {
+    let mut rebase = repo
+        .open_rebase(None)
+        .ok()?;
+
+    let current = rebase.operation_current()?;
+    let oid = rebase
+        .nth(current)?
+        .id();
+
+    Some(side_from_rev(repo, &oid.to_string()))
+}
+
+/// read and parse a conflicted file from the
+/// working tree, if the markers dont carry the
+/// base (not diff3 style) its filled in from
+/// the index entries
+pub fn get_conflict_file(
+    repo: &Repository,
+    workdir: &Path,
+    path: &str,
+) -> anyhow::Result<ConflictFile> {
+    let raw = std::fs::read(workdir.join(path)).unwrap_or_default();
+
+    // binary, nothing we can suggest
+    let content = String::from_utf8(raw).unwrap_or_default();
+
+    let mut hunks = parse_conflicts(&content);
+
+    let (base, ours, theirs) = get_index_entries(repo, path)?;
+
+    if hunks
+        .iter()
+        .any(|h| h.base.is_none())
+        && let (Some(base), Some(ours), Some(theirs)) =
+            (&base, &ours, &theirs)
+    {
+        fill_base(repo, &mut hunks, base, ours, theirs);
+    }
+
+    Ok(ConflictFile {
+        path: path.to_owned(),
+        content,
+        hunks,
+        ours_deleted: ours.is_none(),
+        theirs_deleted: theirs.is_none(),
+    })
+}
+
+/// (base, ours, theirs) conflict entries for a path
+fn get_index_entries(
+    repo: &Repository,
+    path: &str,
+) -> anyhow::Result<(
+    Option<IndexEntry>,
+    Option<IndexEntry>,
+    Option<IndexEntry>,
+)> {
+    let index = repo.index()?;
+
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+
+        let matches =
+            [&conflict.ancestor, &conflict.our, &conflict.their]
+                .iter()
+                .any(|e| {
+                    e.as_ref()
+                        .is_some_and(|e| e.path == path.as_bytes())
+                });
+
+        if matches {
+            return Ok((
+                conflict.ancestor,
+                conflict.our,
+                conflict.their,
+            ));
+        }
+    }
+
+    Ok((None, None, None))
+}
+
+/// redo the merge from the index in diff3 style
+/// and copy the base over to the blocks that
+/// line up, if the workdir was edited since
+/// they might not, thats fine
+fn fill_base(
+    repo: &Repository,
+    hunks: &mut [ConflictHunk],
+    base: &IndexEntry,
+    ours: &IndexEntry,
+    theirs: &IndexEntry,
+) {
+    let mut opts = MergeFileOptions::new();
+    opts.style_diff3(true);
+
+    let Ok(merged) = repo.merge_file_from_index(
+        base,
+        ours,
+        theirs,
+        Some(&mut opts),
+    ) else {
+        return;
+    };
+
+    let Ok(content) = std::str::from_utf8(merged.content()) else {
+        return;
+    };
+
+    let diff3 = parse_conflicts(content);
+
+    for hunk in hunks
+        .iter_mut()
+        .filter(|h| h.base.is_none())
+    {
+        if let Some(found) = diff3
+            .iter()
+            .find(|d| d.ours == hunk.ours && d.theirs == hunk.theirs)
+        {
+            hunk.base = found
+                .base
+                .to_owned();
+        }
+    }
+}
+
+/// settle a conflict where one side deleted
+/// the file, keep stages the workdir file,
+/// otherwise its removed
+pub fn resolve_deleted(
+    repo: &Repository,
+    workdir: &Path,
+    path: &str,
+    keep: bool,
+) -> anyhow::Result<()> {
+    let mut index = repo.index()?;
+
+    if keep {
+        index.add_path(Path::new(path))?;
+    } else {
+        let full = workdir.join(path);
+
+        if full.exists() {
+            std::fs::remove_file(full)?;
+        }
+
+        index.remove_path(Path::new(path))?;
+    }
+
+    index.write()?;
+
+    Ok(())
+}
+
+/// write the resolved content, once no markers
+/// are left the file is staged which clears
+/// the conflict from the index
+/// returns true if it was staged
+pub fn write_resolution(
+    repo: &Repository,
+    workdir: &Path,
+    path: &str,
+    content: &str,
+) -> anyhow::Result<bool> {
+    std::fs::write(workdir.join(path), content)?;
+
+    if has_markers(content) {
+        return Ok(false);
+    }
+
+    let mut index = repo.index()?;
+
+    index.add_path(Path::new(path))?;
+    index.write()?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::tests::{repo_init, write_commit_file};
+
+    const CONFLICT: &str = "\
+top
+<<<<<<< HEAD
+ours
+||||||| base
+base
+=======
+theirs
+>>>>>>> feature
+middle
+<<<<<<< HEAD
+a
+=======
+b
+>>>>>>> feature
+bottom
+";
+
+    #[test]
+    fn test_parse_conflicts() {
+        let hunks = parse_conflicts(CONFLICT);
+
+        assert_eq!(hunks.len(), 2);
+
+        assert_eq!(hunks[0].start, 1);
+        assert_eq!(hunks[0].end, 7);
+        assert_eq!(hunks[0].ours_label, "HEAD");
+        assert_eq!(hunks[0].theirs_label, "feature");
+        assert_eq!(hunks[0].ours, vec!["ours"]);
+        assert_eq!(hunks[0].base, Some(vec!["base".to_owned()]));
+        assert_eq!(hunks[0].theirs, vec!["theirs"]);
+
+        assert_eq!(hunks[1].base, None);
+        assert_eq!(hunks[1].theirs, vec!["b"]);
+
+        // unterminated blocks are ignored
+        assert!(
+            parse_conflicts("<<<<<<< HEAD\nours\n=======\n")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_apply_resolutions() {
+        let hunks = parse_conflicts(CONFLICT);
+
+        let partial = apply_resolutions(
+            CONFLICT,
+            &hunks,
+            &[Some("merged\nlines".to_owned()), None],
+        );
+
+        assert!(partial.starts_with("top\nmerged\nlines\nmiddle\n"));
+        assert_eq!(parse_conflicts(&partial).len(), 1);
+
+        let full = apply_resolutions(
+            CONFLICT,
+            &hunks,
+            &[Some("merged".to_owned()), Some(String::new())],
+        );
+
+        assert_eq!(full, "top\nmerged\nmiddle\nbottom\n");
+        assert!(!has_markers(&full));
+    }
+
+    #[test]
+    fn test_merge_conflict_from_index() {
+        let (td, repo) = repo_init();
+
+        let first =
+            write_commit_file(&repo, "a.txt", "1\n2\n3\n", "add a");
+
+        let main = repo
+            .head()
+            .unwrap()
+            .name()
+            .unwrap()
+            .to_owned();
+
+        repo.branch(
+            "feature",
+            &repo
+                .find_commit(first)
+                .unwrap(),
+            false,
+        )
+        .unwrap();
+
+        write_commit_file(&repo, "a.txt", "1\nours\n3\n", "ours a");
+
+        let force = || {
+            repo.checkout_head(Some(
+                git2::build::CheckoutBuilder::new().force(),
+            ))
+            .unwrap()
+        };
+
+        repo.set_head("refs/heads/feature")
+            .unwrap();
+        force();
+
+        let theirs = write_commit_file(
+            &repo,
+            "a.txt",
+            "1\ntheirs\n3\n",
+            "theirs a",
+        );
+
+        repo.set_head(&main)
+            .unwrap();
+        force();
+
+        let annotated = repo
+            .find_annotated_commit(theirs)
+            .unwrap();
+
+        repo.merge(&[&annotated], None, None)
+            .unwrap();
+
+        let context = get_conflict_context(&repo);
+
+        assert_eq!(context.operation, ConflictOperation::Merge);
+        assert_eq!(
+            context
+                .ours
+                .message
+                .as_deref(),
+            Some("ours a")
+        );
+        assert_eq!(
+            context
+                .theirs
+                .message
+                .as_deref(),
+            Some("theirs a")
+        );
+
+        let file =
+            get_conflict_file(&repo, td.path(), "a.txt").unwrap();
+
+        assert_eq!(file.hunks.len(), 1);
+        assert_eq!(file.hunks[0].ours, vec!["ours"]);
+        assert_eq!(file.hunks[0].theirs, vec!["theirs"]);
+
+        // libgit2 doesnt write diff3 markers by
+        // default, the base comes from the index
+        assert_eq!(file.hunks[0].base, Some(vec!["2".to_owned()]));
+        assert!(!file.ours_deleted && !file.theirs_deleted);
+    }
+}
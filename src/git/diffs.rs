@@ -12,6 +12,7 @@ use crate::git::{
 
 use super::{
     errors::GitError,
+    staging::StagingStrategy,
     status::StatusStrategy,
     status::get_status,
     utils::{get_head_repo, is_newline, new_file_content},
@@ -59,6 +60,19 @@ pub struct HunkId {
     pub index: usize,
 }
 
+/// same idea as HunkId but for Lines staging
+/// points to a block of changed lines in a hunk,
+/// a block being a run of additions/deletions
+/// between context lines
+/// src/main.rs:0:1 is the second block of the
+/// first hunk in src/main.rs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineId {
+    pub path: String,
+    pub hunk: usize,
+    pub block: usize,
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct FileDiff {
     pub path: String,
@@ -207,6 +221,107 @@ impl TryFrom<&str> for HunkId {
     }
 }
 
+impl TryFrom<&str> for LineId {
+    type Error = GitError;
+
+    fn try_from(v: &str) -> Result<Self, Self::Error> {
+        let invalid = || GitError::InvalidHunk(v.to_owned());
+
+        let (rest, block) = v
+            .rsplit_once(':')
+            .ok_or_else(invalid)?;
+        let (path, hunk) = rest
+            .rsplit_once(':')
+            .ok_or_else(invalid)?;
+
+        Ok(Self {
+            path: path.to_owned(),
+            hunk: hunk
+                .parse()
+                .map_err(|_| invalid())?,
+            block: block
+                .parse()
+                .map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl Hunk {
+    /// the blocks of changed lines in this
+    /// hunk, indexed the same as LineId.block
+    pub fn blocks(&self) -> Vec<&[DiffLine]> {
+        change_blocks(&self.lines)
+    }
+}
+
+/// split lines into runs of additions/deletions
+/// anything else (context, headers) ends a run
+pub fn change_blocks(lines: &[DiffLine]) -> Vec<&[DiffLine]> {
+    let mut blocks = Vec::new();
+    let mut start = None;
+
+    for (i, line) in lines
+        .iter()
+        .enumerate()
+    {
+        let is_change = matches!(
+            line.line_type,
+            DiffLineType::Add | DiffLineType::Delete
+        );
+
+        match (is_change, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                blocks.push(&lines[s..i]);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(s) = start {
+        blocks.push(&lines[s..]);
+    }
+
+    blocks
+}
+
+/// where each block of change_blocks() sits on the
+/// workdir side, the workdir doesnt change while we
+/// commit hunk by hunk (only the index does), so
+/// this stays stable across partial commits
+pub fn block_anchors(
+    new_start: u32,
+    lines: &[DiffLine],
+) -> Vec<u32> {
+    let mut anchors = Vec::new();
+
+    let mut next_new = new_start;
+    let mut in_block = false;
+
+    for line in lines {
+        let is_change = matches!(
+            line.line_type,
+            DiffLineType::Add | DiffLineType::Delete
+        );
+
+        if is_change && !in_block {
+            anchors.push(next_new);
+        }
+
+        in_block = is_change;
+
+        if let Some(n) = line
+            .position
+            .new_lineno
+        {
+            next_new = n + 1;
+        }
+    }
+
+    anchors
+}
+
 impl Diffs {
     /// helper to returns diffs as a list of files
     pub fn as_files(&self) -> Vec<String> {
@@ -232,6 +347,76 @@ impl Diffs {
 
         vec
     }
+
+    /// helper to return diffs as a list of line id strings
+    /// format: file:hunk:block
+    pub fn as_line_ids(&self) -> Vec<String> {
+        let mut vec = Vec::new();
+
+        for diff in &self.files {
+            for hunk in &diff.hunks {
+                for block in 0..hunk.blocks().len() {
+                    vec.push(format!(
+                        "{}:{}:{}",
+                        diff.path, hunk.id, block
+                    ));
+                }
+            }
+        }
+
+        vec
+    }
+
+    /// the diffs as sent to the LLM, Lines staging
+    /// also labels each block of changed lines
+    pub fn to_request_string(
+        &self,
+        staging_type: &StagingStrategy,
+    ) -> String {
+        if !matches!(staging_type, StagingStrategy::Lines) {
+            return self.to_string();
+        }
+
+        let mut s = String::new();
+
+        for file in &self.files {
+            for hunk in file.hunks.iter() {
+                s.push_str(&format!(
+                    "HunkId[{}:{}]\n",
+                    file.path, hunk.id
+                ));
+
+                let mut block = 0;
+                let mut in_block = false;
+
+                for line in &hunk.lines {
+                    let is_change = matches!(
+                        line.line_type,
+                        DiffLineType::Add | DiffLineType::Delete
+                    );
+
+                    if is_change && !in_block {
+                        s.push_str(&format!(
+                            "LineId[{}:{}:{}]\n",
+                            file.path, hunk.id, block
+                        ));
+                        block += 1;
+                    }
+
+                    in_block = is_change;
+
+                    s.push_str(&format!(
+                        "{}{}",
+                        line.line_type, line.content
+                    ));
+                    s.push('\n');
+                }
+            }
+            s.push('\n');
+        }
+
+        s
+    }
 }
 
 /// helper for converting into a string
@@ -395,11 +580,12 @@ pub fn get_diffs_from_statuses(
 /// from specified Oid, can use
 /// an optional to Oid, if None
 /// is supplied, will use the head
-/// of current branch
+/// of current branch. a None from
+/// diffs against nothing, for the root
 pub fn get_diffs_from_commits(
     repo: &Repository,
     work_dir: &Path,
-    from: Oid,
+    from: Option<Oid>,
     to: Option<Oid>,
 ) -> anyhow::Result<Diffs> {
     let mut files = Vec::new();
@@ -410,13 +596,24 @@ pub fn get_diffs_from_commits(
         get_head_oid(repo)?
     };
 
-    let raw_diff = get_compare_commits_diff(
-        repo,
-        OldNew {
-            old: from,
-            new: head,
-        },
-    )?;
+    let raw_diff = match from {
+        Some(from) => get_compare_commits_diff(
+            repo,
+            OldNew {
+                old: from,
+                new: head,
+            },
+        )?,
+        None => repo.diff_tree_to_tree(
+            None,
+            Some(
+                &repo
+                    .find_commit(head)?
+                    .tree()?,
+            ),
+            None,
+        )?,
+    };
 
     // collect diffs from each file
     for delta in raw_diff.deltas() {
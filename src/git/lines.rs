@@ -41,6 +41,26 @@ pub fn get_changes_from_raw(
         .collect()
 }
 
+/// same as get_changes_from_raw but keeps
+/// every line, used by line staging to split
+/// regenerated hunks into blocks
+pub fn get_lines_from_raw(
+    hunk: &HunkLines
+) -> Vec<super::diffs::DiffLine> {
+    hunk.lines
+        .iter()
+        .map(|l| super::diffs::DiffLine {
+            line_type: super::diffs::DiffLineType::from(
+                l.origin_value(),
+            ),
+            content: String::from_utf8_lossy(l.content())
+                .trim_matches(is_newline)
+                .into(),
+            position: DiffLinePosition::from(l),
+        })
+        .collect()
+}
+
 /// helper fn to get ONLY the additions or deletions
 /// within a specific specified hunk
 /// this is called by stage_hunks()
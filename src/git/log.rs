@@ -266,6 +266,19 @@ pub fn get_log(
     Ok(log)
 }
 
+/// narrows down get_logs, the default
+/// keeps every commit in the range
+#[derive(Debug, Clone, Default)]
+pub struct LogFilters<'a> {
+    /// stop at commits older than this
+    pub since: Option<std::time::Duration>,
+    pub author: Option<&'a str>,
+
+    /// a file, or a directory the commit
+    /// has to touch something under
+    pub path: Option<&'a str>,
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn get_logs(
     git_repo: &GitRepo,
@@ -275,42 +288,18 @@ pub fn get_logs(
     reverse: bool,
     from_hash: Option<&str>,
     to_hash: Option<&str>,
-    since: Option<std::time::Duration>,
+    filters: &LogFilters,
 ) -> anyhow::Result<Logs> {
-    let repo = &git_repo.repo;
-    let mut revwalk = repo.revwalk()?;
-
-    if reverse {
-        revwalk.set_sorting(git2::Sort::REVERSE)?;
-    }
-
-    match (from_hash, to_hash) {
-        // range exists
-        (Some(from), Some(to)) => {
-            revwalk.push_range(&format!("{}..{}", from, to))?;
-        }
+    let LogFilters {
+        since,
+        author,
+        path,
+    } = *filters;
 
-        // from: hide it, walk from HEAD
-        (Some(from), None) => {
-            let oid = Oid::from_str(from)?;
-            revwalk.hide(oid)?;
-            revwalk.push_head()?;
-        }
-
-        // to: walk from that commit
-        (None, Some(to)) => {
-            let oid = Oid::from_str(to)?;
-            revwalk.push(oid)?;
-        }
-
-        // if none just walk from HEAD
-        (None, None) => {
-            revwalk.push_head()?;
-        }
-    }
+    let repo = &git_repo.repo;
+    let revwalk = walk_range(repo, reverse, from_hash, to_hash)?;
 
     let cont = if count == 0 { !0 } else { count };
-    let revwalk = revwalk.take(cont);
 
     let mut git_logs = Vec::new();
 
@@ -321,6 +310,13 @@ pub fn get_logs(
     };
 
     for oid in revwalk {
+        // count is applied after filtering
+        // otherwise --author/--path would
+        // only look at the first n commits
+        if git_logs.len() >= cont {
+            break;
+        }
+
         let oid = oid?;
         let commit = repo.find_commit(oid)?;
 
@@ -333,20 +329,44 @@ pub fn get_logs(
             break;
         }
 
+        let commit_author = commit.author();
+
+        if let Some(author) = author
+            && !author_matches(&commit_author, author)
+        {
+            continue;
+        }
+
+        // only diff the commit when we need to
+        let commit_files = if files || path.is_some() {
+            get_commit_files(repo, oid, None)?
+                .iter()
+                .map(|f| f.path.to_string())
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        if let Some(path) = path
+            && !commit_files
+                .iter()
+                .any(|f| path_matches(f, path))
+        {
+            continue;
+        }
+
         let mut log: GitLog = commit
             .message_bytes()
             .into();
 
-        let author = commit.author();
-
-        log.author = author
+        log.author = commit_author
             .name()
             .unwrap_or("unknown author")
             .to_string();
 
         log.commit_hash = oid.to_string();
         log.date = DateTime::from_timestamp(
-            author
+            commit_author
                 .when()
                 .seconds(),
             0,
@@ -358,10 +378,7 @@ pub fn get_logs(
         .unwrap_or_default();
 
         if files {
-            log.files = get_commit_files(repo, oid, None)?
-                .iter()
-                .map(|f| f.path.to_string())
-                .collect();
+            log.files = commit_files;
 
             if diffs {
                 for file in &log.files {
@@ -384,3 +401,247 @@ pub fn get_logs(
 
     Ok(Logs { git_logs })
 }
+
+/// hashes in the same range and order as get_logs,
+/// without building logs or diffs. there is no
+/// count or path filter, the caller has the files
+/// from somewhere cheaper (the commit index)
+pub fn get_log_hashes(
+    git_repo: &GitRepo,
+    reverse: bool,
+    from_hash: Option<&str>,
+    to_hash: Option<&str>,
+    since: Option<std::time::Duration>,
+    author: Option<&str>,
+) -> anyhow::Result<Vec<String>> {
+    let repo = &git_repo.repo;
+    let revwalk = walk_range(repo, reverse, from_hash, to_hash)?;
+
+    let last_time = if let Some(since) = since {
+        Utc::now().timestamp() - since.as_secs() as i64
+    } else {
+        0
+    };
+
+    let mut res = Vec::new();
+
+    for oid in revwalk {
+        let oid = oid?;
+        let commit = repo.find_commit(oid)?;
+        let commit_author = commit.author();
+
+        if commit_author
+            .when()
+            .seconds()
+            < last_time
+        {
+            break;
+        }
+
+        if let Some(author) = author
+            && !author_matches(&commit_author, author)
+        {
+            continue;
+        }
+
+        res.push(oid.to_string());
+    }
+
+    Ok(res)
+}
+
+fn walk_range<'a>(
+    repo: &'a git2::Repository,
+    reverse: bool,
+    from_hash: Option<&str>,
+    to_hash: Option<&str>,
+) -> anyhow::Result<git2::Revwalk<'a>> {
+    let mut revwalk = repo.revwalk()?;
+
+    if reverse {
+        revwalk.set_sorting(git2::Sort::REVERSE)?;
+    }
+
+    match (from_hash, to_hash) {
+        // range exists
+        (Some(from), Some(to)) => {
+            revwalk.push_range(&format!("{}..{}", from, to))?;
+        }
+
+        // from: hide it, walk from HEAD
+        (Some(from), None) => {
+            let oid = Oid::from_str(from)?;
+            revwalk.hide(oid)?;
+            revwalk.push_head()?;
+        }
+
+        // to: walk from that commit
+        (None, Some(to)) => {
+            let oid = Oid::from_str(to)?;
+            revwalk.push(oid)?;
+        }
+
+        // if none just walk from HEAD
+        (None, None) => {
+            revwalk.push_head()?;
+        }
+    }
+
+    Ok(revwalk)
+}
+
+/// case insensitive substring match
+/// against the author name or email
+fn author_matches(
+    sig: &git2::Signature,
+    author: &str,
+) -> bool {
+    let author = author.to_lowercase();
+
+    [sig.name(), sig.email()]
+        .into_iter()
+        .flatten()
+        .any(|s| {
+            s.to_lowercase()
+                .contains(&author)
+        })
+}
+
+/// a file matches if its the path itself
+/// or somewhere under it as a directory
+pub fn path_matches(
+    file: &str,
+    path: &str,
+) -> bool {
+    let path = path.trim_end_matches('/');
+
+    path.is_empty()
+        || file == path
+        || file
+            .strip_prefix(path)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::tests::{repo_init, write_commit_file};
+
+    #[test]
+    fn test_get_logs_filters() {
+        let (td, repo) = repo_init();
+
+        write_commit_file(&repo, "a.txt", "a", "add a");
+
+        std::fs::create_dir(
+            td.path()
+                .join("src"),
+        )
+        .unwrap();
+        write_commit_file(&repo, "src/b.txt", "b", "add b");
+        write_commit_file(&repo, "a.txt", "aa", "change a");
+
+        let git_repo = GitRepo {
+            workdir: td.path().to_owned(),
+            repo,
+        };
+
+        let logs = get_logs(
+            &git_repo,
+            false,
+            false,
+            0,
+            false,
+            None,
+            None,
+            &LogFilters {
+                path: Some("src"),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(logs.git_logs.len(), 1);
+        assert_eq!(logs.git_logs[0].raw, "add b");
+
+        // count applies to the filtered commits
+        let logs = get_logs(
+            &git_repo,
+            false,
+            false,
+            1,
+            false,
+            None,
+            None,
+            &LogFilters {
+                author: Some("EMAIL"),
+                path: Some("a.txt"),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(logs.git_logs.len(), 1);
+        assert_eq!(logs.git_logs[0].raw, "change a");
+
+        let logs = get_logs(
+            &git_repo,
+            false,
+            false,
+            0,
+            false,
+            None,
+            None,
+            &LogFilters {
+                author: Some("someone else"),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert!(
+            logs.git_logs
+                .is_empty()
+        );
+
+        // the whole range, in the same order as get_logs
+        let hashes =
+            get_log_hashes(&git_repo, false, None, None, None, None)
+                .unwrap();
+
+        let logs = get_logs(
+            &git_repo,
+            false,
+            false,
+            0,
+            false,
+            None,
+            None,
+            &LogFilters::default(),
+        )
+        .unwrap();
+
+        assert_eq!(hashes.len(), 4);
+        assert_eq!(
+            hashes,
+            logs.git_logs
+                .iter()
+                .map(|l| l
+                    .commit_hash
+                    .to_owned())
+                .collect::<Vec<_>>()
+        );
+        assert!(
+            get_log_hashes(
+                &git_repo,
+                false,
+                None,
+                None,
+                None,
+                Some("someone else"),
+            )
+            .unwrap()
+            .is_empty()
+        );
+    }
+}
@@ -1,16 +1,25 @@
+pub mod absorb;
+pub mod backup;
+pub mod blame;
 pub mod branch;
 pub mod checkout;
 pub mod commit;
+pub mod conflicts;
 pub mod diffs;
 pub mod errors;
 pub mod lines;
 pub mod log;
 pub mod patches;
+pub mod plan;
 pub mod rebase;
 pub mod repo;
 pub mod reset;
+pub mod rewrite;
 pub mod staging;
 pub mod status;
+pub mod sync;
+pub mod tag;
+pub mod todo;
 pub mod utils;
 
 pub use diffs::{DiffStrategy, Diffs};
@@ -19,7 +28,7 @@ pub use staging::StagingStrategy;
 pub use status::StatusStrategy;
 
 #[cfg(test)]
-mod tests {
+pub mod tests {
     use git2::Repository;
     use tempfile::TempDir;
 
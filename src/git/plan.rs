@@ -0,0 +1,517 @@
+// two-phase commits
+// gai commit --plan-out writes the generated commits
+// along with a fingerprint of every file diff
+// they were generated against. gai apply reads it back,
+// regenerates the diffs and refuses to apply if anything
+// changed in between, since hunk ids would no longer
+// point to the same changes
+//
+// the plan is plain json so it can be edited by hand
+// or kept around as an audit trail
+
+use std::{fs, path::Path};
+
+use git2::{ObjectType, Oid, Repository};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    StagingStrategy, StatusStrategy,
+    commit::GitCommit,
+    diffs::{Diffs, FileDiff, HunkId, LineId},
+    errors::GitError,
+    utils::get_head_repo,
+};
+
+/// bump when the plan format changes
+pub const PLAN_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitPlan {
+    pub version: u32,
+
+    /// rfc3339 timestamp
+    pub created_at: String,
+
+    /// HEAD when the plan was generated, for the audit
+    /// trail only, the fingerprints are what gets checked
+    pub head: Option<String>,
+
+    /// provider and model that proposed these commits
+    pub provider: String,
+    pub model: String,
+
+    /// what the diffs were collected from
+    pub status_strategy: StatusStrategy,
+
+    /// how the commits should be staged
+    pub staging_type: StagingStrategy,
+
+    pub fingerprints: Vec<FileFingerprint>,
+
+    pub commits: Vec<GitCommit>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileFingerprint {
+    pub path: String,
+    /// sha1 of the hunk headers and lines
+    pub hash: String,
+}
+
+impl CommitPlan {
+    pub fn new(
+        repo: &Repository,
+        diffs: &Diffs,
+        commits: Vec<GitCommit>,
+        status_strategy: StatusStrategy,
+        staging_type: StagingStrategy,
+        provider: String,
+        model: String,
+    ) -> anyhow::Result<Self> {
+        let head = get_head_repo(repo)
+            .ok()
+            .map(|o| o.to_string());
+
+        Ok(Self {
+            version: PLAN_VERSION,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            head,
+            provider,
+            model,
+            status_strategy,
+            staging_type,
+            fingerprints: fingerprint_diffs(diffs)?,
+            commits,
+        })
+    }
+
+    /// read a plan from disk, errors on
+    /// an unknown version
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let raw = fs::read_to_string(path)?;
+
+        let plan: Self = serde_json::from_str(&raw)?;
+
+        if plan.version != PLAN_VERSION {
+            return Err(GitError::Generic(format!(
+                "unsupported plan version {}, expected {}",
+                plan.version, PLAN_VERSION
+            ))
+            .into());
+        }
+
+        Ok(plan)
+    }
+
+    pub fn write(
+        &self,
+        path: &Path,
+    ) -> anyhow::Result<()> {
+        let raw = serde_json::to_string_pretty(self)?;
+
+        fs::write(path, raw)?;
+
+        Ok(())
+    }
+
+    /// compare the stored fingerprints against
+    /// freshly collected diffs, returns the paths that
+    /// changed or went away since. new files only count
+    /// if a commit references them, anything else is
+    /// left alone when applying anyway. ignore is the
+    /// plan file itself when it lives in the workdir
+    pub fn stale_files(
+        &self,
+        diffs: &Diffs,
+        ignore: Option<&str>,
+    ) -> anyhow::Result<Vec<String>> {
+        let current = fingerprint_diffs(diffs)?;
+        let referenced = self.referenced_paths()?;
+
+        let mut stale = Vec::new();
+
+        for fp in &self.fingerprints {
+            if Some(fp.path.as_str()) != ignore
+                && !current.contains(fp)
+            {
+                stale.push(fp.path.to_owned());
+            }
+        }
+
+        for fp in &current {
+            if Some(fp.path.as_str()) != ignore
+                && referenced.contains(&fp.path)
+                && !self
+                    .fingerprints
+                    .iter()
+                    .any(|f| f.path == fp.path)
+            {
+                stale.push(fp.path.to_owned());
+            }
+        }
+
+        stale.sort();
+        stale.dedup();
+
+        Ok(stale)
+    }
+
+    /// every path the commits touch, whichever
+    /// of files, hunk or line ids they use
+    fn referenced_paths(&self) -> anyhow::Result<Vec<String>> {
+        let mut paths = Vec::new();
+
+        for commit in &self.commits {
+            paths.extend(
+                commit
+                    .files
+                    .iter()
+                    .cloned(),
+            );
+
+            for hunk in &commit.hunk_ids {
+                paths.push(HunkId::try_from(hunk.as_str())?.path);
+            }
+
+            for line in &commit.line_ids {
+                paths.push(LineId::try_from(line.as_str())?.path);
+            }
+        }
+
+        Ok(paths)
+    }
+
+    /// make sure every file and hunk id in the
+    /// commits exists in the diffs, mostly for
+    /// hand edited plans
+    pub fn validate(
+        &self,
+        diffs: &Diffs,
+    ) -> anyhow::Result<()> {
+        validate_commits(&self.commits, diffs, &self.staging_type)
+    }
+}
+
+/// checks files/hunk ids that commits reference
+/// against the diffs they'll be applied with
+pub fn validate_commits(
+    commits: &[GitCommit],
+    diffs: &Diffs,
+    staging_type: &StagingStrategy,
+) -> anyhow::Result<()> {
+    for commit in commits {
+        if matches!(staging_type, StagingStrategy::Lines) {
+            for line in &commit.line_ids {
+                let line_id = LineId::try_from(line.as_str())?;
+
+                let exists = diffs
+                    .files
+                    .iter()
+                    .filter(|f| f.path == line_id.path)
+                    .flat_map(|f| f.hunks.iter())
+                    .any(|h| {
+                        h.id == line_id.hunk
+                            && line_id.block < h.blocks().len()
+                    });
+
+                if !exists {
+                    return Err(GitError::InvalidHunk(
+                        line.to_owned(),
+                    )
+                    .into());
+                }
+            }
+        } else if matches!(staging_type, StagingStrategy::Hunks) {
+            for hunk in &commit.hunk_ids {
+                let hunk_id = HunkId::try_from(hunk.as_str())?;
+
+                let exists = diffs
+                    .files
+                    .iter()
+                    .any(|f| {
+                        f.path == hunk_id.path
+                            && f.hunks
+                                .iter()
+                                .any(|h| h.id == hunk_id.index)
+                    });
+
+                if !exists {
+                    return Err(GitError::InvalidHunk(
+                        hunk.to_owned(),
+                    )
+                    .into());
+                }
+            }
+        } else {
+            for file in &commit.files {
+                if !diffs
+                    .files
+                    .iter()
+                    .any(|f| &f.path == file)
+                {
+                    return Err(GitError::Generic(format!(
+                        "{} in \"{}\" has no changes",
+                        file, commit
+                    ))
+                    .into());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// one fingerprint per file diff
+pub fn fingerprint_diffs(
+    diffs: &Diffs
+) -> anyhow::Result<Vec<FileFingerprint>> {
+    let mut res = Vec::new();
+
+    for file in &diffs.files {
+        res.push(FileFingerprint {
+            path: file.path.to_owned(),
+            hash: fingerprint_file(file)?.to_string(),
+        });
+    }
+
+    Ok(res)
+}
+
+fn fingerprint_file(file: &FileDiff) -> anyhow::Result<Oid> {
+    let mut s = String::new();
+
+    if file.untracked {
+        s.push_str("untracked\n");
+    }
+
+    for hunk in &file.hunks {
+        s.push_str(&format!("{}\n", hunk.header));
+
+        for line in &hunk.lines {
+            s.push_str(&format!(
+                "{}{}\n",
+                line.line_type, line.content
+            ));
+        }
+    }
+
+    Ok(Oid::hash_object(ObjectType::Blob, s.as_bytes())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::{
+        DiffStrategy, diffs::get_diffs_from_statuses,
+        tests::repo_init, tests::write_commit_file,
+    };
+
+    fn diffs(repo: &Repository) -> Diffs {
+        get_diffs_from_statuses(
+            repo,
+            repo.workdir()
+                .unwrap(),
+            &DiffStrategy::default(),
+        )
+        .unwrap()
+    }
+
+    fn plan(
+        repo: &Repository,
+        diffs: &Diffs,
+    ) -> CommitPlan {
+        let commits = vec![GitCommit {
+            files: vec!["a.txt".to_owned()],
+            hunk_ids: Vec::new(),
+            line_ids: Vec::new(),
+            message: "change a".to_owned(),
+        }];
+
+        CommitPlan::new(
+            repo,
+            diffs,
+            commits,
+            StatusStrategy::default(),
+            StagingStrategy::AtomicCommits,
+            "Gai".to_owned(),
+            "model".to_owned(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_plan_round_trip() {
+        let (dir, repo) = repo_init();
+
+        write_commit_file(&repo, "a.txt", "a\n", "add a");
+
+        std::fs::write(
+            dir.path()
+                .join("a.txt"),
+            "a\nb\n",
+        )
+        .unwrap();
+
+        let diffs = diffs(&repo);
+        let plan = plan(&repo, &diffs);
+
+        let path = dir
+            .path()
+            .join("plan.json");
+
+        plan.write(&path)
+            .unwrap();
+
+        let read = CommitPlan::read(&path).unwrap();
+
+        assert_eq!(read.fingerprints, plan.fingerprints);
+        assert_eq!(read.commits[0].message, "change a");
+
+        read.validate(&diffs)
+            .unwrap();
+
+        assert!(
+            read.stale_files(&diffs, None)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_plan_stale_after_change() {
+        let (dir, repo) = repo_init();
+
+        write_commit_file(&repo, "a.txt", "a\n", "add a");
+
+        std::fs::write(
+            dir.path()
+                .join("a.txt"),
+            "a\nb\n",
+        )
+        .unwrap();
+
+        let plan = plan(&repo, &diffs(&repo));
+
+        // edit the same file again, and add a new one
+        std::fs::write(
+            dir.path()
+                .join("a.txt"),
+            "a\nc\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path()
+                .join("new.txt"),
+            "new\n",
+        )
+        .unwrap();
+
+        let stale = plan
+            .stale_files(&diffs(&repo), None)
+            .unwrap();
+
+        // new.txt isnt in any commit, so it doesnt matter
+        assert_eq!(stale, vec!["a.txt"]);
+
+        let mut plan = plan;
+        plan.commits[0]
+            .files
+            .push("new.txt".to_owned());
+
+        let stale = plan
+            .stale_files(&diffs(&repo), None)
+            .unwrap();
+
+        assert_eq!(stale, vec!["a.txt", "new.txt"]);
+    }
+
+    #[test]
+    fn test_plan_inside_workdir_applies() {
+        let (dir, repo) = repo_init();
+
+        write_commit_file(&repo, "a.txt", "a\n", "add a");
+
+        std::fs::write(
+            dir.path()
+                .join("a.txt"),
+            "a\nb\n",
+        )
+        .unwrap();
+
+        // gai commit --plan-out plan.json, then gai apply
+        // plan.json, the plan shows up as untracked
+        let plan = plan(&repo, &diffs(&repo));
+
+        plan.write(
+            &dir.path()
+                .join("plan.json"),
+        )
+        .unwrap();
+
+        let mut diffs = diffs(&repo);
+
+        assert!(
+            diffs
+                .files
+                .iter()
+                .any(|f| f.path == "plan.json")
+        );
+        assert!(
+            plan.stale_files(&diffs, Some("plan.json"))
+                .unwrap()
+                .is_empty()
+        );
+
+        plan.validate(&diffs)
+            .unwrap();
+
+        crate::git::commit::apply_commits(
+            &repo,
+            &plan.commits,
+            &mut diffs.files,
+            &plan.staging_type,
+        )
+        .unwrap();
+
+        let head = repo
+            .head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+
+        assert_eq!(head.summary(), Some("change a"));
+        assert!(
+            head.tree()
+                .unwrap()
+                .get_name("plan.json")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_plan_validate_missing_file() {
+        let (dir, repo) = repo_init();
+
+        write_commit_file(&repo, "a.txt", "a\n", "add a");
+
+        std::fs::write(
+            dir.path()
+                .join("a.txt"),
+            "a\nb\n",
+        )
+        .unwrap();
+
+        let diffs = diffs(&repo);
+        let mut plan = plan(&repo, &diffs);
+
+        plan.commits[0]
+            .files
+            .push("nope.txt".to_owned());
+
+        assert!(
+            plan.validate(&diffs)
+                .is_err()
+        );
+    }
+}
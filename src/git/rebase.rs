@@ -23,9 +23,17 @@
 // to do with generating commits from the diff
 // of the specified divergent point
 
-use git2::{Oid, Repository, Sort};
+use std::collections::HashMap;
 
-use super::{checkout::force_checkout_head, errors::GitError};
+use git2::{
+    Commit, ErrorCode, Index, MergeOptions, Oid, Repository, Sort,
+};
+
+use super::{
+    checkout::force_checkout_head,
+    errors::GitError,
+    reset::{reflog_message, set_head_target},
+};
 
 /// cherry pick commits, this would take in a list
 /// of commits OID that should've been captured
@@ -61,38 +69,11 @@ pub fn cherry_pick_single(
 
     let commit = repo.find_commit(oid)?;
 
-    let head = repo
-        .head()?
-        .peel_to_commit()?;
-
-    let mut index =
-        repo.cherrypick_commit(&commit, &head, 0, None)?;
-
-    if index.has_conflicts() {
-        return Err(GitError::Generic(
-            "cannot cherrypick, repo has conflicts".into(),
-        )
-        .into());
-    }
-
-    let tree_oid = index.write_tree_to(repo)?;
-    let tree = repo.find_tree(tree_oid)?;
-    let sig = repo.signature()?;
-
     let message = commit
         .message()
         .unwrap_or_default();
 
-    let oid = repo.commit(
-        Some("HEAD"),
-        &sig,
-        &sig,
-        message,
-        &tree,
-        &[&head],
-    )?;
-
-    Ok(oid.to_string())
+    pick_with_message(repo, &commit, message)
 }
 
 /// cherrypick a commit
@@ -109,12 +90,23 @@ pub fn cherry_pick_reword(
 
     let commit = repo.find_commit(oid)?;
 
-    let head = repo
-        .head()?
-        .peel_to_commit()?;
+    pick_with_message(repo, &commit, message)
+}
 
-    let mut index =
-        repo.cherrypick_commit(&commit, &head, 0, None)?;
+/// picks onto HEAD, if HEAD is unborn after
+/// resetting to the root this makes a new root
+fn pick_with_message(
+    repo: &Repository,
+    commit: &Commit,
+    message: &str,
+) -> anyhow::Result<String> {
+    let head = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit()?),
+        Err(e) if e.code() == ErrorCode::UnbornBranch => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut index = pick_index(repo, commit, head.as_ref())?;
 
     if index.has_conflicts() {
         return Err(GitError::Generic(
@@ -127,24 +119,70 @@ pub fn cherry_pick_reword(
     let tree = repo.find_tree(tree_oid)?;
     let sig = repo.signature()?;
 
-    let oid = repo.commit(
-        Some("HEAD"),
-        &sig,
-        &sig,
-        message,
-        &tree,
-        &[&head],
+    let parents: Vec<&Commit> = head
+        .iter()
+        .collect();
+
+    let oid =
+        repo.commit(None, &sig, &sig, message, &tree, &parents)?;
+
+    set_head_target(
+        repo,
+        Some(oid),
+        &reflog_message("pick", message),
     )?;
 
     Ok(oid.to_string())
 }
 
+/// the cherrypick as an in memory index,
+/// without a head the commit's changes are
+/// merged into an empty tree
+pub(super) fn pick_index(
+    repo: &Repository,
+    commit: &Commit,
+    head: Option<&Commit>,
+) -> anyhow::Result<Index> {
+    if let Some(head) = head {
+        return Ok(repo.cherrypick_commit(commit, head, 0, None)?);
+    }
+
+    let empty = repo.find_tree(
+        repo.treebuilder(None)?
+            .write()?,
+    )?;
+
+    let ancestor = if commit.parent_count() > 0 {
+        commit
+            .parent(0)?
+            .tree()?
+    } else {
+        empty.to_owned()
+    };
+
+    Ok(repo.merge_trees(
+        &ancestor,
+        &empty,
+        &commit.tree()?,
+        Some(&MergeOptions::new()),
+    )?)
+}
+
 /// helper func to get a list of trailing commits
 /// from a specified oid, this just walks from that commit
 /// back
 pub fn trailing_commits(
     repo: &Repository,
     from: &str,
+) -> anyhow::Result<Vec<String>> {
+    commits_after(repo, Some(Oid::from_str(from)?))
+}
+
+/// same as trailing_commits, None walks
+/// all the way back to the root
+fn commits_after(
+    repo: &Repository,
+    from: Option<Oid>,
 ) -> anyhow::Result<Vec<String>> {
     let mut trails = Vec::new();
 
@@ -152,12 +190,10 @@ pub fn trailing_commits(
     revwalk.push_head()?;
     revwalk.set_sorting(Sort::TOPOLOGICAL)?;
 
-    let from_oid = Oid::from_str(from)?;
-
     for oid in revwalk {
         let oid = oid?;
 
-        if oid == from_oid {
+        if Some(oid) == from {
             break;
         }
 
@@ -169,6 +205,174 @@ pub fn trailing_commits(
     Ok(trails)
 }
 
+/// recreates the commits between hide and tip with
+/// their original trees, parents point at the rewritten
+/// ones so merges keep their shape. a commit without a
+/// new message or a rewritten parent is kept as is.
+/// returns the new tip and old -> new oids, HEAD
+/// is not moved
+pub fn rewrite_keeping_trees(
+    repo: &Repository,
+    tip: Oid,
+    hide: &[Oid],
+    mut rewritten: HashMap<Oid, Oid>,
+    messages: &HashMap<Oid, String>,
+) -> anyhow::Result<(Oid, HashMap<Oid, Oid>)> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(tip)?;
+
+    for oid in hide {
+        revwalk.hide(*oid)?;
+    }
+
+    // parents before children
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+
+    let sig = repo.signature()?;
+
+    for oid in revwalk {
+        let oid = oid?;
+        let commit = repo.find_commit(oid)?;
+
+        let parent_ids: Vec<Oid> = commit
+            .parent_ids()
+            .map(|p| {
+                rewritten
+                    .get(&p)
+                    .copied()
+                    .unwrap_or(p)
+            })
+            .collect();
+
+        let message = messages.get(&oid);
+
+        if message.is_none()
+            && parent_ids
+                == commit
+                    .parent_ids()
+                    .collect::<Vec<_>>()
+        {
+            continue;
+        }
+
+        let parents = parent_ids
+            .iter()
+            .map(|p| repo.find_commit(*p))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let parents: Vec<&Commit> = parents
+            .iter()
+            .collect();
+
+        let new = repo.commit(
+            None,
+            &commit.author(),
+            &sig,
+            message
+                .map(String::as_str)
+                .unwrap_or(
+                    commit
+                        .message()
+                        .unwrap_or_default(),
+                ),
+            &commit.tree()?,
+            &parents,
+        )?;
+
+        rewritten.insert(oid, new);
+    }
+
+    let new_tip = rewritten
+        .get(&tip)
+        .copied()
+        .unwrap_or(tip);
+
+    Ok((new_tip, rewritten))
+}
+
+/// replays the commits after `from` up to tip onto
+/// HEAD, which must have the same tree as `from`.
+/// merges after it survive, see rewrite_keeping_trees.
+/// calls force_checkout_head()
+pub fn replay_onto_head(
+    repo: &Repository,
+    from: &str,
+    tip: &str,
+) -> anyhow::Result<Vec<String>> {
+    let from = Oid::from_str(from)?;
+    let head = super::utils::get_head_repo(repo)?;
+
+    if repo
+        .find_commit(from)?
+        .tree_id()
+        != repo
+            .find_commit(head)?
+            .tree_id()
+    {
+        return Err(GitError::Generic(
+            "HEAD does not match the commit being replayed onto"
+                .to_owned(),
+        )
+        .into());
+    }
+
+    let (new_tip, rewritten) = rewrite_keeping_trees(
+        repo,
+        Oid::from_str(tip)?,
+        &[from],
+        HashMap::from([(from, head)]),
+        &HashMap::new(),
+    )?;
+
+    set_head_target(
+        repo,
+        Some(new_tip),
+        &format!("gai replay: onto {}", &head.to_string()[..7]),
+    )?;
+    force_checkout_head(repo)?;
+
+    Ok(rewritten
+        .into_iter()
+        .filter(|(old, _)| *old != from)
+        .map(|(_, new)| new.to_string())
+        .collect())
+}
+
+/// merges among commits that would lose what they
+/// merged when flattened onto base. a merge is fine
+/// when everything it brought in is already in base
+pub fn unflattenable_merges(
+    repo: &Repository,
+    commits: &[String],
+    base: Option<Oid>,
+) -> anyhow::Result<Vec<String>> {
+    let mut res = Vec::new();
+
+    for hash in commits {
+        let commit = repo.find_commit(Oid::from_str(hash)?)?;
+
+        for merged in commit
+            .parent_ids()
+            .skip(1)
+        {
+            let in_base = match base {
+                Some(base) => {
+                    merged == base
+                        || repo.graph_descendant_of(base, merged)?
+                }
+                None => false,
+            };
+
+            if !in_base {
+                res.push(hash.to_owned());
+                break;
+            }
+        }
+    }
+
+    Ok(res)
+}
+
 /// squashes commit to previous commit
 /// new message required
 pub fn squash_to_head(
@@ -203,8 +407,8 @@ pub fn squash_to_head(
     // signature?
     let sig = repo.signature()?;
 
-    head.amend(
-        Some("HEAD"),
+    let oid = head.amend(
+        None,
         Some(&sig),
         Some(&sig),
         None,
@@ -212,9 +416,164 @@ pub fn squash_to_head(
         Some(&tree),
     )?;
 
+    set_head_target(
+        repo,
+        Some(oid),
+        &reflog_message("squash", message),
+    )?;
+
     Ok(())
 }
 
+/// replays commits in memory onto base, HEAD and
+/// the workdir are left alone. returns the first
+/// commit that would conflict, squashes land on the
+/// same tree as picks so only the order matters
+pub fn find_replay_conflict(
+    repo: &Repository,
+    base: Option<Oid>,
+    commits: &[String],
+) -> anyhow::Result<Option<String>> {
+    let empty = repo
+        .treebuilder(None)?
+        .write()?;
+
+    let mut tree = match base {
+        Some(oid) => repo
+            .find_commit(oid)?
+            .tree_id(),
+        None => empty,
+    };
+
+    for hash in commits {
+        let commit = repo.find_commit(Oid::from_str(hash)?)?;
+
+        let ancestor = if commit.parent_count() > 0 {
+            commit
+                .parent(0)?
+                .tree_id()
+        } else {
+            empty
+        };
+
+        let mut index = repo.merge_trees(
+            &repo.find_tree(ancestor)?,
+            &repo.find_tree(tree)?,
+            &commit.tree()?,
+            None,
+        )?;
+
+        if index.has_conflicts() {
+            return Ok(Some(hash.to_owned()));
+        }
+
+        tree = index.write_tree_to(repo)?;
+    }
+
+    Ok(None)
+}
+
+/// squashes fixup commits into their targets,
+/// (fixup, target) pairs. replays every commit after
+/// base in place, only HEAD moves so the workdir and
+/// index are left alone. the result must have the same
+/// tree as before, HEAD is moved back otherwise.
+/// a None base replays from the root
+pub fn autosquash(
+    repo: &Repository,
+    base: Option<Oid>,
+    fixups: &[(String, String)],
+) -> anyhow::Result<String> {
+    let original = super::utils::get_head_repo(repo)?;
+    let original_tree = repo
+        .find_commit(original)?
+        .tree_id();
+
+    let trailing = commits_after(repo, base)?;
+
+    for (fixup, target) in fixups {
+        if !trailing.contains(fixup) || !trailing.contains(target) {
+            return Err(GitError::Generic(format!(
+                "{} or {} is not in the replayed commits",
+                &fixup[..7],
+                &target[..7],
+            ))
+            .into());
+        }
+    }
+
+    set_head_target(
+        repo,
+        base,
+        &format!("gai absorb: squash {} fixup/s", fixups.len()),
+    )?;
+
+    let res =
+        replay_squashed(repo, &trailing, fixups).and_then(|oid| {
+            let tree = repo
+                .find_commit(Oid::from_str(&oid)?)?
+                .tree_id();
+
+            if tree != original_tree {
+                return Err(GitError::Generic(
+                    "squashed history does not match HEAD".to_owned(),
+                )
+                .into());
+            }
+
+            Ok(oid)
+        });
+
+    if res.is_err() {
+        set_head_target(
+            repo,
+            Some(original),
+            "gai absorb: restore after failed squash",
+        )?;
+    }
+
+    res
+}
+
+fn replay_squashed(
+    repo: &Repository,
+    trailing: &[String],
+    fixups: &[(String, String)],
+) -> anyhow::Result<String> {
+    let mut head = head_hash(repo)?;
+
+    for commit in trailing {
+        if fixups
+            .iter()
+            .any(|(f, _)| f == commit)
+        {
+            continue;
+        }
+
+        head = cherry_pick_single(repo, commit)?;
+
+        let message = repo
+            .find_commit(Oid::from_str(commit)?)?
+            .message()
+            .unwrap_or_default()
+            .to_owned();
+
+        for (fixup, _) in fixups
+            .iter()
+            .filter(|(_, t)| t == commit)
+        {
+            squash_to_head(repo, fixup, &message)?;
+            head = head_hash(repo)?;
+        }
+    }
+
+    Ok(head)
+}
+
+fn head_hash(repo: &Repository) -> anyhow::Result<String> {
+    Ok(super::utils::get_head_repo(repo)?.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -831,4 +1190,409 @@ mod tests {
         // which should be 2 total
         assert_eq!(commit_count, 2);
     }
+
+    #[test]
+    fn test_autosquash() {
+        let (_dir, repo) = repo_init();
+
+        let base = repo
+            .head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap()
+            .id()
+            .to_string();
+
+        let c1 = write_commit_file(&repo, "a.txt", "a", "add a");
+        let _c2 = write_commit_file(&repo, "b.txt", "b", "add b");
+        let fix =
+            write_commit_file(&repo, "a.txt", "aa", "fixup! add a");
+
+        // left in the workdir, autosquash shouldnt touch it
+        std::fs::write(
+            repo.workdir()
+                .unwrap()
+                .join("b.txt"),
+            "dirty",
+        )
+        .unwrap();
+
+        autosquash(
+            &repo,
+            Some(Oid::from_str(&base).unwrap()),
+            &[(fix.to_string(), c1.to_string())],
+        )
+        .unwrap();
+
+        let mut walk = repo
+            .revwalk()
+            .unwrap();
+        walk.push_head()
+            .unwrap();
+
+        let messages: Vec<String> = walk
+            .map(|oid| {
+                repo.find_commit(oid.unwrap())
+                    .unwrap()
+                    .message()
+                    .unwrap()
+                    .to_owned()
+            })
+            .collect();
+
+        assert_eq!(messages, vec!["add b", "add a", "initial"]);
+
+        // the fix landed in add a
+        let squashed = repo
+            .revparse_single("HEAD~1")
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+
+        let blob = squashed
+            .tree()
+            .unwrap()
+            .get_name("a.txt")
+            .unwrap()
+            .to_object(&repo)
+            .unwrap()
+            .peel_to_blob()
+            .unwrap();
+
+        assert_eq!(blob.content(), b"aa");
+
+        assert_eq!(
+            std::fs::read_to_string(
+                repo.workdir()
+                    .unwrap()
+                    .join("b.txt")
+            )
+            .unwrap(),
+            "dirty"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_from_root() {
+        let (_dir, repo) = repo_init();
+
+        let root = repo
+            .head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap()
+            .id()
+            .to_string();
+
+        let c1 = write_commit_file(&repo, "a.txt", "a", "add a");
+        let c2 = write_commit_file(&repo, "b.txt", "b", "add b");
+
+        let parent =
+            crate::git::commit::find_parent_commit(&repo, &root)
+                .unwrap();
+
+        assert!(parent.is_none());
+
+        crate::git::reset::reset_to_parent_hard(
+            &repo, parent, "test",
+        )
+        .unwrap();
+
+        // reword the root, squash into it, replay the rest
+        cherry_pick_reword(&repo, &root, "new root").unwrap();
+        squash_to_head(&repo, &c1.to_string(), "root with a")
+            .unwrap();
+        cherry_pick_single(&repo, &c2.to_string()).unwrap();
+
+        let head = repo
+            .head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+
+        let new_root = head
+            .parent(0)
+            .unwrap();
+
+        assert_eq!(head.message(), Some("add b"));
+        assert_eq!(new_root.message(), Some("root with a"));
+        assert_eq!(new_root.parent_count(), 0);
+        assert!(
+            new_root
+                .tree()
+                .unwrap()
+                .get_name("a.txt")
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_cherry_pick_new_root() {
+        let (_dir, repo) = repo_init();
+
+        let _c1 = write_commit_file(&repo, "a.txt", "a", "add a");
+        let c2 = write_commit_file(&repo, "b.txt", "b", "add b");
+
+        crate::git::reset::reset_to_parent_hard(&repo, None, "test")
+            .unwrap();
+
+        // only the changes of c2 end up in the new root
+        cherry_pick_single(&repo, &c2.to_string()).unwrap();
+
+        let head = repo
+            .head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+
+        let tree = head.tree().unwrap();
+
+        assert_eq!(head.parent_count(), 0);
+        assert!(
+            tree.get_name("b.txt")
+                .is_some()
+        );
+        assert!(
+            tree.get_name("a.txt")
+                .is_none()
+        );
+    }
+
+    /// feature branch that merged main midway,
+    /// returns (initial, f1, m1, merge, f2)
+    fn merged_history(
+        repo: &Repository
+    ) -> (Oid, Oid, Oid, Oid, Oid) {
+        let initial = repo
+            .head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+
+        let f1 = write_commit_file(repo, "f1.txt", "f1", "add f1");
+
+        // a commit on main, off the initial commit
+        let mut builder = repo
+            .treebuilder(Some(
+                &initial
+                    .tree()
+                    .unwrap(),
+            ))
+            .unwrap();
+        let blob = repo
+            .blob(b"main")
+            .unwrap();
+        builder
+            .insert("main.txt", blob, 0o100644)
+            .unwrap();
+        let tree = repo
+            .find_tree(
+                builder
+                    .write()
+                    .unwrap(),
+            )
+            .unwrap();
+        let sig = repo
+            .signature()
+            .unwrap();
+        let m1 = repo
+            .commit(None, &sig, &sig, "add main", &tree, &[&initial])
+            .unwrap();
+
+        let f1_commit = repo
+            .find_commit(f1)
+            .unwrap();
+        let m1_commit = repo
+            .find_commit(m1)
+            .unwrap();
+
+        let merge_tree = repo
+            .find_tree(
+                repo.merge_commits(&f1_commit, &m1_commit, None)
+                    .unwrap()
+                    .write_tree_to(repo)
+                    .unwrap(),
+            )
+            .unwrap();
+
+        let merge = repo
+            .commit(
+                Some("HEAD"),
+                &sig,
+                &sig,
+                "merge main",
+                &merge_tree,
+                &[&f1_commit, &m1_commit],
+            )
+            .unwrap();
+
+        force_checkout_head(repo).unwrap();
+
+        let f2 = write_commit_file(repo, "f2.txt", "f2", "add f2");
+
+        (initial.id(), f1, m1, merge, f2)
+    }
+
+    #[test]
+    fn test_rewrite_keeping_trees_merge() {
+        let (_dir, repo) = repo_init();
+        let (initial, f1, m1, _merge, f2) = merged_history(&repo);
+
+        let (tip, rewritten) = rewrite_keeping_trees(
+            &repo,
+            f2,
+            &[initial],
+            HashMap::new(),
+            &HashMap::from([(f1, "feat: add f1".to_owned())]),
+        )
+        .unwrap();
+
+        // the main side is untouched
+        assert!(!rewritten.contains_key(&m1));
+
+        let tip = repo
+            .find_commit(tip)
+            .unwrap();
+
+        assert_eq!(
+            tip.tree_id(),
+            repo.find_commit(f2)
+                .unwrap()
+                .tree_id()
+        );
+
+        let merge = tip
+            .parent(0)
+            .unwrap();
+
+        assert_eq!(merge.parent_count(), 2);
+        assert_eq!(
+            merge
+                .parent_id(1)
+                .unwrap(),
+            m1
+        );
+        assert_eq!(
+            merge
+                .parent(0)
+                .unwrap()
+                .message(),
+            Some("feat: add f1")
+        );
+    }
+
+    #[test]
+    fn test_replay_onto_head_merge() {
+        let (_dir, repo) = repo_init();
+        let (initial, f1, m1, _merge, f2) = merged_history(&repo);
+
+        // recreate f1 with the same tree, like
+        // gai rebase does for the range
+        crate::git::reset::reset_repo_hard(
+            &repo,
+            &initial.to_string(),
+            "test",
+        )
+        .unwrap();
+        let new_f1 =
+            cherry_pick_reword(&repo, &f1.to_string(), "feat: f1")
+                .unwrap();
+
+        replay_onto_head(&repo, &f1.to_string(), &f2.to_string())
+            .unwrap();
+
+        let head = repo
+            .head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+
+        let merge = head
+            .parent(0)
+            .unwrap();
+
+        assert_eq!(head.message(), Some("add f2"));
+        assert_eq!(
+            merge
+                .parent_ids()
+                .collect::<Vec<_>>(),
+            vec![Oid::from_str(&new_f1).unwrap(), m1]
+        );
+        assert!(
+            repo.workdir()
+                .unwrap()
+                .join("main.txt")
+                .exists()
+        );
+    }
+
+    #[test]
+    fn test_unflattenable_merges() {
+        let (_dir, repo) = repo_init();
+        let (initial, f1, m1, merge, f2) = merged_history(&repo);
+
+        let range = [f1, merge, f2].map(|o| o.to_string());
+
+        // main isnt in the base, flattening loses it
+        assert_eq!(
+            unflattenable_merges(&repo, &range, Some(initial))
+                .unwrap(),
+            vec![merge.to_string()]
+        );
+
+        assert!(
+            unflattenable_merges(&repo, &range, Some(m1))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_find_replay_conflict() {
+        let (_dir, repo) = repo_init();
+
+        let base = write_commit_file(&repo, "a.txt", "a\n", "add a");
+        let b = write_commit_file(&repo, "a.txt", "b\n", "a to b")
+            .to_string();
+        let c = write_commit_file(&repo, "a.txt", "c\n", "b to c")
+            .to_string();
+        let head = repo
+            .head()
+            .unwrap()
+            .target();
+
+        assert!(
+            find_replay_conflict(
+                &repo,
+                Some(base),
+                &[b.to_owned(), c.to_owned()]
+            )
+            .unwrap()
+            .is_none()
+        );
+
+        // c only applies on top of b
+        assert_eq!(
+            find_replay_conflict(
+                &repo,
+                Some(base),
+                &[c.to_owned(), b.to_owned()]
+            )
+            .unwrap(),
+            Some(c.to_owned())
+        );
+        assert_eq!(
+            find_replay_conflict(&repo, Some(base), &[c.to_owned()])
+                .unwrap(),
+            Some(c)
+        );
+
+        // nothing moved
+        assert_eq!(
+            repo.head()
+                .unwrap()
+                .target(),
+            head
+        );
+    }
 }
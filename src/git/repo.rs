@@ -44,4 +44,12 @@ impl GitRepo {
 
         Ok(Self { repo, workdir })
     }
+
+    /// where gai keeps its own state
+    /// inside the git dir, ex. .git/gai/
+    pub fn gai_dir(&self) -> PathBuf {
+        self.repo
+            .path()
+            .join("gai")
+    }
 }
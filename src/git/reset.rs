@@ -1,33 +1,157 @@
-use git2::{Oid, Repository, ResetType};
+use git2::{Oid, Repository, ResetType, build::CheckoutBuilder};
+
+use super::errors::GitError;
 
 /// modified from asyncgit
 /// resets to commit HARD (deletes changes)
 pub fn reset_repo_hard(
     repo: &Repository,
     commit: &str,
+    message: &str,
 ) -> anyhow::Result<()> {
     let commit = Oid::from_str(commit)?;
 
-    reset_repo(repo, commit, ResetType::Hard)
+    reset_repo(repo, commit, ResetType::Hard, message)
 }
 
 /// reset repo to commit, mixed (keep changes)
 pub fn reset_repo_mixed(
     repo: &Repository,
     commit: &str,
+    message: &str,
 ) -> anyhow::Result<()> {
     let commit = Oid::from_str(commit)?;
 
-    reset_repo(repo, commit, ResetType::Mixed)
+    reset_repo(repo, commit, ResetType::Mixed, message)
 }
 
+/// reset HARD to the parent of a rewrite,
+/// None is the parent of the root commit,
+/// which leaves the branch unborn and empty
+pub fn reset_to_parent_hard(
+    repo: &Repository,
+    parent: Option<Oid>,
+    message: &str,
+) -> anyhow::Result<()> {
+    match parent {
+        Some(oid) => reset_repo(repo, oid, ResetType::Hard, message),
+        None => reset_to_unborn(repo, true, message),
+    }
+}
+
+/// same as reset_to_parent_hard but
+/// keeps the changes in the workdir
+pub fn reset_to_parent_mixed(
+    repo: &Repository,
+    parent: Option<Oid>,
+    message: &str,
+) -> anyhow::Result<()> {
+    match parent {
+        Some(oid) => reset_repo(repo, oid, ResetType::Mixed, message),
+        None => reset_to_unborn(repo, false, message),
+    }
+}
+
+/// reflog message for a gai operation, the action
+/// then the first line of a commit message,
+/// ex. "gai reword: feat: add login"
+pub fn reflog_message(
+    action: &str,
+    message: &str,
+) -> String {
+    format!(
+        "gai {action}: {}",
+        message
+            .lines()
+            .next()
+            .unwrap_or_default()
+    )
+}
+
+/// point HEAD, or the branch it is on, at target
+/// without touching the index or workdir. None
+/// deletes the branch so the next commit is a root
+pub fn set_head_target(
+    repo: &Repository,
+    target: Option<Oid>,
+    message: &str,
+) -> anyhow::Result<()> {
+    let head = repo.find_reference("HEAD")?;
+
+    match (head.symbolic_target(), target) {
+        (Some(branch), Some(oid)) => {
+            repo.reference(branch, oid, true, message)?;
+        }
+        (Some(branch), None) => {
+            if let Ok(mut reference) = repo.find_reference(branch) {
+                reference.delete()?;
+            }
+        }
+        (None, Some(oid)) => {
+            // a direct HEAD ref keeps it detached
+            // and takes the reflog message
+            repo.reference("HEAD", oid, true, message)?;
+        }
+        (None, None) => {
+            return Err(GitError::Generic(
+                "rewriting the root commit needs a branch checked out"
+                    .to_owned(),
+            )
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+fn reset_to_unborn(
+    repo: &Repository,
+    hard: bool,
+    message: &str,
+) -> anyhow::Result<()> {
+    if hard {
+        // checking out an empty tree removes
+        // everything HEAD tracks, untracked stays
+        let empty = repo.find_tree(
+            repo.treebuilder(None)?
+                .write()?,
+        )?;
+
+        repo.checkout_tree(
+            empty.as_object(),
+            Some(CheckoutBuilder::new().force()),
+        )?;
+    }
+
+    let mut index = repo.index()?;
+    index.clear()?;
+    index.write()?;
+
+    set_head_target(repo, None, message)
+}
+
+/// moves HEAD first so the reflog gets our message,
+/// the reset after it is then a no-op for the refs
+/// and only syncs the index and workdir
 fn reset_repo(
     repo: &Repository,
     commit: Oid,
     kind: ResetType,
+    message: &str,
 ) -> anyhow::Result<()> {
     let c = repo.find_commit(commit)?;
 
+    // hard resets check out against the old
+    // HEAD, so anything it tracked gets removed
+    if kind == ResetType::Hard {
+        repo.checkout_tree(
+            c.as_object(),
+            Some(CheckoutBuilder::new().force()),
+        )?;
+    }
+
+    set_head_target(repo, Some(commit), message)?;
+
     repo.reset(c.as_object(), kind, None)?;
 
     Ok(())
@@ -62,7 +186,7 @@ mod tests {
                 .exists()
         );
 
-        reset_repo_hard(&repo, &initial).unwrap();
+        reset_repo_hard(&repo, &initial, "test").unwrap();
 
         let head = repo
             .head()
@@ -106,7 +230,7 @@ mod tests {
 
         let _c1 = write_commit_file(&repo, "a.txt", "foo", "add a");
 
-        reset_repo_mixed(&repo, &initial).unwrap();
+        reset_repo_mixed(&repo, &initial, "test").unwrap();
 
         let head = repo
             .head()
@@ -129,4 +253,54 @@ mod tests {
                 .exists()
         );
     }
+
+    #[test]
+    fn test_reset_to_root() {
+        let (_dir, repo) = repo_init();
+
+        let c1 = write_commit_file(&repo, "a.txt", "root", "add a");
+
+        reset_to_parent_mixed(&repo, None, "test").unwrap();
+
+        assert!(
+            repo.head()
+                .is_err_and(
+                    |e| e.code() == git2::ErrorCode::UnbornBranch
+                )
+        );
+        assert!(
+            repo.index()
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            repo.workdir()
+                .unwrap()
+                .join("a.txt")
+                .exists()
+        );
+
+        reset_repo_hard(&repo, &c1.to_string(), "test").unwrap();
+        reset_to_parent_hard(&repo, None, "test").unwrap();
+
+        assert!(
+            !repo
+                .workdir()
+                .unwrap()
+                .join("a.txt")
+                .exists()
+        );
+
+        // and back, like a rollback would
+        reset_repo_hard(&repo, &c1.to_string(), "test").unwrap();
+
+        assert_eq!(
+            repo.head()
+                .unwrap()
+                .peel_to_commit()
+                .unwrap()
+                .id(),
+            c1
+        );
+    }
 }
@@ -0,0 +1,726 @@
+// rewrites that survive a ctrl-c, a crash or a conflict
+//
+// before gai moves HEAD for a rewrite it writes
+// .git/gai/rewrite/state.json with the original refs,
+// and for plans the steps left to run. every step saves
+// the state before HEAD moves, so after an interruption
+// the saved head is always the one to trust
+//
+// gai rebase --continue, --skip and --abort read it back,
+// like git does with .git/rebase-merge
+
+use std::{fs, path::PathBuf};
+
+use git2::{CherrypickOptions, Commit, Oid, Repository, ResetType};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    backup::create_backup,
+    checkout::force_checkout_head,
+    errors::GitError,
+    rebase::pick_index,
+    reset::{reflog_message, reset_repo_hard, set_head_target},
+    status::is_workdir_clean,
+    todo::{TodoCommand, TodoStep},
+};
+
+/// why a rewrite stopped
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Stop {
+    /// after an edit step, waiting for an amend
+    Edit,
+
+    /// a step conflicted, the conflict is in the
+    /// index and workdir waiting to be resolved
+    Conflict,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RewriteState {
+    /// the gai command that started it, ex. rebase
+    pub command: String,
+
+    /// branch HEAD was on, None when detached
+    pub head_name: Option<String>,
+    pub orig_head: String,
+
+    /// the plan with full hashes. empty for rewrites
+    /// that cant be resumed, those can only be aborted
+    pub steps: Vec<TodoStep>,
+
+    /// steps already applied
+    pub done: usize,
+
+    /// HEAD after the last applied step,
+    /// None while the branch is unborn
+    pub head: Option<String>,
+    pub stopped: Option<Stop>,
+}
+
+impl RewriteState {
+    /// records where HEAD is before anything moves and
+    /// backs it up under refs/gai/backup, errors if
+    /// another rewrite is still going
+    pub fn begin(
+        repo: &Repository,
+        command: &str,
+        steps: Vec<TodoStep>,
+    ) -> anyhow::Result<Self> {
+        ensure_no_rewrite(repo)?;
+
+        let head = repo.head()?;
+
+        let orig_head = head
+            .peel_to_commit()?
+            .id()
+            .to_string();
+
+        let head_name = if head.is_branch() {
+            head.name()
+                .map(|n| n.to_owned())
+        } else {
+            None
+        };
+
+        create_backup(
+            repo,
+            Oid::from_str(&orig_head)?,
+            head_name.as_deref(),
+            &format!("gai {command}: backup before rewrite"),
+        )?;
+
+        let state = Self {
+            command: command.to_owned(),
+            head_name,
+            orig_head: orig_head.to_owned(),
+            steps,
+            done: 0,
+            head: Some(orig_head),
+            stopped: None,
+        };
+
+        state.save(repo)?;
+
+        Ok(state)
+    }
+
+    /// written to a temp file first so a crash
+    /// never leaves half a state behind
+    pub fn save(
+        &self,
+        repo: &Repository,
+    ) -> anyhow::Result<()> {
+        let dir = state_dir(repo);
+        fs::create_dir_all(&dir)?;
+
+        let tmp = dir.join("state.json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(tmp, dir.join("state.json"))?;
+
+        Ok(())
+    }
+
+    /// the step it stopped at, if any
+    pub fn current(&self) -> Option<&TodoStep> {
+        match self.stopped {
+            Some(Stop::Conflict) => self
+                .steps
+                .get(self.done),
+            Some(Stop::Edit) => self
+                .done
+                .checked_sub(1)
+                .and_then(|i| self.steps.get(i)),
+            None => None,
+        }
+    }
+}
+
+fn state_dir(repo: &Repository) -> PathBuf {
+    repo.path()
+        .join("gai")
+        .join("rewrite")
+}
+
+pub fn load_rewrite(
+    repo: &Repository
+) -> anyhow::Result<Option<RewriteState>> {
+    let path = state_dir(repo).join("state.json");
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+}
+
+pub fn clear_rewrite(repo: &Repository) -> anyhow::Result<()> {
+    let dir = state_dir(repo);
+
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+
+    Ok(())
+}
+
+/// anything that moves HEAD should check
+/// this first
+pub fn ensure_no_rewrite(repo: &Repository) -> anyhow::Result<()> {
+    if let Some(state) = load_rewrite(repo)? {
+        return Err(GitError::Generic(format!(
+            "a gai {} is in progress, run `gai rebase --continue` or `gai rebase --abort` first",
+            state.command
+        ))
+        .into());
+    }
+
+    Ok(())
+}
+
+/// runs the steps from state.done on. a conflict or an
+/// edit stops it with the state saved, finishing syncs
+/// the workdir and clears the state
+pub fn run_steps(
+    repo: &Repository,
+    state: &mut RewriteState,
+) -> anyhow::Result<Option<Stop>> {
+    while let Some(step) = state
+        .steps
+        .get(state.done)
+        .cloned()
+    {
+        if step.command == TodoCommand::Drop {
+            state.done += 1;
+            state.save(repo)?;
+            continue;
+        }
+
+        let commit =
+            repo.find_commit(Oid::from_str(&step.commit)?)?;
+        let head = saved_head(repo, state)?;
+
+        let mut index = pick_index(repo, &commit, head.as_ref())?;
+
+        if index.has_conflicts() {
+            write_conflict(repo, &commit)?;
+
+            state.stopped = Some(Stop::Conflict);
+            state.save(repo)?;
+
+            return Ok(Some(Stop::Conflict));
+        }
+
+        let tree = repo.find_tree(index.write_tree_to(repo)?)?;
+        let oid =
+            commit_step(repo, &step, &commit, head.as_ref(), &tree)?;
+
+        state.head = Some(oid.to_string());
+        state.done += 1;
+
+        if step.command == TodoCommand::Edit {
+            state.stopped = Some(Stop::Edit);
+        }
+
+        state.save(repo)?;
+
+        set_head_target(
+            repo,
+            Some(oid),
+            &reflog_message(
+                &format!("{} ({})", state.command, step.command),
+                repo.find_commit(oid)?
+                    .message()
+                    .unwrap_or_default(),
+            ),
+        )?;
+
+        if step.command == TodoCommand::Edit {
+            force_checkout_head(repo)?;
+            return Ok(Some(Stop::Edit));
+        }
+    }
+
+    force_checkout_head(repo)?;
+    clear_rewrite(repo)?;
+
+    Ok(None)
+}
+
+/// picks a stopped rewrite back up. a resolved conflict is
+/// committed from the index, after an edit whatever HEAD
+/// is now is kept. after a crash HEAD is moved to the last
+/// saved step first
+pub fn continue_rewrite(
+    repo: &Repository,
+    state: &mut RewriteState,
+) -> anyhow::Result<Option<Stop>> {
+    if state
+        .steps
+        .is_empty()
+    {
+        return Err(GitError::Generic(format!(
+            "gai {} cant be continued, run `gai rebase --abort` to go back to {}",
+            state.command,
+            &state.orig_head[..7]
+        ))
+        .into());
+    }
+
+    let head = current_head(repo)?;
+
+    match state.stopped {
+        Some(Stop::Conflict) => {
+            let mut index = repo.index()?;
+
+            if index.has_conflicts() {
+                return Err(GitError::Generic(
+                    "resolve and stage the conflicts before continuing"
+                        .to_owned(),
+                )
+                .into());
+            }
+
+            if head == state.head {
+                let step = &state.steps[state.done];
+                let commit =
+                    repo.find_commit(Oid::from_str(&step.commit)?)?;
+
+                let tree =
+                    repo.find_tree(index.write_tree_to(repo)?)?;
+                let oid = commit_step(
+                    repo,
+                    step,
+                    &commit,
+                    saved_head(repo, state)?.as_ref(),
+                    &tree,
+                )?;
+
+                state.head = Some(oid.to_string());
+            } else {
+                // committed by hand
+                state.head = head.to_owned();
+            }
+
+            state.done += 1;
+        }
+        Some(Stop::Edit) => {
+            if !is_workdir_clean(repo)? {
+                return Err(GitError::Generic(
+                    "commit or amend the changes before continuing"
+                        .to_owned(),
+                )
+                .into());
+            }
+
+            state.head = head.to_owned();
+        }
+        None => {}
+    }
+
+    state.stopped = None;
+    state.save(repo)?;
+
+    sync_head(repo, state, head)?;
+
+    run_steps(repo, state)
+}
+
+/// drops the step that conflicted and
+/// goes on with the rest
+pub fn skip_step(
+    repo: &Repository,
+    state: &mut RewriteState,
+) -> anyhow::Result<Option<Stop>> {
+    if state.stopped != Some(Stop::Conflict) {
+        return Err(GitError::Generic(
+            "nothing to skip, only a conflicted step can be skipped"
+                .to_owned(),
+        )
+        .into());
+    }
+
+    // throw away the half applied step
+    reset_to_saved(repo, state)?;
+
+    state.stopped = None;
+    state.done += 1;
+    state.save(repo)?;
+
+    run_steps(repo, state)
+}
+
+/// puts the branch back where it was and
+/// forgets the rewrite
+pub fn abort_rewrite(
+    repo: &Repository,
+    state: &RewriteState,
+) -> anyhow::Result<()> {
+    let orig = Oid::from_str(&state.orig_head)?;
+    let message = format!(
+        "gai {} (abort): back to {}",
+        state.command,
+        &state.orig_head[..7]
+    );
+
+    match state.head_name {
+        Some(ref name) => {
+            repo.reference(name, orig, true, &message)?;
+            repo.set_head(name)?;
+        }
+        None => set_head_target(repo, Some(orig), &message)?,
+    }
+
+    repo.reset(
+        repo.find_commit(orig)?
+            .as_object(),
+        ResetType::Hard,
+        None,
+    )?;
+
+    clear_rewrite(repo)
+}
+
+/// the commit a step makes, squash and fixup replace
+/// HEAD instead of going on top of it
+fn commit_step(
+    repo: &Repository,
+    step: &TodoStep,
+    commit: &Commit,
+    head: Option<&Commit>,
+    tree: &git2::Tree,
+) -> anyhow::Result<Oid> {
+    let sig = repo.signature()?;
+
+    if matches!(
+        step.command,
+        TodoCommand::Squash | TodoCommand::Fixup
+    ) {
+        let Some(head) = head else {
+            return Err(GitError::Generic(format!(
+                "nothing to {} {} into",
+                step.command,
+                &step.commit[..7]
+            ))
+            .into());
+        };
+
+        let message = match step.message {
+            Some(ref msg) if step.command == TodoCommand::Squash => {
+                msg
+            }
+            _ => head
+                .message()
+                .unwrap_or_default(),
+        };
+
+        let parents: Vec<Commit> = head
+            .parents()
+            .collect();
+        let parents: Vec<&Commit> = parents
+            .iter()
+            .collect();
+
+        return Ok(
+            repo.commit(None, &sig, &sig, message, tree, &parents)?
+        );
+    }
+
+    let message = match step.message {
+        Some(ref msg) => msg,
+        None => commit
+            .message()
+            .unwrap_or_default(),
+    };
+
+    let parents: Vec<&Commit> = head
+        .into_iter()
+        .collect();
+
+    Ok(repo.commit(None, &sig, &sig, message, tree, &parents)?)
+}
+
+/// HEAD as the state last saved it
+fn saved_head<'r>(
+    repo: &'r Repository,
+    state: &RewriteState,
+) -> anyhow::Result<Option<Commit<'r>>> {
+    state
+        .head
+        .as_deref()
+        .map(|h| Ok(repo.find_commit(Oid::from_str(h)?)?))
+        .transpose()
+}
+
+fn current_head(repo: &Repository) -> anyhow::Result<Option<String>> {
+    match repo.head() {
+        Ok(head) => Ok(Some(
+            head.peel_to_commit()?
+                .id()
+                .to_string(),
+        )),
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
+            Ok(None)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// moves HEAD to the saved head, a crash between
+/// saving a step and moving HEAD leaves it behind
+fn sync_head(
+    repo: &Repository,
+    state: &RewriteState,
+    head: Option<String>,
+) -> anyhow::Result<()> {
+    if head == state.head {
+        return Ok(());
+    }
+
+    set_head_target(
+        repo,
+        state
+            .head
+            .as_deref()
+            .map(Oid::from_str)
+            .transpose()?,
+        &format!("gai {} (continue): sync HEAD", state.command),
+    )
+}
+
+/// HEAD, index and workdir back to the saved head
+fn reset_to_saved(
+    repo: &Repository,
+    state: &RewriteState,
+) -> anyhow::Result<()> {
+    let Some(head) = saved_head(repo, state)? else {
+        return Err(GitError::Generic(
+            "cannot skip the first step of a new root".to_owned(),
+        )
+        .into());
+    };
+
+    reset_repo_hard(
+        repo,
+        &head
+            .id()
+            .to_string(),
+        &reflog_message(
+            &format!("{} (skip)", state.command),
+            head.message()
+                .unwrap_or_default(),
+        ),
+    )
+}
+
+/// leaves the conflicted pick in the index and
+/// workdir for the user to resolve, without
+/// git's own cherry-pick state
+fn write_conflict(
+    repo: &Repository,
+    commit: &Commit,
+) -> anyhow::Result<()> {
+    if repo.head().is_err() {
+        return Err(GitError::Generic(format!(
+            "{} conflicts on an empty branch",
+            &commit
+                .id()
+                .to_string()[..7]
+        ))
+        .into());
+    }
+
+    force_checkout_head(repo)?;
+
+    repo.cherrypick(commit, Some(&mut CherrypickOptions::new()))?;
+    repo.cleanup_state()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::tests::{repo_init, write_commit_file};
+
+    fn step(
+        command: TodoCommand,
+        commit: Oid,
+    ) -> TodoStep {
+        TodoStep {
+            command,
+            commit: commit.to_string(),
+            summary: String::new(),
+            message: None,
+        }
+    }
+
+    fn head_commit(repo: &Repository) -> Commit<'_> {
+        repo.head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_rewrite_edit_then_continue() {
+        let (_dir, repo) = repo_init();
+        let base = head_commit(&repo).id();
+
+        let a = write_commit_file(&repo, "a.txt", "a\n", "add a");
+        let b = write_commit_file(&repo, "b.txt", "b\n", "add b");
+
+        let mut state = RewriteState::begin(
+            &repo,
+            "rebase",
+            vec![
+                step(TodoCommand::Edit, a),
+                step(TodoCommand::Pick, b),
+            ],
+        )
+        .unwrap();
+
+        assert!(ensure_no_rewrite(&repo).is_err());
+
+        repo.reset(
+            repo.find_commit(base)
+                .unwrap()
+                .as_object(),
+            ResetType::Hard,
+            None,
+        )
+        .unwrap();
+        state.head = Some(base.to_string());
+
+        assert_eq!(
+            run_steps(&repo, &mut state).unwrap(),
+            Some(Stop::Edit)
+        );
+
+        // amend like a user would, then pick up
+        // from disk like a new process
+        head_commit(&repo)
+            .amend(
+                Some("HEAD"),
+                None,
+                None,
+                None,
+                Some("amended"),
+                None,
+            )
+            .unwrap();
+
+        let mut state = load_rewrite(&repo)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(state.done, 1);
+        assert_eq!(
+            continue_rewrite(&repo, &mut state).unwrap(),
+            None
+        );
+
+        let head = head_commit(&repo);
+
+        assert_eq!(head.message(), Some("add b"));
+        assert_eq!(
+            head.parent(0)
+                .unwrap()
+                .message(),
+            Some("amended")
+        );
+        assert!(
+            load_rewrite(&repo)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_rewrite_conflict_skip_and_abort() {
+        let (_dir, repo) = repo_init();
+
+        let base = write_commit_file(&repo, "a.txt", "a\n", "add a");
+        write_commit_file(&repo, "a.txt", "b\n", "a to b");
+        let c = write_commit_file(&repo, "a.txt", "c\n", "b to c");
+        let d = write_commit_file(&repo, "d.txt", "d\n", "add d");
+
+        let steps = vec![
+            step(TodoCommand::Pick, c),
+            step(TodoCommand::Pick, d),
+        ];
+
+        for skip in [true, false] {
+            let mut state = RewriteState::begin(
+                &repo,
+                "rebase",
+                steps.to_owned(),
+            )
+            .unwrap();
+
+            repo.reset(
+                repo.find_commit(base)
+                    .unwrap()
+                    .as_object(),
+                ResetType::Hard,
+                None,
+            )
+            .unwrap();
+            state.head = Some(base.to_string());
+
+            // c needs b
+            assert_eq!(
+                run_steps(&repo, &mut state).unwrap(),
+                Some(Stop::Conflict)
+            );
+            assert!(
+                repo.index()
+                    .unwrap()
+                    .has_conflicts()
+            );
+            assert!(continue_rewrite(&repo, &mut state).is_err());
+
+            if skip {
+                assert_eq!(
+                    skip_step(&repo, &mut state).unwrap(),
+                    None
+                );
+
+                let head = head_commit(&repo);
+                assert_eq!(head.message(), Some("add d"));
+                assert_eq!(
+                    head.parent_id(0)
+                        .unwrap(),
+                    base
+                );
+
+                // back to where the loop started
+                repo.reset(
+                    repo.find_commit(d)
+                        .unwrap()
+                        .as_object(),
+                    ResetType::Hard,
+                    None,
+                )
+                .unwrap();
+            } else {
+                abort_rewrite(&repo, &state).unwrap();
+
+                assert_eq!(head_commit(&repo).id(), d);
+                assert!(
+                    repo.head()
+                        .unwrap()
+                        .is_branch()
+                );
+            }
+
+            assert!(
+                load_rewrite(&repo)
+                    .unwrap()
+                    .is_none()
+            );
+        }
+    }
+}
@@ -35,18 +35,31 @@
 // of the same changes
 // do we try to increase the diff context in this case?
 // to try and get a bigger match?
-// for now lets bail
+//
+// content alone isnt enough for that, so candidates
+// are narrowed down by position on the workdir side
+// (new_start), which doesnt move between our commits,
+// then by their context lines, see pick_candidate
 //
 
 /// for different types
 /// of adding/staging per commit
 #[derive(
-    Debug, Clone, Default, serde::Serialize, serde::Deserialize,
+    Debug,
+    Clone,
+    Default,
+    serde::Serialize,
+    serde::Deserialize,
+    clap::ValueEnum,
 )]
 pub enum StagingStrategy {
     /// as individual hunks
     Hunks,
 
+    /// blocks of changed lines within hunks
+    /// for when one hunk mixes unrelated changes
+    Lines,
+
     /// only stage one file PER commit
     OneFilePerCommit,
 
@@ -67,10 +80,15 @@ pub enum StagingStrategy {
 use git2::{IndexAddOption, Repository};
 use std::{collections::HashSet, path::Path};
 
-use crate::git::lines::{get_changes_from_gai, get_changes_from_raw};
+use crate::git::lines::{
+    get_changes_from_gai, get_changes_from_raw, get_lines_from_raw,
+};
 
 use super::{
-    diffs::Hunk,
+    diffs::{
+        DiffLine, DiffLinePosition, DiffLineType, FileDiff, Hunk,
+        LineId, block_anchors, change_blocks,
+    },
     errors::GitError,
     lines::stage_lines,
     patches::{get_file_diff_patch, patch_get_hunklines},
@@ -108,27 +126,37 @@ pub fn stage_hunks(
     for hunk in og_hunks_to_stage {
         let gai_diff_lines = get_changes_from_gai(hunk);
 
-        // attempt to find matching hunk
-        let matching_hunk = new_hunks
+        // every unused hunk with the same changes
+        let candidates: Vec<Candidate> = new_hunks
             .iter()
             .enumerate()
             .filter(|(idx, _new_hunk)| {
                 !already_used_new_hunks.contains(idx)
             })
-            .find(|(_idx, new_hunk)| {
-                let raw_diff_lines = get_changes_from_raw(new_hunk);
-                if gai_diff_lines.len() != raw_diff_lines.len() {
-                    false
-                } else {
-                    gai_diff_lines
-                        .iter()
-                        .zip(raw_diff_lines.iter())
-                        .all(|(a, b)| {
-                            a.line_type == b.line_type
-                                && a.content == b.content
-                        })
-                }
-            });
+            .filter(|(_idx, new_hunk)| {
+                same_lines(
+                    &gai_diff_lines,
+                    &get_changes_from_raw(new_hunk),
+                )
+            })
+            .map(|(idx, new_hunk)| Candidate {
+                idx,
+                position: new_hunk
+                    .hunk
+                    .new_start,
+                context: same_lines(
+                    &hunk.lines,
+                    &get_lines_from_raw(new_hunk),
+                ),
+            })
+            .collect();
+
+        let matching_hunk = pick_candidate(
+            &candidates,
+            hunk.header
+                .new_start,
+        )?
+        .map(|idx| (idx, &new_hunks[idx]));
 
         match matching_hunk {
             Some((idx, matching_hunk)) => {
@@ -170,6 +198,196 @@ pub fn stage_hunks(
     Ok(used)
 }
 
+/// for line staging, the blocks are looked up
+/// in the og file diff and matched by content
+/// against the blocks of the regenerated diff,
+/// since earlier commits shift the line numbers
+pub fn stage_line_blocks(
+    repo: &Repository,
+    og_file_diff: &FileDiff,
+    ids: &[LineId],
+) -> anyhow::Result<()> {
+    let file_path = og_file_diff
+        .path
+        .as_str();
+
+    // regen diff
+    let patch = get_file_diff_patch(repo, file_path)?;
+    let new_hunks = patch_get_hunklines(&patch)?;
+
+    let new_lines: Vec<_> = new_hunks
+        .iter()
+        .map(get_lines_from_raw)
+        .collect();
+
+    // (anchor, block)
+    let new_blocks: Vec<_> = new_hunks
+        .iter()
+        .zip(new_lines.iter())
+        .flat_map(|(hunk, lines)| {
+            block_anchors(hunk.hunk.new_start, lines)
+                .into_iter()
+                .zip(change_blocks(lines))
+        })
+        .collect();
+
+    let mut already_used: HashSet<usize> = HashSet::new();
+    let mut lines: Vec<DiffLinePosition> = Vec::new();
+
+    for id in ids {
+        let invalid = || {
+            GitError::InvalidHunk(format!(
+                "{}:{}:{}",
+                id.path, id.hunk, id.block
+            ))
+        };
+
+        let og_hunk = og_file_diff
+            .hunks
+            .iter()
+            .find(|h| h.id == id.hunk)
+            .ok_or_else(invalid)?;
+
+        let og_block = og_hunk
+            .blocks()
+            .get(id.block)
+            .copied()
+            .ok_or_else(invalid)?;
+
+        let og_anchor = block_anchors(
+            og_hunk
+                .header
+                .new_start,
+            &og_hunk.lines,
+        )[id.block];
+
+        // blocks have no context of their own
+        // so only the position can break ties
+        let candidates: Vec<Candidate> = new_blocks
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !already_used.contains(idx))
+            .filter(|(_, (_, block))| same_lines(block, og_block))
+            .map(|(idx, (anchor, _))| Candidate {
+                idx,
+                position: *anchor,
+                context: true,
+            })
+            .collect();
+
+        let idx = pick_candidate(&candidates, og_anchor)?
+            .ok_or_else(|| {
+                GitError::Generic(format!(
+                    "no matching lines found for {}:{}:{}",
+                    id.path, id.hunk, id.block
+                ))
+            })?;
+
+        already_used.insert(idx);
+
+        lines.extend(
+            new_blocks[idx]
+                .1
+                .iter()
+                .map(|l| l.position),
+        );
+    }
+
+    stage_lines(repo, file_path, &lines)
+}
+
+/// a regenerated hunk/block whose changes
+/// match the one we're trying to stage
+struct Candidate {
+    idx: usize,
+    /// workdir line it starts at
+    position: u32,
+    /// same context lines around the changes
+    context: bool,
+}
+
+/// pick which of the content matching candidates is the
+/// one we're after. the anchor is where the og hunk was on the
+/// workdir side, an exact hit wins. otherwise prefer the ones
+/// with the same context and take the nearest, if two are
+/// equally near we error instead of guessing wrong
+fn pick_candidate(
+    candidates: &[Candidate],
+    anchor: u32,
+) -> anyhow::Result<Option<usize>> {
+    match candidates {
+        [] => return Ok(None),
+        [only] => return Ok(Some(only.idx)),
+        _ => {}
+    }
+
+    if let Some(c) = candidates
+        .iter()
+        .find(|c| c.position == anchor)
+    {
+        return Ok(Some(c.idx));
+    }
+
+    let with_context: Vec<&Candidate> = candidates
+        .iter()
+        .filter(|c| c.context)
+        .collect();
+
+    let pool: Vec<&Candidate> = if with_context.is_empty() {
+        candidates
+            .iter()
+            .collect()
+    } else {
+        with_context
+    };
+
+    let distance = |c: &&Candidate| {
+        c.position
+            .abs_diff(anchor)
+    };
+
+    let nearest = pool
+        .iter()
+        .map(distance)
+        .min()
+        .unwrap_or_default();
+
+    let mut nearest: Vec<&&Candidate> = pool
+        .iter()
+        .filter(|c| distance(c) == nearest)
+        .collect();
+
+    if nearest.len() > 1 {
+        return Err(GitError::Generic(format!(
+            "ambiguous hunk, {} identical changes near line {}",
+            nearest.len(),
+            anchor
+        ))
+        .into());
+    }
+
+    Ok(nearest
+        .pop()
+        .map(|c| c.idx))
+}
+
+/// same line types and content, headers are
+/// skipped since their line numbers shift
+fn same_lines(
+    a: &[DiffLine],
+    b: &[DiffLine],
+) -> bool {
+    let a = a
+        .iter()
+        .filter(|l| l.line_type != DiffLineType::Header);
+    let b = b
+        .iter()
+        .filter(|l| l.line_type != DiffLineType::Header);
+
+    a.map(|l| (l.line_type, &l.content))
+        .eq(b.map(|l| (l.line_type, &l.content)))
+}
+
 /// for atomic commits
 pub fn stage_file(
     repo: &Repository,
@@ -216,3 +434,432 @@ pub fn stage_all(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::{
+        DiffStrategy,
+        commit::{GitCommit, apply_commits},
+        diffs::get_diffs_from_statuses,
+        tests::{repo_init, write_commit_file},
+    };
+
+    fn lines_commit(
+        line_ids: &[&str],
+        message: &str,
+    ) -> GitCommit {
+        GitCommit {
+            files: Vec::new(),
+            hunk_ids: Vec::new(),
+            line_ids: line_ids
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+            message: message.to_owned(),
+        }
+    }
+
+    fn committed_content(
+        repo: &Repository,
+        oid: &str,
+        path: &str,
+    ) -> String {
+        let commit = repo
+            .find_commit(git2::Oid::from_str(oid).unwrap())
+            .unwrap();
+
+        let entry = commit
+            .tree()
+            .unwrap()
+            .get_path(Path::new(path))
+            .unwrap();
+
+        let blob = repo
+            .find_blob(entry.id())
+            .unwrap();
+
+        String::from_utf8(
+            blob.content()
+                .to_vec(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_stage_line_blocks_split_hunk() {
+        let (dir, repo) = repo_init();
+
+        let original: String = (1..=12)
+            .map(|i| format!("l{i}\n"))
+            .collect();
+
+        write_commit_file(&repo, "a.txt", &original, "add a");
+
+        // two replaced lines (delete + add pairs) and
+        // one added line, all close enough to be one hunk
+        let changed = original
+            .replace("l2\n", "two\n")
+            .replace("l5\n", "l5\nnew\n")
+            .replace("l8\n", "eight\n");
+
+        std::fs::write(
+            dir.path()
+                .join("a.txt"),
+            &changed,
+        )
+        .unwrap();
+
+        let mut diffs = get_diffs_from_statuses(
+            &repo,
+            dir.path(),
+            &DiffStrategy::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            diffs.files[0]
+                .hunks
+                .len(),
+            1
+        );
+        assert_eq!(
+            diffs
+                .as_line_ids()
+                .len(),
+            3
+        );
+
+        // the replacements first, out of order
+        // then the addition
+        let commits = vec![
+            lines_commit(&["a.txt:0:2", "a.txt:0:0"], "replace"),
+            lines_commit(&["a.txt:0:1"], "add"),
+        ];
+
+        let oids = apply_commits(
+            &repo,
+            &commits,
+            &mut diffs.files,
+            &StagingStrategy::Lines,
+        )
+        .unwrap();
+
+        assert_eq!(
+            committed_content(&repo, &oids[0], "a.txt"),
+            original
+                .replace("l2\n", "two\n")
+                .replace("l8\n", "eight\n")
+        );
+        assert_eq!(
+            committed_content(&repo, &oids[1], "a.txt"),
+            changed
+        );
+
+        // every block staged, so the hunk is gone
+        assert!(
+            diffs.files[0]
+                .hunks
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_stage_line_blocks_modified_pair() {
+        let (dir, repo) = repo_init();
+
+        let original: String = (1..=8)
+            .map(|i| format!("l{i}\n"))
+            .collect();
+
+        write_commit_file(&repo, "a.txt", &original, "add a");
+
+        // two modified lines in one hunk, each one
+        // a delete + add pair of its own
+        let changed = original
+            .replace("l3\n", "three\n")
+            .replace("l5\n", "five\n");
+
+        std::fs::write(
+            dir.path()
+                .join("a.txt"),
+            &changed,
+        )
+        .unwrap();
+
+        let mut diffs = get_diffs_from_statuses(
+            &repo,
+            dir.path(),
+            &DiffStrategy::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            diffs.files[0]
+                .hunks
+                .len(),
+            1
+        );
+
+        let oids = apply_commits(
+            &repo,
+            &[lines_commit(&["a.txt:0:1"], "five")],
+            &mut diffs.files,
+            &StagingStrategy::Lines,
+        )
+        .unwrap();
+
+        // only the second pair, the first one
+        // is still left in the workdir
+        assert_eq!(
+            committed_content(&repo, &oids[0], "a.txt"),
+            original.replace("l5\n", "five\n")
+        );
+        assert_eq!(
+            std::fs::read_to_string(
+                dir.path()
+                    .join("a.txt")
+            )
+            .unwrap(),
+            changed
+        );
+        assert!(
+            !diffs.files[0]
+                .hunks
+                .is_empty()
+        );
+    }
+
+    fn hunks_commit(
+        hunk_ids: &[&str],
+        message: &str,
+    ) -> GitCommit {
+        GitCommit {
+            files: Vec::new(),
+            hunk_ids: hunk_ids
+                .iter()
+                .map(|h| h.to_string())
+                .collect(),
+            line_ids: Vec::new(),
+            message: message.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_stage_duplicate_hunks_by_context() {
+        let (dir, repo) = repo_init();
+
+        let original: String = (1..=30)
+            .map(|i| format!("l{i}\n"))
+            .collect();
+
+        write_commit_file(&repo, "a.txt", &original, "add a");
+
+        // the same line added in two places
+        let bottom = original.replace("l25\n", "l25\nimport\n");
+        let changed = bottom.replace("l2\n", "l2\nimport\n");
+
+        std::fs::write(
+            dir.path()
+                .join("a.txt"),
+            &changed,
+        )
+        .unwrap();
+
+        let mut diffs = get_diffs_from_statuses(
+            &repo,
+            dir.path(),
+            &DiffStrategy::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            diffs.files[0]
+                .hunks
+                .len(),
+            2
+        );
+
+        // second hunk first, used to grab the first one
+        let commits = vec![
+            hunks_commit(&["a.txt:1"], "bottom"),
+            hunks_commit(&["a.txt:0"], "top"),
+        ];
+
+        let oids = apply_commits(
+            &repo,
+            &commits,
+            &mut diffs.files,
+            &StagingStrategy::Hunks,
+        )
+        .unwrap();
+
+        assert_eq!(
+            committed_content(&repo, &oids[0], "a.txt"),
+            bottom
+        );
+        assert_eq!(
+            committed_content(&repo, &oids[1], "a.txt"),
+            changed
+        );
+    }
+
+    #[test]
+    fn test_stage_duplicate_hunks_by_position() {
+        let (dir, repo) = repo_init();
+
+        // identical context around both changes
+        let original = "a\nb\nc\nd\n".repeat(10);
+
+        write_commit_file(&repo, "a.txt", &original, "add a");
+
+        let mut lines: Vec<&str> = original
+            .lines()
+            .collect();
+        lines[5] = "renamed";
+        let first = format!("{}\n", lines.join("\n"));
+        lines[29] = "renamed";
+        let changed = format!("{}\n", lines.join("\n"));
+
+        std::fs::write(
+            dir.path()
+                .join("a.txt"),
+            &changed,
+        )
+        .unwrap();
+
+        let mut diffs = get_diffs_from_statuses(
+            &repo,
+            dir.path(),
+            &DiffStrategy::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            diffs.files[0]
+                .hunks
+                .len(),
+            2
+        );
+
+        let commits = vec![
+            hunks_commit(&["a.txt:0"], "first"),
+            hunks_commit(&["a.txt:1"], "second"),
+        ];
+
+        let oids = apply_commits(
+            &repo,
+            &commits,
+            &mut diffs.files,
+            &StagingStrategy::Hunks,
+        )
+        .unwrap();
+
+        assert_eq!(
+            committed_content(&repo, &oids[0], "a.txt"),
+            first
+        );
+        assert_eq!(
+            committed_content(&repo, &oids[1], "a.txt"),
+            changed
+        );
+
+        // and the other way around
+        let (dir, repo) = repo_init();
+
+        write_commit_file(&repo, "a.txt", &original, "add a");
+        std::fs::write(
+            dir.path()
+                .join("a.txt"),
+            &changed,
+        )
+        .unwrap();
+
+        let mut diffs = get_diffs_from_statuses(
+            &repo,
+            dir.path(),
+            &DiffStrategy::default(),
+        )
+        .unwrap();
+
+        let commits = vec![
+            hunks_commit(&["a.txt:1"], "second"),
+            hunks_commit(&["a.txt:0"], "first"),
+        ];
+
+        let oids = apply_commits(
+            &repo,
+            &commits,
+            &mut diffs.files,
+            &StagingStrategy::Hunks,
+        )
+        .unwrap();
+
+        let mut lines: Vec<&str> = original
+            .lines()
+            .collect();
+        lines[29] = "renamed";
+        let second = format!("{}\n", lines.join("\n"));
+
+        assert_eq!(
+            committed_content(&repo, &oids[0], "a.txt"),
+            second
+        );
+        assert_eq!(
+            committed_content(&repo, &oids[1], "a.txt"),
+            changed
+        );
+    }
+
+    #[test]
+    fn test_pick_candidate_ambiguous() {
+        let candidate = |idx, position, context| Candidate {
+            idx,
+            position,
+            context,
+        };
+
+        let candidates =
+            [candidate(0, 10, true), candidate(1, 30, true)];
+
+        // exact, then nearest
+        assert_eq!(pick_candidate(&candidates, 30).unwrap(), Some(1));
+        assert_eq!(pick_candidate(&candidates, 12).unwrap(), Some(0));
+
+        // context wins over distance
+        let candidates =
+            [candidate(0, 10, false), candidate(1, 30, true)];
+        assert_eq!(pick_candidate(&candidates, 12).unwrap(), Some(1));
+
+        // equally near, dont guess
+        let candidates =
+            [candidate(0, 10, true), candidate(1, 30, true)];
+        assert!(pick_candidate(&candidates, 20).is_err());
+    }
+
+    #[test]
+    fn test_stage_line_blocks_invalid_block() {
+        let (dir, repo) = repo_init();
+
+        write_commit_file(&repo, "a.txt", "a\nb\n", "add a");
+        std::fs::write(
+            dir.path()
+                .join("a.txt"),
+            "a\nc\n",
+        )
+        .unwrap();
+
+        let diffs = get_diffs_from_statuses(
+            &repo,
+            dir.path(),
+            &DiffStrategy::default(),
+        )
+        .unwrap();
+
+        let ids = vec![LineId::try_from("a.txt:0:5").unwrap()];
+
+        assert!(
+            stage_line_blocks(&repo, &diffs.files[0], &ids).is_err()
+        );
+    }
+}
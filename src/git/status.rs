@@ -32,13 +32,23 @@ pub struct GitStatus {
     pub statuses: Vec<FileStatus>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 pub struct FileStatus {
     pub path: String,
     pub status: StatusItemType,
 }
 
-#[derive(strum::Display, Copy, Clone, Hash, PartialEq, Eq, Debug)]
+#[derive(
+    strum::Display,
+    serde::Serialize,
+    Copy,
+    Clone,
+    Hash,
+    PartialEq,
+    Eq,
+    Debug,
+)]
+#[serde(rename_all = "lowercase")]
 pub enum StatusItemType {
     New,
     Modified,
@@ -135,7 +145,6 @@ pub fn is_workdir_clean(repo: &Repository) -> anyhow::Result<bool> {
 /// specifically, files changed and the inserts
 /// deletions within em. meant to mimic commiting
 /// completion output
-/// FIXME: this breaks on a root commit
 pub(crate) fn get_commit_stats(
     repo: &Repository,
     hash: &str,
@@ -146,16 +155,19 @@ pub(crate) fn get_commit_stats(
 
     let tree = commit.tree()?;
 
-    // not using find_parent_commit.
-    // FIXME: this is assuming that this func
-    // runs after committing, this will break
-    // if this is the root commit
-    let parent = commit
-        .parent(0)?
-        .tree()?;
+    // a root commit is diffed against nothing
+    let parent = if commit.parent_count() > 0 {
+        Some(
+            commit
+                .parent(0)?
+                .tree()?,
+        )
+    } else {
+        None
+    };
 
     let diff =
-        repo.diff_tree_to_tree(Some(&parent), Some(&tree), None)?;
+        repo.diff_tree_to_tree(parent.as_ref(), Some(&tree), None)?;
 
     let stats = diff.stats()?;
     let branch_name = get_branch_name(repo)?;
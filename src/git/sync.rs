@@ -0,0 +1,641 @@
+// gai sync, fetch the upstream and replay the
+// local commits on top of it. unlike gai rebase
+// this can stop on conflicts, the rebase state
+// lives in .git like a regular `git rebase`
+// so it can be continued or aborted later
+
+use git2::{
+    BranchType, ErrorCode, Oid, Rebase, RebaseOptions, Repository,
+    StatusOptions, build::CheckoutBuilder,
+};
+
+use super::{
+    backup::create_backup, branch::BranchDivergence,
+    conflicts::get_conflicted_paths, errors::GitError,
+};
+
+/// the current branch against its upstream
+#[derive(Clone, Debug)]
+pub struct SyncInfo {
+    /// short name, ex. main
+    pub branch: String,
+
+    /// ex. origin/main
+    pub upstream: String,
+
+    pub remote: String,
+
+    pub local_oid: Oid,
+    pub upstream_oid: Oid,
+
+    pub divergence: BranchDivergence,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SyncOutcome {
+    UpToDate,
+    FastForward,
+
+    /// new oids of the replayed commits
+    /// that were committed in this run
+    Rebased(Vec<String>),
+
+    /// stopped, these paths need resolving
+    Conflicts(Vec<String>),
+}
+
+/// upstream remote of the current branch
+fn upstream_remote(repo: &Repository) -> anyhow::Result<String> {
+    let head = repo.head()?;
+
+    let reference = head
+        .name()
+        .ok_or(GitError::NoHead)?;
+
+    let remote = repo
+        .branch_upstream_remote(reference)
+        .map_err(|_| {
+            no_upstream(
+                head.shorthand()
+                    .unwrap_or("HEAD"),
+            )
+        })?;
+
+    Ok(remote
+        .as_str()
+        .unwrap_or_default()
+        .to_owned())
+}
+
+fn no_upstream(branch: &str) -> anyhow::Error {
+    GitError::Generic(format!(
+        "{} has no upstream, set one with `git branch -u <remote>/<branch>`",
+        branch
+    ))
+    .into()
+}
+
+/// fetch the upstream remote using its
+/// configured refspecs, returns the remote name
+pub fn fetch_upstream(repo: &Repository) -> anyhow::Result<String> {
+    let name = upstream_remote(repo)?;
+
+    let mut remote = repo.find_remote(&name)?;
+
+    remote.fetch(&[] as &[&str], None, Some("gai sync: fetch"))?;
+
+    Ok(name)
+}
+
+pub fn get_sync_info(repo: &Repository) -> anyhow::Result<SyncInfo> {
+    let head = repo.head()?;
+
+    if !head.is_branch() {
+        return Err(GitError::Generic(
+            "HEAD is detached, checkout a branch to sync".to_owned(),
+        )
+        .into());
+    }
+
+    let branch_name = head
+        .shorthand()
+        .unwrap_or_default()
+        .to_owned();
+
+    let branch = repo.find_branch(&branch_name, BranchType::Local)?;
+
+    let upstream = branch
+        .upstream()
+        .map_err(|_| no_upstream(&branch_name))?;
+
+    let upstream_name = upstream
+        .name()?
+        .unwrap_or_default()
+        .to_owned();
+
+    let local_oid = head
+        .target()
+        .ok_or(GitError::NoHead)?;
+
+    let upstream_oid = upstream
+        .get()
+        .peel_to_commit()?
+        .id();
+
+    let merge_base = repo.merge_base(local_oid, upstream_oid)?;
+    let (ahead, behind) =
+        repo.graph_ahead_behind(local_oid, upstream_oid)?;
+
+    Ok(SyncInfo {
+        branch: branch_name,
+        upstream: upstream_name,
+        remote: upstream_remote(repo)?,
+        local_oid,
+        upstream_oid,
+        divergence: BranchDivergence {
+            merge_base,
+            ahead,
+            behind,
+        },
+    })
+}
+
+/// local commits that would be replayed
+/// newest first, (hash, summary)
+pub fn get_local_commits(
+    repo: &Repository,
+    info: &SyncInfo,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let mut revwalk = repo.revwalk()?;
+
+    revwalk.push(info.local_oid)?;
+    revwalk.hide(info.upstream_oid)?;
+
+    let mut res = Vec::new();
+
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+
+        res.push((
+            commit
+                .id()
+                .to_string(),
+            commit
+                .summary()
+                .unwrap_or_default()
+                .to_owned(),
+        ));
+    }
+
+    Ok(res)
+}
+
+/// untracked files are fine, anything
+/// staged or modified is not
+pub fn has_tracked_changes(
+    repo: &Repository
+) -> anyhow::Result<bool> {
+    let mut opts = StatusOptions::new();
+
+    opts.include_untracked(false)
+        .include_ignored(false);
+
+    Ok(!repo
+        .statuses(Some(&mut opts))?
+        .is_empty())
+}
+
+/// is there a rebase we can continue
+pub fn sync_in_progress(repo: &Repository) -> bool {
+    matches!(
+        repo.state(),
+        git2::RepositoryState::Rebase
+            | git2::RepositoryState::RebaseMerge
+            | git2::RepositoryState::RebaseInteractive
+    )
+}
+
+/// fast forward or rebase onto the upstream
+pub fn start_sync(
+    repo: &Repository,
+    info: &SyncInfo,
+) -> anyhow::Result<SyncOutcome> {
+    if info
+        .divergence
+        .behind
+        == 0
+    {
+        return Ok(SyncOutcome::UpToDate);
+    }
+
+    if sync_in_progress(repo) {
+        return Err(GitError::Generic(
+            "a rebase is already in progress, use `gai sync --continue` or `--abort`"
+                .to_owned(),
+        )
+        .into());
+    }
+
+    if has_tracked_changes(repo)? {
+        return Err(GitError::Generic(
+            "commit or stash your changes before syncing".to_owned(),
+        )
+        .into());
+    }
+
+    if info
+        .divergence
+        .ahead
+        == 0
+    {
+        let target = repo.find_commit(info.upstream_oid)?;
+
+        repo.checkout_tree(
+            target.as_object(),
+            Some(CheckoutBuilder::new().safe()),
+        )?;
+
+        repo.head()?
+            .set_target(
+                info.upstream_oid,
+                &format!(
+                    "gai sync: fast-forward to {}",
+                    info.upstream
+                ),
+            )?;
+
+        return Ok(SyncOutcome::FastForward);
+    }
+
+    create_backup(
+        repo,
+        info.local_oid,
+        Some(&info.branch),
+        &format!(
+            "gai sync: backup before rebasing onto {}",
+            info.upstream
+        ),
+    )?;
+
+    let branch = repo.reference_to_annotated_commit(&repo.head()?)?;
+    let upstream = repo.find_annotated_commit(info.upstream_oid)?;
+
+    let mut opts = RebaseOptions::new();
+
+    let mut rebase = repo.rebase(
+        Some(&branch),
+        Some(&upstream),
+        None,
+        Some(&mut opts),
+    )?;
+
+    run_rebase(repo, &mut rebase)
+}
+
+/// commit the resolved operation and
+/// keep going, the conflicts have to be
+/// staged (resolved) first
+pub fn continue_sync(
+    repo: &Repository
+) -> anyhow::Result<SyncOutcome> {
+    let mut rebase = open_sync(repo)?;
+
+    let conflicts = get_conflicted_paths(repo)?;
+
+    if !conflicts.is_empty() {
+        return Ok(SyncOutcome::Conflicts(conflicts));
+    }
+
+    let sig = repo.signature()?;
+
+    let mut oids = Vec::new();
+
+    if rebase
+        .operation_current()
+        .is_some()
+        && let Some(oid) = commit_operation(&mut rebase, &sig)?
+    {
+        oids.push(oid);
+    }
+
+    match run_rebase(repo, &mut rebase)? {
+        SyncOutcome::Rebased(rest) => {
+            oids.extend(rest);
+            Ok(SyncOutcome::Rebased(oids))
+        }
+        outcome => Ok(outcome),
+    }
+}
+
+/// back to where we were before syncing
+pub fn abort_sync(repo: &Repository) -> anyhow::Result<()> {
+    open_sync(repo)?.abort()?;
+
+    log_sync(repo, "gai sync (abort): back to the original branch")
+}
+
+fn open_sync(repo: &Repository) -> anyhow::Result<Rebase<'_>> {
+    repo.open_rebase(None)
+        .map_err(|_| {
+            GitError::Generic("no sync in progress".to_owned()).into()
+        })
+}
+
+fn run_rebase(
+    repo: &Repository,
+    rebase: &mut Rebase,
+) -> anyhow::Result<SyncOutcome> {
+    let sig = repo.signature()?;
+
+    let mut oids = Vec::new();
+
+    while let Some(op) = rebase.next() {
+        op?;
+
+        // stop here, the markers are in the
+        // working tree and the rebase state
+        // is saved for --continue
+        let conflicts = get_conflicted_paths(repo)?;
+
+        if !conflicts.is_empty() {
+            return Ok(SyncOutcome::Conflicts(conflicts));
+        }
+
+        if let Some(oid) = commit_operation(rebase, &sig)? {
+            oids.push(oid);
+        }
+    }
+
+    let branch = rebase
+        .orig_head_name()
+        .unwrap_or("HEAD")
+        .trim_start_matches("refs/heads/")
+        .to_owned();
+
+    rebase.finish(Some(&sig))?;
+
+    log_sync(
+        repo,
+        &format!("gai sync: rebased {branch} onto its upstream"),
+    )?;
+
+    Ok(SyncOutcome::Rebased(oids))
+}
+
+/// libgit2 writes its own rebase entries and takes
+/// no message for them, so gai adds one on top for
+/// HEAD and the branch at where they ended up
+fn log_sync(
+    repo: &Repository,
+    message: &str,
+) -> anyhow::Result<()> {
+    let sig = repo.signature()?;
+    let head = repo.head()?;
+
+    let Some(tip) = head.target() else {
+        return Ok(());
+    };
+
+    let mut names = vec!["HEAD".to_owned()];
+    names.extend(
+        head.name()
+            .filter(|_| head.is_branch())
+            .map(|n| n.to_owned()),
+    );
+
+    for name in names {
+        let mut reflog = repo.reflog(&name)?;
+        reflog.append(tip, &sig, Some(message))?;
+        reflog.write()?;
+    }
+
+    Ok(())
+}
+
+/// none if the change is already upstream
+fn commit_operation(
+    rebase: &mut Rebase,
+    sig: &git2::Signature,
+) -> anyhow::Result<Option<String>> {
+    match rebase.commit(None, sig, None) {
+        Ok(oid) => Ok(Some(oid.to_string())),
+        Err(e) if e.code() == ErrorCode::Applied => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use git2::Repository;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::git::{
+        conflicts::{
+            ConflictOperation, apply_resolutions,
+            get_conflict_context, get_conflict_file,
+            write_resolution,
+        },
+        tests::{repo_init, write_commit_file},
+    };
+
+    /// upstream repo with one file, and
+    /// a clone of it tracking origin
+    fn clone_pair() -> (TempDir, Repository, TempDir, Repository) {
+        let (up_td, upstream) = repo_init();
+
+        write_commit_file(&upstream, "a.txt", "1\n2\n3\n", "add a");
+
+        let local_td = TempDir::new().unwrap();
+        let local = Repository::clone(
+            up_td
+                .path()
+                .to_str()
+                .unwrap(),
+            local_td.path(),
+        )
+        .unwrap();
+
+        {
+            let mut config = local
+                .config()
+                .unwrap();
+
+            config
+                .set_str("user.name", "name")
+                .unwrap();
+            config
+                .set_str("user.email", "email")
+                .unwrap();
+        }
+
+        (up_td, upstream, local_td, local)
+    }
+
+    #[test]
+    fn test_sync_rebases_local_commits() {
+        let (_up_td, upstream, _local_td, local) = clone_pair();
+
+        write_commit_file(&local, "b.txt", "b\n", "local b");
+        let upstream_head = write_commit_file(
+            &upstream,
+            "c.txt",
+            "c\n",
+            "upstream c",
+        );
+
+        fetch_upstream(&local).unwrap();
+
+        let info = get_sync_info(&local).unwrap();
+
+        assert_eq!(
+            info.divergence
+                .ahead,
+            1
+        );
+        assert_eq!(
+            info.divergence
+                .behind,
+            1
+        );
+
+        let outcome = start_sync(&local, &info).unwrap();
+
+        let SyncOutcome::Rebased(oids) = outcome else {
+            panic!("expected a rebase, got {:?}", outcome);
+        };
+
+        assert_eq!(oids.len(), 1);
+
+        let head = local
+            .head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+
+        assert_eq!(
+            head.parent_id(0)
+                .unwrap(),
+            upstream_head
+        );
+        assert!(!sync_in_progress(&local));
+
+        // nothing left to do
+        let info = get_sync_info(&local).unwrap();
+        assert_eq!(
+            start_sync(&local, &info).unwrap(),
+            SyncOutcome::UpToDate
+        );
+    }
+
+    #[test]
+    fn test_sync_fast_forward() {
+        let (_up_td, upstream, local_td, local) = clone_pair();
+
+        let upstream_head = write_commit_file(
+            &upstream,
+            "c.txt",
+            "c\n",
+            "upstream c",
+        );
+
+        fetch_upstream(&local).unwrap();
+
+        let info = get_sync_info(&local).unwrap();
+
+        assert_eq!(
+            start_sync(&local, &info).unwrap(),
+            SyncOutcome::FastForward
+        );
+
+        assert_eq!(
+            local
+                .head()
+                .unwrap()
+                .target(),
+            Some(upstream_head)
+        );
+        assert!(
+            local_td
+                .path()
+                .join("c.txt")
+                .exists()
+        );
+    }
+
+    #[test]
+    fn test_sync_stops_on_conflict() {
+        let (_up_td, upstream, local_td, local) = clone_pair();
+
+        write_commit_file(
+            &local,
+            "a.txt",
+            "1\nlocal\n3\n",
+            "local a",
+        );
+        write_commit_file(
+            &upstream,
+            "a.txt",
+            "1\nupstream\n3\n",
+            "upstream a",
+        );
+
+        fetch_upstream(&local).unwrap();
+
+        let info = get_sync_info(&local).unwrap();
+
+        let outcome = start_sync(&local, &info).unwrap();
+
+        assert_eq!(
+            outcome,
+            SyncOutcome::Conflicts(vec!["a.txt".to_owned()])
+        );
+        assert!(sync_in_progress(&local));
+
+        let file =
+            get_conflict_file(&local, local_td.path(), "a.txt")
+                .unwrap();
+
+        assert_eq!(file.hunks.len(), 1);
+        assert_eq!(file.hunks[0].ours, vec!["upstream"]);
+        assert_eq!(file.hunks[0].theirs, vec!["local"]);
+        assert_eq!(file.hunks[0].base, Some(vec!["2".to_owned()]));
+
+        let context = get_conflict_context(&local);
+
+        assert_eq!(context.operation, ConflictOperation::Rebase);
+        assert_eq!(
+            context
+                .theirs
+                .message
+                .as_deref(),
+            Some("local a")
+        );
+        assert_eq!(
+            context
+                .ours
+                .message
+                .as_deref(),
+            Some("upstream a")
+        );
+
+        // still conflicted, continue just reports it again
+        assert!(matches!(
+            continue_sync(&local).unwrap(),
+            SyncOutcome::Conflicts(_)
+        ));
+
+        let resolved = apply_resolutions(
+            &file.content,
+            &file.hunks,
+            &[Some("both".to_owned())],
+        );
+
+        assert!(
+            write_resolution(
+                &local,
+                local_td.path(),
+                "a.txt",
+                &resolved
+            )
+            .unwrap()
+        );
+
+        let outcome = continue_sync(&local).unwrap();
+
+        assert!(
+            matches!(outcome, SyncOutcome::Rebased(ref o) if o.len() == 1)
+        );
+        assert!(!sync_in_progress(&local));
+
+        assert_eq!(
+            std::fs::read_to_string(
+                local_td
+                    .path()
+                    .join("a.txt")
+            )
+            .unwrap(),
+            "1\nboth\n3\n"
+        );
+    }
+}
@@ -0,0 +1,244 @@
+// tags and revspecs, used to figure out the
+// range for changelogs and releases
+
+use std::collections::HashMap;
+
+use git2::{Oid, Repository};
+
+use super::errors::GitError;
+
+/// any revspec (tag, branch, HEAD~2, hash)
+/// to the commit it points at
+pub fn resolve_rev(
+    repo: &Repository,
+    rev: &str,
+) -> anyhow::Result<Oid> {
+    let commit = repo
+        .revparse_single(rev)
+        .and_then(|o| o.peel_to_commit())
+        .map_err(|_| {
+            GitError::Generic(format!("unknown revision {}", rev))
+        })?;
+
+    Ok(commit.id())
+}
+
+/// commit -> tag names, annotated
+/// tags are peeled to their commit
+pub fn get_tags(
+    repo: &Repository
+) -> anyhow::Result<HashMap<Oid, Vec<String>>> {
+    let mut tags: HashMap<Oid, Vec<String>> = HashMap::new();
+
+    for name in repo
+        .tag_names(None)?
+        .iter()
+        .flatten()
+    {
+        let Ok(commit) = repo
+            .revparse_single(&format!("refs/tags/{}", name))
+            .and_then(|o| o.peel_to_commit())
+        else {
+            continue;
+        };
+
+        tags.entry(commit.id())
+            .or_default()
+            .push(name.to_owned());
+    }
+
+    for names in tags.values_mut() {
+        names.sort();
+    }
+
+    Ok(tags)
+}
+
+/// tag pointing right at this commit
+pub fn tag_at(
+    repo: &Repository,
+    oid: Oid,
+) -> anyhow::Result<Option<String>> {
+    Ok(get_tags(repo)?
+        .remove(&oid)
+        .and_then(|names| {
+            names
+                .last()
+                .cloned()
+        }))
+}
+
+/// nearest tag reachable from oid, not
+/// counting a tag on oid itself, so for a
+/// tagged release this is the one before it
+pub fn previous_tag(
+    repo: &Repository,
+    oid: Oid,
+) -> anyhow::Result<Option<String>> {
+    let tags = get_tags(repo)?;
+
+    if tags.is_empty() {
+        return Ok(None);
+    }
+
+    let mut revwalk = repo.revwalk()?;
+
+    revwalk
+        .set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+    revwalk.push(oid)?;
+
+    for commit in revwalk {
+        let commit = commit?;
+
+        if commit == oid {
+            continue;
+        }
+
+        if let Some(names) = tags.get(&commit) {
+            return Ok(names
+                .last()
+                .cloned());
+        }
+    }
+
+    Ok(None)
+}
+
+/// every tag reachable from oid,
+/// including one on oid itself
+pub fn reachable_tags(
+    repo: &Repository,
+    oid: Oid,
+) -> anyhow::Result<Vec<String>> {
+    let tags = get_tags(repo)?;
+
+    if tags.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(oid)?;
+
+    let mut res = Vec::new();
+
+    for commit in revwalk {
+        if let Some(names) = tags.get(&commit?) {
+            res.extend(names.to_owned());
+        }
+    }
+
+    Ok(res)
+}
+
+/// annotated tag on oid, fails if it exists
+pub fn create_tag(
+    repo: &Repository,
+    name: &str,
+    oid: Oid,
+    message: &str,
+) -> anyhow::Result<Oid> {
+    ensure_no_tag(repo, name)?;
+
+    let target = repo.find_object(oid, None)?;
+    let sig = repo.signature()?;
+
+    Ok(repo.tag(name, &target, &sig, message, false)?)
+}
+
+/// errors if the tag exists anywhere,
+/// not just on the current branch
+pub fn ensure_no_tag(
+    repo: &Repository,
+    name: &str,
+) -> anyhow::Result<()> {
+    if repo
+        .find_reference(&format!("refs/tags/{}", name))
+        .is_ok()
+    {
+        return Err(GitError::Generic(format!(
+            "tag {} already exists",
+            name
+        ))
+        .into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::tests::{repo_init, write_commit_file};
+
+    #[test]
+    fn test_previous_tag() {
+        let (_td, repo) = repo_init();
+
+        let first = write_commit_file(&repo, "a.txt", "a", "first");
+        let second = write_commit_file(&repo, "a.txt", "b", "second");
+        let third = write_commit_file(&repo, "a.txt", "c", "third");
+
+        let sig = repo
+            .signature()
+            .unwrap();
+
+        repo.tag(
+            "v0.1.0",
+            &repo
+                .find_object(first, None)
+                .unwrap(),
+            &sig,
+            "v0.1.0",
+            false,
+        )
+        .unwrap();
+
+        repo.tag_lightweight(
+            "v0.2.0",
+            &repo
+                .find_object(third, None)
+                .unwrap(),
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(
+            previous_tag(&repo, third)
+                .unwrap()
+                .as_deref(),
+            Some("v0.1.0")
+        );
+        assert_eq!(
+            previous_tag(&repo, second)
+                .unwrap()
+                .as_deref(),
+            Some("v0.1.0")
+        );
+        assert_eq!(previous_tag(&repo, first).unwrap(), None);
+
+        assert_eq!(
+            tag_at(&repo, third)
+                .unwrap()
+                .as_deref(),
+            Some("v0.2.0")
+        );
+        assert_eq!(
+            reachable_tags(&repo, second).unwrap(),
+            vec!["v0.1.0"]
+        );
+
+        create_tag(&repo, "v0.1.1", second, "notes").unwrap();
+        assert!(
+            create_tag(&repo, "v0.1.1", second, "notes").is_err()
+        );
+        assert_eq!(
+            previous_tag(&repo, third)
+                .unwrap()
+                .as_deref(),
+            Some("v0.1.1")
+        );
+
+        assert_eq!(resolve_rev(&repo, "v0.1.0").unwrap(), first);
+        assert!(resolve_rev(&repo, "nope").is_err());
+    }
+}
@@ -0,0 +1,355 @@
+// git-rebase-todo files, the list git rebase -i
+// opens in GIT_SEQUENCE_EDITOR
+//
+// only the commands a gai plan can express are read,
+// pick, reword, edit, squash, fixup and drop. a new
+// message is written as an exec that amends the commit
+// right before it, git runs it like any other exec so
+// the file still works in a native git rebase -i:
+//
+//   pick 1a2b3c4 add login
+//   exec printf '%s\n' 'feat: add login' | git commit --amend --quiet -F -
+//
+// reword and squash would open an editor in git, so
+// gai writes them as pick/fixup with that exec instead
+
+use serde::{Deserialize, Serialize};
+
+use super::errors::GitError;
+
+/// the tail every message exec ends with
+const AMEND: [&str; 6] =
+    ["|", "git", "commit", "--amend", "--quiet", "-F"];
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    strum::Display,
+    strum::EnumString,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum TodoCommand {
+    #[strum(to_string = "pick", serialize = "p")]
+    Pick,
+    #[strum(to_string = "reword", serialize = "r")]
+    Reword,
+    #[strum(to_string = "edit", serialize = "e")]
+    Edit,
+    #[strum(to_string = "squash", serialize = "s")]
+    Squash,
+    #[strum(to_string = "fixup", serialize = "f")]
+    Fixup,
+    #[strum(to_string = "drop", serialize = "d")]
+    Drop,
+}
+
+/// one commit line, with the message
+/// from an exec right after it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TodoStep {
+    pub command: TodoCommand,
+
+    /// hash as written, usually abbreviated
+    pub commit: String,
+
+    /// the rest of the line, git ignores it
+    pub summary: String,
+    pub message: Option<String>,
+}
+
+/// reads a todo, comments, blank lines and the noop
+/// git writes when there is nothing to do are skipped.
+/// anything gai cant replay errors with the line
+/// it stopped at
+pub fn parse_todo(text: &str) -> anyhow::Result<Vec<TodoStep>> {
+    let mut steps: Vec<TodoStep> = Vec::new();
+
+    for (i, line) in text
+        .lines()
+        .enumerate()
+    {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') || line == "noop"
+        {
+            continue;
+        }
+
+        let unsupported = |why: &str| -> anyhow::Error {
+            GitError::Generic(format!(
+                "todo line {}: {why}\n  {line}",
+                i + 1
+            ))
+            .into()
+        };
+
+        let (command, rest) = line
+            .split_once(char::is_whitespace)
+            .unwrap_or((line, ""));
+
+        if matches!(command, "exec" | "x") {
+            let message = amend_message(rest).ok_or_else(|| {
+                unsupported(
+                    "only the exec gai writes to set a message is supported",
+                )
+            })?;
+
+            let Some(step) = steps
+                .last_mut()
+                .filter(|s| {
+                    s.command != TodoCommand::Edit
+                        && s.command != TodoCommand::Drop
+                })
+            else {
+                return Err(unsupported(
+                    "a message exec has to follow a pick, reword, squash or fixup",
+                ));
+            };
+
+            step.message = Some(message);
+            continue;
+        }
+
+        let Ok(command) = command.parse::<TodoCommand>() else {
+            return Err(unsupported(&format!(
+                "gai cannot replay `{command}`"
+            )));
+        };
+
+        let rest = rest.trim_start();
+
+        if rest.starts_with('-') {
+            return Err(unsupported(&format!(
+                "{command} options are not supported"
+            )));
+        }
+
+        let (commit, summary) = rest
+            .split_once(char::is_whitespace)
+            .unwrap_or((rest, ""));
+
+        if commit.is_empty() {
+            return Err(unsupported("missing commit"));
+        }
+
+        steps.push(TodoStep {
+            command,
+            commit: commit.to_owned(),
+            summary: summary
+                .trim()
+                .to_owned(),
+            message: None,
+        });
+    }
+
+    Ok(steps)
+}
+
+/// writes steps back out, reword and squash
+/// become pick and fixup plus a message exec
+pub fn format_todo(steps: &[TodoStep]) -> String {
+    let mut res = String::new();
+
+    for step in steps {
+        let command = match (step.command, &step.message) {
+            (TodoCommand::Reword, Some(_)) => TodoCommand::Pick,
+            (TodoCommand::Squash, Some(_)) => TodoCommand::Fixup,
+            (command, _) => command,
+        };
+
+        let line =
+            format!("{} {} {}", command, step.commit, step.summary);
+
+        res.push_str(line.trim_end());
+        res.push('\n');
+
+        if let Some(ref message) = step.message {
+            let lines: Vec<String> = message
+                .trim_end()
+                .lines()
+                .map(quote)
+                .collect();
+
+            res.push_str(&format!(
+                "exec printf '%s\\n' {} {} -\n",
+                lines.join(" "),
+                AMEND.join(" ")
+            ));
+        }
+    }
+
+    res.push_str(concat!(
+        "\n",
+        "# written by gai, messages are set by the exec line after\n",
+        "# their commit. the usual git rebase -i commands apply\n",
+    ));
+
+    res
+}
+
+/// the message out of an exec gai wrote
+fn amend_message(exec: &str) -> Option<String> {
+    let words = split_words(exec)?;
+
+    let (printf, tail) = words.split_at(
+        words
+            .iter()
+            .rposition(|w| w == "|")?,
+    );
+
+    if tail.len() != AMEND.len() + 1
+        || tail[..AMEND.len()] != AMEND
+        || tail[AMEND.len()] != "-"
+        || printf.len() < 3
+        || printf[0] != "printf"
+        || printf[1] != "%s\\n"
+    {
+        return None;
+    }
+
+    Some(printf[2..].join("\n"))
+}
+
+/// single quotes for the shell,
+/// a quote inside becomes '\''
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// just enough of sh word splitting to read back
+/// what quote() wrote, quotes and backslashes.
+/// None on an unterminated quote
+fn split_words(s: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let w = word.get_or_insert_default();
+
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => w.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let w = word.get_or_insert_default();
+
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => w.push(chars.next()?),
+                        c => w.push(c),
+                    }
+                }
+            }
+            '\\' => word
+                .get_or_insert_default()
+                .push(chars.next()?),
+            c if c.is_whitespace() => {
+                words.extend(word.take());
+            }
+            c => word
+                .get_or_insert_default()
+                .push(c),
+        }
+    }
+
+    words.extend(word);
+
+    Some(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_todo_round_trip() {
+        let steps = vec![
+            TodoStep {
+                command: TodoCommand::Reword,
+                commit: "1a2b3c4".to_owned(),
+                summary: "add login".to_owned(),
+                message: Some(
+                    "feat: add login\n\nit's behind a flag"
+                        .to_owned(),
+                ),
+            },
+            TodoStep {
+                command: TodoCommand::Fixup,
+                commit: "5d6e7f8".to_owned(),
+                summary: "typo".to_owned(),
+                message: None,
+            },
+            TodoStep {
+                command: TodoCommand::Squash,
+                commit: "9a8b7c6".to_owned(),
+                summary: "tests".to_owned(),
+                message: Some(
+                    "feat: add login with tests".to_owned(),
+                ),
+            },
+            TodoStep {
+                command: TodoCommand::Edit,
+                commit: "0f0f0f0".to_owned(),
+                summary: "config".to_owned(),
+                message: None,
+            },
+        ];
+
+        let text = format_todo(&steps);
+
+        assert!(text.starts_with("pick 1a2b3c4 add login\n"));
+        assert!(text.contains("\nfixup 9a8b7c6 tests\n"));
+
+        let parsed = parse_todo(&text).unwrap();
+
+        // pick + message reads back as a pick, the
+        // plan decides if thats a reword
+        assert_eq!(parsed[0].command, TodoCommand::Pick);
+        assert_eq!(parsed[0].message, steps[0].message);
+        assert_eq!(parsed[1], steps[1]);
+        assert_eq!(parsed[2].command, TodoCommand::Fixup);
+        assert_eq!(parsed[2].message, steps[2].message);
+        assert_eq!(parsed[3], steps[3]);
+    }
+
+    #[test]
+    fn test_parse_todo_native() {
+        let text =
+            "p 1a2b3c4 one\n# comment\n\ns 5d6e7f8 two\nd 9a8b7c6\n";
+
+        let parsed = parse_todo(text).unwrap();
+
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[1].command, TodoCommand::Squash);
+        assert_eq!(parsed[2].command, TodoCommand::Drop);
+        assert_eq!(parsed[2].summary, "");
+
+        assert!(
+            parse_todo("noop\n")
+                .unwrap()
+                .is_empty()
+        );
+
+        for bad in [
+            "pick 1a2b3c4 one\nexec make test\n",
+            "merge -C 1a2b3c4 topic\n",
+            "fixup -C 1a2b3c4 one\n",
+            "exec printf '%s\\n' 'x' | git commit --amend --quiet -F -\n",
+        ] {
+            assert!(parse_todo(bad).is_err());
+        }
+    }
+}
@@ -0,0 +1,131 @@
+// plain bm25 keyword ranking, this is the
+// offline fallback and what gets mixed in
+// with embeddings when theyre available
+
+use std::collections::HashMap;
+
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+
+/// lowercase alphanumeric words, splits
+/// paths and snake_case into their parts
+/// single chars are mostly noise
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| t.len() > 1)
+        .map(|t| t.to_lowercase())
+        .collect()
+}
+
+/// score every document against the query
+/// returns (doc idx, score) sorted from best
+/// to worst, docs with no matching terms
+/// are left out
+pub fn rank(
+    docs: &[Vec<String>],
+    query: &str,
+) -> Vec<(usize, f32)> {
+    let mut terms = tokenize(query);
+    terms.sort();
+    terms.dedup();
+
+    if docs.is_empty() || terms.is_empty() {
+        return Vec::new();
+    }
+
+    let n = docs.len() as f32;
+
+    let avg_len = docs
+        .iter()
+        .map(|d| d.len())
+        .sum::<usize>() as f32
+        / n;
+
+    // document frequency per query term
+    let mut df: HashMap<&str, f32> = HashMap::new();
+
+    for doc in docs {
+        for term in &terms {
+            if doc.contains(term) {
+                *df.entry(term)
+                    .or_default() += 1.0;
+            }
+        }
+    }
+
+    let mut scores: Vec<(usize, f32)> = docs
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, doc)| {
+            let len = doc.len() as f32;
+
+            let score: f32 = terms
+                .iter()
+                .map(|term| {
+                    let tf = doc
+                        .iter()
+                        .filter(|t| *t == term)
+                        .count() as f32;
+
+                    if tf == 0.0 {
+                        return 0.0;
+                    }
+
+                    let df = df
+                        .get(term.as_str())
+                        .copied()
+                        .unwrap_or_default();
+
+                    let idf =
+                        ((n - df + 0.5) / (df + 0.5) + 1.0).ln();
+
+                    idf * (tf * (K1 + 1.0))
+                        / (tf + K1 * (1.0 - B + B * len / avg_len))
+                })
+                .sum();
+
+            (score > 0.0).then_some((idx, score))
+        })
+        .collect();
+
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    scores
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("fix(git/staging.rs): stage_hunks a"),
+            vec!["fix", "git", "staging", "rs", "stage", "hunks"]
+        );
+    }
+
+    #[test]
+    fn test_rank() {
+        let docs: Vec<Vec<String>> = [
+            "feat: add login page src/auth/login.rs",
+            "fix: typo in readme README.md",
+            "fix: login redirect loop src/auth/redirect.rs",
+        ]
+        .iter()
+        .map(|d| tokenize(d))
+        .collect();
+
+        let ranked = rank(&docs, "login redirect");
+
+        let ids: Vec<usize> = ranked
+            .iter()
+            .map(|(idx, _)| *idx)
+            .collect();
+
+        // readme doesnt match at all
+        assert_eq!(ids, vec![2, 0]);
+
+        assert!(rank(&docs, "").is_empty());
+    }
+}
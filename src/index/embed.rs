@@ -0,0 +1,110 @@
+// embeddings from a local endpoint, anything
+// that speaks the openai /embeddings format
+// works (ollama, llama.cpp, lm studio, etc)
+
+use serde_json::json;
+
+use crate::providers::provider::ProviderError;
+
+/// texts per request, local servers tend
+/// to choke on huge batches
+const BATCH_SIZE: usize = 32;
+
+/// embed every text, keeps the input order
+pub fn embed(
+    endpoint: &str,
+    model: &str,
+    texts: &[String],
+) -> anyhow::Result<Vec<Vec<f32>>> {
+    let mut res = Vec::with_capacity(texts.len());
+
+    for chunk in texts.chunks(BATCH_SIZE) {
+        let body = json!({
+            "model": model,
+            "input": chunk,
+        });
+
+        let response = minreq::post(endpoint)
+            .with_header("Content-Type", "application/json")
+            .with_body(body.to_string())
+            .send()?;
+
+        let response: serde_json::Value =
+            serde_json::from_str(response.as_str()?)?;
+
+        let data = response
+            .get("data")
+            .and_then(|d| d.as_array())
+            .ok_or(ProviderError::NoContent)?;
+
+        // the spec has an index field
+        // dont trust the order
+        let mut embeddings = vec![Vec::new(); chunk.len()];
+
+        for (pos, item) in data
+            .iter()
+            .enumerate()
+        {
+            let idx = item
+                .get("index")
+                .and_then(|i| i.as_u64())
+                .map(|i| i as usize)
+                .unwrap_or(pos);
+
+            let embedding: Vec<f32> = serde_json::from_value(
+                item.get("embedding")
+                    .ok_or(ProviderError::NoContent)?
+                    .to_owned(),
+            )?;
+
+            if let Some(slot) = embeddings.get_mut(idx) {
+                *slot = embedding;
+            }
+        }
+
+        if embeddings
+            .iter()
+            .any(|e| e.is_empty())
+        {
+            return Err(ProviderError::NoContent.into());
+        }
+
+        res.extend(embeddings);
+    }
+
+    Ok(res)
+}
+
+/// 0.0 for mismatched or empty vectors
+pub fn cosine_similarity(
+    a: &[f32],
+    b: &[f32],
+) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+
+    let dot: f32 = a
+        .iter()
+        .zip(b)
+        .map(|(x, y)| x * y)
+        .sum();
+
+    let norm_a = a
+        .iter()
+        .map(|x| x * x)
+        .sum::<f32>()
+        .sqrt();
+
+    let norm_b = b
+        .iter()
+        .map(|x| x * x)
+        .sum::<f32>()
+        .sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+
+    dot / (norm_a * norm_b)
+}
@@ -0,0 +1,524 @@
+// local commit index under .git/gai/
+// so gai find doesnt have to send the whole
+// history every time, keyed by commit oid
+// and updated incrementally
+//
+// keyword search (bm25) works offline, embeddings
+// are optional and come from a local endpoint
+
+pub mod bm25;
+pub mod embed;
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use git2::{Oid, Repository};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    git::{GitRepo, commit::get_commit_diff},
+    settings::IndexSettings,
+};
+
+/// bump when the entry format changes
+/// older indexes get rebuilt
+const INDEX_VERSION: u32 = 1;
+
+const INDEX_FILE: &str = "index.json";
+
+/// function contexts kept per file
+/// in the diff summary
+const MAX_SUMMARY_CONTEXTS: usize = 8;
+
+/// weight of the embedding score when
+/// mixing it with bm25
+const EMBEDDING_WEIGHT: f32 = 0.5;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommitIndex {
+    pub version: u32,
+
+    /// model the embeddings came from
+    /// vectors from different models
+    /// cant be compared
+    #[serde(default)]
+    pub embedding_model: Option<String>,
+
+    pub entries: HashMap<String, IndexEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub message: String,
+    pub author: String,
+    pub timestamp: i64,
+    pub files: Vec<String>,
+
+    /// condensed diff, one line per file
+    /// with +/- counts and the function
+    /// contexts from the hunk headers
+    pub summary: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct UpdateStats {
+    pub added: usize,
+    pub removed: usize,
+    pub total: usize,
+}
+
+impl Default for CommitIndex {
+    fn default() -> Self {
+        Self {
+            version: INDEX_VERSION,
+            embedding_model: None,
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl IndexEntry {
+    /// text thats searched and embedded
+    pub fn document(&self) -> String {
+        format!(
+            "{}\n{}\n{}",
+            self.message,
+            self.files
+                .join("\n"),
+            self.summary
+        )
+    }
+}
+
+impl CommitIndex {
+    pub fn path(git: &GitRepo) -> PathBuf {
+        git.gai_dir()
+            .join(INDEX_FILE)
+    }
+
+    pub fn exists(git: &GitRepo) -> bool {
+        Self::path(git).exists()
+    }
+
+    /// missing or outdated indexes
+    /// start out empty
+    pub fn load(git: &GitRepo) -> anyhow::Result<Self> {
+        let path = Self::path(git);
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let raw = std::fs::read_to_string(&path)?;
+
+        match serde_json::from_str::<Self>(&raw) {
+            Ok(index) if index.version == INDEX_VERSION => Ok(index),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(
+        &self,
+        git: &GitRepo,
+    ) -> anyhow::Result<()> {
+        let path = Self::path(git);
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(&path, serde_json::to_string(self)?)?;
+
+        Ok(())
+    }
+
+    /// index every commit reachable from HEAD
+    /// and the local branches, skipping the ones
+    /// we already have, commits that are no longer
+    /// reachable (rebased, deleted branches) are
+    /// dropped
+    pub fn update(
+        &mut self,
+        git: &GitRepo,
+    ) -> anyhow::Result<UpdateStats> {
+        let repo = &git.repo;
+
+        let mut stats = UpdateStats::default();
+
+        let mut revwalk = repo.revwalk()?;
+
+        // unborn HEAD, nothing to index
+        if revwalk
+            .push_head()
+            .is_err()
+        {
+            stats.removed = self.entries.len();
+            self.entries.clear();
+            return Ok(stats);
+        }
+
+        revwalk.push_glob("refs/heads")?;
+
+        let mut seen = HashSet::new();
+
+        for oid in revwalk {
+            let oid = oid?;
+            let hash = oid.to_string();
+
+            if !self
+                .entries
+                .contains_key(&hash)
+            {
+                let entry = index_commit(repo, oid)?;
+
+                self.entries
+                    .insert(hash.to_owned(), entry);
+
+                stats.added += 1;
+            }
+
+            seen.insert(hash);
+        }
+
+        let before = self.entries.len();
+
+        self.entries
+            .retain(|hash, _| seen.contains(hash));
+
+        stats.removed = before - self.entries.len();
+
+        stats.total = self.entries.len();
+
+        Ok(stats)
+    }
+
+    /// embed entries without one, everything
+    /// gets redone if the model changed
+    /// does nothing without an endpoint
+    pub fn embed_missing(
+        &mut self,
+        settings: &IndexSettings,
+    ) -> anyhow::Result<usize> {
+        let Some(ref endpoint) = settings.embedding_endpoint else {
+            return Ok(0);
+        };
+
+        let model = settings
+            .embedding_model
+            .as_str();
+
+        if self
+            .embedding_model
+            .as_deref()
+            != Some(model)
+        {
+            for entry in self
+                .entries
+                .values_mut()
+            {
+                entry.embedding = None;
+            }
+
+            self.embedding_model = Some(model.to_owned());
+        }
+
+        let missing: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, e)| {
+                e.embedding
+                    .is_none()
+            })
+            .map(|(hash, _)| hash.to_owned())
+            .collect();
+
+        if missing.is_empty() {
+            return Ok(0);
+        }
+
+        let docs: Vec<String> = missing
+            .iter()
+            .map(|hash| self.entries[hash].document())
+            .collect();
+
+        let embeddings = embed::embed(endpoint, model, &docs)?;
+
+        for (hash, embedding) in missing
+            .iter()
+            .zip(embeddings)
+        {
+            if let Some(entry) = self
+                .entries
+                .get_mut(hash)
+            {
+                entry.embedding = Some(embedding);
+            }
+        }
+
+        Ok(missing.len())
+    }
+
+    /// embed the query, only if the index
+    /// has embeddings from the configured model
+    pub fn embed_query(
+        &self,
+        settings: &IndexSettings,
+        query: &str,
+    ) -> anyhow::Result<Option<Vec<f32>>> {
+        let Some(ref endpoint) = settings.embedding_endpoint else {
+            return Ok(None);
+        };
+
+        let has_embeddings = self
+            .embedding_model
+            .as_deref()
+            == Some(
+                settings
+                    .embedding_model
+                    .as_str(),
+            )
+            && self
+                .entries
+                .values()
+                .any(|e| {
+                    e.embedding
+                        .is_some()
+                });
+
+        if !has_embeddings {
+            return Ok(None);
+        }
+
+        let mut res = embed::embed(
+            endpoint,
+            &settings.embedding_model,
+            &[query.to_owned()],
+        )?;
+
+        Ok(res.pop())
+    }
+
+    /// rank the given commits against the query
+    /// returns (position in hashes, score) from
+    /// best to worst, commits that arent indexed
+    /// or dont match at all are left out
+    pub fn rank(
+        &self,
+        hashes: &[&str],
+        query: &str,
+        query_embedding: Option<&[f32]>,
+    ) -> Vec<(usize, f32)> {
+        let indexed: Vec<(usize, &IndexEntry)> = hashes
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, hash)| {
+                self.entries
+                    .get(*hash)
+                    .map(|e| (pos, e))
+            })
+            .collect();
+
+        let docs: Vec<Vec<String>> = indexed
+            .iter()
+            .map(|(_, e)| bm25::tokenize(&e.document()))
+            .collect();
+
+        let mut scores = vec![0.0; indexed.len()];
+
+        let keyword = bm25::rank(&docs, query);
+
+        // normalize so it can be mixed with
+        // cosine similarity
+        let max = keyword
+            .first()
+            .map(|(_, s)| *s)
+            .unwrap_or(1.0);
+
+        let keyword_weight = if query_embedding.is_some() {
+            1.0 - EMBEDDING_WEIGHT
+        } else {
+            1.0
+        };
+
+        for (idx, score) in keyword {
+            scores[idx] += keyword_weight * score / max;
+        }
+
+        if let Some(query_embedding) = query_embedding {
+            for (idx, (_, entry)) in indexed
+                .iter()
+                .enumerate()
+            {
+                if let Some(ref embedding) = entry.embedding {
+                    let similarity = embed::cosine_similarity(
+                        query_embedding,
+                        embedding,
+                    );
+
+                    scores[idx] +=
+                        EMBEDDING_WEIGHT * similarity.max(0.0);
+                }
+            }
+        }
+
+        let mut res: Vec<(usize, f32)> = indexed
+            .iter()
+            .zip(scores)
+            .filter(|(_, score)| *score > 0.0)
+            .map(|((pos, _), score)| (*pos, score))
+            .collect();
+
+        res.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        res
+    }
+}
+
+fn index_commit(
+    repo: &Repository,
+    oid: Oid,
+) -> anyhow::Result<IndexEntry> {
+    let commit = repo.find_commit(oid)?;
+    let author = commit.author();
+
+    let diff = get_commit_diff(repo, oid)?;
+
+    let mut files = Vec::new();
+    let mut summary = Vec::new();
+
+    for idx in 0..diff.deltas().len() {
+        let Some(patch) = git2::Patch::from_diff(&diff, idx)? else {
+            continue;
+        };
+
+        let path = patch
+            .delta()
+            .new_file()
+            .path()
+            .and_then(|p| p.to_str())
+            .unwrap_or_default()
+            .to_owned();
+
+        let (_, insertions, deletions) = patch.line_stats()?;
+
+        // hunk headers carry the enclosing
+        // fn/class when git can find one
+        let mut contexts: Vec<String> = Vec::new();
+
+        for h in 0..patch.num_hunks() {
+            let (hunk, _) = patch.hunk(h)?;
+
+            let header = String::from_utf8_lossy(hunk.header());
+
+            let context = header
+                .rsplit("@@")
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_owned();
+
+            if !context.is_empty()
+                && !contexts.contains(&context)
+                && contexts.len() < MAX_SUMMARY_CONTEXTS
+            {
+                contexts.push(context);
+            }
+        }
+
+        let mut line =
+            format!("{} +{} -{}", path, insertions, deletions);
+
+        if !contexts.is_empty() {
+            line.push_str(&format!(" [{}]", contexts.join("; ")));
+        }
+
+        summary.push(line);
+        files.push(path);
+    }
+
+    Ok(IndexEntry {
+        message: String::from_utf8_lossy(commit.message_bytes())
+            .into_owned(),
+        author: author
+            .name()
+            .unwrap_or("unknown author")
+            .to_owned(),
+        timestamp: author
+            .when()
+            .seconds(),
+        files,
+        summary: summary.join("\n"),
+        embedding: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::tests::{repo_init, write_commit_file};
+
+    #[test]
+    fn test_index_update_and_rank() {
+        let (td, repo) = repo_init();
+
+        write_commit_file(
+            &repo,
+            "login.rs",
+            "fn login() {}\n",
+            "feat: add login form",
+        );
+        write_commit_file(&repo, "README.md", "hi\n", "docs: readme");
+
+        let git = GitRepo {
+            workdir: td.path().to_owned(),
+            repo,
+        };
+
+        let mut index = CommitIndex::default();
+
+        let stats = index
+            .update(&git)
+            .unwrap();
+
+        // initial commit from repo_init too
+        assert_eq!(stats.added, 3);
+        assert_eq!(stats.total, 3);
+
+        index
+            .save(&git)
+            .unwrap();
+
+        let mut index = CommitIndex::load(&git).unwrap();
+
+        // nothing new the second time around
+        let stats = index
+            .update(&git)
+            .unwrap();
+
+        assert_eq!(stats.added, 0);
+        assert_eq!(stats.total, 3);
+
+        let hashes: Vec<String> = index
+            .entries
+            .keys()
+            .cloned()
+            .collect();
+        let hashes: Vec<&str> = hashes
+            .iter()
+            .map(|h| h.as_str())
+            .collect();
+
+        let ranked = index.rank(&hashes, "login", None);
+
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(
+            index.entries[hashes[ranked[0].0]].files,
+            vec!["login.rs"]
+        );
+    }
+}
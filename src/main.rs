@@ -1,10 +1,15 @@
+use std::{ffi::OsString, path::Path};
+
 use clap::Parser;
 
 pub mod args;
+pub mod changelog;
 pub mod cmd;
 pub mod git;
+pub mod index;
 pub mod print;
 pub mod providers;
+pub mod release;
 pub mod requests;
 pub mod responses;
 pub mod schema;
@@ -13,23 +18,70 @@ pub mod state;
 pub mod utils;
 
 use crate::args::Commands::{
-    Auth, Commit, Find, Log, Rebase, Reword, Status,
+    Absorb, Apply, Auth, Backups, Blame, Branch, Changelog, Commit,
+    Explain, Find, Index, Log, Pr, Rebase, Release, Resolve, Review,
+    Reword, Split, Status, Sync, Todo,
 };
 
 fn main() -> anyhow::Result<()> {
     dotenv::dotenv().ok();
 
-    let args = args::Cli::parse();
+    let args = args::Cli::parse_from(sequence_editor_args());
 
-    match &args.command {
-        Auth(a) => cmd::auth::run(&a.auth)?,
-        Status(a) => cmd::status::run(a, &args.global)?,
-        Commit(a) => cmd::commit::run(a, &args.global)?,
-        Log(a) => cmd::log::run(a, &args.global)?,
-        Find(a) => cmd::find::run(a, &args.global)?,
-        Rebase(a) => cmd::rebase::run(a, &args.global)?,
-        Reword(a) => cmd::reword::run(a, &args.global)?,
+    let res = match &args.command {
+        Auth(a) => cmd::auth::run(&a.auth),
+        Status(a) => cmd::status::run(a, &args.global),
+        Commit(a) => cmd::commit::run(a, &args.global),
+        Apply(a) => cmd::apply::run(a, &args.global),
+        Review(a) => cmd::review::run(a, &args.global),
+        Log(a) => cmd::log::run(a, &args.global),
+        Find(a) => cmd::find::run(a, &args.global),
+        Index(a) => cmd::index::run(a, &args.global),
+        Blame(a) => cmd::blame::run(a, &args.global),
+        Explain(a) => cmd::explain::run(a, &args.global),
+        Changelog(a) => cmd::changelog::run(a, &args.global),
+        Branch(a) => cmd::branch::run(a, &args.global),
+        Pr(a) => cmd::pr::run(a, &args.global),
+        Release(a) => cmd::release::run(a, &args.global),
+        Sync(a) => cmd::sync::run(a, &args.global),
+        Resolve(a) => cmd::resolve::run(a, &args.global),
+        Rebase(a) => cmd::rebase::run(a, &args.global),
+        Split(a) => cmd::split::run(a, &args.global),
+        Absorb(a) => cmd::absorb::run(a, &args.global),
+        Reword(a) => cmd::reword::run(a, &args.global),
+        Todo(a) => cmd::todo::run(a, &args.global),
+        Backups(a) => cmd::backups::run(a, &args.global),
     };
 
+    // json errors go to stdout as an object
+    // with a stable code, see print::json
+    if let Err(e) = res {
+        if args
+            .global
+            .is_json()
+        {
+            print::json::emit_error(&e);
+            std::process::exit(1);
+        }
+
+        return Err(e);
+    }
+
     Ok(())
 }
+
+/// git runs GIT_SEQUENCE_EDITOR=gai as `gai <path>/git-rebase-todo`,
+/// which is `gai todo <path>`
+fn sequence_editor_args() -> Vec<OsString> {
+    let mut argv: Vec<OsString> = std::env::args_os().collect();
+
+    if argv.len() == 2
+        && Path::new(&argv[1])
+            .file_name()
+            .is_some_and(|f| f == "git-rebase-todo")
+    {
+        argv.insert(1, "todo".into());
+    }
+
+    argv
+}
@@ -0,0 +1,73 @@
+use owo_colors::{OwoColorize, Style};
+
+use crate::{
+    git::absorb::AbsorbCandidate, responses::absorb::Absorbed,
+};
+
+use super::tree::{Tree, TreeItem};
+
+/// one branch per target commit with the hunks
+/// going into it, then whatever stays in the workdir
+pub fn absorb(
+    candidates: &[AbsorbCandidate],
+    absorbed: &[Absorbed],
+    unabsorbed: &[String],
+) -> anyhow::Result<()> {
+    let mut roots = Vec::new();
+
+    for candidate in candidates {
+        let children: Vec<TreeItem<String>> = absorbed
+            .iter()
+            .filter(|a| a.commit == candidate.hash)
+            .map(|a| {
+                TreeItem::new_leaf(
+                    a.hunk_id.to_owned(),
+                    format!(
+                        "{} {}",
+                        a.hunk_id,
+                        format!("({})", a.source).dimmed()
+                    ),
+                )
+            })
+            .collect();
+
+        if children.is_empty() {
+            continue;
+        }
+
+        roots.push(
+            TreeItem::new(
+                candidate
+                    .hash
+                    .to_owned(),
+                format!(
+                    "{} {}",
+                    (&candidate.hash[..7]).yellow(),
+                    candidate.summary
+                ),
+                children,
+            )?
+            .style(Style::new().bold()),
+        );
+    }
+
+    if !unabsorbed.is_empty() {
+        let children = unabsorbed
+            .iter()
+            .map(|h| TreeItem::new_leaf(h.to_owned(), h))
+            .collect();
+
+        roots.push(
+            TreeItem::new(
+                "unabsorbed".to_owned(),
+                "Left in the workdir",
+                children,
+            )?
+            .style(Style::new().dimmed()),
+        );
+    }
+
+    Tree::new(&roots)?.render();
+
+    Ok(())
+}
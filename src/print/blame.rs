@@ -0,0 +1,97 @@
+use std::io::Write;
+
+use anstream::stdout;
+use owo_colors::{OwoColorize, Style};
+
+use crate::{
+    git::blame::{BlameTarget, RegionCommit},
+    schema::blame::BlameSchema,
+};
+
+use super::{
+    tree::{Tree, TreeItem},
+    utils::{tput_size, wrap},
+};
+
+/// the region with one branch per cited
+/// commit, oldest first, then the summary
+pub fn blame(
+    target: &BlameTarget,
+    explanation: &BlameSchema,
+    commits: &[RegionCommit],
+) -> anyhow::Result<()> {
+    let (width, _) = tput_size().unwrap_or((80, 100));
+
+    // tree indent eats into this
+    let max_length = width.saturating_sub(10) as usize;
+
+    let mut children = Vec::new();
+
+    for (i, step) in explanation
+        .steps
+        .iter()
+        .enumerate()
+    {
+        let Some(commit) = commits
+            .iter()
+            .find(|c| c.commit_hash == step.commit)
+        else {
+            continue;
+        };
+
+        let mut step_children = vec![
+            TreeItem::new_leaf(
+                format!("{}_author", i),
+                format!("{}, {}", commit.author, commit.date),
+            )
+            .style(Style::new().dimmed()),
+        ];
+
+        for (l, line) in wrap(&step.explanation, max_length)
+            .into_iter()
+            .enumerate()
+        {
+            step_children.push(TreeItem::new_leaf(
+                format!("{}_explanation_{}", i, l),
+                line,
+            ));
+        }
+
+        let header = commit
+            .message
+            .lines()
+            .next()
+            .unwrap_or_default();
+
+        let display = format!(
+            "{} {}",
+            format!("[{}]", &commit.commit_hash[..7]).yellow(),
+            header
+        );
+
+        children.push(TreeItem::new(
+            format!("{}_step", i),
+            display,
+            step_children,
+        )?);
+    }
+
+    let root = TreeItem::new(
+        "region".to_owned(),
+        target.to_string(),
+        children,
+    )?
+    .style(Style::new().bold());
+
+    Tree::new(&[root])?.render();
+
+    let mut out = stdout();
+
+    writeln!(out)?;
+
+    for line in wrap(&explanation.summary, max_length) {
+        writeln!(out, "{}", line)?;
+    }
+
+    Ok(())
+}
@@ -3,7 +3,14 @@ use std::io::Write;
 use anstream::stdout;
 use owo_colors::OwoColorize;
 
-use crate::{print::utils::tput_size, schema::commit::CommitSchema};
+use crate::{
+    git::{
+        Diffs, StagingStrategy,
+        diffs::{DiffLineType, LineId},
+    },
+    print::utils::tput_size,
+    schema::commit::CommitSchema,
+};
 
 use super::tree::{Tree, TreeItem};
 
@@ -176,6 +183,159 @@ pub fn response_commits(
     Ok(())
 }
 
+/// preview the hunks of the given commits
+/// after moving things around in the edit menu
+/// commits are (index, commit) so the numbering
+/// matches the full list
+pub fn preview_hunks(
+    commits: &[(usize, &CommitSchema)],
+    strategy: &StagingStrategy,
+    diffs: &Diffs,
+) -> anyhow::Result<()> {
+    let mut items = Vec::new();
+
+    for (i, commit) in commits {
+        let mut files: Vec<TreeItem<String>> = Vec::new();
+
+        for item in commit.items(strategy) {
+            // hunk ids only stage the one hunk, line
+            // ids one block, files stage all of them
+            let (path, index, block) = match strategy {
+                StagingStrategy::Hunks => match item.rsplit_once(':')
+                {
+                    Some((p, idx)) => {
+                        (p.to_owned(), idx.parse().ok(), None)
+                    }
+                    None => (item.to_owned(), None, None),
+                },
+                StagingStrategy::Lines => {
+                    match LineId::try_from(item.as_str()) {
+                        Ok(id) => {
+                            (id.path, Some(id.hunk), Some(id.block))
+                        }
+                        Err(_) => (item.to_owned(), None, None),
+                    }
+                }
+                _ => (item.to_owned(), None, None),
+            };
+
+            let Some(file) = diffs
+                .files
+                .iter()
+                .find(|f| f.path == path)
+            else {
+                files.push(TreeItem::new_leaf(
+                    item.to_owned(),
+                    format!("{} {}", item, "(no changes)".red()),
+                ));
+                continue;
+            };
+
+            let mut hunks = Vec::new();
+
+            for hunk in &file.hunks {
+                if index.is_some_and(|idx: usize| idx != hunk.id) {
+                    continue;
+                }
+
+                let (id, lines) = match block {
+                    Some(b) => (
+                        format!("{}:{}:{}", path, hunk.id, b),
+                        hunk.blocks()
+                            .get(b)
+                            .copied()
+                            .unwrap_or_default(),
+                    ),
+                    None => (
+                        format!("{}:{}", path, hunk.id),
+                        hunk.lines
+                            .as_slice(),
+                    ),
+                };
+
+                let (adds, dels) =
+                    lines
+                        .iter()
+                        .fold((0, 0), |(a, d), l| {
+                            match l.line_type {
+                                DiffLineType::Add => (a + 1, d),
+                                DiffLineType::Delete => (a, d + 1),
+                                _ => (a, d),
+                            }
+                        });
+
+                hunks.push(TreeItem::new_leaf(
+                    id.to_owned(),
+                    format!(
+                        "{} {} {}{}",
+                        id,
+                        hunk.header
+                            .to_string()
+                            .dimmed(),
+                        format!("+{adds}").green(),
+                        format!(" -{dels}").red(),
+                    ),
+                ));
+            }
+
+            // a file can show up twice with hunks
+            // so merge them under the same node
+            if let Some(existing) = files
+                .iter_mut()
+                .find(|f| f.identifier() == &path)
+            {
+                let mut children = existing
+                    .children()
+                    .to_vec();
+                children.extend(hunks);
+
+                *existing =
+                    TreeItem::new(path.to_owned(), path, children)?;
+            } else {
+                files.push(TreeItem::new(
+                    path.to_owned(),
+                    path,
+                    hunks,
+                )?);
+            }
+        }
+
+        if files.is_empty() {
+            files.push(TreeItem::new_leaf(
+                format!("commit_{}_empty", i),
+                "(empty)"
+                    .dimmed()
+                    .to_string(),
+            ));
+        }
+
+        let display = format!(
+            "[{}] {}: {}",
+            i + 1,
+            commit.prefix,
+            commit.header
+        )
+        .style(
+            commit
+                .prefix
+                .style(),
+        )
+        .to_string();
+
+        items.push(TreeItem::new(
+            format!("commit_{}", i),
+            display,
+            files,
+        )?);
+    }
+
+    if !items.is_empty() {
+        Tree::new(&items)?.render();
+    }
+
+    Ok(())
+}
+
 pub(crate) fn completed_commit(
     branch_name: &str,
     hash: &str,
@@ -0,0 +1,90 @@
+use owo_colors::{OwoColorize, Style};
+
+use crate::schema::explain::ExplainSchema;
+
+use super::{
+    tree::{Tree, TreeItem},
+    utils::{tput_size, wrap},
+};
+
+/// the target with one branch per section,
+/// empty sections are left out
+pub fn explain(
+    target: &str,
+    commits: usize,
+    explanation: &ExplainSchema,
+) -> anyhow::Result<()> {
+    let (width, _) = tput_size().unwrap_or((80, 100));
+
+    // tree indent eats into this
+    let max_length = width.saturating_sub(10) as usize;
+
+    let mut sections = Vec::new();
+
+    let intent: Vec<String> = wrap(&explanation.intent, max_length);
+
+    sections.push(section("intent", "Intent", intent)?);
+
+    let components = explanation
+        .components
+        .iter()
+        .map(|c| format!("{}: {}", c.name.bold(), c.summary))
+        .collect();
+
+    sections.push(section("components", "Components", components)?);
+
+    let behavior = explanation
+        .behavior_changes
+        .iter()
+        .flat_map(|b| wrap(b, max_length))
+        .collect();
+
+    sections.push(section("behavior", "Behavior changes", behavior)?);
+
+    let risks = explanation
+        .risky_areas
+        .iter()
+        .map(|r| format!("{}: {}", r.area.yellow(), r.reason))
+        .collect();
+
+    sections.push(section("risks", "Risky areas", risks)?);
+
+    let sections: Vec<TreeItem<String>> = sections
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let root = TreeItem::new(
+        "target".to_owned(),
+        format!("{} ({} commit/s)", target, commits),
+        sections,
+    )?
+    .style(Style::new().bold());
+
+    Tree::new(&[root])?.render();
+
+    Ok(())
+}
+
+fn section(
+    id: &str,
+    title: &str,
+    lines: Vec<String>,
+) -> anyhow::Result<Option<TreeItem<String>>> {
+    if lines.is_empty() {
+        return Ok(None);
+    }
+
+    let children = lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, line)| {
+            TreeItem::new_leaf(format!("{}_{}", id, i), line)
+        })
+        .collect();
+
+    Ok(Some(
+        TreeItem::new(id.to_owned(), title, children)?
+            .style(Style::new().blue()),
+    ))
+}
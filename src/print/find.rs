@@ -1,29 +1,56 @@
-use owo_colors::Style;
+use std::io::Write;
 
-use crate::{git::log::GitLog, schema::find::Confidence};
+use anstream::stdout;
+use owo_colors::{OwoColorize, Style};
+
+use crate::{
+    git::{commit::CommitPatch, log::GitLog},
+    schema::find::{Confidence, FindCommitSchema},
+};
 
 use super::{tree::Tree, tree::TreeItem};
 
-pub fn found_commit(
-    commit: &GitLog,
-    _reasoning: &str,
-    _confidence: Confidence,
+/// ranked matches, best first
+pub fn found_commits(
+    matches: &[(&GitLog, &FindCommitSchema)],
+    show_reasoning: bool,
 ) -> anyhow::Result<()> {
+    let mut tree = Vec::new();
+
+    for (rank, (commit, result)) in matches
+        .iter()
+        .enumerate()
+    {
+        tree.push(found_commit(
+            rank,
+            commit,
+            result,
+            show_reasoning,
+        )?);
+    }
+
+    Tree::new(&tree)?.render();
+
+    Ok(())
+}
+
+fn found_commit(
+    rank: usize,
+    commit: &GitLog,
+    result: &FindCommitSchema,
+    show_reasoning: bool,
+) -> anyhow::Result<TreeItem<String>> {
     //let commit_color = prefix_color(commit.prefix);
     let mut children = Vec::new();
     let date_item = TreeItem::new_leaf(
-        commit
-            .date
-            .to_owned(),
+        format!("{}_date", rank),
         format!("Date: {}", commit.date),
     );
 
     children.push(date_item);
 
     let author_item = TreeItem::new_leaf(
-        commit
-            .author
-            .to_owned(),
+        format!("{}_author", rank),
         format!("Author: {}", commit.author),
     );
 
@@ -35,11 +62,20 @@ pub fn found_commit(
         .files
         .join(", ");
 
-    let files_item =
-        TreeItem::new_leaf("raw_files".to_string(), logs.to_string());
+    let files_item = TreeItem::new_leaf(
+        format!("{}_files", rank),
+        logs.to_string(),
+    );
 
     children.push(files_item);
 
+    if show_reasoning {
+        children.push(TreeItem::new_leaf(
+            format!("{}_reasoning", rank),
+            format!("Reasoning: {}", result.reasoning),
+        ));
+    }
+
     let message: String = commit
         .to_owned()
         .into();
@@ -56,20 +92,131 @@ pub fn found_commit(
         message
     };
 
-    let display = format!("{} {}", hash_display, truncated);
+    let display = format!(
+        "{}. {} {} {}",
+        rank + 1,
+        hash_display,
+        truncated,
+        format!("({})", result.confidence)
+            .style(confidence_style(result.confidence)),
+    );
 
-    let tree = vec![
-        TreeItem::new(
-            commit
-                .raw
-                .to_string(),
-            display.to_string(),
-            children,
-        )?
-        .style(Style::new()),
-    ];
+    Ok(
+        TreeItem::new(format!("{}_commit", rank), display, children)?
+            .style(Style::new()),
+    )
+}
 
-    Tree::new(&tree)?.render();
+fn confidence_style(confidence: Confidence) -> Style {
+    match confidence {
+        Confidence::Exact => Style::new().green(),
+        Confidence::Likely => Style::new().yellow(),
+        Confidence::Ambiguous => Style::new().dimmed(),
+    }
+}
+
+/// git show esque view, full message
+/// stats and the patch
+pub fn full_commit(
+    commit: &GitLog,
+    patch: &CommitPatch,
+) -> anyhow::Result<()> {
+    let mut out = stdout();
+
+    writeln!(
+        out,
+        "\n{} {}",
+        "commit".yellow(),
+        commit
+            .commit_hash
+            .yellow()
+    )?;
+    writeln!(out, "Author: {}", commit.author)?;
+    writeln!(out, "Date:   {}\n", commit.date)?;
+
+    for line in commit
+        .raw
+        .trim_end()
+        .lines()
+    {
+        writeln!(out, "    {}", line)?;
+    }
+
+    writeln!(
+        out,
+        "\n {} files changed, {} insertions{}, {} deletions{}\n",
+        patch.files_changed,
+        patch.insertions,
+        "(+)".green(),
+        patch.deletions,
+        "(-)".red(),
+    )?;
+
+    for (origin, content) in &patch.lines {
+        match origin {
+            '+' | '>' => {
+                writeln!(out, "{}", format!("+{content}").green())?
+            }
+            '-' | '<' => {
+                writeln!(out, "{}", format!("-{content}").red())?
+            }
+            'F' => writeln!(out, "{}", content.bold())?,
+            'H' => writeln!(out, "{}", content.cyan())?,
+            ' ' => writeln!(out, " {}", content)?,
+            _ => writeln!(out, "{}", content)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// one line per commit around the
+/// selected one, like a short git log
+pub fn log_context(
+    logs: &[GitLog],
+    selected: usize,
+    radius: usize,
+) -> anyhow::Result<()> {
+    let mut out = stdout();
+
+    let start = selected.saturating_sub(radius);
+    let end = (selected + radius + 1).min(logs.len());
+
+    writeln!(out)?;
+
+    for (i, log) in logs[start..end]
+        .iter()
+        .enumerate()
+    {
+        let idx = start + i;
+
+        let short_hash = &log.commit_hash[..7];
+
+        let first_line = log
+            .raw
+            .lines()
+            .next()
+            .unwrap_or("");
+
+        if idx == selected {
+            writeln!(
+                out,
+                "{} {} {}",
+                ">".green().bold(),
+                short_hash.yellow(),
+                first_line.bold()
+            )?;
+        } else {
+            writeln!(
+                out,
+                "  {} {}",
+                short_hash.dimmed(),
+                first_line
+            )?;
+        }
+    }
+
+    writeln!(out)?;
 
     Ok(())
 }
@@ -0,0 +1,506 @@
+// everything that gets printed with --output json
+// lives here, editor plugins (vscode, nvim) parse
+// this, so treat the field names and error codes
+// as a stable contract, add fields, dont rename them
+
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::{
+    changelog::Changelog,
+    git::{errors::GitError, status::FileStatus, sync::SyncOutcome},
+    index::UpdateStats,
+    providers::provider::ProviderError,
+    release::Bump,
+    responses::{absorb::Absorbed, review::ReviewFinding},
+    schema::{
+        commit::CommitSchema,
+        explain::{ComponentChange, RiskyArea},
+        find::Confidence,
+    },
+};
+
+/// stable error codes, these are what
+/// integrations should match on instead
+/// of the message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    NotARepository,
+    BareRepository,
+    NoHead,
+    InvalidHunk,
+    PatchError,
+    Conflict,
+    Git,
+    NotAuthenticated,
+    ProviderHttp,
+    ProviderResponse,
+    InvalidResponse,
+    Io,
+    Unknown,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorOutput<'a> {
+    error: &'a JsonError,
+}
+
+/// gai commit output, applied is only
+/// filled when the commits were
+/// actually created
+#[derive(Debug, Serialize)]
+pub struct CommitOutput {
+    pub commits: Vec<CommitSchema>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub applied: Option<Vec<AppliedCommit>>,
+
+    /// path of the written plan, with --plan-out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan: Option<String>,
+}
+
+/// gai split output
+#[derive(Debug, Serialize)]
+pub struct SplitOutput {
+    /// the commit that was split
+    pub commit: String,
+    pub commits: Vec<CommitSchema>,
+
+    /// only with -y
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub applied: Option<Vec<AppliedCommit>>,
+
+    /// commits after the split one,
+    /// replayed when applied
+    pub replayed: usize,
+}
+
+/// gai absorb output
+#[derive(Debug, Serialize)]
+pub struct AbsorbOutput {
+    pub absorbed: Vec<Absorbed>,
+
+    /// hunk ids left in the workdir
+    pub unabsorbed: Vec<String>,
+
+    /// fixup commits, only with -y
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub applied: Option<Vec<AppliedCommit>>,
+
+    /// new HEAD after --squash
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub squashed: Option<String>,
+}
+
+/// gai apply output
+#[derive(Debug, Serialize)]
+pub struct ApplyOutput {
+    pub applied: Vec<AppliedCommit>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppliedCommit {
+    pub oid: String,
+    pub message: String,
+}
+
+/// gai find output
+#[derive(Debug, Serialize)]
+pub struct FindOutput {
+    pub query: String,
+    pub matches: Vec<FindMatch>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FindMatch {
+    pub commit_hash: String,
+    pub message: String,
+    pub author: String,
+    pub date: String,
+    pub files: Vec<String>,
+    pub confidence: Confidence,
+    pub reasoning: String,
+}
+
+/// gai blame output
+#[derive(Debug, Serialize)]
+pub struct BlameOutput {
+    pub path: String,
+    pub start: usize,
+    pub end: usize,
+    pub summary: String,
+    pub steps: Vec<BlameStepOutput>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlameStepOutput {
+    pub commit_hash: String,
+    pub message: String,
+    pub author: String,
+    pub date: String,
+    pub explanation: String,
+}
+
+/// gai changelog output
+#[derive(Debug, Serialize)]
+pub struct ChangelogOutput {
+    /// none when starting from the root
+    pub from: Option<String>,
+    pub to: String,
+    pub changelog: Changelog,
+
+    /// the rendered release
+    pub markdown: String,
+
+    /// path that was prepended to
+    pub written: Option<String>,
+}
+
+/// gai explain output
+#[derive(Debug, Serialize)]
+pub struct ExplainOutput {
+    pub target: String,
+
+    /// oldest first
+    pub commits: Vec<String>,
+
+    /// requests the range was split into,
+    /// not counting the one combining them
+    pub chunks: usize,
+
+    pub intent: String,
+    pub components: Vec<ComponentChange>,
+    pub behavior_changes: Vec<String>,
+    pub risky_areas: Vec<RiskyArea>,
+}
+
+/// gai branch output
+#[derive(Debug, Serialize)]
+pub struct BranchOutput {
+    /// best first
+    pub suggestions: Vec<String>,
+
+    /// only with -y
+    pub created: Option<String>,
+    pub checked_out: bool,
+}
+
+/// gai pr output
+#[derive(Debug, Serialize)]
+pub struct PrOutput {
+    pub base: String,
+    pub title: String,
+    pub body: String,
+
+    /// template that was filled in
+    pub template: Option<String>,
+
+    /// oldest first
+    pub commits: Vec<String>,
+}
+
+/// gai release output
+#[derive(Debug, Default, Serialize)]
+pub struct ReleaseOutput {
+    /// none on the first release
+    pub previous: Option<String>,
+
+    /// empty when there is nothing to release
+    pub tag: String,
+    pub version: String,
+
+    /// none on the first release or with --exact
+    pub bump: Option<Bump>,
+    pub commits: usize,
+    pub notes: String,
+
+    /// with --commit or --changelog
+    pub release_commit: Option<String>,
+    pub files: Vec<String>,
+    pub dry_run: bool,
+}
+
+/// gai sync output
+#[derive(Debug, Default, Serialize)]
+pub struct SyncOutput {
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+
+    /// up_to_date, fast_forward, rebased,
+    /// conflict or aborted
+    pub status: String,
+
+    /// new hashes of the replayed commits
+    pub commits: Vec<String>,
+
+    /// paths still conflicted
+    pub conflicts: Vec<String>,
+}
+
+impl SyncOutput {
+    pub fn status(status: &str) -> Self {
+        Self {
+            status: status.to_owned(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&SyncOutcome> for SyncOutput {
+    fn from(outcome: &SyncOutcome) -> Self {
+        match outcome {
+            SyncOutcome::UpToDate => Self::status("up_to_date"),
+            SyncOutcome::FastForward => Self::status("fast_forward"),
+            SyncOutcome::Rebased(oids) => Self {
+                commits: oids.to_owned(),
+                ..Self::status("rebased")
+            },
+            SyncOutcome::Conflicts(paths) => Self {
+                conflicts: paths.to_owned(),
+                ..Self::status("conflict")
+            },
+        }
+    }
+}
+
+/// gai review output
+#[derive(Debug, Serialize)]
+pub struct ReviewOutput {
+    /// staged, all or the range that was reviewed
+    pub source: String,
+
+    pub summary: String,
+
+    /// most severe first
+    pub findings: Vec<ReviewFinding>,
+}
+
+/// gai resolve output
+#[derive(Debug, Serialize)]
+pub struct ResolveOutput {
+    /// merge, rebase, cherry-pick, revert or unknown
+    pub operation: String,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+    pub files: Vec<ResolveFileOutput>,
+
+    /// paths still conflicted
+    pub remaining: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResolveFileOutput {
+    pub path: String,
+
+    /// only with --accept-all
+    pub staged: bool,
+    pub conflicts: Vec<ResolveConflictOutput>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResolveConflictOutput {
+    pub id: usize,
+
+    /// none if there was no suggestion
+    pub reasoning: Option<String>,
+    pub resolution: Option<String>,
+}
+
+/// gai backups output, list fills backups,
+/// restore the restored and saved names
+/// and prune the pruned ones
+#[derive(Debug, Default, Serialize)]
+pub struct BackupsOutput {
+    pub backups: Vec<BackupOutput>,
+    pub restored: Option<String>,
+
+    /// the backup of the tip restore replaced
+    pub saved: Option<String>,
+    pub pruned: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackupOutput {
+    pub name: String,
+    pub commit: String,
+    pub summary: String,
+
+    /// none when HEAD was detached
+    pub branch: Option<String>,
+    pub reason: String,
+}
+
+/// gai index output
+#[derive(Debug, Serialize)]
+pub struct IndexOutput {
+    pub path: String,
+
+    #[serde(flatten)]
+    pub stats: UpdateStats,
+
+    pub embedded: usize,
+}
+
+/// gai status output
+#[derive(Debug, Serialize)]
+pub struct StatusOutput<'a> {
+    pub provider: String,
+    pub model: &'a str,
+    pub branch: &'a str,
+    pub staged: &'a [FileStatus],
+    pub working_dir: &'a [FileStatus],
+    pub tokens: TokenEstimates,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct TokenEstimates {
+    pub total: u32,
+    pub files: Vec<FileTokens>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileTokens {
+    pub path: String,
+    pub tokens: u32,
+}
+
+impl From<&anyhow::Error> for JsonError {
+    fn from(e: &anyhow::Error) -> Self {
+        Self {
+            code: error_code(e),
+            message: format!("{:#}", e),
+        }
+    }
+}
+
+/// walk the error chain and pick
+/// the first thing we recognize
+pub fn error_code(e: &anyhow::Error) -> ErrorCode {
+    for cause in e.chain() {
+        if let Some(e) = cause.downcast_ref::<GitError>() {
+            return match e {
+                GitError::BareRepo => ErrorCode::BareRepository,
+                GitError::NoHead => ErrorCode::NoHead,
+                GitError::InvalidHunk(_) => ErrorCode::InvalidHunk,
+                GitError::PatchError => ErrorCode::PatchError,
+                GitError::RebaseConflict => ErrorCode::Conflict,
+                GitError::Git2(_) | GitError::Generic(_) => {
+                    ErrorCode::Git
+                }
+            };
+        }
+
+        if let Some(e) = cause.downcast_ref::<ProviderError>() {
+            return match e {
+                ProviderError::NotAuthenticated => {
+                    ErrorCode::NotAuthenticated
+                }
+                ProviderError::HttpError(_) => {
+                    ErrorCode::ProviderHttp
+                }
+                ProviderError::ParseError(_)
+                | ProviderError::NoContent
+                | ProviderError::InvalidSchema => {
+                    ErrorCode::ProviderResponse
+                }
+            };
+        }
+
+        if let Some(e) = cause.downcast_ref::<git2::Error>() {
+            return if e.code() == git2::ErrorCode::NotFound
+                && e.class() == git2::ErrorClass::Repository
+            {
+                ErrorCode::NotARepository
+            } else {
+                ErrorCode::Git
+            };
+        }
+
+        if cause
+            .downcast_ref::<serde_json::Error>()
+            .is_some()
+        {
+            return ErrorCode::InvalidResponse;
+        }
+
+        if cause
+            .downcast_ref::<std::io::Error>()
+            .is_some()
+        {
+            return ErrorCode::Io;
+        }
+    }
+
+    ErrorCode::Unknown
+}
+
+/// print a single json document to stdout
+pub fn emit<T: Serialize>(value: &T) -> anyhow::Result<()> {
+    let mut out = std::io::stdout();
+
+    serde_json::to_writer(&mut out, value)?;
+    writeln!(out)?;
+
+    out.flush()?;
+
+    Ok(())
+}
+
+/// print an error as {"error": {"code", "message"}}
+/// also on stdout, so integrations only
+/// have to read one stream
+pub fn emit_error(e: &anyhow::Error) {
+    let error = JsonError::from(e);
+
+    // if this fails theres not much
+    // else we can do
+    emit(&ErrorOutput { error: &error }).ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_codes() {
+        let e: anyhow::Error = GitError::NoHead.into();
+        assert_eq!(error_code(&e), ErrorCode::NoHead);
+
+        let e: anyhow::Error = ProviderError::NotAuthenticated.into();
+        assert_eq!(error_code(&e), ErrorCode::NotAuthenticated);
+
+        // context shouldnt hide the code
+        let e = anyhow::Error::from(GitError::BareRepo)
+            .context("opening repo");
+        assert_eq!(error_code(&e), ErrorCode::BareRepository);
+
+        let e = anyhow::anyhow!("something else");
+        assert_eq!(error_code(&e), ErrorCode::Unknown);
+    }
+
+    #[test]
+    fn error_shape() {
+        let e: anyhow::Error = GitError::RebaseConflict.into();
+        let error = JsonError::from(&e);
+
+        let v = serde_json::to_value(ErrorOutput { error: &error })
+            .unwrap();
+
+        assert_eq!(v["error"]["code"], "conflict");
+        assert_eq!(
+            v["error"]["message"],
+            "Conflict exists, aborting"
+        );
+    }
+}
@@ -1,8 +1,16 @@
+pub mod absorb;
+pub mod blame;
 pub mod commits;
+pub mod explain;
 pub mod find;
 pub mod input;
+pub mod json;
 pub mod menu;
+pub mod resolve;
+pub mod review;
+pub mod sarif;
 pub mod spinner;
 pub mod status;
+pub mod sync;
 pub mod tree;
 pub mod utils;
@@ -0,0 +1,71 @@
+use std::io::Write;
+
+use anstream::stdout;
+use owo_colors::OwoColorize;
+
+use crate::{
+    git::conflicts::ConflictHunk, schema::resolve::HunkResolution,
+};
+
+/// both sides of a conflict, then the
+/// suggested merge if there is one
+pub fn conflict(
+    path: &str,
+    idx: usize,
+    total: usize,
+    hunk: &ConflictHunk,
+    resolution: Option<&HunkResolution>,
+) -> anyhow::Result<()> {
+    let mut out = stdout();
+
+    writeln!(
+        out,
+        "\n{} {}",
+        path.bold(),
+        format!("conflict {}/{}", idx + 1, total).dimmed()
+    )?;
+
+    writeln!(
+        out,
+        "{}",
+        format!("ours ({})", hunk.ours_label).dimmed()
+    )?;
+    for line in &hunk.ours {
+        writeln!(out, "{}", format!("- {}", line).red())?;
+    }
+
+    if let Some(ref base) = hunk.base {
+        writeln!(out, "{}", "base".dimmed())?;
+        for line in base {
+            writeln!(out, "{}", format!("  {}", line).dimmed())?;
+        }
+    }
+
+    writeln!(
+        out,
+        "{}",
+        format!("theirs ({})", hunk.theirs_label).dimmed()
+    )?;
+    for line in &hunk.theirs {
+        writeln!(out, "{}", format!("+ {}", line).green())?;
+    }
+
+    match resolution {
+        Some(r) => {
+            writeln!(out, "{}", "suggested".yellow())?;
+            for line in r.resolution.lines() {
+                writeln!(out, "  {}", line)?;
+            }
+            writeln!(out, "{}", r.reasoning.dimmed())?;
+        }
+        None => writeln!(
+            out,
+            "{}",
+            "no suggestion for this one".yellow()
+        )?,
+    }
+
+    out.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use std::io::Write;
+
+use anstream::stdout;
+use owo_colors::{OwoColorize, Style};
+
+use crate::{
+    git::Diffs,
+    responses::review::{Review, ReviewFinding},
+    schema::review::Severity,
+};
+
+use super::tree::{Tree, TreeItem};
+
+/// one branch per file, then per hunk with
+/// its header, findings are the leaves
+pub fn review(
+    review: &Review,
+    diffs: &Diffs,
+) -> anyhow::Result<()> {
+    let mut out = stdout();
+
+    if !review
+        .findings
+        .is_empty()
+    {
+        let mut roots = Vec::new();
+
+        for file in &diffs.files {
+            let mut hunks = Vec::new();
+
+            for hunk in &file.hunks {
+                let findings: Vec<&ReviewFinding> = review
+                    .findings
+                    .iter()
+                    .filter(|f| {
+                        f.file == file.path && f.hunk_id == hunk.id
+                    })
+                    .collect();
+
+                if findings.is_empty() {
+                    continue;
+                }
+
+                let id = format!("{}:{}", file.path, hunk.id);
+
+                let children = findings
+                    .iter()
+                    .enumerate()
+                    .map(|(i, f)| finding_item(&id, i, f))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+
+                hunks.push(
+                    TreeItem::new(
+                        id.to_owned(),
+                        format!("{} {}", id, hunk.header),
+                        children,
+                    )?
+                    .style(Style::new().dimmed()),
+                );
+            }
+
+            if hunks.is_empty() {
+                continue;
+            }
+
+            roots.push(
+                TreeItem::new(
+                    file.path.to_owned(),
+                    file.path.to_owned(),
+                    hunks,
+                )?
+                .style(Style::new().bold()),
+            );
+        }
+
+        Tree::new(&roots)?.render();
+
+        writeln!(out)?;
+    }
+
+    writeln!(out, "{}", review.summary)?;
+    writeln!(out, "{}", counts(review))?;
+
+    Ok(())
+}
+
+fn finding_item(
+    id: &str,
+    i: usize,
+    finding: &ReviewFinding,
+) -> anyhow::Result<TreeItem<String>> {
+    let mut children = Vec::new();
+
+    if let Some(ref fix) = finding.suggested_fix {
+        for (l, line) in fix
+            .lines()
+            .enumerate()
+        {
+            let text = if l == 0 {
+                format!("fix: {}", line)
+            } else {
+                format!("     {}", line)
+            };
+
+            children.push(
+                TreeItem::new_leaf(
+                    format!("{}_{}_fix_{}", id, i, l),
+                    text,
+                )
+                .style(Style::new().green()),
+            );
+        }
+    }
+
+    let display = format!(
+        "{} {} L{}: {}",
+        format!("[{}]", finding.severity)
+            .style(severity_style(finding.severity)),
+        finding.category,
+        finding.line,
+        finding.message
+    );
+
+    Ok(TreeItem::new(format!("{}_{}", id, i), display, children)?)
+}
+
+fn severity_style(severity: Severity) -> Style {
+    match severity {
+        Severity::High => Style::new()
+            .red()
+            .bold(),
+        Severity::Medium => Style::new().yellow(),
+        Severity::Low => Style::new().blue(),
+        Severity::Info => Style::new().dimmed(),
+    }
+}
+
+/// ex. 1 high, 2 low
+fn counts(review: &Review) -> String {
+    if review
+        .findings
+        .is_empty()
+    {
+        return "No findings"
+            .green()
+            .bold()
+            .to_string();
+    }
+
+    [
+        Severity::High,
+        Severity::Medium,
+        Severity::Low,
+        Severity::Info,
+    ]
+    .iter()
+    .filter_map(|s| {
+        let n = review
+            .findings
+            .iter()
+            .filter(|f| f.severity == *s)
+            .count();
+
+        (n > 0).then(|| {
+            format!("{} {}", n, s)
+                .style(severity_style(*s))
+                .to_string()
+        })
+    })
+    .collect::<Vec<_>>()
+    .join(", ")
+}
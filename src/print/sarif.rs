@@ -0,0 +1,116 @@
+// sarif 2.1.0 so review findings show up in
+// code scanning and editors that read it
+// only the parts we can fill are written
+
+use serde_json::{Value, json};
+use strum::IntoEnumIterator;
+
+use crate::{
+    responses::review::{Review, ReviewFinding},
+    schema::review::{Category, Severity},
+};
+
+const SARIF_SCHEMA: &str =
+    "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// one run, one rule per category
+pub fn review_to_sarif(review: &Review) -> Value {
+    let rules: Vec<Value> = Category::iter()
+        .map(|c| {
+            json!({
+                "id": c.to_string(),
+                "shortDescription": { "text": format!("{} issue", c) },
+            })
+        })
+        .collect();
+
+    let results: Vec<Value> = review
+        .findings
+        .iter()
+        .map(result)
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "gai",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/cube-cult/gai",
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+fn result(finding: &ReviewFinding) -> Value {
+    let mut text = finding
+        .message
+        .to_owned();
+
+    if let Some(ref fix) = finding.suggested_fix {
+        text.push_str(&format!("\nSuggested fix:\n{}", fix));
+    }
+
+    json!({
+        "ruleId": finding.category.to_string(),
+        "level": level(finding.severity),
+        "message": { "text": text },
+        "locations": [{
+            "physicalLocation": {
+                "artifactLocation": { "uri": finding.file },
+                "region": { "startLine": finding.line },
+            }
+        }],
+        "properties": {
+            "severity": finding.severity,
+            "hunkId": format!("{}:{}", finding.file, finding.hunk_id),
+        },
+    })
+}
+
+/// sarif only has error, warning and note
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::High => "error",
+        Severity::Medium => "warning",
+        Severity::Low | Severity::Info => "note",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sarif_result() {
+        let review = Review {
+            summary: String::new(),
+            findings: vec![ReviewFinding {
+                file: "src/main.rs".to_owned(),
+                hunk_id: 1,
+                line: 12,
+                severity: Severity::High,
+                category: Category::Security,
+                message: "sql injection".to_owned(),
+                suggested_fix: None,
+            }],
+        };
+
+        let sarif = review_to_sarif(&review);
+        let result = &sarif["runs"][0]["results"][0];
+
+        assert_eq!(sarif["version"], "2.1.0");
+        assert_eq!(result["ruleId"], "security");
+        assert_eq!(result["level"], "error");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["region"]["startLine"],
+            12
+        );
+        assert_eq!(result["properties"]["hunkId"], "src/main.rs:1");
+    }
+}
@@ -41,6 +41,7 @@ enum SpinnerCommand {
 // Holds all the data needed to actually render the spinner on a render thread.
 struct Spinner {
     text: Str,
+    hidden: bool,
     rx: Receiver<SpinnerCommand>,
 }
 
@@ -48,6 +49,7 @@ struct Spinner {
 #[derive(Clone, Default)]
 pub struct SpinnerBuilder {
     text: Option<Str>,
+    hidden: bool,
 }
 
 impl SpinnerBuilder {
@@ -65,6 +67,16 @@ impl SpinnerBuilder {
         self
     }
 
+    /// Don't render anything, the handle still works as usual.
+    /// Used with --output json so stdout stays parseable.
+    pub fn hidden(
+        mut self,
+        hidden: bool,
+    ) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
     /// Starts the spinner and renders it on a separate thread.
     ///
     /// # Returns
@@ -80,6 +92,7 @@ impl SpinnerBuilder {
         let (tx, rx) = channel();
         let spinner = Spinner {
             text: self.text.unwrap(),
+            hidden: self.hidden,
             rx,
         };
 
@@ -93,6 +106,12 @@ impl Spinner {
         tx: Sender<SpinnerCommand>,
     ) -> SpinnerHandle {
         let handle = thread::spawn(move || {
+            if self.hidden {
+                // just wait for the stop command
+                let _ = self.rx.recv();
+                return;
+            }
+
             let mut out = stdout();
 
             let start = Instant::now();
@@ -0,0 +1,56 @@
+use owo_colors::{OwoColorize, Style};
+
+use crate::git::sync::SyncInfo;
+
+use super::tree::{Tree, TreeItem};
+
+/// branch -> upstream, with the local
+/// commits that would be replayed
+pub fn sync_status(
+    info: &SyncInfo,
+    local_commits: &[(String, String)],
+) -> anyhow::Result<()> {
+    let commits: Vec<TreeItem<String>> = local_commits
+        .iter()
+        .map(|(hash, summary)| {
+            TreeItem::new_leaf(
+                hash.to_owned(),
+                format!(
+                    "{} {}",
+                    format!("[{}]", &hash[..7]).yellow(),
+                    summary
+                ),
+            )
+        })
+        .collect();
+
+    let ahead = TreeItem::new(
+        "ahead".to_owned(),
+        format!(
+            "{} local commit/s to replay",
+            info.divergence
+                .ahead
+        ),
+        commits,
+    )?;
+
+    let behind = TreeItem::new_leaf(
+        "behind".to_owned(),
+        format!(
+            "{} new upstream commit/s",
+            info.divergence
+                .behind
+        ),
+    );
+
+    let root = TreeItem::new(
+        "sync".to_owned(),
+        format!("{} -> {}", info.branch, info.upstream),
+        vec![behind, ahead],
+    )?
+    .style(Style::new().bold());
+
+    Tree::new(&[root])?.render();
+
+    Ok(())
+}
@@ -31,3 +31,32 @@ pub(super) fn tput_size() -> Option<(u16, u16)> {
         _ => None,
     }
 }
+
+/// greedy word wrap
+pub(super) fn wrap(
+    text: &str,
+    width: usize,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.len() + word.len() + 1 > width
+            {
+                lines.push(std::mem::take(&mut line));
+            }
+
+            if !line.is_empty() {
+                line.push(' ');
+            }
+
+            line.push_str(word);
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
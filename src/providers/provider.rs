@@ -68,6 +68,16 @@ impl Display for ProviderError {
     }
 }
 
+impl std::error::Error for ProviderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProviderError::HttpError(e) => Some(e),
+            ProviderError::ParseError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
 impl Error for ProviderError {
     fn kind(&self) -> ErrorKind {
         match self {
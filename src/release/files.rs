@@ -0,0 +1,248 @@
+// version fields in project manifests, plain
+// line edits so formatting and comments stay
+// the way they were
+
+/// manifests we know how to bump
+pub const VERSION_FILES: [&str; 3] =
+    ["Cargo.toml", "Cargo.lock", "package.json"];
+
+/// dispatch on the file name, none if
+/// nothing changed or its not a manifest
+pub fn bump_file(
+    name: &str,
+    content: &str,
+    package: Option<&str>,
+    version: &str,
+) -> Option<String> {
+    let updated = match name {
+        "Cargo.toml" => bump_cargo_toml(content, version),
+        "Cargo.lock" => bump_cargo_lock(content, package?, version),
+        "package.json" => bump_package_json(content, version),
+        _ => None,
+    }?;
+
+    (updated != content).then_some(updated)
+}
+
+/// name from the [package] table, used to
+/// find our entry in Cargo.lock
+pub fn cargo_package_name(content: &str) -> Option<String> {
+    let mut in_package = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+
+        if trimmed.starts_with('[') {
+            in_package = trimmed == "[package]";
+            continue;
+        }
+
+        if in_package && let Some(value) = toml_value(trimmed, "name")
+        {
+            return Some(value.to_owned());
+        }
+    }
+
+    None
+}
+
+/// version = "..." under [package], workspace
+/// inherited versions are left alone
+pub fn bump_cargo_toml(
+    content: &str,
+    version: &str,
+) -> Option<String> {
+    let mut in_package = false;
+    let mut found = false;
+
+    let lines = content
+        .split_inclusive('\n')
+        .map(|line| {
+            let trimmed = line.trim();
+
+            if trimmed.starts_with('[') {
+                in_package = trimmed == "[package]";
+            } else if in_package
+                && !found
+                && toml_value(trimmed, "version").is_some()
+            {
+                found = true;
+                return replace_quoted(line, version);
+            }
+
+            line.to_owned()
+        })
+        .collect::<String>();
+
+    found.then_some(lines)
+}
+
+/// the version line of our own [[package]]
+pub fn bump_cargo_lock(
+    content: &str,
+    package: &str,
+    version: &str,
+) -> Option<String> {
+    let mut current_is_ours = false;
+    let mut found = false;
+
+    let lines = content
+        .split_inclusive('\n')
+        .map(|line| {
+            let trimmed = line.trim();
+
+            if trimmed == "[[package]]" {
+                current_is_ours = false;
+            } else if let Some(name) = toml_value(trimmed, "name") {
+                current_is_ours = name == package;
+            } else if current_is_ours
+                && !found
+                && toml_value(trimmed, "version").is_some()
+            {
+                found = true;
+                return replace_quoted(line, version);
+            }
+
+            line.to_owned()
+        })
+        .collect::<String>();
+
+    found.then_some(lines)
+}
+
+/// the top level "version", the first one at
+/// an indent of one level
+pub fn bump_package_json(
+    content: &str,
+    version: &str,
+) -> Option<String> {
+    let mut depth = 0usize;
+    let mut found = false;
+
+    let lines = content
+        .split_inclusive('\n')
+        .map(|line| {
+            let trimmed = line.trim_start();
+
+            let is_version = depth == 1
+                && !found
+                && trimmed.starts_with("\"version\"");
+
+            for c in line.chars() {
+                match c {
+                    '{' | '[' => depth += 1,
+                    '}' | ']' => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+
+            if is_version {
+                found = true;
+
+                // the value is the second quoted string
+                if let Some(colon) = line.find(':') {
+                    return format!(
+                        "{}{}",
+                        &line[..colon],
+                        replace_quoted(&line[colon..], version)
+                    );
+                }
+            }
+
+            line.to_owned()
+        })
+        .collect::<String>();
+
+    found.then_some(lines)
+}
+
+/// key = "value" -> value
+fn toml_value<'a>(
+    line: &'a str,
+    key: &str,
+) -> Option<&'a str> {
+    let (k, v) = line.split_once('=')?;
+
+    if k.trim() != key {
+        return None;
+    }
+
+    let v = v.trim();
+
+    v.strip_prefix('"')?
+        .split('"')
+        .next()
+}
+
+/// swap the first "..." in the line
+fn replace_quoted(
+    line: &str,
+    value: &str,
+) -> String {
+    let Some(start) = line.find('"') else {
+        return line.to_owned();
+    };
+
+    let Some(len) = line[start + 1..].find('"') else {
+        return line.to_owned();
+    };
+
+    format!(
+        "{}\"{}\"{}",
+        &line[..start],
+        value,
+        &line[start + 1 + len + 1..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bump_cargo() {
+        let toml = "[package]\nname = \"gai\"\nversion = \"0.1.0\" # keep\n\n[dependencies]\nfoo = { version = \"1\" }\n";
+
+        let bumped = bump_cargo_toml(toml, "0.2.0").unwrap();
+
+        assert_eq!(
+            bumped,
+            "[package]\nname = \"gai\"\nversion = \"0.2.0\" # keep\n\n[dependencies]\nfoo = { version = \"1\" }\n"
+        );
+        assert_eq!(cargo_package_name(toml).as_deref(), Some("gai"));
+
+        let lock = "[[package]]\nname = \"anyhow\"\nversion = \"1.0.0\"\n\n[[package]]\nname = \"gai\"\nversion = \"0.1.0\"\n";
+
+        let bumped = bump_cargo_lock(lock, "gai", "0.2.0").unwrap();
+
+        assert!(
+            bumped.contains("name = \"anyhow\"\nversion = \"1.0.0\"")
+        );
+        assert!(
+            bumped.contains("name = \"gai\"\nversion = \"0.2.0\"")
+        );
+
+        // workspace inherited, nothing to do
+        assert!(
+            bump_cargo_toml(
+                "[package]\nversion.workspace = true\n",
+                "1.0.0"
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_bump_package_json() {
+        let json = "{\n  \"name\": \"x\",\n  \"version\": \"1.0.0\",\n  \"dependencies\": {\n    \"version\": \"2.0.0\"\n  }\n}\n";
+
+        let bumped = bump_package_json(json, "1.1.0").unwrap();
+
+        assert!(bumped.contains("  \"version\": \"1.1.0\",\n"));
+        assert!(bumped.contains("    \"version\": \"2.0.0\"\n"));
+        assert!(
+            bump_file("package.json", &bumped, None, "1.1.0")
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,392 @@
+// semver from conventional commits, what
+// gai release uses to pick the next version
+
+pub mod files;
+
+use std::{cmp::Ordering, fmt};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::git::log::GitLog;
+
+/// major.minor.patch with an optional
+/// prerelease, build metadata is dropped
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Option<String>,
+}
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Version {
+    /// accepts a leading v, ex. v1.2.3-rc.1+build
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s
+            .trim()
+            .strip_prefix('v')
+            .unwrap_or(s.trim());
+
+        let s = s
+            .split_once('+')
+            .map(|(v, _)| v)
+            .unwrap_or(s);
+
+        let (core, pre) = match s.split_once('-') {
+            Some((core, pre)) if !pre.is_empty() => {
+                (core, Some(pre.to_owned()))
+            }
+            Some(_) => return None,
+            None => (s, None),
+        };
+
+        let mut parts = core.split('.');
+
+        let mut next = || -> Option<u64> {
+            let part = parts.next()?;
+
+            // no leading zeros, 01.2.3 isnt semver
+            if part.len() > 1 && part.starts_with('0') {
+                return None;
+            }
+
+            part.parse().ok()
+        };
+
+        let version = Self {
+            major: next()?,
+            minor: next()?,
+            patch: next()?,
+            pre,
+        };
+
+        if parts
+            .next()
+            .is_some()
+        {
+            return None;
+        }
+
+        Some(version)
+    }
+
+    /// prereleases are dropped, 1.2.3-rc.1
+    /// bumped by patch is 1.2.3
+    pub fn bump(
+        &self,
+        bump: Bump,
+    ) -> Self {
+        let (major, minor, patch) = match (bump, &self.pre) {
+            (_, Some(_)) if self.is_bumped_by_release(bump) => {
+                (self.major, self.minor, self.patch)
+            }
+            (Bump::Major, _) => (self.major + 1, 0, 0),
+            (Bump::Minor, _) => (self.major, self.minor + 1, 0),
+            (Bump::Patch, _) => {
+                (self.major, self.minor, self.patch + 1)
+            }
+        };
+
+        Self {
+            major,
+            minor,
+            patch,
+            pre: None,
+        }
+    }
+
+    /// a prerelease already points at the next
+    /// version, releasing it is enough if the
+    /// bump doesnt go past it
+    fn is_bumped_by_release(
+        &self,
+        bump: Bump,
+    ) -> bool {
+        match bump {
+            Bump::Major => self.minor == 0 && self.patch == 0,
+            Bump::Minor => self.patch == 0,
+            Bump::Patch => true,
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+
+        if let Some(ref pre) = self.pre {
+            write!(f, "-{}", pre)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Ord for Version {
+    fn cmp(
+        &self,
+        other: &Self,
+    ) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                // a release is newer than its prereleases
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => cmp_pre(a, b),
+            })
+    }
+}
+
+/// semver 11, the dot separated identifiers
+/// left to right. numbers compare as numbers and
+/// rank below words, and with every identifier
+/// so far equal the shorter one is older
+fn cmp_pre(
+    a: &str,
+    b: &str,
+) -> Ordering {
+    let is_num = |s: &str| {
+        !s.is_empty()
+            && s.bytes()
+                .all(|c| c.is_ascii_digit())
+    };
+
+    let mut a = a.split('.');
+    let mut b = b.split('.');
+
+    loop {
+        let (x, y) = match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => (x, y),
+        };
+
+        let ord = match (is_num(x), is_num(y)) {
+            // no leading zeros, so the longer
+            // one is bigger, and no overflow
+            (true, true) => x
+                .len()
+                .cmp(&y.len())
+                .then_with(|| x.cmp(y)),
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            (false, false) => x.cmp(y),
+        };
+
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(
+        &self,
+        other: &Self,
+    ) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Bump {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        let s = match self {
+            Self::Patch => "patch",
+            Self::Minor => "minor",
+            Self::Major => "major",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+/// bump from the conventional commits, the
+/// second value is the commits we couldnt
+/// classify (no prefix), left for the llm
+pub fn conventional_bump(
+    logs: &[GitLog]
+) -> (Option<Bump>, Vec<&GitLog>) {
+    let mut bump = None;
+    let mut unknown = Vec::new();
+
+    for log in logs {
+        if log
+            .raw
+            .starts_with("Merge ")
+        {
+            continue;
+        }
+
+        let breaking = log.breaking
+            || log
+                .body
+                .as_deref()
+                .is_some_and(|b| {
+                    b.contains("BREAKING CHANGE")
+                        || b.contains("BREAKING-CHANGE")
+                });
+
+        let this = if breaking {
+            Bump::Major
+        } else {
+            match log
+                .prefix
+                .as_deref()
+                .map(|p| p.to_lowercase())
+                .as_deref()
+            {
+                Some("feat") => Bump::Minor,
+                Some(_) => Bump::Patch,
+                None => {
+                    unknown.push(log);
+                    continue;
+                }
+            }
+        };
+
+        bump = bump.max(Some(this));
+    }
+
+    (bump, unknown)
+}
+
+/// below 1.0.0 breaking changes only bump
+/// the minor, the api isnt stable yet
+pub fn next_version(
+    current: &Version,
+    bump: Bump,
+) -> Version {
+    let bump = if current.major == 0 && bump == Bump::Major {
+        Bump::Minor
+    } else {
+        bump
+    };
+
+    current.bump(bump)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version() {
+        let v = Version::parse("v1.2.3").unwrap();
+        assert_eq!(v.to_string(), "1.2.3");
+
+        assert_eq!(
+            v.bump(Bump::Major)
+                .to_string(),
+            "2.0.0"
+        );
+        assert_eq!(
+            v.bump(Bump::Minor)
+                .to_string(),
+            "1.3.0"
+        );
+        assert_eq!(
+            v.bump(Bump::Patch)
+                .to_string(),
+            "1.2.4"
+        );
+
+        let rc = Version::parse("2.0.0-rc.1+abc").unwrap();
+        assert_eq!(rc.pre.as_deref(), Some("rc.1"));
+        assert_eq!(
+            rc.bump(Bump::Minor)
+                .to_string(),
+            "2.0.0"
+        );
+        assert!(rc < Version::parse("2.0.0").unwrap());
+        assert!(v < rc);
+
+        assert!(Version::parse("1.2").is_none());
+        assert!(Version::parse("01.2.3").is_none());
+        assert!(Version::parse("release-1").is_none());
+
+        let zero = Version::parse("0.4.1").unwrap();
+        assert_eq!(
+            next_version(&zero, Bump::Major).to_string(),
+            "0.5.0"
+        );
+    }
+
+    #[test]
+    fn test_prerelease_order() {
+        let v = |s| Version::parse(s).unwrap();
+
+        assert!(v("1.0.0-rc.2") < v("1.0.0-rc.10"));
+        assert!(v("1.0.0-beta.2") < v("1.0.0-beta.11"));
+        assert!(v("1.0.0-alpha") < v("1.0.0-alpha.1"));
+        assert!(v("1.0.0-alpha.1") < v("1.0.0-beta"));
+        assert!(v("1.0.0-alpha.1") < v("1.0.0-alpha.beta"));
+        assert!(v("1.0.0-rc.1") < v("1.0.0"));
+        assert_eq!(
+            v("1.0.0-rc.1").cmp(&v("v1.0.0-rc.1")),
+            Ordering::Equal
+        );
+
+        // the newest tag wins, not the last string
+        let newest = ["v1.0.0-rc.9", "v1.0.0-rc.10", "v1.0.0-rc.2"]
+            .into_iter()
+            .map(v)
+            .max()
+            .unwrap();
+        assert_eq!(newest.to_string(), "1.0.0-rc.10");
+    }
+
+    #[test]
+    fn test_conventional_bump() {
+        let logs: Vec<GitLog> = [
+            "fix: a",
+            "feat(x): b",
+            "update stuff",
+            "Merge branch 'a'",
+        ]
+        .iter()
+        .map(|m| m.as_bytes().into())
+        .collect();
+
+        let (bump, unknown) = conventional_bump(&logs);
+
+        assert_eq!(bump, Some(Bump::Minor));
+        assert_eq!(unknown.len(), 1);
+
+        let breaking: Vec<GitLog> =
+            ["fix: a\n\nBREAKING CHANGE: gone"]
+                .iter()
+                .map(|m| m.as_bytes().into())
+                .collect();
+
+        assert_eq!(conventional_bump(&breaking).0, Some(Bump::Major));
+    }
+}
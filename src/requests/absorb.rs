@@ -0,0 +1,100 @@
+use crate::{
+    git::{
+        absorb::{AbsorbCandidate, HunkOwners},
+        diffs::FileDiff,
+    },
+    settings::Settings,
+};
+
+use super::Request;
+
+/// create an absorb request for the hunks blame
+/// couldnt settle, each with the commits blame
+/// pointed at if any
+pub fn create_absorb_request(
+    settings: &Settings,
+    candidates: &[AbsorbCandidate],
+    files: &[FileDiff],
+    ambiguous: &[HunkOwners],
+) -> Request {
+    let prompt = build_prompt(settings);
+
+    let commits = candidates
+        .iter()
+        .map(|c| format!("[{}] {}", &c.hash[..7], c.summary))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let hunks: Vec<String> = ambiguous
+        .iter()
+        .filter_map(|h| hunk_item(files, h))
+        .collect();
+
+    Request::new(&prompt)
+        .insert_content(&format!(
+            "Commits, newest first:\n{}",
+            commits
+        ))
+        .insert_contents(&hunks)
+}
+
+fn hunk_item(
+    files: &[FileDiff],
+    owners: &HunkOwners,
+) -> Option<String> {
+    let (path, idx) = owners
+        .hunk_id
+        .rsplit_once(':')?;
+
+    let hunk = files
+        .iter()
+        .find(|f| f.path == path)?
+        .hunks
+        .iter()
+        .find(|h| h.id.to_string() == idx)?;
+
+    let blamed = if owners
+        .owners
+        .is_empty()
+    {
+        "none".to_owned()
+    } else {
+        owners
+            .owners
+            .iter()
+            .map(|o| o[..7].to_owned())
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+
+    let mut s = format!(
+        "HunkId[{}] {}\nBlamed:{}\n",
+        owners.hunk_id, hunk.header, blamed
+    );
+
+    for line in &hunk.lines {
+        s.push_str(&format!("{}{}\n", line.line_type, line.content));
+    }
+
+    Some(s)
+}
+
+fn build_prompt(cfg: &Settings) -> String {
+    let mut prompt = concat!(
+        "You are an assistant tasked with routing uncommitted changes ",
+        "into the recent commits they fix. You are given the candidate ",
+        "commits and a list of hunks, each with the commits that last ",
+        "touched the surrounding lines. For every hunk pick the commit ",
+        "whose change it corrects or completes, use the commit messages ",
+        "and the blamed commits as evidence. Answer none when the hunk ",
+        "is unrelated new work or when no commit clearly fits, a wrong ",
+        "fixup is worse than none."
+    )
+    .to_string();
+
+    if let Some(hint) = &cfg.prompt.hint {
+        prompt.push_str(&format!("\nUSE THIS AS A HINT: {}", hint));
+    }
+
+    prompt
+}
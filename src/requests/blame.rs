@@ -0,0 +1,63 @@
+use crate::{
+    git::blame::{BlameTarget, RegionCommit},
+    settings::Settings,
+};
+
+use super::Request;
+
+/// create a blame request, the region as it
+/// is now and every commit that touched it
+pub fn create_blame_request(
+    settings: &Settings,
+    target: &BlameTarget,
+    region: &str,
+    commits: &[RegionCommit],
+) -> Request {
+    let prompt = build_prompt(settings);
+
+    let region =
+        format!("Region: {}\nCurrent code:\n{}", target, region);
+
+    let commits: Vec<String> = commits
+        .iter()
+        .map(commit_str)
+        .collect();
+
+    Request::new(&prompt)
+        .insert_content(&region)
+        .insert_contents(&commits)
+}
+
+fn commit_str(commit: &RegionCommit) -> String {
+    format!(
+        "Commit:[{}]\nAuthor:{}\nDate:{}\nPath:{}\nMessage:{}\nHunks:\n{}",
+        &commit.commit_hash[..7],
+        commit.author,
+        commit.date,
+        commit.path,
+        commit.message,
+        commit
+            .hunks
+            .join("\n")
+    )
+}
+
+fn build_prompt(cfg: &Settings) -> String {
+    let mut prompt = concat!(
+        "You are an assistant tasked with explaining the history ",
+        "of a region of code. You are given the code as it is now ",
+        "and the commits that touched it, newest first, with the ",
+        "hunks that changed the region. Explain how and why the code ",
+        "evolved, citing commits by their short hash. Prefer the reasons ",
+        "given in the commit messages, if a reason is unclear say so ",
+        "instead of guessing. Skip commits that only moved or reformatted ",
+        "the code unless it matters."
+    )
+    .to_string();
+
+    if let Some(hint) = &cfg.prompt.hint {
+        prompt.push_str(&format!("\nUSE THIS AS A HINT: {}", hint));
+    }
+
+    prompt
+}
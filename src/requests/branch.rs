@@ -0,0 +1,53 @@
+use crate::settings::Settings;
+
+use super::Request;
+
+/// create a branch name request from a
+/// description, the uncommitted diff or both
+pub fn create_branch_request(
+    settings: &Settings,
+    description: Option<&str>,
+    diffs: Option<&str>,
+    count: usize,
+) -> Request {
+    let prompt = build_prompt(settings, count);
+
+    let mut req = Request::new(&prompt);
+
+    if let Some(description) = description {
+        req = req
+            .insert_content(&format!("Description:{}", description));
+    }
+
+    if let Some(diffs) = diffs {
+        req = req.insert_content(&format!(
+            "Uncommitted changes:\n{}",
+            diffs
+        ));
+    }
+
+    req
+}
+
+fn build_prompt(
+    cfg: &Settings,
+    count: usize,
+) -> String {
+    let mut prompt = format!(
+        concat!(
+            "You are an assistant tasked with naming a git branch. ",
+            "You are given a description of the work, the uncommitted ",
+            "changes, or both. Suggest {} different options, best first. ",
+            "Each has the conventional commit type of the work, a ticket ",
+            "id only if one is mentioned, and a few plain words describing ",
+            "the work. Do not invent ticket ids."
+        ),
+        count
+    );
+
+    if let Some(hint) = &cfg.prompt.hint {
+        prompt.push_str(&format!("\nUSE THIS AS A HINT: {}", hint));
+    }
+
+    prompt
+}
@@ -0,0 +1,78 @@
+use crate::{
+    changelog::Changelog, git::log::GitLog, settings::Settings,
+};
+
+use super::Request;
+
+/// create a request to summarize every section
+/// commit bodies are included for the why
+pub fn create_changelog_request(
+    settings: &Settings,
+    changelog: &Changelog,
+    logs: &[GitLog],
+) -> Request {
+    let prompt = build_prompt(settings);
+
+    let sections: Vec<String> = changelog
+        .sections
+        .iter()
+        .map(|section| {
+            let entries = section
+                .entries
+                .iter()
+                .map(|entry| {
+                    let body = logs
+                        .iter()
+                        .find(|l| l.commit_hash == entry.commit_hash)
+                        .and_then(|l| l.body.as_deref())
+                        .map(|b| {
+                            format!("\n  {}", b.replace('\n', "\n  "))
+                        })
+                        .unwrap_or_default();
+
+                    format!(
+                        "- {}{}{}{}",
+                        entry
+                            .scope
+                            .as_ref()
+                            .map(|s| format!("{}: ", s))
+                            .unwrap_or_default(),
+                        entry.header,
+                        if entry.breaking {
+                            " (BREAKING)"
+                        } else {
+                            ""
+                        },
+                        body
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            format!("Section:{}\n{}", section.title, entries)
+        })
+        .collect();
+
+    Request::new(&prompt)
+        .insert_content(&format!("Release:{}", changelog.title))
+        .insert_contents(&sections)
+}
+
+fn build_prompt(cfg: &Settings) -> String {
+    let mut prompt = concat!(
+        "You are an assistant tasked with writing release notes. ",
+        "You are given the sections of a changelog, each with the ",
+        "commits that belong to it. For every section write a short ",
+        "paragraph for the people using the project, describing what ",
+        "changed and why it matters. Do not list every commit again, ",
+        "do not invent changes that are not in the commits and call ",
+        "out breaking changes clearly."
+    )
+    .to_string();
+
+    if let Some(hint) = &cfg.prompt.hint {
+        prompt.push_str(&format!("\nUSE THIS AS A HINT: {}", hint));
+    }
+
+    prompt
+}
@@ -1,6 +1,7 @@
 use crate::{
     git::{
-        GitRepo, StagingStrategy, StatusStrategy, log::get_logs,
+        GitRepo, StagingStrategy, StatusStrategy,
+        log::{LogFilters, get_logs},
         status::get_status,
     },
     settings::{PromptRules, Settings},
@@ -74,6 +75,7 @@ fn build_prompt(
         // todo impl other staging methods
         // likely during validation as well
         StagingStrategy::Hunks => prompt.push_str(PROMPT_STAGE_HUNKS),
+        StagingStrategy::Lines => prompt.push_str(PROMPT_STAGE_LINES),
         _ => prompt.push_str(PROMPT_STAGE_FILES),
     }
 
@@ -117,7 +119,7 @@ fn build_prompt(
             false,
             None,
             None,
-            None,
+            &LogFilters::default(),
         )
         .unwrap_or_default();
 
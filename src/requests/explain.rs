@@ -0,0 +1,106 @@
+use crate::{
+    git::log::GitLog, schema::explain::ExplainSchema,
+    settings::Settings,
+};
+
+use super::Request;
+
+/// one commit with its message and per file
+/// patches, what gets batched into chunks
+pub fn commit_item(
+    log: &GitLog,
+    patches: &[String],
+) -> String {
+    format!(
+        "Commit:[{}]\nAuthor:{}\nDate:{}\nMessage:{}\n{}",
+        &log.commit_hash[..7.min(
+            log.commit_hash
+                .len()
+        )],
+        log.author,
+        log.date,
+        log.raw.trim(),
+        patches.join("\n")
+    )
+}
+
+/// create an explain request, part is (index, total)
+/// when the range was too large for one request
+pub fn create_explain_request(
+    settings: &Settings,
+    target: &str,
+    items: &[String],
+    part: Option<(usize, usize)>,
+) -> Request {
+    let mut prompt = build_prompt(settings);
+
+    if let Some((idx, total)) = part {
+        prompt.push_str(&format!(
+            concat!(
+                "\nThe range is too large for one request, this is ",
+                "part {} of {}. Only explain the commits given here, ",
+                "the parts are combined afterwards."
+            ),
+            idx + 1,
+            total
+        ));
+    }
+
+    Request::new(&prompt)
+        .insert_content(&format!("Explaining:{}", target))
+        .insert_contents(items)
+}
+
+/// combine the explanations of each
+/// chunk into one for the whole range
+pub fn create_explain_merge_request(
+    settings: &Settings,
+    target: &str,
+    partials: &[ExplainSchema],
+) -> Request {
+    let mut prompt = build_prompt(settings);
+
+    prompt.push_str(concat!(
+        "\nInstead of the diffs you are given explanations of ",
+        "consecutive parts of the range, oldest first. Combine them ",
+        "into one explanation of the whole range, merge components ",
+        "that are the same and drop behavior changes that a later ",
+        "part undid."
+    ));
+
+    let partials: Vec<String> = partials
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            format!(
+                "Part {}:\n{}",
+                i + 1,
+                serde_json::to_string(p).unwrap_or_default()
+            )
+        })
+        .collect();
+
+    Request::new(&prompt)
+        .insert_content(&format!("Explaining:{}", target))
+        .insert_contents(&partials)
+}
+
+fn build_prompt(cfg: &Settings) -> String {
+    let mut prompt = concat!(
+        "You are an assistant tasked with explaining existing changes ",
+        "to someone new to the code. You are given one or more commits, ",
+        "oldest first, with their messages and diffs. Explain the intent ",
+        "of the change, the components it affects, how behavior differs ",
+        "afterwards and the areas that are risky or deserve a closer ",
+        "look. Prefer the reasons given in the commit messages, if a ",
+        "reason is unclear say so instead of guessing. Some diffs may be ",
+        "truncated."
+    )
+    .to_string();
+
+    if let Some(hint) = &cfg.prompt.hint {
+        prompt.push_str(&format!("\nUSE THIS AS A HINT: {}", hint));
+    }
+
+    prompt
+}
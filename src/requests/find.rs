@@ -4,12 +4,46 @@ use super::Request;
 
 /// create a find request object
 /// takes in git logs and a query
+/// top is how many commits to rank
 pub fn create_find_request(
     settings: &Settings,
     git_logs: &[String],
     query: &str,
+    top: usize,
 ) -> Request {
-    let prompt = build_prompt(settings);
+    let mut prompt = build_prompt(settings);
+
+    prompt.push_str(&format!(
+        " Return up to {} commits ranked from best to worst match, \
+        only include commits that are plausible matches.",
+        top.max(1)
+    ));
+
+    let query = format!("Query:{}", query);
+
+    Request::new(&prompt)
+        .insert_content(&query)
+        .insert_contents(git_logs)
+}
+
+/// request for a single batch when the
+/// history doesnt fit in one request, the
+/// candidates get ranked again afterwards
+/// with create_find_request
+pub fn create_find_candidates_request(
+    settings: &Settings,
+    git_logs: &[String],
+    query: &str,
+    limit: usize,
+) -> Request {
+    let mut prompt = build_prompt(settings);
+
+    prompt.push_str(&format!(
+        " These commits are one batch of a larger history. \
+        Return up to {} candidate commits that could match, \
+        return an empty list if none of them plausibly match.",
+        limit.max(1)
+    ));
 
     let query = format!("Query:{}", query);
 
@@ -31,7 +65,7 @@ fn build_prompt(_cfg: &Settings) -> String {
         "a Git Commit that matches the query. ",
         "Ensure your input your reasoning as well as ",
         "Confidence level to describe your choice.",
-        "Confidence should accurately describe your assessment. ",
+        " Confidence should accurately describe your assessment. ",
         "Choose Exact, if the commit matches EXACTLY, ",
         "Choose Likely, if pretty sure but needs checking, ",
         "Choose Ambiguous, if not exact/likely but the closest."
@@ -0,0 +1,72 @@
+use crate::{git::log::GitLog, settings::Settings};
+
+use super::Request;
+
+/// create a pr request from the branch commits
+/// (oldest first) and the combined diff, already
+/// cut down to fit
+pub fn create_pr_request(
+    settings: &Settings,
+    base: &str,
+    logs: &[GitLog],
+    patches: &[String],
+    template: Option<&str>,
+) -> Request {
+    let prompt = build_prompt(settings, template.is_some());
+
+    let commits = logs
+        .iter()
+        .map(|log| {
+            format!(
+                "[{}] {}",
+                &log.commit_hash[..7.min(
+                    log.commit_hash
+                        .len()
+                )],
+                log.raw.trim()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let mut req = Request::new(&prompt).insert_content(&format!(
+        "Base:{}\nCommits:\n{}",
+        base, commits
+    ));
+
+    if let Some(template) = template {
+        req = req.insert_content(&format!("Template:\n{}", template));
+    }
+
+    req.insert_contents(patches)
+}
+
+fn build_prompt(
+    cfg: &Settings,
+    template: bool,
+) -> String {
+    let mut prompt = concat!(
+        "You are an assistant tasked with writing a pull request ",
+        "description. You are given the commits of a branch and its ",
+        "combined diff against the base. Write a title and a summary ",
+        "of what the branch does and why, list the notable changes, ",
+        "how they were or can be tested and the areas that are risky ",
+        "or deserve a closer review. Only describe what is in the ",
+        "commits and diff, some diffs may be truncated."
+    )
+    .to_string();
+
+    if template {
+        prompt.push_str(concat!(
+            "\nThe repository has a pull request template, fill in ",
+            "every section of it using its heading, keep checklists ",
+            "from the template and only check items that are true."
+        ));
+    }
+
+    if let Some(hint) = &cfg.prompt.hint {
+        prompt.push_str(&format!("\nUSE THIS AS A HINT: {}", hint));
+    }
+
+    prompt
+}
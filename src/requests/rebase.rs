@@ -73,6 +73,7 @@ fn build_prompt(cfg: &Settings) -> String {
         // todo impl other staging methods
         // likely during validation as well
         StagingStrategy::Hunks => prompt.push_str(PROMPT_STAGE_HUNKS),
+        StagingStrategy::Lines => prompt.push_str(PROMPT_STAGE_LINES),
         _ => prompt.push_str(PROMPT_STAGE_FILES),
     }
 
@@ -9,13 +9,29 @@ pub fn create_rebase_plan_request(
 ) -> Request {
     let prompt = build_prompt(settings);
 
-    Request::new(prompt)
+    Request::new(&prompt)
         .insert_contents(git_logs)
         .insert_content(diffs)
 }
 
-fn build_prompt(_cfg: &Settings) -> &str {
-    "You are a Git master.\
+fn build_prompt(cfg: &Settings) -> String {
+    let mut prompt = concat!(
+        "You are a Git master.\
         You are currently tasked with creating a Rebase Plan in the style of an --edit-todo \
-        Here is a list of logs, and diffs. Ensure you use the proper RebaseOperationTypes"
+        Here is a list of logs, and diffs. Ensure you use the proper RebaseOperationTypes. ",
+        "List every commit exactly once, the order of the operations is the new commit order. ",
+        "squash and fixup fold a commit into an earlier one in the plan, set squash_with to its ",
+        "index, fixup keeps that commit's message. Use edit only when a commit needs to be ",
+        "amended by hand. Only reorder commits that do not depend on each other."
+    )
+    .to_string();
+
+    if !cfg
+        .rebase
+        .allow_drop
+    {
+        prompt.push_str(" Never drop a commit.");
+    }
+
+    prompt
 }
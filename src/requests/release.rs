@@ -0,0 +1,46 @@
+use crate::{git::log::GitLog, settings::Settings};
+
+use super::Request;
+
+/// create a request to classify commits
+/// that dont follow conventional commits
+pub fn create_release_request(
+    settings: &Settings,
+    logs: &[&GitLog],
+) -> Request {
+    let prompt = build_prompt(settings);
+
+    let commits: Vec<String> = logs
+        .iter()
+        .map(|log| {
+            format!(
+                "Commit:[{}]\nMessage:{}\nFiles:{}",
+                &log.commit_hash[..7.min(
+                    log.commit_hash
+                        .len()
+                )],
+                log.raw.trim(),
+                log.files.join(", ")
+            )
+        })
+        .collect();
+
+    Request::new(&prompt).insert_contents(&commits)
+}
+
+fn build_prompt(cfg: &Settings) -> String {
+    let mut prompt = concat!(
+        "You are an assistant tasked with picking the next semantic ",
+        "version. You are given commits that do not follow conventional ",
+        "commits. Pick major if any commit breaks compatibility for ",
+        "users, minor if any adds a feature, otherwise patch. When ",
+        "unsure between two, pick the smaller one."
+    )
+    .to_string();
+
+    if let Some(hint) = &cfg.prompt.hint {
+        prompt.push_str(&format!("\nUSE THIS AS A HINT: {}", hint));
+    }
+
+    prompt
+}
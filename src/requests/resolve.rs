@@ -0,0 +1,137 @@
+use crate::{
+    git::conflicts::{
+        ConflictContext, ConflictFile, ConflictHunk,
+        ConflictOperation, ConflictSide,
+    },
+    settings::Settings,
+};
+
+use super::Request;
+
+/// lines shown around each conflict
+const CONTEXT_LINES: usize = 5;
+
+/// create a resolve request for every
+/// conflict block in a single file, with
+/// the commits on both sides for intent
+pub fn create_resolve_request(
+    settings: &Settings,
+    context: &ConflictContext,
+    file: &ConflictFile,
+) -> Request {
+    let prompt = build_prompt(settings, context.operation);
+
+    let lines: Vec<&str> = file
+        .content
+        .lines()
+        .collect();
+
+    let conflicts: Vec<String> = file
+        .hunks
+        .iter()
+        .enumerate()
+        .map(|(idx, hunk)| {
+            conflict_str(idx, &file.path, &lines, hunk)
+        })
+        .collect();
+
+    Request::new(&prompt)
+        .insert_content(&side_str("Ours", &context.ours))
+        .insert_content(&side_str("Theirs", &context.theirs))
+        .insert_contents(&conflicts)
+}
+
+fn side_str(
+    name: &str,
+    side: &ConflictSide,
+) -> String {
+    format!(
+        "{} Commit:{}\nMessage:\n{}",
+        name,
+        side.commit_hash
+            .as_deref()
+            .unwrap_or("unknown"),
+        side.message
+            .as_deref()
+            .unwrap_or("unknown")
+    )
+}
+
+fn conflict_str(
+    idx: usize,
+    path: &str,
+    lines: &[&str],
+    hunk: &ConflictHunk,
+) -> String {
+    let before = lines[hunk
+        .start
+        .saturating_sub(CONTEXT_LINES)
+        ..hunk.start]
+        .join("\n");
+
+    let after_end = (hunk.end + 1 + CONTEXT_LINES).min(lines.len());
+    let after =
+        lines[(hunk.end + 1).min(after_end)..after_end].join("\n");
+
+    let mut s = format!(
+        "ConflictID:[{}]\nPath:{}\nBefore:\n{}\nOurs ({}):\n{}\n",
+        idx,
+        path,
+        before,
+        hunk.ours_label,
+        hunk.ours.join("\n"),
+    );
+
+    if let Some(ref base) = hunk.base {
+        s.push_str(&format!("Base:\n{}\n", base.join("\n")));
+    }
+
+    s.push_str(&format!(
+        "Theirs ({}):\n{}\nAfter:\n{}",
+        hunk.theirs_label,
+        hunk.theirs
+            .join("\n"),
+        after
+    ));
+
+    s
+}
+
+fn build_prompt(
+    cfg: &Settings,
+    operation: ConflictOperation,
+) -> String {
+    let mut prompt = concat!(
+        "You are an assistant tasked with resolving merge conflicts. ",
+        "Each conflict has the lines before and after it, our side, ",
+        "their side and sometimes the common base. For every conflict ",
+        "return the merged code that replaces the whole conflict block. ",
+        "Keep the intent of both sides where possible, do not include ",
+        "conflict markers or the surrounding context lines, and keep ",
+        "the original indentation. Use the commit messages of both ",
+        "sides to understand what each change was trying to do."
+    )
+    .to_string();
+
+    prompt.push_str(match operation {
+        ConflictOperation::Merge => {
+            "\nThis is a merge, ours is the current branch and theirs is the branch being merged in."
+        }
+        ConflictOperation::Rebase => {
+            "\nThis is a rebase, ours is the upstream being rebased onto and theirs is the local commit being replayed on top of it."
+        }
+        ConflictOperation::CherryPick => {
+            "\nThis is a cherry-pick, ours is the current branch and theirs is the commit being picked."
+        }
+        ConflictOperation::Revert => {
+            "\nThis is a revert, ours is the current branch and theirs is the reverse of the commit being reverted."
+        }
+        ConflictOperation::Unknown => "",
+    });
+
+    if let Some(hint) = &cfg.prompt.hint {
+        prompt.push_str(&format!("\nUSE THIS AS A HINT: {}", hint));
+    }
+
+    prompt
+}
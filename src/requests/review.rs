@@ -0,0 +1,79 @@
+use crate::{
+    git::{Diffs, diffs::DiffLineType},
+    settings::Settings,
+};
+
+use super::Request;
+
+/// create a review request, one content
+/// per file so large diffs stay readable
+pub fn create_review_request(
+    settings: &Settings,
+    diffs: &Diffs,
+) -> Request {
+    let prompt = build_prompt(settings);
+
+    let files: Vec<String> = diffs
+        .files
+        .iter()
+        .map(|file| {
+            let mut s = String::new();
+
+            for hunk in &file.hunks {
+                s.push_str(&format!(
+                    "HunkId[{}:{}] {}\n",
+                    file.path, hunk.id, hunk.header
+                ));
+
+                for line in &hunk.lines {
+                    s.push_str(&numbered_line(
+                        line.line_type,
+                        line.position
+                            .new_lineno,
+                        &line.content,
+                    ));
+                }
+            }
+
+            s
+        })
+        .collect();
+
+    Request::new(&prompt).insert_contents(&files)
+}
+
+/// prefix each line with its number in the
+/// new file, deleted lines dont have one
+fn numbered_line(
+    line_type: DiffLineType,
+    new_lineno: Option<u32>,
+    content: &str,
+) -> String {
+    let number = match (line_type, new_lineno) {
+        (DiffLineType::Delete, _) | (_, None) => String::new(),
+        (_, Some(n)) => n.to_string(),
+    };
+
+    format!("{:>5} {}{}\n", number, line_type, content)
+}
+
+fn build_prompt(cfg: &Settings) -> String {
+    let mut prompt = concat!(
+        "You are an assistant tasked with reviewing a code change ",
+        "before it is committed. Each hunk starts with its HunkId and ",
+        "header, each line is labeled with its number in the new file, ",
+        "followed by + for added, - for removed or a space for context. ",
+        "Only report real problems in the added or removed lines, such as ",
+        "bugs, security issues, performance problems, missing tests or ",
+        "unclear code. Do not comment on context lines or restate what the ",
+        "change does. Use high severity only for issues that should block ",
+        "the commit. Return no findings if the change looks fine."
+    )
+    .to_string();
+
+    if let Some(hint) = &cfg.prompt.hint {
+        prompt.push_str(&format!("\nUSE THIS AS A HINT: {}", hint));
+    }
+
+    prompt
+}
@@ -1,6 +1,8 @@
 use crate::{
     git::{
-        GitRepo, StatusStrategy, log::get_logs, status::get_status,
+        GitRepo, StatusStrategy,
+        log::{LogFilters, get_logs},
+        status::get_status,
     },
     settings::{PromptRules, Settings},
     utils::consts::*,
@@ -107,7 +109,7 @@ fn build_prompt(
             false,
             None,
             None,
-            None,
+            &LogFilters::default(),
         )
         .unwrap_or_default();
 
@@ -9,3 +9,84 @@
 pub fn estimate_token_count(text: &str) -> u32 {
     (text.len() as u32 + 3).div_ceil(4)
 }
+
+/// split items into batches that each fit
+/// in the token budget, an item thats bigger
+/// than the budget gets a batch to itself
+pub fn batch_by_tokens<T: AsRef<str>>(
+    items: &[T],
+    budget: u32,
+) -> Vec<&[T]> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut tokens = 0;
+
+    for (idx, item) in items
+        .iter()
+        .enumerate()
+    {
+        let count = estimate_token_count(item.as_ref());
+
+        if idx > start && tokens + count > budget {
+            batches.push(&items[start..idx]);
+            start = idx;
+            tokens = 0;
+        }
+
+        tokens += count;
+    }
+
+    if start < items.len() {
+        batches.push(&items[start..]);
+    }
+
+    batches
+}
+
+/// cut text down to roughly the token count
+pub fn truncate_to_tokens(
+    text: &mut String,
+    tokens: u32,
+) {
+    if estimate_token_count(text) <= tokens {
+        return;
+    }
+
+    let max = (tokens as usize) * 4;
+
+    let end = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .take_while(|i| *i <= max)
+        .last()
+        .unwrap_or(0);
+
+    text.truncate(end);
+    text.push_str("\n...(truncated)");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_by_tokens() {
+        // 8 chars is 3 tokens with the estimate
+        let items = vec!["aaaaaaaa"; 5];
+
+        let batches = batch_by_tokens(&items, 6);
+        let sizes: Vec<usize> = batches
+            .iter()
+            .map(|b| b.len())
+            .collect();
+
+        assert_eq!(sizes, vec![2, 2, 1]);
+
+        // too big for the budget, still sent
+        let batches = batch_by_tokens(&items, 1);
+        assert_eq!(batches.len(), 5);
+
+        let empty: Vec<String> = Vec::new();
+        assert!(batch_by_tokens(&empty, 10).is_empty());
+    }
+}
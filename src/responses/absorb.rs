@@ -0,0 +1,117 @@
+use serde::Serialize;
+
+use crate::{
+    git::absorb::AbsorbCandidate, schema::absorb::AbsorbSchema,
+};
+
+/// how a hunk was assigned
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Serialize, strum::Display,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum AbsorbSource {
+    Blame,
+    Llm,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Absorbed {
+    pub hunk_id: String,
+
+    /// full hash of the target
+    pub commit: String,
+    pub source: AbsorbSource,
+}
+
+/// extract the assignments, only for hunks we asked
+/// about and commits we offered. "none" and repeats
+/// of a hunk are dropped
+pub fn parse_to_absorbed(
+    value: serde_json::Value,
+    hunk_ids: &[String],
+    candidates: &[AbsorbCandidate],
+) -> anyhow::Result<Vec<Absorbed>> {
+    let schema: AbsorbSchema = serde_json::from_value(value)?;
+
+    let mut res: Vec<Absorbed> = Vec::new();
+
+    for a in schema.assignments {
+        if !hunk_ids.contains(&a.hunk_id)
+            || res
+                .iter()
+                .any(|r| r.hunk_id == a.hunk_id)
+        {
+            continue;
+        }
+
+        let short = a.commit.trim();
+
+        let Some(target) = candidates
+            .iter()
+            .find(|c| {
+                short.len() >= 7
+                    && c.hash
+                        .starts_with(short)
+            })
+        else {
+            continue;
+        };
+
+        res.push(Absorbed {
+            hunk_id: a.hunk_id,
+            commit: target
+                .hash
+                .to_owned(),
+            source: AbsorbSource::Llm,
+        });
+    }
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_absorb_assignments_validated() {
+        let candidates = vec![
+            AbsorbCandidate {
+                hash: "aaaaaaa1111".to_owned(),
+                summary: "add a".to_owned(),
+            },
+            AbsorbCandidate {
+                hash: "bbbbbbb2222".to_owned(),
+                summary: "add b".to_owned(),
+            },
+        ];
+
+        let hunk_ids = vec![
+            "src/a.rs:0".to_owned(),
+            "src/a.rs:1".to_owned(),
+            "src/b.rs:0".to_owned(),
+        ];
+
+        let value = json!({
+            "assignments": [
+                {"hunk_id": "src/a.rs:0", "commit": "aaaaaaa"},
+                {"hunk_id": "src/a.rs:0", "commit": "bbbbbbb"},
+                {"hunk_id": "src/a.rs:1", "commit": "none"},
+                {"hunk_id": "src/b.rs:0", "commit": "bbbbbbb"},
+                {"hunk_id": "src/c.rs:0", "commit": "bbbbbbb"},
+            ]
+        });
+
+        let absorbed =
+            parse_to_absorbed(value, &hunk_ids, &candidates).unwrap();
+
+        assert_eq!(absorbed.len(), 2);
+        assert_eq!(absorbed[0].hunk_id, "src/a.rs:0");
+        assert_eq!(absorbed[0].commit, "aaaaaaa1111");
+        assert_eq!(absorbed[1].commit, "bbbbbbb2222");
+        assert_eq!(absorbed[1].source, AbsorbSource::Llm);
+    }
+}
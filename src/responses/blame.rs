@@ -0,0 +1,99 @@
+use crate::{
+    git::blame::RegionCommit,
+    schema::blame::{BlameSchema, BlameStep},
+};
+
+/// extract the BlameSchema from the response
+/// steps are resolved to full hashes, the ones
+/// citing commits we never sent are dropped
+pub fn parse_to_blame_schema(
+    value: serde_json::Value,
+    commits: &[RegionCommit],
+) -> anyhow::Result<BlameSchema> {
+    let mut blame: BlameSchema = serde_json::from_value(value)?;
+
+    blame.steps = blame
+        .steps
+        .into_iter()
+        .filter_map(|step| {
+            let commit = resolve_hash(&step.commit, commits)?;
+
+            Some(BlameStep {
+                commit: commit
+                    .commit_hash
+                    .to_owned(),
+                explanation: step.explanation,
+            })
+        })
+        .collect();
+
+    Ok(blame)
+}
+
+/// llms like to add brackets or
+/// shorten hashes even more
+fn resolve_hash<'a>(
+    cited: &str,
+    commits: &'a [RegionCommit],
+) -> Option<&'a RegionCommit> {
+    let cited = cited
+        .trim()
+        .trim_matches(|c: char| !c.is_ascii_hexdigit())
+        .to_lowercase();
+
+    // too short to be unambiguous
+    if cited.len() < 4 {
+        return None;
+    }
+
+    commits
+        .iter()
+        .find(|c| {
+            c.commit_hash
+                .starts_with(&cited)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn commit(hash: &str) -> RegionCommit {
+        RegionCommit {
+            commit_hash: hash.to_owned(),
+            message: String::new(),
+            author: String::new(),
+            date: String::new(),
+            timestamp: 0,
+            path: String::new(),
+            hunks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_blame_steps_resolved() {
+        let commits =
+            vec![commit("abcdef1234"), commit("1234567890")];
+
+        let value = json!({
+            "summary": "it changed",
+            "steps": [
+                {"commit": "[abcdef1]", "explanation": "added"},
+                {"commit": "deadbee", "explanation": "made up"},
+                {"commit": "1234567", "explanation": "fixed"},
+            ]
+        });
+
+        let blame = parse_to_blame_schema(value, &commits).unwrap();
+
+        let hashes: Vec<&str> = blame
+            .steps
+            .iter()
+            .map(|s| s.commit.as_str())
+            .collect();
+
+        assert_eq!(hashes, vec!["abcdef1234", "1234567890"]);
+    }
+}
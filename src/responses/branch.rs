@@ -0,0 +1,206 @@
+// turn suggestions into branch names that
+// follow settings.branch.pattern
+
+use crate::{
+    schema::branch::{BranchSchema, BranchSuggestion},
+    settings::BranchSettings,
+};
+
+const SEPARATORS: [char; 3] = ['-', '_', '.'];
+
+/// extract the suggestions and turn
+/// them into branch names
+pub fn parse_to_branch_names(
+    value: serde_json::Value,
+    settings: &BranchSettings,
+    ticket: Option<&str>,
+) -> anyhow::Result<Vec<String>> {
+    let response: BranchSchema = serde_json::from_value(value)?;
+
+    Ok(process_branch_suggestions(
+        &response.suggestions,
+        settings,
+        ticket,
+    ))
+}
+
+/// valid, unique names, best first, ticket
+/// overrides whatever the llm came up with
+pub fn process_branch_suggestions(
+    suggestions: &[BranchSuggestion],
+    settings: &BranchSettings,
+    ticket: Option<&str>,
+) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+
+    for suggestion in suggestions {
+        let ticket = ticket.unwrap_or(&suggestion.ticket);
+
+        let name = render_branch_name(
+            settings,
+            &suggestion
+                .prefix
+                .to_string(),
+            ticket,
+            &suggestion.description,
+        );
+
+        if !name.is_empty()
+            && git2::Reference::is_valid_name(&format!(
+                "refs/heads/{}",
+                name
+            ))
+            && !names.contains(&name)
+        {
+            names.push(name);
+        }
+    }
+
+    names
+}
+
+/// fill the pattern, an empty part takes
+/// the separators around it along
+pub fn render_branch_name(
+    settings: &BranchSettings,
+    prefix: &str,
+    ticket: &str,
+    description: &str,
+) -> String {
+    let name = settings
+        .pattern
+        .replace("{type}", &slugify(prefix, usize::MAX))
+        .replace("{ticket}", &ticket_slug(ticket))
+        .replace(
+            "{slug}",
+            &slugify(description, settings.max_slug_length),
+        );
+
+    // each path segment on its own, so
+    // feat/-add-x becomes feat/add-x
+    name.split('/')
+        .map(|segment| {
+            let mut out = String::new();
+
+            for c in segment.chars() {
+                if SEPARATORS.contains(&c)
+                    && (out.is_empty() || out.ends_with(SEPARATORS))
+                {
+                    continue;
+                }
+
+                out.push(c);
+            }
+
+            out.trim_end_matches(SEPARATORS)
+                .to_owned()
+        })
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// tickets keep their case, ex. ABC-123
+fn ticket_slug(ticket: &str) -> String {
+    ticket
+        .trim()
+        .chars()
+        .filter(|c| {
+            c.is_ascii_alphanumeric() || *c == '-' || *c == '_'
+        })
+        .collect()
+}
+
+/// lowercase ascii words joined by dashes
+/// cut at a word boundary past max_length,
+/// or inside the first word if thats too long
+pub fn slugify(
+    text: &str,
+    max_length: usize,
+) -> String {
+    let mut slug = String::new();
+
+    for word in text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        let word = word.to_ascii_lowercase();
+
+        if slug.is_empty() && word.len() > max_length {
+            slug.extend(
+                word.chars()
+                    .take(max_length),
+            );
+            break;
+        }
+
+        if !slug.is_empty() {
+            if slug.len() + 1 + word.len() > max_length {
+                break;
+            }
+
+            slug.push('-');
+        }
+
+        slug.push_str(&word);
+    }
+
+    slug
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_branch_name() {
+        let settings = BranchSettings::default();
+
+        assert_eq!(
+            render_branch_name(
+                &settings,
+                "feat",
+                "ABC-12",
+                "Add OAuth login!"
+            ),
+            "feat/ABC-12-add-oauth-login"
+        );
+
+        // no ticket, no dangling dash
+        assert_eq!(
+            render_branch_name(
+                &settings,
+                "fix",
+                "",
+                "crash on empty repo"
+            ),
+            "fix/crash-on-empty-repo"
+        );
+
+        let settings = BranchSettings {
+            pattern: "{ticket}_{slug}".to_owned(),
+            max_slug_length: 12,
+        };
+
+        assert_eq!(
+            render_branch_name(
+                &settings,
+                "fix",
+                "",
+                "handle root commits properly"
+            ),
+            "handle-root"
+        );
+
+        assert_eq!(
+            slugify("Über   cool_feature", 40),
+            "ber-cool-feature"
+        );
+
+        // one long identifier, cut inside it
+        assert_eq!(
+            slugify("RenderBranchNameFromSettings fix", 12),
+            "renderbranch"
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use crate::{
+    changelog::Changelog, schema::changelog::ChangelogSchema,
+};
+
+/// put each summary on its section, ones
+/// for unknown sections are dropped
+pub fn apply_changelog_summaries(
+    value: serde_json::Value,
+    changelog: &mut Changelog,
+) -> anyhow::Result<()> {
+    let response: ChangelogSchema = serde_json::from_value(value)?;
+
+    for summary in response.sections {
+        if let Some(section) = changelog
+            .sections
+            .iter_mut()
+            .find(|s| s.title == summary.title)
+            && !summary
+                .summary
+                .trim()
+                .is_empty()
+        {
+            section.summary = Some(summary.summary);
+        }
+    }
+
+    Ok(())
+}
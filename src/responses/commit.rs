@@ -114,7 +114,7 @@ pub fn process_commit(
             .unwrap_or_default(),
         _ => {
             // do nothing for AllFilesOneCommit
-            // or hunks
+            // or hunks/lines
             // assume to cover both
             vec![]
         }
@@ -124,9 +124,175 @@ pub fn process_commit(
         .hunk_ids
         .unwrap_or_default();
 
+    let line_ids = raw_commit
+        .line_ids
+        .unwrap_or_default();
+
     GitCommit {
         files,
         hunk_ids,
+        line_ids,
         message,
     }
 }
+
+// regrouping helpers for the edit menu
+// these only shuffle files/hunk ids around
+// the messages are left for the user to fix
+
+/// does a file, hunk or line id match what
+/// the user typed, a bare path matches every
+/// hunk in that file, a hunk id every block in it
+fn item_matches(
+    item: &str,
+    selector: &str,
+) -> bool {
+    item == selector
+        || item
+            .strip_prefix(selector)
+            .is_some_and(|rest| rest.starts_with(':'))
+}
+
+/// move the matching items from one commit
+/// to another, returns what was moved
+pub fn move_items(
+    commits: &mut [CommitSchema],
+    from: usize,
+    to: usize,
+    selectors: &[&str],
+    strategy: &StagingStrategy,
+) -> Vec<String> {
+    let (moved, kept): (Vec<String>, Vec<String>) = commits[from]
+        .items(strategy)
+        .into_iter()
+        .partition(|item| {
+            selectors
+                .iter()
+                .any(|s| item_matches(item, s))
+        });
+
+    if moved.is_empty() {
+        return moved;
+    }
+
+    commits[from].set_items(strategy, kept);
+
+    let mut target = commits[to].items(strategy);
+
+    for item in &moved {
+        if !target.contains(item) {
+            target.push(item.to_owned());
+        }
+    }
+
+    commits[to].set_items(strategy, target);
+
+    moved
+}
+
+/// new commit without any files or hunks
+/// inserted after `after`, reuses the prefix
+/// and scope since thats likely what
+/// the user wants anyway
+pub fn insert_empty_commit(
+    commits: &mut Vec<CommitSchema>,
+    after: usize,
+    strategy: &StagingStrategy,
+) {
+    let mut new = commits[after].to_owned();
+
+    new.reasoning = format!("added after commit {}", after + 1);
+    new.body = None;
+    new.set_items(strategy, Vec::new());
+
+    commits.insert(after + 1, new);
+}
+
+/// fold the next commit into this one
+/// keeping this commits message
+pub fn merge_with_next(
+    commits: &mut Vec<CommitSchema>,
+    idx: usize,
+    strategy: &StagingStrategy,
+) -> bool {
+    if idx + 1 >= commits.len() {
+        return false;
+    }
+
+    let next = commits.remove(idx + 1);
+
+    let mut items = commits[idx].items(strategy);
+
+    for item in next.items(strategy) {
+        if !items.contains(&item) {
+            items.push(item);
+        }
+    }
+
+    commits[idx].set_items(strategy, items);
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::commit::PrefixType;
+
+    fn commit(hunk_ids: &[&str]) -> CommitSchema {
+        CommitSchema {
+            reasoning: String::new(),
+            path: None,
+            paths: None,
+            hunk_ids: Some(
+                hunk_ids
+                    .iter()
+                    .map(|h| h.to_string())
+                    .collect(),
+            ),
+            line_ids: None,
+            prefix: PrefixType::Feat,
+            scope: None,
+            breaking: None,
+            header: "header".to_owned(),
+            body: None,
+        }
+    }
+
+    #[test]
+    fn test_move_file_moves_all_hunks() {
+        let strategy = StagingStrategy::Hunks;
+        let mut commits = vec![
+            commit(&["src/a.rs:0", "src/a.rs:1", "src/b.rs:0"]),
+            commit(&["src/c.rs:0"]),
+        ];
+
+        let moved =
+            move_items(&mut commits, 0, 1, &["src/a.rs"], &strategy);
+
+        assert_eq!(moved, vec!["src/a.rs:0", "src/a.rs:1"]);
+        assert_eq!(commits[0].items(&strategy), vec!["src/b.rs:0"]);
+        assert_eq!(
+            commits[1].items(&strategy),
+            vec!["src/c.rs:0", "src/a.rs:0", "src/a.rs:1"]
+        );
+    }
+
+    #[test]
+    fn test_split_and_merge() {
+        let strategy = StagingStrategy::Hunks;
+        let mut commits = vec![commit(&["a:0", "a:1"])];
+
+        insert_empty_commit(&mut commits, 0, &strategy);
+        move_items(&mut commits, 0, 1, &["a:1"], &strategy);
+
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[1].items(&strategy), vec!["a:1"]);
+
+        assert!(merge_with_next(&mut commits, 0, &strategy));
+        assert!(!merge_with_next(&mut commits, 0, &strategy));
+
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].items(&strategy), vec!["a:0", "a:1"]);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::schema::explain::ExplainSchema;
+
+/// extract the ExplainSchema from the response,
+/// blank entries some models pad arrays with are dropped
+pub fn parse_to_explain_schema(
+    value: serde_json::Value
+) -> anyhow::Result<ExplainSchema> {
+    let mut explain: ExplainSchema = serde_json::from_value(value)?;
+
+    explain.intent = explain
+        .intent
+        .trim()
+        .to_owned();
+
+    explain
+        .components
+        .retain(|c| {
+            !c.name
+                .trim()
+                .is_empty()
+                && !c
+                    .summary
+                    .trim()
+                    .is_empty()
+        });
+
+    explain.behavior_changes = explain
+        .behavior_changes
+        .into_iter()
+        .map(|b| b.trim().to_owned())
+        .filter(|b| !b.is_empty())
+        .collect();
+
+    explain
+        .risky_areas
+        .retain(|r| {
+            !r.reason
+                .trim()
+                .is_empty()
+        });
+
+    Ok(explain)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_explain_blank_entries_dropped() {
+        let value = json!({
+            "intent": "  adds sync  ",
+            "components": [
+                {"name": "git/sync", "summary": "new"},
+                {"name": "", "summary": ""},
+            ],
+            "behavior_changes": ["gai sync exists", " "],
+            "risky_areas": [
+                {"area": "rebase", "reason": ""},
+                {"area": "conflicts", "reason": "untested"},
+            ]
+        });
+
+        let explain = parse_to_explain_schema(value).unwrap();
+
+        assert_eq!(explain.intent, "adds sync");
+        assert_eq!(
+            explain
+                .components
+                .len(),
+            1
+        );
+        assert_eq!(explain.behavior_changes, vec!["gai sync exists"]);
+        assert_eq!(
+            explain
+                .risky_areas
+                .len(),
+            1
+        );
+        assert_eq!(explain.risky_areas[0].area, "conflicts");
+    }
+}
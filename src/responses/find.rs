@@ -1,11 +1,64 @@
-use crate::schema::find::FindCommitSchema;
+use crate::schema::find::{FindCommitSchema, FindResponse};
 
-/// extract FindCommitschema from
-/// response
-pub fn parse_to_find_schema(
-    value: serde_json::Value
-) -> anyhow::Result<FindCommitSchema> {
-    let val = serde_json::from_value(value)?;
+/// extract the ranked FindCommitSchemas from
+/// the response, drops commit ids that dont
+/// exist and duplicates, then sorts by
+/// confidence keeping the LLM's order
+/// within the same confidence
+pub fn parse_to_find_response(
+    value: serde_json::Value,
+    log_count: usize,
+    top: usize,
+) -> anyhow::Result<Vec<FindCommitSchema>> {
+    let response: FindResponse = serde_json::from_value(value)?;
 
-    Ok(val)
+    let mut res: Vec<FindCommitSchema> = Vec::new();
+
+    for commit in response.commits {
+        if (commit.commit_id as usize) < log_count
+            && !res
+                .iter()
+                .any(|c| c.commit_id == commit.commit_id)
+        {
+            res.push(commit);
+        }
+    }
+
+    res.sort_by_key(|c| c.confidence);
+    res.truncate(top.max(1));
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::schema::find::Confidence;
+
+    #[test]
+    fn test_find_response_ranking() {
+        let value = json!({
+            "commits": [
+                {"reasoning": "a", "commit_id": 4, "confidence": "Ambiguous"},
+                {"reasoning": "b", "commit_id": 1, "confidence": "Likely"},
+                {"reasoning": "c", "commit_id": 99, "confidence": "Exact"},
+                {"reasoning": "d", "commit_id": 2, "confidence": "Exact"},
+                {"reasoning": "e", "commit_id": 1, "confidence": "Exact"},
+                {"reasoning": "f", "commit_id": 3, "confidence": "Likely"},
+            ]
+        });
+
+        let res = parse_to_find_response(value, 10, 3).unwrap();
+
+        let ids: Vec<u32> = res
+            .iter()
+            .map(|c| c.commit_id)
+            .collect();
+
+        // 99 is out of range, the second 1 is a dupe
+        assert_eq!(ids, vec![2, 1, 3]);
+        assert_eq!(res[0].confidence, Confidence::Exact);
+    }
 }
@@ -1,5 +1,14 @@
+pub mod absorb;
+pub mod blame;
+pub mod branch;
+pub mod changelog;
 pub mod commit;
+pub mod explain;
 pub mod find;
+pub mod pr;
 pub mod rebase;
 pub mod rebase_plan;
+pub mod release;
+pub mod resolve;
+pub mod review;
 pub mod reword;
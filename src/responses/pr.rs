@@ -0,0 +1,189 @@
+// turn the pr response into markdown, either
+// our own layout or the repos template with
+// each section filled in
+
+use crate::schema::pr::PrSchema;
+
+/// extract the PrSchema, sections for
+/// headings we didnt send are dropped
+pub fn parse_to_pr_schema(
+    value: serde_json::Value,
+    headings: &[String],
+) -> anyhow::Result<PrSchema> {
+    let mut pr: PrSchema = serde_json::from_value(value)?;
+
+    pr.sections
+        .retain(|s| headings.contains(&s.heading));
+
+    Ok(pr)
+}
+
+/// headings in a template, ex. "## Summary"
+/// -> "Summary", in order
+pub fn template_headings(template: &str) -> Vec<String> {
+    let mut headings: Vec<String> = Vec::new();
+
+    for line in template.lines() {
+        if let Some(heading) = heading_text(line)
+            && !headings.contains(&heading)
+        {
+            headings.push(heading);
+        }
+    }
+
+    headings
+}
+
+fn heading_text(line: &str) -> Option<String> {
+    let trimmed = line.trim_start();
+
+    let text = trimmed.trim_start_matches('#');
+
+    if text.len() == trimmed.len() || !text.starts_with(' ') {
+        return None;
+    }
+
+    let text = text.trim();
+
+    (!text.is_empty()).then(|| text.to_owned())
+}
+
+/// the pr body as markdown, without the title
+pub fn render_pr_body(
+    pr: &PrSchema,
+    template: Option<&str>,
+) -> String {
+    match template {
+        Some(template)
+            if !pr
+                .sections
+                .is_empty() =>
+        {
+            fill_template(template, pr)
+        }
+        _ => default_body(pr),
+    }
+}
+
+fn default_body(pr: &PrSchema) -> String {
+    let mut out = format!("## Summary\n\n{}\n", pr.summary.trim());
+
+    for (heading, items) in [
+        ("Changes", &pr.changes),
+        ("Testing", &pr.testing),
+        ("Risks", &pr.risks),
+    ] {
+        if items.is_empty() {
+            continue;
+        }
+
+        out.push_str(&format!("\n## {}\n\n", heading));
+
+        for item in items {
+            out.push_str(&format!("- {}\n", item.trim()));
+        }
+    }
+
+    out
+}
+
+/// keep every heading of the template and swap
+/// the text under it for the generated content,
+/// sections the llm skipped keep the template
+/// text, html comments (instructions) are dropped
+fn fill_template(
+    template: &str,
+    pr: &PrSchema,
+) -> String {
+    let mut out = String::new();
+    let mut replacing = false;
+    let mut in_comment = false;
+
+    for line in template.lines() {
+        if let Some(heading) = heading_text(line) {
+            replacing = false;
+            out.push_str(line);
+            out.push('\n');
+
+            if let Some(section) = pr
+                .sections
+                .iter()
+                .find(|s| s.heading == heading)
+            {
+                out.push('\n');
+                out.push_str(
+                    section
+                        .content
+                        .trim(),
+                );
+                out.push_str("\n\n");
+                replacing = true;
+            }
+
+            continue;
+        }
+
+        if replacing {
+            continue;
+        }
+
+        // <!-- ... --> can span lines
+        let trimmed = line.trim();
+
+        if in_comment || trimmed.starts_with("<!--") {
+            in_comment = !trimmed.ends_with("-->");
+            continue;
+        }
+
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    out.trim_end()
+        .to_owned()
+        + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::pr::TemplateSection;
+
+    #[test]
+    fn test_fill_template() {
+        let template = "## What\n<!-- describe it -->\n\n## Checklist\n- [ ] tests\n\n### Notes\nleave me\n";
+
+        assert_eq!(
+            template_headings(template),
+            vec!["What", "Checklist", "Notes"]
+        );
+
+        let pr = PrSchema {
+            title: "t".to_owned(),
+            summary: "s".to_owned(),
+            changes: Vec::new(),
+            testing: Vec::new(),
+            risks: Vec::new(),
+            sections: vec![
+                TemplateSection {
+                    heading: "What".to_owned(),
+                    content: "adds a thing".to_owned(),
+                },
+                TemplateSection {
+                    heading: "Checklist".to_owned(),
+                    content: "- [x] tests".to_owned(),
+                },
+            ],
+        };
+
+        assert_eq!(
+            render_pr_body(&pr, Some(template)),
+            "## What\n\nadds a thing\n\n## Checklist\n\n- [x] tests\n\n### Notes\nleave me\n"
+        );
+
+        // no template, our own layout
+        let body = render_pr_body(&pr, None);
+        assert!(body.starts_with("## Summary\n\ns\n"));
+        assert!(!body.contains("## Risks"));
+    }
+}
@@ -1,16 +1,446 @@
-use crate::schema::rebase_plan::{
-    PlanOperationSchema, RebasePlanResponse,
+use std::collections::HashMap;
+
+use crate::{
+    git::{
+        errors::GitError,
+        log::GitLog,
+        todo::{TodoCommand, TodoStep},
+    },
+    schema::rebase_plan::{
+        PlanOperationKind, PlanOperationSchema, RebasePlanResponse,
+    },
 };
 
 /// extract rebase_plan from
 /// response returns a list of
-/// operations
-/// TODO: impl validation
+/// operations, validated and in the
+/// order they get applied
 pub fn parse_from_rebase_plan_schema(
-    value: serde_json::Value
+    value: serde_json::Value,
+    commit_count: usize,
+    allow_drop: bool,
 ) -> anyhow::Result<Vec<PlanOperationSchema>> {
     let plan_resp =
         serde_json::from_value::<RebasePlanResponse>(value)?;
 
-    Ok(plan_resp.operations)
+    validate_plan(plan_resp.operations, commit_count, allow_drop)
+}
+
+/// every commit has to show up exactly once, drops only
+/// when allowed, and squash/fixup have to point at a commit
+/// that comes earlier and is kept. the result is the plan
+/// order with every fold moved right after the commit it
+/// folds into, and squash_with filled in
+pub fn validate_plan(
+    ops: Vec<PlanOperationSchema>,
+    commit_count: usize,
+    allow_drop: bool,
+) -> anyhow::Result<Vec<PlanOperationSchema>> {
+    let invalid = |msg: String| -> anyhow::Error {
+        GitError::Generic(format!("invalid rebase plan, {msg}"))
+            .into()
+    };
+
+    let mut seen = vec![false; commit_count];
+
+    for op in &ops {
+        let idx = op.commit_index as usize;
+
+        match seen.get_mut(idx) {
+            None => {
+                return Err(invalid(format!(
+                    "commit {idx} does not exist"
+                )));
+            }
+            Some(true) => {
+                return Err(invalid(format!(
+                    "commit {idx} shows up more than once"
+                )));
+            }
+            Some(s) => *s = true,
+        }
+
+        match op.operation {
+            PlanOperationKind::Drop if !allow_drop => {
+                return Err(invalid(format!(
+                    "it drops commit {idx}, set rebase.allow_drop to allow that"
+                )));
+            }
+            PlanOperationKind::Reword | PlanOperationKind::Squash
+                if op
+                    .new_message
+                    .as_deref()
+                    .is_none_or(|m| m.trim().is_empty()) =>
+            {
+                return Err(invalid(format!(
+                    "{} of commit {idx} has no message",
+                    op.operation
+                )));
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(missing) = seen
+        .iter()
+        .position(|s| !s)
+    {
+        return Err(invalid(format!(
+            "commit {missing} is missing, every commit needs an operation"
+        )));
+    }
+
+    // fold -> the commit it ends up in
+    let mut roots: HashMap<u32, u32> = HashMap::new();
+    let mut last_kept: Option<u32> = None;
+
+    for (pos, op) in ops
+        .iter()
+        .enumerate()
+    {
+        if op.operation == PlanOperationKind::Drop {
+            continue;
+        }
+
+        if !op.operation.folds() {
+            last_kept = Some(op.commit_index);
+            continue;
+        }
+
+        let Some(target) = op
+            .squash_with
+            .or(last_kept)
+        else {
+            return Err(invalid(format!(
+                "commit {} has nothing before it to {} into",
+                op.commit_index, op.operation
+            )));
+        };
+
+        let Some(target_op) = ops[..pos]
+            .iter()
+            .find(|o| o.commit_index == target)
+        else {
+            return Err(invalid(format!(
+                "commit {} folds into {target}, which does not come before it",
+                op.commit_index
+            )));
+        };
+
+        if target_op.operation == PlanOperationKind::Drop {
+            return Err(invalid(format!(
+                "commit {} folds into {target}, which is dropped",
+                op.commit_index
+            )));
+        }
+
+        let root = roots
+            .get(&target)
+            .copied()
+            .unwrap_or(target);
+
+        roots.insert(op.commit_index, root);
+        last_kept = Some(root);
+    }
+
+    let mut res = Vec::new();
+
+    for op in ops
+        .iter()
+        .filter(|o| !o.operation.folds())
+    {
+        res.push(op.to_owned());
+
+        for fold in ops
+            .iter()
+            .filter(|o| {
+                roots.get(&o.commit_index) == Some(&op.commit_index)
+            })
+        {
+            let mut fold = fold.to_owned();
+            fold.squash_with = Some(op.commit_index);
+            res.push(fold);
+        }
+    }
+
+    Ok(res)
+}
+
+/// a todo back into plan ops, hashes are matched against
+/// the commits in the range. a pick with a message is a
+/// reword, a fixup with one is a squash and a squash without
+/// one keeps both messages like git would. a reword needs
+/// one, theres no editor to ask for it. a commit left out
+/// of the todo is dropped, if dropping is allowed
+pub fn plan_from_todo(
+    steps: &[TodoStep],
+    logs: &[GitLog],
+    allow_drop: bool,
+) -> anyhow::Result<Vec<PlanOperationSchema>> {
+    let mut ops: Vec<PlanOperationSchema> = Vec::new();
+    let mut last_message = String::new();
+
+    for step in steps {
+        let Some(idx) = logs
+            .iter()
+            .position(|l| {
+                step.commit.len() >= 4
+                    && l.commit_hash
+                        .starts_with(&step.commit)
+            })
+        else {
+            return Err(GitError::Generic(format!(
+                "{} from the todo is not in the rebase range",
+                step.commit
+            ))
+            .into());
+        };
+
+        let raw = logs[idx]
+            .raw
+            .trim_end();
+
+        let (operation, new_message) = match (
+            step.command,
+            step.message
+                .to_owned(),
+        ) {
+            (TodoCommand::Pick, None) => {
+                (PlanOperationKind::Pick, None)
+            }
+            (TodoCommand::Pick | TodoCommand::Reword, Some(msg)) => {
+                (PlanOperationKind::Reword, Some(msg))
+            }
+            (TodoCommand::Reword, None) => {
+                return Err(GitError::Generic(format!(
+                    "reword {} needs a message exec after it, see `--todo-out`",
+                    step.commit
+                ))
+                .into());
+            }
+            (TodoCommand::Edit, _) => (PlanOperationKind::Edit, None),
+            (TodoCommand::Fixup, None) => {
+                (PlanOperationKind::Fixup, None)
+            }
+            (TodoCommand::Fixup | TodoCommand::Squash, Some(msg)) => {
+                (PlanOperationKind::Squash, Some(msg))
+            }
+            (TodoCommand::Squash, None) => (
+                PlanOperationKind::Squash,
+                Some(format!("{last_message}\n\n{raw}")),
+            ),
+            (TodoCommand::Drop, _) => (PlanOperationKind::Drop, None),
+        };
+
+        match operation {
+            PlanOperationKind::Drop | PlanOperationKind::Fixup => {}
+            _ => {
+                last_message = new_message
+                    .to_owned()
+                    .unwrap_or(raw.to_owned())
+            }
+        }
+
+        ops.push(PlanOperationSchema {
+            reasoning: String::new(),
+            commit_index: idx as u32,
+            operation,
+            new_message,
+            squash_with: None,
+        });
+    }
+
+    if allow_drop {
+        for idx in 0..logs.len() as u32 {
+            if !ops
+                .iter()
+                .any(|o| o.commit_index == idx)
+            {
+                ops.push(PlanOperationSchema {
+                    reasoning: "left out of the todo".to_owned(),
+                    commit_index: idx,
+                    operation: PlanOperationKind::Drop,
+                    new_message: None,
+                    squash_with: None,
+                });
+            }
+        }
+    }
+
+    validate_plan(ops, logs.len(), allow_drop)
+}
+
+/// the other way around, for writing a todo.
+/// ops should already be validated
+pub fn plan_to_todo(
+    ops: &[PlanOperationSchema],
+    logs: &[GitLog],
+) -> Vec<TodoStep> {
+    ops.iter()
+        .map(|op| {
+            let log = &logs[op.commit_index as usize];
+
+            let command = match op.operation {
+                PlanOperationKind::Pick => TodoCommand::Pick,
+                PlanOperationKind::Squash => TodoCommand::Squash,
+                PlanOperationKind::Fixup => TodoCommand::Fixup,
+                PlanOperationKind::Reword => TodoCommand::Reword,
+                PlanOperationKind::Edit => TodoCommand::Edit,
+                PlanOperationKind::Drop => TodoCommand::Drop,
+            };
+
+            let message = match op.operation {
+                PlanOperationKind::Reword
+                | PlanOperationKind::Squash => op
+                    .new_message
+                    .to_owned(),
+                _ => None,
+            };
+
+            TodoStep {
+                command,
+                commit: log.commit_hash[..7].to_owned(),
+                summary: log
+                    .raw
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_owned(),
+                message,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn op(
+        index: u32,
+        operation: &str,
+        squash_with: Option<u32>,
+    ) -> serde_json::Value {
+        json!({
+            "reasoning": "",
+            "commit_index": index,
+            "operation": operation,
+            "new_message": "feat: combined",
+            "squash_with": squash_with,
+        })
+    }
+
+    #[test]
+    fn test_plan_folds_move_to_target() {
+        // 2 moves first, 0 squashes into it from the end,
+        // 3 fixes up whatever came right before it
+        let value = json!({
+            "operations": [
+                op(2, "pick", None),
+                op(1, "edit", None),
+                op(3, "fixup", None),
+                op(0, "squash", Some(2)),
+            ]
+        });
+
+        let ops =
+            parse_from_rebase_plan_schema(value, 4, false).unwrap();
+
+        let order: Vec<(u32, Option<u32>)> = ops
+            .iter()
+            .map(|o| (o.commit_index, o.squash_with))
+            .collect();
+
+        assert_eq!(
+            order,
+            vec![(2, None), (0, Some(2)), (1, None), (3, Some(1))]
+        );
+    }
+
+    #[test]
+    fn test_plan_rejected() {
+        let plans = [
+            // drop without allow_drop
+            vec![op(0, "pick", None), op(1, "drop", None)],
+            // squash into a later commit
+            vec![op(0, "squash", Some(1)), op(1, "pick", None)],
+            // commit 1 missing
+            vec![op(0, "pick", None), op(0, "reword", None)],
+            // nothing to fold into
+            vec![op(0, "fixup", None), op(1, "pick", None)],
+        ];
+
+        for plan in plans {
+            let value = json!({ "operations": plan });
+
+            assert!(
+                parse_from_rebase_plan_schema(value, 2, false)
+                    .is_err()
+            );
+        }
+
+        let value = json!({
+            "operations": [op(0, "pick", None), op(1, "drop", None)]
+        });
+
+        assert!(
+            parse_from_rebase_plan_schema(value, 2, true).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_plan_from_todo() {
+        let logs: Vec<GitLog> = ["aaaaaaa1", "bbbbbbb2", "ccccccc3"]
+            .iter()
+            .zip(["add a", "add b", "add c"])
+            .map(|(hash, raw)| GitLog {
+                commit_hash: hash.to_string(),
+                raw: raw.to_owned(),
+                ..Default::default()
+            })
+            .collect();
+
+        let steps = crate::git::todo::parse_todo(
+            "pick ccccccc\nsquash aaaaaaa\npick bbbbbbb\n",
+        )
+        .unwrap();
+
+        let ops = plan_from_todo(&steps, &logs, false).unwrap();
+
+        assert_eq!(ops[0].commit_index, 2);
+        assert_eq!(ops[1].operation, PlanOperationKind::Squash);
+        assert_eq!(ops[1].squash_with, Some(2));
+        assert_eq!(
+            ops[1]
+                .new_message
+                .as_deref(),
+            Some("add c\n\nadd a")
+        );
+
+        let todo = plan_to_todo(&ops, &logs);
+        assert_eq!(todo[1].commit, "aaaaaaa");
+
+        // b left out is a drop, only when allowed
+        let steps = &steps[..2];
+        assert!(plan_from_todo(steps, &logs, false).is_err());
+        assert_eq!(
+            plan_from_todo(steps, &logs, true).unwrap()[2].operation,
+            PlanOperationKind::Drop
+        );
+
+        let steps =
+            crate::git::todo::parse_todo("pick ddddddd\n").unwrap();
+        assert!(plan_from_todo(&steps, &logs, true).is_err());
+
+        // a bare reword has no message to go with it
+        let steps =
+            crate::git::todo::parse_todo("reword aaaaaaa\n").unwrap();
+        let err = plan_from_todo(&steps, &logs, true).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("needs a message exec")
+        );
+    }
 }
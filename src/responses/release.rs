@@ -0,0 +1,10 @@
+use crate::{release::Bump, schema::release::ReleaseSchema};
+
+/// extract the suggested bump
+pub fn parse_to_bump(
+    value: serde_json::Value
+) -> anyhow::Result<(Bump, String)> {
+    let response: ReleaseSchema = serde_json::from_value(value)?;
+
+    Ok((response.bump, response.reasoning))
+}
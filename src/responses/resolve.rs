@@ -0,0 +1,66 @@
+use crate::{
+    git::conflicts::has_markers,
+    schema::resolve::{HunkResolution, ResolveResponse},
+};
+
+/// one slot per conflict block, none where the
+/// llm skipped it or left markers in, dupes
+/// and unknown ids are dropped
+pub fn parse_to_resolutions(
+    value: serde_json::Value,
+    conflict_count: usize,
+) -> anyhow::Result<Vec<Option<HunkResolution>>> {
+    let response: ResolveResponse = serde_json::from_value(value)?;
+
+    let mut res = vec![None; conflict_count];
+
+    for resolution in response.resolutions {
+        let idx = resolution.conflict_id as usize;
+
+        if idx >= conflict_count
+            || res[idx].is_some()
+            || has_markers(&resolution.resolution)
+        {
+            continue;
+        }
+
+        res[idx] = Some(resolution);
+    }
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_resolutions() {
+        let value = json!({
+            "resolutions": [
+                {"conflict_id": 1, "reasoning": "a", "resolution": "merged"},
+                {"conflict_id": 1, "reasoning": "dupe", "resolution": "x"},
+                {"conflict_id": 5, "reasoning": "unknown", "resolution": "x"},
+                {
+                    "conflict_id": 0,
+                    "reasoning": "lazy",
+                    "resolution": "<<<<<<< HEAD\na\n=======\nb\n>>>>>>> x"
+                },
+            ]
+        });
+
+        let res = parse_to_resolutions(value, 2).unwrap();
+
+        assert!(res[0].is_none());
+        assert_eq!(
+            res[1]
+                .as_ref()
+                .map(|r| r
+                    .resolution
+                    .as_str()),
+            Some("merged")
+        );
+    }
+}
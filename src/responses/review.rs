@@ -0,0 +1,243 @@
+use serde::Serialize;
+
+use crate::{
+    git::{
+        Diffs,
+        diffs::{DiffLineType, Hunk, HunkId},
+    },
+    schema::review::{Category, ReviewSchema, Severity},
+};
+
+/// a review with every finding
+/// pointing at a hunk we sent
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Review {
+    pub summary: String,
+
+    /// most severe first
+    pub findings: Vec<ReviewFinding>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ReviewFinding {
+    pub file: String,
+    pub hunk_id: usize,
+
+    /// line in the new file, always inside the hunk
+    pub line: u32,
+
+    pub severity: Severity,
+    pub category: Category,
+    pub message: String,
+    pub suggested_fix: Option<String>,
+}
+
+impl Review {
+    /// findings that should block a commit
+    pub fn at_or_above(
+        &self,
+        severity: Severity,
+    ) -> Vec<&ReviewFinding> {
+        self.findings
+            .iter()
+            .filter(|f| f.severity >= severity)
+            .collect()
+    }
+}
+
+/// extract the Review from the response, findings
+/// citing unknown hunks are dropped and lines
+/// outside the hunk are moved to its first change
+pub fn parse_to_review(
+    value: serde_json::Value,
+    diffs: &Diffs,
+) -> anyhow::Result<Review> {
+    let schema: ReviewSchema = serde_json::from_value(value)?;
+
+    let mut findings: Vec<ReviewFinding> = schema
+        .findings
+        .into_iter()
+        .filter_map(|f| {
+            let hunk_id = HunkId::try_from(f.hunk_id.trim()).ok()?;
+
+            let hunk = diffs
+                .files
+                .iter()
+                .find(|file| file.path == hunk_id.path)?
+                .hunks
+                .iter()
+                .find(|h| h.id == hunk_id.index)?;
+
+            let suggested_fix = f
+                .suggested_fix
+                .trim();
+
+            Some(ReviewFinding {
+                line: clamp_line(hunk, f.line),
+                file: hunk_id.path,
+                hunk_id: hunk_id.index,
+                severity: f.severity,
+                category: f.category,
+                message: f
+                    .message
+                    .trim()
+                    .to_owned(),
+                suggested_fix: if suggested_fix.is_empty() {
+                    None
+                } else {
+                    Some(suggested_fix.to_owned())
+                },
+            })
+        })
+        .collect();
+
+    // stable, keeps the llm's order within a severity
+    findings.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+    });
+
+    Ok(Review {
+        summary: schema.summary,
+        findings,
+    })
+}
+
+fn clamp_line(
+    hunk: &Hunk,
+    line: u32,
+) -> u32 {
+    let start = hunk
+        .header
+        .new_start;
+    let end = start
+        + hunk
+            .header
+            .new_lines;
+
+    if line >= start.max(1) && line < end {
+        return line;
+    }
+
+    hunk.lines
+        .iter()
+        .find(|l| l.line_type == DiffLineType::Add)
+        .and_then(|l| {
+            l.position
+                .new_lineno
+        })
+        .unwrap_or(start)
+        .max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::git::diffs::{
+        DiffLine, DiffLinePosition, FileDiff, HunkHeader,
+    };
+
+    fn line(
+        line_type: DiffLineType,
+        new_lineno: Option<u32>,
+    ) -> DiffLine {
+        DiffLine {
+            content: "x".into(),
+            line_type,
+            position: DiffLinePosition {
+                old_lineno: None,
+                new_lineno,
+            },
+        }
+    }
+
+    #[test]
+    fn test_review_findings_validated() {
+        let diffs = Diffs {
+            files: vec![FileDiff {
+                path: "src/main.rs".to_owned(),
+                hunks: vec![Hunk {
+                    id: 0,
+                    header: HunkHeader {
+                        old_start: 10,
+                        old_lines: 2,
+                        new_start: 10,
+                        new_lines: 3,
+                    },
+                    lines: vec![
+                        line(DiffLineType::None, Some(10)),
+                        line(DiffLineType::Add, Some(11)),
+                        line(DiffLineType::None, Some(12)),
+                    ],
+                }],
+                ..Default::default()
+            }],
+        };
+
+        let value = json!({
+            "summary": "looks risky",
+            "findings": [
+                {
+                    "hunk_id": "src/main.rs:0",
+                    "line": 40,
+                    "severity": "low",
+                    "category": "style",
+                    "message": "naming",
+                    "suggested_fix": ""
+                },
+                {
+                    "hunk_id": "src/lib.rs:0",
+                    "line": 1,
+                    "severity": "high",
+                    "category": "bug",
+                    "message": "made up",
+                    "suggested_fix": ""
+                },
+                {
+                    "hunk_id": "src/main.rs:0",
+                    "line": 12,
+                    "severity": "high",
+                    "category": "bug",
+                    "message": "unwrap on none",
+                    "suggested_fix": "use ?"
+                }
+            ]
+        });
+
+        let review = parse_to_review(value, &diffs).unwrap();
+
+        assert_eq!(
+            review
+                .findings
+                .len(),
+            2
+        );
+
+        // most severe first
+        assert_eq!(review.findings[0].severity, Severity::High);
+        assert_eq!(review.findings[0].line, 12);
+        assert_eq!(
+            review.findings[0]
+                .suggested_fix
+                .as_deref(),
+            Some("use ?")
+        );
+
+        // out of range goes to the first added line
+        assert_eq!(review.findings[1].line, 11);
+        assert!(
+            review.findings[1]
+                .suggested_fix
+                .is_none()
+        );
+
+        assert_eq!(
+            review
+                .at_or_above(Severity::Medium)
+                .len(),
+            1
+        );
+    }
+}
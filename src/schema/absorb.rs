@@ -0,0 +1,61 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::schema::{SchemaBuilder, SchemaSettings};
+
+/// the raw assignments, checked
+/// against what we sent in responses::absorb
+#[derive(Clone, Debug, Deserialize)]
+pub struct AbsorbSchema {
+    #[serde(default)]
+    pub assignments: Vec<AssignmentSchema>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct AssignmentSchema {
+    /// format file:index
+    pub hunk_id: String,
+
+    /// short hash or "none"
+    pub commit: String,
+}
+
+/// creates a schema for picking the commit each
+/// ambiguous hunk fixes, commits are short hashes
+/// with "none" for hunks that are new work
+pub fn create_absorb_schema(
+    schema_settings: SchemaSettings,
+    hunk_ids: &[String],
+    commits: &[String],
+) -> anyhow::Result<Value> {
+    let mut commits = commits.to_vec();
+    commits.push("none".to_owned());
+
+    let assignment_schema = SchemaBuilder::new()
+        .settings(schema_settings.to_owned())
+        .insert_enum(
+            "hunk_id",
+            Some("hunk being assigned, format: file:index (e.g. src/main.rs:0)"),
+            true,
+            hunk_ids,
+        )
+        .insert_enum(
+            "commit",
+            Some("short hash of the commit this hunk fixes, none if it is new work"),
+            true,
+            &commits,
+        )
+        .build_inner();
+
+    let schema = SchemaBuilder::new()
+        .settings(schema_settings)
+        .insert_object_array(
+            "assignments",
+            Some("one entry per hunk"),
+            true,
+            assignment_schema,
+        )
+        .build();
+
+    Ok(schema)
+}
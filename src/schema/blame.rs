@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::schema::{SchemaBuilder, SchemaSettings};
+
+/// the explanation of how a region evolved
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlameSchema {
+    /// short overall narrative
+    pub summary: String,
+
+    /// oldest to newest
+    #[serde(default)]
+    pub steps: Vec<BlameStep>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlameStep {
+    /// short hash as cited by the llm
+    pub commit: String,
+
+    /// what changed here and why
+    pub explanation: String,
+}
+
+/// creates a schema for explaining the history
+/// of a region, one step per commit
+pub fn create_blame_schema(
+    schema_settings: SchemaSettings
+) -> anyhow::Result<Value> {
+    let step_schema = SchemaBuilder::new()
+        .settings(schema_settings.to_owned())
+        .insert_str(
+            "commit",
+            Some("short hash of the commit this step is about"),
+            true,
+        )
+        .insert_str(
+            "explanation",
+            Some("what this commit changed in the region and why"),
+            true,
+        )
+        .build_inner();
+
+    let schema = SchemaBuilder::new()
+        .settings(schema_settings)
+        .insert_str(
+            "summary",
+            Some("how and why the code evolved, citing short hashes"),
+            true,
+        )
+        .insert_object_array(
+            "steps",
+            Some("one step per relevant commit, oldest first"),
+            true,
+            step_schema,
+        )
+        .build();
+
+    Ok(schema)
+}
@@ -0,0 +1,63 @@
+use serde::Deserialize;
+use serde_json::Value;
+use strum::VariantNames;
+
+use crate::schema::{
+    SchemaBuilder, SchemaSettings, commit::PrefixType,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct BranchSchema {
+    #[serde(default)]
+    pub suggestions: Vec<BranchSuggestion>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct BranchSuggestion {
+    pub prefix: PrefixType,
+
+    /// ticket id if the description has one,
+    /// empty otherwise
+    #[serde(default)]
+    pub ticket: String,
+
+    /// a few words, turned into the slug
+    pub description: String,
+}
+
+/// creates a schema for branch name suggestions
+pub fn create_branch_schema(
+    schema_settings: SchemaSettings
+) -> anyhow::Result<Value> {
+    let suggestion_schema = SchemaBuilder::new()
+        .settings(schema_settings.to_owned())
+        .insert_enum(
+            "prefix",
+            Some("conventional commit type of the work"),
+            true,
+            PrefixType::VARIANTS,
+        )
+        .insert_str(
+            "ticket",
+            Some("ticket or issue id mentioned in the description, ex. ABC-123, empty if none"),
+            true,
+        )
+        .insert_str(
+            "description",
+            Some("two to five words describing the work"),
+            true,
+        )
+        .build_inner();
+
+    let schema = SchemaBuilder::new()
+        .settings(schema_settings)
+        .insert_object_array(
+            "suggestions",
+            Some("branch name ideas, best first"),
+            true,
+            suggestion_schema,
+        )
+        .build();
+
+    Ok(schema)
+}
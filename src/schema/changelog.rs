@@ -0,0 +1,51 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::schema::{SchemaBuilder, SchemaSettings};
+
+#[derive(Debug, Deserialize)]
+pub struct ChangelogSchema {
+    #[serde(default)]
+    pub sections: Vec<SectionSummary>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SectionSummary {
+    pub title: String,
+    pub summary: String,
+}
+
+/// creates a schema for summarizing each
+/// changelog section, titles are limited to
+/// the sections that actually exist
+pub fn create_changelog_schema(
+    schema_settings: SchemaSettings,
+    titles: &[String],
+) -> anyhow::Result<Value> {
+    let section_schema = SchemaBuilder::new()
+        .settings(schema_settings.to_owned())
+        .insert_enum(
+            "title",
+            Some("the section this summarizes"),
+            true,
+            titles,
+        )
+        .insert_str(
+            "summary",
+            Some("a short human readable paragraph for end users"),
+            true,
+        )
+        .build_inner();
+
+    let schema = SchemaBuilder::new()
+        .settings(schema_settings)
+        .insert_object_array(
+            "sections",
+            Some("one summary per section"),
+            true,
+            section_schema,
+        )
+        .build();
+
+    Ok(schema)
+}
@@ -37,7 +37,7 @@ impl From<CommitResponse> for Vec<CommitSchema> {
 
 /// raw commit schema struct, used when we
 /// deserialize the response Value object
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CommitSchema {
     /// reason why you decided to make this
     /// commit. ex. why are they grouped together?
@@ -63,6 +63,12 @@ pub struct CommitSchema {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub hunk_ids: Option<Vec<String>>,
 
+    /// used when staging as lines
+    /// format file:hunk:block
+    /// ex: src/main.rs:0:1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_ids: Option<Vec<String>>,
+
     // commit message components
     /// commit type
     pub prefix: PrefixType,
@@ -115,6 +121,56 @@ impl fmt::Display for CommitSchema {
     }
 }
 
+impl CommitSchema {
+    /// the files or hunk ids this commit
+    /// stages, depends on the strategy
+    pub fn items(
+        &self,
+        strategy: &StagingStrategy,
+    ) -> Vec<String> {
+        match strategy {
+            StagingStrategy::Hunks => self
+                .hunk_ids
+                .to_owned()
+                .unwrap_or_default(),
+            StagingStrategy::Lines => self
+                .line_ids
+                .to_owned()
+                .unwrap_or_default(),
+            StagingStrategy::AtomicCommits => self
+                .paths
+                .to_owned()
+                .unwrap_or_default(),
+            StagingStrategy::OneFilePerCommit => self
+                .path
+                .iter()
+                .cloned()
+                .collect(),
+            StagingStrategy::AllFilesOneCommit => Vec::new(),
+        }
+    }
+
+    pub fn set_items(
+        &mut self,
+        strategy: &StagingStrategy,
+        items: Vec<String>,
+    ) {
+        match strategy {
+            StagingStrategy::Hunks => self.hunk_ids = Some(items),
+            StagingStrategy::Lines => self.line_ids = Some(items),
+            StagingStrategy::AtomicCommits => {
+                self.paths = Some(items)
+            }
+            StagingStrategy::OneFilePerCommit => {
+                self.path = items
+                    .into_iter()
+                    .next()
+            }
+            StagingStrategy::AllFilesOneCommit => {}
+        }
+    }
+}
+
 /// conventional commit type prefix
 #[derive(
     Clone,
@@ -175,6 +231,7 @@ pub fn create_commit_response_schema(
     settings: &Settings,
     files: &[String],
     hunk_ids: &[String],
+    line_ids: &[String],
 ) -> anyhow::Result<Value> {
     let mut builder = SchemaBuilder::new()
         .settings(schema_settings.to_owned())
@@ -196,6 +253,16 @@ pub fn create_commit_response_schema(
                 hunk_ids,
             );
         }
+        // same as hunks, but the blocks
+        // of changed lines within them
+        StagingStrategy::Lines => {
+            builder = builder.insert_enum_array(
+                "line_ids",
+                Some("line block IDs to stage, format: file:hunk:block (e.g. src/main.rs:0:1)"),
+                true,
+                line_ids,
+            );
+        }
         // only ONE file PER commit
         // that means that the file path
         // for this commit entry can be only
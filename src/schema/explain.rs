@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::schema::{SchemaBuilder, SchemaSettings};
+
+/// what a commit or range did, also used
+/// for the partial explanations of a chunk
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ExplainSchema {
+    /// why the change was made
+    pub intent: String,
+
+    #[serde(default)]
+    pub components: Vec<ComponentChange>,
+
+    /// what behaves differently for
+    /// users or callers afterwards
+    #[serde(default)]
+    pub behavior_changes: Vec<String>,
+
+    #[serde(default)]
+    pub risky_areas: Vec<RiskyArea>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ComponentChange {
+    /// module, directory or subsystem
+    pub name: String,
+    pub summary: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RiskyArea {
+    /// file, function or component
+    pub area: String,
+    pub reason: String,
+}
+
+/// creates a schema for explaining existing changes
+pub fn create_explain_schema(
+    schema_settings: SchemaSettings
+) -> anyhow::Result<Value> {
+    let component_schema = SchemaBuilder::new()
+        .settings(schema_settings.to_owned())
+        .insert_str(
+            "name",
+            Some("module, directory or subsystem, ex. git/rebase"),
+            true,
+        )
+        .insert_str(
+            "summary",
+            Some("what changed in it, one sentence"),
+            true,
+        )
+        .build_inner();
+
+    let risk_schema = SchemaBuilder::new()
+        .settings(schema_settings.to_owned())
+        .insert_str("area", Some("file, function or component"), true)
+        .insert_str(
+            "reason",
+            Some("why it deserves a closer look"),
+            true,
+        )
+        .build_inner();
+
+    let schema = SchemaBuilder::new()
+        .settings(schema_settings)
+        .insert_str(
+            "intent",
+            Some("what the change set out to do and why, two or three sentences"),
+            true,
+        )
+        .insert_object_array(
+            "components",
+            Some("affected components, most affected first"),
+            true,
+            component_schema,
+        )
+        .insert_str_array(
+            "behavior_changes",
+            Some("observable differences for users or callers, empty if none"),
+            true,
+        )
+        .insert_object_array(
+            "risky_areas",
+            Some("places that could break or need careful review, empty if none"),
+            true,
+            risk_schema,
+        )
+        .build();
+
+    Ok(schema)
+}
@@ -1,4 +1,4 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use strum::VariantNames;
 
@@ -36,8 +36,20 @@ pub struct FindCommitSchema {
     pub confidence: Confidence,
 }
 
+/// ordered from most to least confident
+/// so matches can be sorted by it
 #[derive(
-    Clone, Debug, Deserialize, strum::Display, strum::VariantNames,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    strum::Display,
+    strum::VariantNames,
 )]
 pub enum Confidence {
     // this is absolutely the commit
@@ -48,13 +60,14 @@ pub enum Confidence {
     Ambiguous,
 }
 
-/// creates a schema for finding a specific
-/// commit based on a query
+/// creates a schema for finding commits
+/// based on a query, the response is a
+/// ranked list, best match first
 pub fn create_find_schema(
     schema_settings: SchemaSettings,
     max: u32,
 ) -> anyhow::Result<Value> {
-    let builder = SchemaBuilder::new()
+    let commit_schema = SchemaBuilder::new()
         .settings(schema_settings.to_owned())
         .insert_str(
             "reasoning",
@@ -73,9 +86,18 @@ pub fn create_find_schema(
             Some("choose your confidence on whether or not this matches the query"), 
             true,
             Confidence::VARIANTS
-        );
+        )
+        .build_inner();
 
-    let schema = builder.build();
+    let schema = SchemaBuilder::new()
+        .settings(schema_settings)
+        .insert_object_array(
+            "commits",
+            Some("matching commits, ranked from best to worst"),
+            true,
+            commit_schema,
+        )
+        .build();
 
     Ok(schema)
 }
@@ -0,0 +1,93 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::schema::{SchemaBuilder, SchemaSettings};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PrSchema {
+    pub title: String,
+    pub summary: String,
+
+    #[serde(default)]
+    pub changes: Vec<String>,
+
+    #[serde(default)]
+    pub testing: Vec<String>,
+
+    #[serde(default)]
+    pub risks: Vec<String>,
+
+    /// only when filling a template
+    #[serde(default)]
+    pub sections: Vec<TemplateSection>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TemplateSection {
+    pub heading: String,
+    pub content: String,
+}
+
+/// creates a schema for a pull request, with
+/// one section per template heading if the
+/// repo has a template
+pub fn create_pr_schema(
+    schema_settings: SchemaSettings,
+    headings: &[String],
+) -> anyhow::Result<Value> {
+    let mut builder = SchemaBuilder::new()
+        .settings(schema_settings.to_owned())
+        .insert_str(
+            "title",
+            Some("short pull request title, imperative mood"),
+            true,
+        )
+        .insert_str(
+            "summary",
+            Some("what the branch does and why, a short paragraph"),
+            true,
+        )
+        .insert_str_array(
+            "changes",
+            Some("notable changes, one per item"),
+            true,
+        )
+        .insert_str_array(
+            "testing",
+            Some("how the changes were or should be tested"),
+            true,
+        )
+        .insert_str_array(
+            "risks",
+            Some("areas reviewers should look at closely, can be empty"),
+            true,
+        );
+
+    if !headings.is_empty() {
+        let section_schema = SchemaBuilder::new()
+            .settings(schema_settings)
+            .insert_enum(
+                "heading",
+                Some("the template heading this fills"),
+                true,
+                headings,
+            )
+            .insert_str(
+                "content",
+                Some(
+                    "markdown for this section, without the heading",
+                ),
+                true,
+            )
+            .build_inner();
+
+        builder = builder.insert_object_array(
+            "sections",
+            Some("one entry per template section"),
+            true,
+            section_schema,
+        );
+    }
+
+    Ok(builder.build())
+}
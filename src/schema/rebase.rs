@@ -38,12 +38,14 @@ pub fn create_rebase_schema(
     settings: &Settings,
     files: &[String],
     hunk_ids: &[String],
+    line_ids: &[String],
 ) -> anyhow::Result<Value> {
     let schema = create_commit_response_schema(
         schema_settings,
         settings,
         files,
         hunk_ids,
+        line_ids,
     )?;
 
     Ok(schema)
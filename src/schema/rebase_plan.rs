@@ -1,5 +1,6 @@
 use serde::Deserialize;
 use serde_json::Value;
+use strum::VariantNames;
 
 use crate::schema::{SchemaBuilder, SchemaSettings};
 
@@ -10,7 +11,8 @@ pub struct RebasePlanResponse {
     pub operations: Vec<PlanOperationSchema>,
 }
 
-/// rebaseplan schema components
+/// rebaseplan schema components, the order
+/// of the operations is the new commit order
 #[derive(Clone, Debug, Deserialize)]
 pub struct PlanOperationSchema {
     pub reasoning: String,
@@ -18,16 +20,19 @@ pub struct PlanOperationSchema {
     pub operation: PlanOperationKind,
     // optional, but required for reword and squash
     pub new_message: Option<String>,
-    // For now, disabling this to simplify
-    // the schema, since i only want to support
-    // squashing with the immediate previous commit
-    // or at least only want the LLM to be able to do
-    //pub squash_with: Option<u32>,
+    // squash and fixup fold into this commit,
+    // defaults to the one right before
+    pub squash_with: Option<u32>,
 }
 
 /// rebase operation types
 #[derive(
-    Clone, Debug, Deserialize, strum::Display, strum::VariantNames,
+    Clone,
+    Debug,
+    PartialEq,
+    Deserialize,
+    strum::Display,
+    strum::VariantNames,
 )]
 #[serde(rename_all = "lowercase")]
 #[strum(serialize_all = "lowercase")]
@@ -36,25 +41,33 @@ pub enum PlanOperationKind {
     Pick,
     /// combine commits,
     /// must gen a commit message
-    /// squashes_with, is other commit
+    /// squash_with is the earlier commit
     /// it will combine with
-    /// for LLM sake and my own sanity
-    /// we will only handle PAIRS
-    /// and FIXME: add validation,
-    /// so that other squashes_with
-    /// does not point to the same commit
     Squash,
+    /// same as squash, but keeps the
+    /// message of the commit it folds into
+    Fixup,
     /// reword a commit
     Reword,
+    /// pick, then stop so the commit
+    /// can be amended by hand
+    Edit,
     /// drop a commit,
     /// this is dangerous
     /// and will be left off by default
     Drop,
 }
 
+impl PlanOperationKind {
+    /// squash and fixup dont make a commit
+    /// of their own
+    pub fn folds(&self) -> bool {
+        matches!(self, Self::Squash | Self::Fixup)
+    }
+}
+
 /// create a rebsase plan schema,
-/// FIXME: allow_drop should be a configurable
-/// option in settings.
+/// drop is only offered when the settings allow it
 pub fn create_rebase_plan_schema(
     schema_settings: SchemaSettings,
     max_commit_index: usize,
@@ -62,6 +75,12 @@ pub fn create_rebase_plan_schema(
 ) -> anyhow::Result<Value> {
     let max_commit_id = max_commit_index as u32;
 
+    let operations: Vec<&str> = PlanOperationKind::VARIANTS
+        .iter()
+        .copied()
+        .filter(|op| allow_drop || *op != "drop")
+        .collect();
+
     let builder = SchemaBuilder::new()
         .settings(schema_settings.clone())
         .insert_str(
@@ -80,18 +99,19 @@ pub fn create_rebase_plan_schema(
             "operation",
             Some("the rebase operation to perform"),
             true,
-            {
-                if allow_drop {
-                    &["pick", "squash", "reword", "drop"]
-                } else {
-                    &["pick", "squash", "reword"]
-                }
-            },
+            &operations,
         )
         .insert_str(
             "new_message",
             Some("new commit message, THIS IS REQUIRED for reword and squash ops"),
             false,
+        )
+        .insert_int(
+            "squash_with",
+            Some("for squash and fixup ops, the commit index to fold into, it must come earlier in the plan. defaults to the operation right before"),
+            false,
+            Some(0),
+            Some(max_commit_id),
         );
 
     let operation_schema = builder.build_inner();
@@ -100,7 +120,7 @@ pub fn create_rebase_plan_schema(
         .settings(schema_settings)
         .insert_object_array(
             "operations",
-            Some("list of rebase operations, one per commit"),
+            Some("list of rebase operations, one per commit, in the new commit order"),
             true,
             operation_schema,
         )
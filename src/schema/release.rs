@@ -0,0 +1,36 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    release::Bump,
+    schema::{SchemaBuilder, SchemaSettings},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ReleaseSchema {
+    pub bump: Bump,
+    pub reasoning: String,
+}
+
+/// creates a schema for classifying commits
+/// without conventional prefixes as a bump
+pub fn create_release_schema(
+    schema_settings: SchemaSettings
+) -> anyhow::Result<Value> {
+    let schema = SchemaBuilder::new()
+        .settings(schema_settings)
+        .insert_enum(
+            "bump",
+            Some("the smallest semver bump that covers every commit"),
+            true,
+            &["major", "minor", "patch"],
+        )
+        .insert_str(
+            "reasoning",
+            Some("which commits decided the bump and why"),
+            true,
+        )
+        .build();
+
+    Ok(schema)
+}
@@ -0,0 +1,64 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::schema::{SchemaBuilder, SchemaSettings};
+
+/// wrapper struct for the resolve response
+#[derive(Debug, Deserialize)]
+pub struct ResolveResponse {
+    #[serde(default)]
+    pub resolutions: Vec<HunkResolution>,
+}
+
+/// proposed merge for a single conflict block
+#[derive(Clone, Debug, Deserialize)]
+pub struct HunkResolution {
+    /// index of the conflict in the file
+    pub conflict_id: u32,
+
+    /// why both sides end up like this
+    pub reasoning: String,
+
+    /// replaces the whole block, markers included
+    pub resolution: String,
+}
+
+/// creates a schema for resolving every
+/// conflict block in a file
+pub fn create_resolve_schema(
+    schema_settings: SchemaSettings,
+    max: u32,
+) -> anyhow::Result<Value> {
+    let resolution_schema = SchemaBuilder::new()
+        .settings(schema_settings.to_owned())
+        .insert_int(
+            "conflict_id",
+            Some("id of the conflict this resolves"),
+            true,
+            Some(0),
+            Some(max),
+        )
+        .insert_str(
+            "reasoning",
+            Some("how the two sides were combined and why"),
+            true,
+        )
+        .insert_str(
+            "resolution",
+            Some("merged code replacing the whole conflict, without conflict markers"),
+            true,
+        )
+        .build_inner();
+
+    let schema = SchemaBuilder::new()
+        .settings(schema_settings)
+        .insert_object_array(
+            "resolutions",
+            Some("one resolution per conflict"),
+            true,
+            resolution_schema,
+        )
+        .build();
+
+    Ok(schema)
+}
@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use strum::VariantNames;
+
+use crate::schema::{SchemaBuilder, SchemaSettings};
+
+/// the raw review, hunk ids and lines
+/// are checked in responses::review
+#[derive(Clone, Debug, Deserialize)]
+pub struct ReviewSchema {
+    /// overall impression of the change
+    pub summary: String,
+
+    #[serde(default)]
+    pub findings: Vec<FindingSchema>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FindingSchema {
+    /// format file:index
+    pub hunk_id: String,
+
+    /// line in the new file, 0 if the
+    /// finding is about the whole hunk
+    #[serde(default)]
+    pub line: u32,
+
+    pub severity: Severity,
+    pub category: Category,
+    pub message: String,
+
+    /// empty if theres nothing concrete to suggest
+    #[serde(default)]
+    pub suggested_fix: String,
+}
+
+/// ordered, so block_on and --fail-on
+/// can compare with >=
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    clap::ValueEnum,
+    strum::Display,
+    strum::VariantNames,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    strum::Display,
+    strum::EnumIter,
+    strum::VariantNames,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Category {
+    Bug,
+    Security,
+    Performance,
+    Maintainability,
+    Style,
+    Tests,
+    Docs,
+}
+
+/// creates a schema for reviewing a diff,
+/// findings can only point at hunks we sent
+pub fn create_review_schema(
+    schema_settings: SchemaSettings,
+    hunk_ids: &[String],
+) -> anyhow::Result<Value> {
+    let finding_schema = SchemaBuilder::new()
+        .settings(schema_settings.to_owned())
+        .insert_enum(
+            "hunk_id",
+            Some("hunk the finding is in, format: file:index (e.g. src/main.rs:0)"),
+            true,
+            hunk_ids,
+        )
+        .insert_int(
+            "line",
+            Some("line number in the new file as labeled in the diff, 0 for the whole hunk"),
+            true,
+            Some(0),
+            None,
+        )
+        .insert_enum(
+            "severity",
+            Some("high for bugs or security issues that should block the commit"),
+            true,
+            Severity::VARIANTS,
+        )
+        .insert_enum(
+            "category",
+            Some("kind of issue"),
+            true,
+            Category::VARIANTS,
+        )
+        .insert_str(
+            "message",
+            Some("what is wrong and why, one or two sentences"),
+            true,
+        )
+        .insert_str(
+            "suggested_fix",
+            Some("replacement code or a concrete change, empty if none"),
+            true,
+        )
+        .build_inner();
+
+    let schema = SchemaBuilder::new()
+        .settings(schema_settings)
+        .insert_str(
+            "summary",
+            Some("one or two sentences on the change as a whole"),
+            true,
+        )
+        .insert_object_array(
+            "findings",
+            Some("issues found in the diff, most severe first, empty if none"),
+            true,
+            finding_schema,
+        )
+        .build();
+
+    Ok(schema)
+}
@@ -1,6 +1,9 @@
-use crate::settings::Settings;
+use crate::{schema::review::Severity, settings::Settings};
 
-use super::{CommitSettings, ContextSettings, PromptRules};
+use super::{
+    BranchSettings, CommitSettings, ContextSettings, IndexSettings,
+    PromptRules, ReviewSettings,
+};
 
 impl Default for Settings {
     fn default() -> Self {
@@ -13,6 +16,10 @@ impl Default for Settings {
             rules: Default::default(),
             context: Default::default(),
             commit: Default::default(),
+            index: Default::default(),
+            branch: Default::default(),
+            review: Default::default(),
+            rebase: Default::default(),
         }
     }
 }
@@ -46,6 +53,16 @@ impl Default for ContextSettings {
     }
 }
 
+impl Default for IndexSettings {
+    fn default() -> Self {
+        Self {
+            embedding_endpoint: None,
+            embedding_model: "nomic-embed-text".to_owned(),
+            candidates: 40,
+        }
+    }
+}
+
 impl Default for CommitSettings {
     fn default() -> Self {
         Self {
@@ -57,3 +74,21 @@ impl Default for CommitSettings {
         }
     }
 }
+
+impl Default for BranchSettings {
+    fn default() -> Self {
+        Self {
+            pattern: "{type}/{ticket}-{slug}".to_owned(),
+            max_slug_length: 40,
+        }
+    }
+}
+
+impl Default for ReviewSettings {
+    fn default() -> Self {
+        Self {
+            before_commit: false,
+            block_on: Severity::High,
+        }
+    }
+}
@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 use crate::{
     git::{StagingStrategy, StatusStrategy},
     providers::provider::{ProviderKind, ProviderSettings},
+    schema::review::Severity,
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -38,6 +39,18 @@ pub struct Settings {
     /// commit process settings after receiving
     /// llm generated commits
     pub commit: CommitSettings,
+
+    /// local commit index used by gai find
+    pub index: IndexSettings,
+
+    /// branch names from gai branch
+    pub branch: BranchSettings,
+
+    /// gai review and the check before gai commit
+    pub review: ReviewSettings,
+
+    /// gai rebase --plan
+    pub rebase: RebaseSettings,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
@@ -148,6 +161,54 @@ pub struct ContextSettings {
     pub truncate_files: Option<Vec<String>>,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndexSettings {
+    /// openai compatible embeddings endpoint
+    /// ex. http://localhost:11434/v1/embeddings
+    /// if empty, only keyword search is used
+    pub embedding_endpoint: Option<String>,
+
+    /// model sent to the embeddings endpoint
+    pub embedding_model: String,
+
+    /// how many of the best index matches
+    /// gai find sends to the llm
+    pub candidates: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BranchSettings {
+    /// {type}, {ticket} and {slug} are filled in
+    /// separators next to an empty {ticket} are
+    /// dropped, ex. feat/add-login
+    pub pattern: String,
+
+    /// slug is cut at a word boundary past this
+    pub max_slug_length: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReviewSettings {
+    /// review the diffs before gai commit
+    /// generates any commits
+    pub before_commit: bool,
+
+    /// findings at or above this stop
+    /// gai commit until confirmed
+    pub block_on: Severity,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct RebaseSettings {
+    /// let a plan drop commits, off by default
+    /// since a dropped commit is gone from the branch
+    pub allow_drop: bool,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct CommitSettings {
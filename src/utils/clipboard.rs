@@ -0,0 +1,58 @@
+// pipes text into whatever clipboard
+// tool is installed, same idea as open.rs
+// without pulling in a clipboard crate
+
+use std::{
+    io::{Error, ErrorKind, Write},
+    process::{Command, Stdio},
+};
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+#[rustfmt::skip]
+static CLIPBOARD_CMDS: &[&str] = &[
+    "wl-copy",
+    "xclip -selection clipboard",
+    "xsel --clipboard --input",
+];
+
+#[cfg(target_os = "macos")]
+static CLIPBOARD_CMDS: &[&str] = &["pbcopy"];
+
+#[cfg(target_os = "windows")]
+static CLIPBOARD_CMDS: &[&str] = &["clip.exe"];
+
+/// copy text to the clipboard, errors
+/// with NotFound if no tool is available
+pub fn copy(text: &str) -> anyhow::Result<()> {
+    for cmd in CLIPBOARD_CMDS {
+        let mut args = cmd.split_ascii_whitespace();
+
+        let Some(bin) = args.next() else {
+            continue;
+        };
+
+        let child = Command::new(bin)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+
+        let Ok(mut child) = child else {
+            continue;
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+
+        if child
+            .wait()?
+            .success()
+        {
+            return Ok(());
+        }
+    }
+
+    Err(Error::from(ErrorKind::NotFound).into())
+}
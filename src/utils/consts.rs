@@ -11,6 +11,11 @@ pub const PROMPT_STAGE_HUNKS: &str = "Fill hunk_ids with the HUNK_ID values show
     Each hunk can only appear in ONE commit.\
     Ex.: [\"src/main.rs:0\", \"src/git/repo.rs:1\"]";
 
+pub const PROMPT_STAGE_LINES: &str = "Fill line_ids with the LINE_ID values shown in the diffs (format: \"filepath:hunk:block\").\
+    Each LineId labels the block of changed lines right below it, split a hunk across commits when its blocks are unrelated.\
+    Each line block can only appear in ONE commit.\
+    Ex.: [\"src/main.rs:0:0\", \"src/main.rs:0:2\"]";
+
 pub const PROMPT_STAGE_FILES: &str = "Fill out files with valid paths and leave hunk_headers empty. Files should ONLY appear in ONE commit";
 
 pub const RULE_GROUP_FILES: &str = "- GROUP related files into LOGICAL commits based on the type of change\n\
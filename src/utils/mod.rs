@@ -1,2 +1,3 @@
+pub mod clipboard;
 pub mod consts;
 pub mod open;